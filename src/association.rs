@@ -0,0 +1,133 @@
+use crate::{associate, Duration, ErrorStats, EuRoC, Result, Sensor, Timestamp, Timestamped};
+
+/// Synchronization summary of one timestamp stream against another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssociationReport {
+    /// number of timestamps in the first stream
+    pub total: usize,
+    /// number of them with a counterpart within the tolerance
+    pub matched: usize,
+    /// signed offsets `other - self` (s) of the matched pairs
+    pub dt: Option<ErrorStats>,
+    /// `(first, last)` timestamps of each run of consecutive unmatched timestamps
+    pub unmatched_spans: Vec<(Timestamp, Timestamp)>,
+}
+
+impl AssociationReport {
+    /// Associate every timestamp of `stream` with the nearest one of `other`.
+    ///
+    /// Both slices must be sorted.
+    pub fn new(stream: &[Timestamp], other: &[Timestamp], tolerance: Duration) -> Self {
+        let pairs = associate(stream, other, tolerance);
+
+        let dt: Vec<_> = pairs
+            .iter()
+            .map(|&(i, j)| (other[j] - stream[i]).as_secs_f64())
+            .collect();
+
+        let mut matched = vec![false; stream.len()];
+        for &(i, _) in &pairs {
+            matched[i] = true;
+        }
+
+        let mut unmatched_spans: Vec<(Timestamp, Timestamp)> = Vec::new();
+        for (i, &ts) in stream.iter().enumerate() {
+            if matched[i] {
+                continue;
+            }
+            match unmatched_spans.last_mut() {
+                Some(span) if i > 0 && !matched[i - 1] => span.1 = ts,
+                _ => unmatched_spans.push((ts, ts)),
+            }
+        }
+
+        Self {
+            total: stream.len(),
+            matched: pairs.len(),
+            dt: ErrorStats::new(&dt),
+            unmatched_spans,
+        }
+    }
+
+    /// Return the fraction of matched timestamps.
+    pub fn match_rate(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.matched as f64 / self.total as f64
+        }
+    }
+}
+
+impl EuRoC {
+    /// Return the timestamps of `sensor` in `data.csv` order, without decoding images.
+    pub fn timestamps(&self, sensor: Sensor) -> Result<Vec<Timestamp>> {
+        fn collect<I, T>(records: I) -> Result<Vec<Timestamp>>
+        where
+            I: Iterator<Item = Result<T>>,
+            T: Timestamped,
+        {
+            records.map(|r| r.map(|r| r.timestamp())).collect()
+        }
+
+        match sensor {
+            Sensor::Cam0 => collect(self.left_camera()?.entries()?),
+            Sensor::Cam1 => collect(self.right_camera()?.entries()?),
+            Sensor::Imu0 => collect(self.imu()?.records()?),
+            Sensor::Leica0 => collect(self.position()?.records()?),
+            Sensor::Vicon0 => collect(self.vicon()?.records()?),
+            Sensor::GroundTruth => collect(self.ground_truth()?.records()?),
+        }
+    }
+
+    /// Report how well the timestamps of `sensor` match those of `other`.
+    pub fn association_report(
+        &self,
+        sensor: Sensor,
+        other: Sensor,
+        tolerance: Duration,
+    ) -> Result<AssociationReport> {
+        Ok(AssociationReport::new(
+            &self.timestamps(sensor)?,
+            &self.timestamps(other)?,
+            tolerance,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unmatched_spans() {
+        let ts = |v: &[u64]| -> Vec<Timestamp> { v.iter().map(|&v| v.into()).collect() };
+        let report = AssociationReport::new(
+            &ts(&[0, 10, 20, 30, 40, 50]),
+            &ts(&[1, 29, 52]),
+            Duration::from_nsecs(2),
+        );
+
+        assert_eq!(report.total, 6);
+        assert_eq!(report.matched, 3);
+        assert_eq!(report.match_rate(), 0.5);
+        assert_eq!(
+            report.unmatched_spans,
+            vec![(10.into(), 20.into()), (40.into(), 40.into())]
+        );
+        assert_eq!(report.dt.unwrap().max, 2e-9);
+    }
+
+    #[test]
+    fn stereo_in_sync() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let report = data.association_report(Sensor::Cam0, Sensor::Cam1, Duration::ZERO)?;
+
+        assert_eq!(report.matched, 5);
+        assert_eq!(report.match_rate(), 1.0);
+        assert!(report.unmatched_spans.is_empty());
+        assert_eq!(report.dt.unwrap().max, 0.0);
+
+        Ok(())
+    }
+}
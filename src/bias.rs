@@ -0,0 +1,133 @@
+use nalgebra as na;
+
+use crate::{
+    interpolation::bracket, GroundTruthData, GroundTruthRecord, ImuRecord, Result, Timestamp,
+};
+
+/// Subtracts time-interpolated ground-truth biases from raw IMU samples.
+#[derive(Debug, Clone)]
+pub struct BiasCorrector {
+    timestamps: Vec<Timestamp>,
+    /// (gyro bias, accel bias)
+    biases: Vec<(na::Vector3<f64>, na::Vector3<f64>)>,
+}
+
+impl BiasCorrector {
+    /// `ground_truth` must be sorted by timestamp.
+    pub fn new(ground_truth: &[GroundTruthRecord]) -> Self {
+        Self {
+            timestamps: ground_truth.iter().map(|r| r.timestamp).collect(),
+            biases: ground_truth
+                .iter()
+                .map(|r| (r.gyro_bias, r.accel_bias))
+                .collect(),
+        }
+    }
+
+    /// Return linearly interpolated (gyro bias, accel bias), or `None` outside the ground truth.
+    pub fn biases_at(&self, ts: Timestamp) -> Option<(na::Vector3<f64>, na::Vector3<f64>)> {
+        let (i, j, alpha) = bracket(&self.timestamps, ts)?;
+        let (gyro_i, accel_i) = &self.biases[i];
+        let (gyro_j, accel_j) = &self.biases[j];
+
+        Some((gyro_i.lerp(gyro_j, alpha), accel_i.lerp(accel_j, alpha)))
+    }
+
+    /// Wrap an IMU record iterator, subtracting the biases from every sample.
+    ///
+    /// Samples outside the time range of the ground truth are skipped.
+    pub fn apply<I>(&self, records: I) -> BiasCorrectedIterator<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = Result<ImuRecord>>,
+    {
+        BiasCorrectedIterator {
+            inner: records.into_iter(),
+            corrector: self,
+        }
+    }
+}
+
+impl GroundTruthData {
+    /// Build a [`BiasCorrector`] from the estimated biases.
+    pub fn bias_corrector(&self) -> Result<BiasCorrector> {
+        let records = self.records()?.collect::<Result<Vec<_>>>()?;
+        Ok(BiasCorrector::new(&records))
+    }
+}
+
+pub struct BiasCorrectedIterator<'a, I> {
+    inner: I,
+    corrector: &'a BiasCorrector,
+}
+
+impl<'a, I> Iterator for BiasCorrectedIterator<'a, I>
+where
+    I: Iterator<Item = Result<ImuRecord>>,
+{
+    type Item = Result<ImuRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut record = match self.inner.next()? {
+                Ok(record) => record,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if let Some((gyro_bias, accel_bias)) = self.corrector.biases_at(record.timestamp) {
+                record.gyro -= gyro_bias;
+                record.accel -= accel_bias;
+                return Some(Ok(record));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EuRoC;
+
+    fn ground_truth(ts: Timestamp, gyro: f64, accel: f64) -> GroundTruthRecord {
+        GroundTruthRecord {
+            gyro_bias: na::Vector3::repeat(gyro),
+            accel_bias: na::Vector3::repeat(accel),
+            ..GroundTruthRecord::from_pose(ts, na::Isometry3::identity())
+        }
+    }
+
+    #[test]
+    fn apply() -> Result<()> {
+        let imu = EuRoC::new("test_data")?.imu()?;
+        let raw = imu.records()?.collect::<Result<Vec<_>>>()?;
+
+        let corrector = BiasCorrector::new(&[
+            ground_truth(raw[1].timestamp, 0.0, 1.0),
+            ground_truth(raw[3].timestamp, 1.0, 3.0),
+        ]);
+        let corrected = corrector
+            .apply(imu.records()?)
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(corrected.len(), 3);
+        assert_eq!(corrected[0].timestamp, raw[1].timestamp);
+        assert_eq!(corrected[0].accel, raw[1].accel - na::Vector3::repeat(1.0));
+
+        let (gyro_bias, accel_bias) = corrector.biases_at(raw[2].timestamp).unwrap();
+        assert!((gyro_bias.x - 0.5).abs() < 1e-3);
+        assert!((accel_bias.x - 2.0).abs() < 1e-3);
+        assert_eq!(corrected[1].gyro, raw[2].gyro - gyro_bias);
+
+        Ok(())
+    }
+
+    #[test]
+    fn bias_corrector() -> Result<()> {
+        let corrector = EuRoC::new("test_data")?.ground_truth()?.bias_corrector()?;
+        let (gyro_bias, accel_bias) = corrector.biases_at(1403636580848555520.into()).unwrap();
+
+        assert_eq!(gyro_bias, na::Vector3::new(-0.003172, 0.021267, 0.078502));
+        assert_eq!(accel_bias, na::Vector3::new(-0.025266, 0.136696, 0.075593));
+
+        Ok(())
+    }
+}
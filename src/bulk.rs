@@ -0,0 +1,293 @@
+use std::str::FromStr;
+
+use crate::{
+    CameraRecords, EurocError, GroundTruthData, GroundTruthRecord, ImageEntry, ImageRecord,
+    ImuData, ImuRecord, PositionData, PositionRecord, Result, ViconData, ViconRecord,
+};
+
+/// How record iterators handle records that cannot be parsed or decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Fail on the first bad record.
+    Strict,
+    /// Skip records with malformed rows or images, reporting them; other errors still fail.
+    SkipWithWarning,
+}
+
+impl Default for ErrorPolicy {
+    fn default() -> Self {
+        Self::Strict
+    }
+}
+
+impl ErrorPolicy {
+    /// Return the name used in config files, e.g. `"skip_with_warning"`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Strict => "strict",
+            Self::SkipWithWarning => "skip_with_warning",
+        }
+    }
+}
+
+impl FromStr for ErrorPolicy {
+    type Err = EurocError;
+
+    /// Parse a name returned by [`ErrorPolicy::as_str`].
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "strict" => Ok(Self::Strict),
+            "skip_with_warning" => Ok(Self::SkipWithWarning),
+            _ => Err(EurocError::InvalidInput(format!(
+                "unknown error policy `{}`, expected strict or skip_with_warning",
+                name
+            ))),
+        }
+    }
+}
+
+/// Iterator returned by [`with_policy`].
+pub struct WithPolicy<I, F> {
+    records: I,
+    policy: ErrorPolicy,
+    on_skip: F,
+}
+
+/// Apply `policy` to any record iterator, passing every skipped error to `on_skip`.
+///
+/// Only [`EurocError::CsvParse`] and [`EurocError::Image`] are skipped, as
+/// they affect a single record; e.g. I/O errors are yielded regardless.
+pub fn with_policy<I, T, F>(
+    records: I,
+    policy: ErrorPolicy,
+    on_skip: F,
+) -> WithPolicy<I::IntoIter, F>
+where
+    I: IntoIterator<Item = Result<T>>,
+    F: FnMut(EurocError),
+{
+    WithPolicy {
+        records: records.into_iter(),
+        policy,
+        on_skip,
+    }
+}
+
+impl<I, T, F> Iterator for WithPolicy<I, F>
+where
+    I: Iterator<Item = Result<T>>,
+    F: FnMut(EurocError),
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.records.next()? {
+                Err(e @ EurocError::CsvParse { .. }) | Err(e @ EurocError::Image(_))
+                    if self.policy == ErrorPolicy::SkipWithWarning =>
+                {
+                    (self.on_skip)(e)
+                }
+                record => return Some(record),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.policy {
+            ErrorPolicy::Strict => self.records.size_hint(),
+            ErrorPolicy::SkipWithWarning => (0, self.records.size_hint().1),
+        }
+    }
+}
+
+/// Collect `records`; skipped errors are dropped, use [`with_policy`] to report them.
+fn load_all<I, T>(records: I, policy: ErrorPolicy) -> Result<Vec<T>>
+where
+    I: Iterator<Item = Result<T>>,
+{
+    with_policy(records, policy, drop).collect()
+}
+
+impl CameraRecords {
+    /// Load and decode all images.
+    ///
+    /// Bad records are handled by the [`Config::on_error`](crate::Config::on_error)
+    /// of the dataset, unless overridden with [`CameraRecords::with_error_policy`].
+    pub fn load_all(&self) -> Result<Vec<ImageRecord>> {
+        load_all(self.records()?, self.error_policy())
+    }
+
+    /// Load all image paths; images are decoded on demand with [`ImageEntry::load`].
+    pub fn load_all_entries(&self) -> Result<Vec<ImageEntry>> {
+        load_all(self.entries()?, self.error_policy())
+    }
+}
+
+impl ImuData {
+    /// Load all records in `data.csv` order, like [`CameraRecords::load_all`].
+    pub fn load_all(&self) -> Result<Vec<ImuRecord>> {
+        load_all(self.records()?, self.error_policy())
+    }
+}
+
+impl PositionData {
+    /// Load all records in `data.csv` order, like [`CameraRecords::load_all`].
+    pub fn load_all(&self) -> Result<Vec<PositionRecord>> {
+        load_all(self.records()?, self.error_policy())
+    }
+}
+
+impl ViconData {
+    /// Load all records in `data.csv` order, like [`CameraRecords::load_all`].
+    pub fn load_all(&self) -> Result<Vec<ViconRecord>> {
+        load_all(self.records()?, self.error_policy())
+    }
+}
+
+impl GroundTruthData {
+    /// Load all records in `data.csv` order, like [`CameraRecords::load_all`].
+    pub fn load_all(&self) -> Result<Vec<GroundTruthRecord>> {
+        load_all(self.records()?, self.error_policy())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use crate::EuRoC;
+
+    #[test]
+    fn load_all() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        assert_eq!(data.imu()?.load_all()?.len(), 5);
+        assert_eq!(data.ground_truth()?.load_all()?.len(), 5);
+
+        let entries = data.left_camera()?.load_all_entries()?;
+        assert_eq!(entries[0].timestamp, 1403636579763555584.into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn io_errors_are_not_skipped() -> Result<()> {
+        use std::{
+            io,
+            path::{Path, PathBuf},
+            sync::Arc,
+        };
+
+        use crate::{DataSource, MemorySource, ReadSeek};
+
+        #[derive(Debug)]
+        struct Unreadable(MemorySource, PathBuf);
+
+        impl DataSource for Unreadable {
+            fn open(&self, path: &Path) -> Result<Box<dyn ReadSeek>> {
+                if path == self.1 {
+                    Err(io::Error::from(io::ErrorKind::PermissionDenied).into())
+                } else {
+                    self.0.open(path)
+                }
+            }
+
+            fn is_file(&self, path: &Path) -> bool {
+                self.0.is_file(path)
+            }
+
+            fn is_dir(&self, path: &Path) -> bool {
+                self.0.is_dir(path)
+            }
+
+            fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+                self.0.read_dir(path)
+            }
+        }
+
+        let unreadable = PathBuf::from("cam0/data/1403636579813555456.png");
+        let source = Unreadable(MemorySource::from_dir("test_data")?, unreadable);
+        let data = EuRoC::from_source(Arc::new(source), "")?;
+        assert!(matches!(
+            data.left_camera()?
+                .with_error_policy(ErrorPolicy::SkipWithWarning)
+                .load_all(),
+            Err(EurocError::Io(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn skip_with_warning() -> Result<()> {
+        let root = TempDir::new("load_all")?;
+        fs::copy("test_data/leica0/sensor.yaml", root.join("sensor.yaml"))?;
+        fs::write(
+            root.join("data.csv"),
+            "#timestamp [ns],p_RS_R_x [m],p_RS_R_y [m],p_RS_R_z [m]\n\
+             1,0.0,0.0,0.0\n\
+             2,0.0,x,0.0\n\
+             3,1.0,0.0,0.0\n",
+        )?;
+
+        let data = PositionData::new(root.path().to_owned())?;
+        assert!(matches!(
+            data.load_all(),
+            Err(EurocError::CsvParse { line: 3, .. })
+        ));
+        let records = data
+            .clone()
+            .with_error_policy(ErrorPolicy::SkipWithWarning)
+            .load_all()?;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].timestamp, 3.into());
+
+        let mut skipped = Vec::new();
+        let count = with_policy(data.records()?, ErrorPolicy::SkipWithWarning, |e| {
+            skipped.push(e)
+        })
+        .count();
+        assert_eq!(count, 2);
+        assert!(matches!(
+            skipped[..],
+            [EurocError::CsvParse { line: 3, .. }]
+        ));
+        assert!(
+            with_policy(data.records()?, ErrorPolicy::Strict, |_| unreachable!())
+                .any(|r| r.is_err())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn config_on_error() -> Result<()> {
+        use std::{path::Path, sync::Arc};
+
+        use crate::{Config, DataSource, MemorySource};
+
+        let mut source = MemorySource::from_dir("test_data")?;
+        let csv = source.read_to_string(Path::new("imu0/data.csv"))?;
+        source.insert("imu0/data.csv", format!("{}1,x\n", csv).as_bytes());
+        let source: Arc<dyn DataSource> = Arc::new(source);
+
+        let data = EuRoC::from_source(Arc::clone(&source), "")?;
+        assert!(data.imu()?.load_all().is_err());
+
+        let config = Config {
+            on_error: ErrorPolicy::SkipWithWarning,
+            ..Config::default()
+        };
+        let data = EuRoC::from_source(source, "")?.with_config(config)?;
+        assert_eq!(data.imu()?.load_all()?.len(), 5);
+        assert!(data
+            .imu()?
+            .with_error_policy(ErrorPolicy::Strict)
+            .load_all()
+            .is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{CameraConfig, EuRoC, Result, Sensor};
+
+/// Calibration field of a sequence differing from the first sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationDifference {
+    /// index into the compared sequences
+    pub sequence: usize,
+    pub sensor: Sensor,
+    pub field: &'static str,
+    /// largest absolute element-wise difference
+    pub max_abs_diff: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationReport {
+    pub differences: Vec<CalibrationDifference>,
+    /// per camera, groups of sequence indices whose resolution, intrinsics and
+    /// distortion agree, i.e. which can share undistortion maps
+    pub shared_groups: Vec<(Sensor, Vec<Vec<usize>>)>,
+}
+
+impl CalibrationReport {
+    pub fn is_consistent(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+/// Compare camera and IMU calibration of `sequences` against the first one.
+///
+/// Differences up to `tolerance` are ignored.
+pub fn compare_calibrations(sequences: &[EuRoC], tolerance: f64) -> Result<CalibrationReport> {
+    let mut differences = Vec::new();
+    let mut shared_groups = Vec::new();
+
+    for &sensor in &[Sensor::Cam0, Sensor::Cam1] {
+        let configs = sequences
+            .iter()
+            .map(|s| {
+                let camera = match sensor {
+                    Sensor::Cam0 => s.left_camera()?,
+                    _ => s.right_camera()?,
+                };
+                Ok(camera.config().clone())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(reference) = configs.first() {
+            for (sequence, config) in configs.iter().enumerate().skip(1) {
+                for (field, diff) in camera_differences(reference, config) {
+                    if diff > tolerance {
+                        differences.push(CalibrationDifference {
+                            sequence,
+                            sensor,
+                            field,
+                            max_abs_diff: diff,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for (i, config) in configs.iter().enumerate() {
+            let shares = |group: &Vec<usize>| {
+                camera_differences(&configs[group[0]], config)
+                    .iter()
+                    .filter(|(field, _)| *field != "T_BS")
+                    .all(|(_, diff)| *diff <= tolerance)
+            };
+            match groups.iter_mut().find(|g| shares(g)) {
+                Some(group) => group.push(i),
+                None => groups.push(vec![i]),
+            }
+        }
+        shared_groups.push((sensor, groups));
+    }
+
+    let imu = sequences
+        .iter()
+        .map(|s| s.imu()?.extrinsics())
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(reference) = imu.first() {
+        for (sequence, t_bs) in imu.iter().enumerate().skip(1) {
+            let diff = max_abs_diff(reference.as_slice(), t_bs.as_slice());
+            if diff > tolerance {
+                differences.push(CalibrationDifference {
+                    sequence,
+                    sensor: Sensor::Imu0,
+                    field: "T_BS",
+                    max_abs_diff: diff,
+                });
+            }
+        }
+    }
+
+    Ok(CalibrationReport {
+        differences,
+        shared_groups,
+    })
+}
+
+fn camera_differences(a: &CameraConfig, b: &CameraConfig) -> Vec<(&'static str, f64)> {
+    let (a_fu, a_fv, a_cu, a_cv) = a.intrinsics;
+    let (b_fu, b_fv, b_cu, b_cv) = b.intrinsics;
+    let resolution = |c: &CameraConfig| [f64::from(c.resolution.0), f64::from(c.resolution.1)];
+
+    vec![
+        ("resolution", max_abs_diff(&resolution(a), &resolution(b))),
+        (
+            "intrinsics",
+            max_abs_diff(&[a_fu, a_fv, a_cu, a_cv], &[b_fu, b_fv, b_cu, b_cv]),
+        ),
+        (
+            "distortion_coefficients",
+            if a.distortion_model == b.distortion_model {
+                max_abs_diff(&a.distortion_coefficients, &b.distortion_coefficients)
+            } else {
+                f64::INFINITY
+            },
+        ),
+        ("T_BS", max_abs_diff(a.t_bs.as_slice(), b.t_bs.as_slice())),
+    ]
+}
+
+fn max_abs_diff(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() != b.len() {
+        return f64::INFINITY;
+    }
+
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compare_calibrations_same() -> Result<()> {
+        let sequences = vec![EuRoC::new("test_data")?, EuRoC::new("test_data")?];
+        let report = compare_calibrations(&sequences, 1e-9)?;
+
+        assert!(report.is_consistent());
+        assert_eq!(
+            report.shared_groups,
+            vec![
+                (Sensor::Cam0, vec![vec![0, 1]]),
+                (Sensor::Cam1, vec![vec![0, 1]])
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn camera_differences_intrinsics() -> Result<()> {
+        let a = EuRoC::new("test_data")?.left_camera()?.config().clone();
+        let mut b = a.clone();
+        b.intrinsics.0 += 0.5;
+
+        let diffs = camera_differences(&a, &b);
+        assert_eq!(diffs[0], ("resolution", 0.0));
+        assert!((diffs[1].1 - 0.5).abs() < 1e-9);
+        assert_eq!(diffs[3], ("T_BS", 0.0));
+
+        Ok(())
+    }
+}
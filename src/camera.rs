@@ -1,111 +1,396 @@
-use std::{convert::TryInto, fs::File, path::PathBuf};
+use std::{
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use anyhow::{ensure, Result};
-use image::DynamicImage;
+use image::{codecs::png::PngDecoder, ColorType, DynamicImage, ImageDecoder, ImageFormat};
 use nalgebra as na;
 use yaml_rust::Yaml;
 
-use crate::{load_yaml, Timestamp};
+use crate::{
+    csv_row::{Checkpoint, IndexCache, IndexedRows, Row},
+    yaml::{rigid_transform, SensorYaml},
+    ConfigWarning, DataSource, Duration, ErrorPolicy, EurocError, FileSystem, RateDiagnostics,
+    ReadSeek, Result, Timestamp, Timestamped,
+};
 
 const DATA: &str = "data";
 const DATA_CSV: &str = "data.csv";
 const SENSOR_YAML: &str = "sensor.yaml";
+const CAMERA_FIELDS: [&str; 6] = [
+    "rate_hz",
+    "resolution",
+    "camera_model",
+    "intrinsics",
+    "distortion_model",
+    "distortion_coefficients",
+];
+
+/// Lens distortion model named by the `distortion_model` key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistortionModel {
+    /// `radial-tangential`: (k1, k2, p1, p2)
+    RadialTangential,
+    /// `equidistant` (Kannala-Brandt, as in TUM-VI): (k1, k2, k3, k4)
+    Equidistant,
+    /// no distortion, or no `distortion_model` key
+    None,
+}
+
+impl DistortionModel {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "radial-tangential" | "radtan" => Some(Self::RadialTangential),
+            "equidistant" | "equi" => Some(Self::Equidistant),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+/// Camera calibration parsed from `sensor.yaml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraConfig {
+    /// image size (width, height)
+    pub resolution: (u32, u32),
+    /// nominal frame rate (Hz)
+    pub rate_hz: f64,
+    pub camera_model: String,
+    /// (fu, fv, cu, cv)
+    pub intrinsics: (f64, f64, f64, f64),
+    pub distortion_model: DistortionModel,
+    /// coefficients in the order of `distortion_model`
+    pub distortion_coefficients: Vec<f64>,
+    /// extrinsics wrt. the body-frame
+    pub t_bs: na::Matrix4<f64>,
+    /// fields of `sensor.yaml` this crate does not use
+    pub warnings: Vec<ConfigWarning>,
+}
+
+impl CameraConfig {
+    /// Parse a camera `sensor.yaml`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::load_from(&FileSystem, path)
+    }
+
+    /// Parse a camera `sensor.yaml` of `source`.
+    pub fn load_from<P: AsRef<Path>>(source: &dyn DataSource, path: P) -> Result<Self> {
+        let yaml = SensorYaml::load(source, path.as_ref().to_owned())?;
+        let resolution = yaml.f64_array("resolution", 2)?;
+        let intrinsics = yaml.f64_array("intrinsics", 4)?;
+        let distortion_model = match yaml.field("distortion_model") {
+            Yaml::BadValue => DistortionModel::None,
+            _ => DistortionModel::parse(&yaml.str("distortion_model")?).ok_or(
+                EurocError::YamlField {
+                    file: path.as_ref().to_owned(),
+                    key: "distortion_model".to_owned(),
+                },
+            )?,
+        };
+        let distortion_coefficients = match distortion_model {
+            DistortionModel::None => yaml.f64_vec("distortion_coefficients").unwrap_or_default(),
+            _ => yaml.f64_vec("distortion_coefficients")?,
+        };
+
+        Ok(Self {
+            resolution: (resolution[0] as u32, resolution[1] as u32),
+            rate_hz: yaml.f64("rate_hz")?,
+            camera_model: yaml.str("camera_model")?,
+            intrinsics: (intrinsics[0], intrinsics[1], intrinsics[2], intrinsics[3]),
+            distortion_model,
+            distortion_coefficients,
+            t_bs: yaml.matrix4("T_BS")?,
+            warnings: yaml.unknown_fields(&CAMERA_FIELDS),
+        })
+    }
+
+    /// Return camera matrix
+    pub fn camera_matrix(&self) -> na::Matrix3<f64> {
+        let (fu, fv, cu, cv) = self.intrinsics;
+
+        na::Matrix3::from_rows(&[
+            na::RowVector3::new(fu, 0., cu),
+            na::RowVector3::new(0., fv, cv),
+            na::RowVector3::new(0.0, 0.0, 1.0),
+        ])
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct CameraRecords {
+    source: Arc<dyn DataSource>,
     path: PathBuf,
+    config: CameraConfig,
+    index: IndexCache,
+    on_error: ErrorPolicy,
 }
 
 impl CameraRecords {
     pub fn new(path: PathBuf) -> Result<Self> {
-        ensure!(path.is_dir());
-        ensure!(path.join(DATA).is_dir());
-        ensure!(path.join(DATA_CSV).is_file());
-        ensure!(path.join(SENSOR_YAML).is_file());
-
-        Ok(Self { path })
+        Self::with_source(FileSystem::shared(), path)
     }
 
-    #[inline]
-    fn read_sensor_yaml(&self) -> Result<Vec<Yaml>> {
-        load_yaml(self.path.join(SENSOR_YAML))
+    /// Open the folder `path` of `source`.
+    pub fn with_source(source: Arc<dyn DataSource>, path: PathBuf) -> Result<Self> {
+        source.require_dir(&path)?;
+        source.require_dir(&path.join(DATA))?;
+        source.require_file(&path.join(DATA_CSV))?;
+        source.require_file(&path.join(SENSOR_YAML))?;
+
+        let config = CameraConfig::load_from(&*source, path.join(SENSOR_YAML))?;
+
+        Ok(Self {
+            on_error: ErrorPolicy::default(),
+            index: IndexCache::new(Arc::clone(&source)),
+            source,
+            path,
+            config,
+        })
     }
 
-    /// Return image size (width, height)
-    pub fn image_size(&self) -> Result<(u32, u32)> {
-        let data: Vec<_> = self.read_sensor_yaml()?[0]["resolution"]
-            .as_vec()
-            .unwrap()
-            .iter()
-            .map(|v| v.as_i64().unwrap().try_into().unwrap())
-            .collect();
+    /// Handle bad records in `load_all` with `policy`.
+    ///
+    /// The accessors of [`EuRoC`](crate::EuRoC) start from
+    /// [`Config::on_error`](crate::Config::on_error), [`Self::new`] from
+    /// [`ErrorPolicy::Strict`].
+    pub const fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.on_error = policy;
+        self
+    }
 
-        assert!(data.len() == 2);
+    /// Return the policy `load_all` handles bad records with.
+    pub const fn error_policy(&self) -> ErrorPolicy {
+        self.on_error
+    }
 
-        Ok((data[0], data[1]))
+    /// Return calibration parsed from `sensor.yaml`
+    pub const fn config(&self) -> &CameraConfig {
+        &self.config
     }
 
-    /// Return intrinsics (fu, fv, cu, cv)
-    pub fn intrinsics(&self) -> Result<(f64, f64, f64, f64)> {
-        let data: Vec<_> = self.read_sensor_yaml()?[0]["intrinsics"]
-            .as_vec()
-            .unwrap()
-            .iter()
-            .map(|v| v.as_f64().unwrap())
-            .collect();
+    /// Return image size (width, height)
+    pub const fn image_size(&self) -> Result<(u32, u32)> {
+        Ok(self.config.resolution)
+    }
 
-        assert!(data.len() == 4);
+    /// Return nominal frame rate (Hz)
+    pub const fn rate_hz(&self) -> Result<f64> {
+        Ok(self.config.rate_hz)
+    }
 
-        Ok((data[0], data[1], data[2], data[3]))
+    /// Return intrinsics (fu, fv, cu, cv)
+    pub const fn intrinsics(&self) -> Result<(f64, f64, f64, f64)> {
+        Ok(self.config.intrinsics)
     }
 
     /// Return camera matrix
     pub fn camera_matrix(&self) -> Result<na::Matrix3<f64>> {
-        let (fu, fv, cu, cv) = self.intrinsics()?;
-
-        Ok(na::Matrix3::from_rows(&[
-            na::RowVector3::new(fu, 0., cu),
-            na::RowVector3::new(0., fv, cv),
-            na::RowVector3::new(0.0, 0.0, 1.0),
-        ]))
+        Ok(self.config.camera_matrix())
     }
 
     /// Return Distortion coefficients
+    ///
+    /// Fails unless there are exactly 4 coefficients; see [`CameraConfig::distortion_coefficients`]
+    /// for other models.
     pub fn distrotion_coeff(&self) -> Result<na::Vector4<f64>> {
-        let data: Vec<_> = self.read_sensor_yaml()?[0]["distortion_coefficients"]
-            .as_vec()
-            .unwrap()
-            .iter()
-            .map(|v| v.as_f64().unwrap())
-            .collect();
+        let coefficients = &self.config.distortion_coefficients;
+        if coefficients.len() == 4 {
+            Ok(na::Vector4::from_column_slice(coefficients))
+        } else {
+            Err(EurocError::InvalidInput(format!(
+                "expected 4 distortion coefficients, found {}",
+                coefficients.len()
+            )))
+        }
+    }
 
-        assert!(data.len() == 4);
+    /// Return extrinsics wrt. the body-frame.
+    pub const fn extrinsics(&self) -> Result<na::Matrix4<f64>> {
+        Ok(self.config.t_bs)
+    }
 
-        Ok(na::Vector4::from_column_slice(&data))
+    /// Return extrinsics wrt. the body-frame as a rigid body transform.
+    ///
+    /// Fails if `T_BS` is not orthonormal. Use [`na::Isometry3::inverse`] for `T_SB`.
+    pub fn extrinsics_isometry(&self) -> Result<na::Isometry3<f64>> {
+        rigid_transform(&self.config.t_bs).ok_or_else(|| EurocError::YamlField {
+            file: self.path.join(SENSOR_YAML),
+            key: "T_BS".to_owned(),
+        })
     }
 
-    /// Return extrinsics wrt. the body-frame.
-    pub fn extrinsics(&self) -> Result<na::Matrix4<f64>> {
-        let data: Vec<_> = self.read_sensor_yaml()?[0]["T_BS"]["data"]
-            .as_vec()
-            .unwrap()
-            .iter()
-            .map(|v| v.as_f64().unwrap())
-            .collect();
+    /// Compare actual inter-frame intervals against the nominal `rate_hz`.
+    pub fn rate_diagnostics(&self) -> Result<RateDiagnostics> {
+        let timestamps = self.timestamps()?;
+        RateDiagnostics::new(self.rate_hz()?, &timestamps).ok_or_else(|| {
+            EurocError::InvalidInput(
+                "not enough frames or invalid rate_hz to estimate rate".to_owned(),
+            )
+        })
+    }
 
-        assert!(data.len() == 16);
+    fn timestamps(&self) -> Result<Vec<Timestamp>> {
+        self.entries()?.map(|e| e.map(|e| e.timestamp)).collect()
+    }
 
-        Ok(na::Matrix4::from_row_slice(&data))
+    /// Return the number of frames, counting the rows of `data.csv` on the first call.
+    pub fn len(&self) -> Result<usize> {
+        self.index.row_count(&self.path.join(DATA_CSV))
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Iterate over image paths in `data.csv` order without decoding them.
+    pub fn entries(&self) -> Result<ImageEntryIterator> {
+        Ok(ImageEntryIterator {
+            source: Arc::clone(&self.source),
+            path: self.path.join(DATA),
+            rows: self.index.rows(self.path.join(DATA_CSV))?,
+        })
     }
 
+    /// Iterate over records in `data.csv` order, independent of directory listing order.
     pub fn records(&self) -> Result<ImageIterator> {
-        let f = File::open(self.path.join(DATA_CSV))?;
+        Ok(ImageIterator {
+            entries: self.entries()?,
+        })
+    }
+
+    /// Iterate over image paths with `start <= timestamp < end`.
+    ///
+    /// The first call indexes `data.csv`; later calls seek directly to `start`.
+    pub fn entries_between(&self, start: Timestamp, end: Timestamp) -> Result<ImageEntryIterator> {
+        Ok(ImageEntryIterator {
+            source: Arc::clone(&self.source),
+            path: self.path.join(DATA),
+            rows: self
+                .index
+                .rows_between(self.path.join(DATA_CSV), start, end)?,
+        })
+    }
 
+    /// Iterate over records with `start <= timestamp < end`.
+    pub fn records_between(&self, start: Timestamp, end: Timestamp) -> Result<ImageIterator> {
         Ok(ImageIterator {
+            entries: self.entries_between(start, end)?,
+        })
+    }
+
+    /// Decode the image recorded exactly at `timestamp`, if any.
+    pub fn get_by_timestamp(&self, timestamp: Timestamp) -> Result<Option<ImageRecord>> {
+        self.entries_between(timestamp, timestamp + Duration::from_nsecs(1))?
+            .next()
+            .map(|entry| entry?.load())
+            .transpose()
+    }
+
+    /// Continue iterating over image paths where `checkpoint` was taken, without indexing `data.csv`.
+    pub fn resume_entries(&self, checkpoint: &Checkpoint) -> Result<ImageEntryIterator> {
+        Ok(ImageEntryIterator {
+            source: Arc::clone(&self.source),
             path: self.path.join(DATA),
-            reader: csv::Reader::from_reader(f).into_records(),
+            rows: self.index.resume(self.path.join(DATA_CSV), checkpoint)?,
+        })
+    }
+
+    /// Continue iterating over records where `checkpoint` was taken.
+    pub fn resume(&self, checkpoint: &Checkpoint) -> Result<ImageIterator> {
+        Ok(ImageIterator {
+            entries: self.resume_entries(checkpoint)?,
+        })
+    }
+}
+
+/// Image that has not been decoded yet.
+#[derive(Debug, Clone)]
+pub struct ImageEntry {
+    pub timestamp: Timestamp,
+    pub path: PathBuf,
+    source: Arc<dyn DataSource>,
+}
+
+impl ImageEntry {
+    /// Refer to the image file at `path` on disk.
+    pub fn new(timestamp: Timestamp, path: PathBuf) -> Self {
+        Self {
+            timestamp,
+            path,
+            source: FileSystem::shared(),
+        }
+    }
+
+    /// Open the file, reporting a missing file as [`EurocError::Image`] like [`image::open`].
+    ///
+    /// Other I/O errors stay [`EurocError::Io`], so that they are not skipped
+    /// as a single bad image.
+    pub(crate) fn open(&self) -> Result<BufReader<Box<dyn ReadSeek>>> {
+        match self.source.open(&self.path) {
+            Ok(file) => Ok(BufReader::new(file)),
+            Err(EurocError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {
+                Err(image::ImageError::IoError(e).into())
+            }
+            Err(EurocError::MissingFile(path)) => Err(image::ImageError::IoError(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("missing `{}`", path.display()),
+            ))
+            .into()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Decode the image.
+    pub fn load(&self) -> Result<ImageRecord> {
+        let format = ImageFormat::from_path(&self.path)?;
+        Ok(ImageRecord {
+            timestamp: self.timestamp,
+            image: image::io::Reader::with_format(self.open()?, format).decode()?,
         })
     }
+
+    /// Decode an 8-bit grayscale PNG into `buf`, which must hold exactly width × height bytes.
+    ///
+    /// Unlike [`ImageEntry::load`] this does not allocate the image, so one
+    /// buffer can be reused over a whole sequence. Returns the dimensions.
+    pub fn decode_into(&self, buf: &mut [u8]) -> Result<(u32, u32)> {
+        let decoder = PngDecoder::new(self.open()?)?;
+        let dimensions = decoder.dimensions();
+        if decoder.color_type() != ColorType::L8 {
+            return Err(EurocError::InvalidInput(format!(
+                "{}: expected 8-bit grayscale, found {:?}",
+                self.path.display(),
+                decoder.color_type()
+            )));
+        }
+        if decoder.total_bytes() != buf.len() as u64 {
+            return Err(EurocError::InvalidInput(format!(
+                "{}: {}x{} image does not fit a buffer of {} bytes",
+                self.path.display(),
+                dimensions.0,
+                dimensions.1,
+                buf.len()
+            )));
+        }
+
+        decoder.read_image(buf)?;
+        Ok(dimensions)
+    }
+}
+
+impl PartialEq for ImageEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.timestamp == other.timestamp && self.path == other.path
+    }
+}
+
+impl Eq for ImageEntry {}
+
+impl Timestamped for ImageEntry {
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -114,30 +399,104 @@ pub struct ImageRecord {
     pub image: DynamicImage,
 }
 
-pub struct ImageIterator {
+impl Timestamped for ImageRecord {
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+pub struct ImageEntryIterator {
+    source: Arc<dyn DataSource>,
     path: PathBuf,
-    reader: csv::StringRecordsIntoIter<File>,
+    rows: IndexedRows,
+}
+
+impl ImageEntryIterator {
+    fn parse(&self, row: Result<Row>) -> Result<ImageEntry> {
+        let row = row?;
+        Ok(ImageEntry {
+            timestamp: row.timestamp()?,
+            path: self.path.join(row.get(1)?),
+            source: Arc::clone(&self.source),
+        })
+    }
+}
+
+impl ImageEntryIterator {
+    /// Return where the iteration stopped, for [`CameraRecords::resume_entries`].
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.rows.checkpoint()
+    }
+
+    /// Decode the next image into `buf` with [`ImageEntry::decode_into`], returning its timestamp.
+    pub fn next_into(&mut self, buf: &mut [u8]) -> Option<Result<Timestamp>> {
+        self.next().map(|entry| {
+            let entry = entry?;
+            entry.decode_into(buf)?;
+            Ok(entry.timestamp)
+        })
+    }
+}
+
+impl Iterator for ImageEntryIterator {
+    type Item = Result<ImageEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.rows.next()?;
+        Some(self.parse(row))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for ImageEntryIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let row = self.rows.next_back()?;
+        Some(self.parse(row))
+    }
+}
+
+impl ExactSizeIterator for ImageEntryIterator {}
+
+pub struct ImageIterator {
+    entries: ImageEntryIterator,
+}
+
+impl ImageIterator {
+    /// Return where the iteration stopped, for [`CameraRecords::resume`].
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.entries.checkpoint()
+    }
 }
 
 impl Iterator for ImageIterator {
     type Item = Result<ImageRecord>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.reader.next().map(|row| {
-            let row = row?;
-            Ok(ImageRecord {
-                timestamp: row[0].parse::<u64>()?.into(),
-                image: image::open(self.path.join(&row[1]))?,
-            })
-        })
+        self.entries.next().map(|entry| entry?.load())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for ImageIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back().map(|entry| entry?.load())
     }
 }
 
+impl ExactSizeIterator for ImageIterator {}
+
 #[cfg(test)]
 mod test {
     use image::GenericImageView;
 
     use super::*;
+    use crate::temp_dir::TempDir;
     use crate::EuRoC;
 
     #[test]
@@ -148,6 +507,120 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn next_into() -> Result<()> {
+        let data = EuRoC::new("test_data")?.left_camera()?;
+        let mut buf = vec![0; 752 * 480];
+
+        let mut entries = data.entries()?;
+        assert_eq!(
+            entries.next_into(&mut buf).unwrap()?,
+            1403636579763555584.into()
+        );
+        let decoded = data.records()?.next().unwrap()?.image.to_luma8();
+        assert_eq!(buf, decoded.into_raw());
+
+        while let Some(ts) = entries.next_into(&mut buf) {
+            ts?;
+        }
+        assert!(entries.next_into(&mut buf[1..]).is_none());
+        assert!(data.entries()?.next_into(&mut buf[1..]).unwrap().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn rate_hz() -> Result<()> {
+        let data = EuRoC::new("test_data")?.left_camera()?;
+        assert_eq!(data.rate_hz()?, 20.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rate_diagnostics() -> Result<()> {
+        let data = EuRoC::new("test_data")?.left_camera()?;
+        let diagnostics = data.rate_diagnostics()?;
+
+        assert_eq!(diagnostics.nominal_hz, 20.0);
+        assert_eq!(diagnostics.irregular, 0);
+        assert!(diagnostics.is_consistent(0.01));
+
+        Ok(())
+    }
+
+    #[test]
+    fn config() -> Result<()> {
+        let data = EuRoC::new("test_data")?.right_camera()?;
+        let config = data.config();
+
+        assert_eq!(config.resolution, (752, 480));
+        assert_eq!(config.rate_hz, 20.0);
+        assert_eq!(config.camera_model, "pinhole");
+        assert_eq!(config.intrinsics, (457.587, 456.134, 379.999, 255.238));
+        assert_eq!(config.distortion_model, DistortionModel::RadialTangential);
+        assert_eq!(
+            config.distortion_coefficients,
+            vec![-0.28368365, 0.07451284, -0.00010473, -3.55590700e-05]
+        );
+        assert_eq!(config.t_bs[(1, 3)], 0.0453689425024);
+        assert!(config.warnings.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn equidistant_config() -> Result<()> {
+        let tmp = TempDir::new("equidistant_sensor")?;
+        let path = tmp.join("equidistant_sensor.yaml");
+        let yaml = std::fs::read_to_string("test_data/cam0/sensor.yaml")?
+            .replace("radial-tangential", "equidistant")
+            .replace(
+                "distortion_coefficients: [-0.28340811, 0.07395907, 0.00019359, 1.76187114e-05]",
+                "distortion_coefficients: [0.0034, 0.0007, -0.0020, 0.0002]\ntimeshift_cam_imu: 0.01",
+            );
+        std::fs::write(&path, yaml)?;
+
+        let config = CameraConfig::load(&path)?;
+        assert_eq!(config.distortion_model, DistortionModel::Equidistant);
+        assert_eq!(
+            config.distortion_coefficients,
+            vec![0.0034, 0.0007, -0.0020, 0.0002]
+        );
+        assert!(crate::PinholeRadTan::from_config(&config).is_err());
+        assert_eq!(config.warnings.len(), 1);
+        assert_eq!(config.warnings[0].key, "timeshift_cam_imu");
+
+        Ok(())
+    }
+
+    #[test]
+    fn distortion_model() -> Result<()> {
+        let tmp = TempDir::new("distortion_model")?;
+        let path = tmp.join("sensor.yaml");
+        let yaml = std::fs::read_to_string("test_data/cam0/sensor.yaml")?;
+
+        let without = yaml.replace("distortion_model: radial-tangential", "");
+        std::fs::write(&path, without)?;
+        assert_eq!(
+            CameraConfig::load(&path)?.distortion_model,
+            DistortionModel::None
+        );
+
+        for model in ["distortion_model: [radtan]", "distortion_model: fisheye"].iter() {
+            std::fs::write(
+                &path,
+                yaml.replace("distortion_model: radial-tangential", model),
+            )?;
+            assert!(matches!(
+                CameraConfig::load(&path),
+                Err(EurocError::YamlField { key, .. }) if key == "distortion_model"
+            ));
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn intrinsics() -> Result<()> {
         let data = EuRoC::new("test_data")?.left_camera()?;
@@ -213,6 +686,17 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn extrinsics_isometry() -> Result<()> {
+        let data = EuRoC::new("test_data")?.left_camera()?;
+        let isometry = data.extrinsics_isometry()?;
+
+        assert!((isometry.to_homogeneous() - data.extrinsics()?).amax() < 1e-6);
+        assert!((isometry.inverse() * isometry).translation.vector.norm() < 1e-12);
+
+        Ok(())
+    }
+
     #[test]
     fn records() -> Result<()> {
         let data = EuRoC::new("test_data")?.left_camera()?;
@@ -225,4 +709,82 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn len() -> Result<()> {
+        let data = EuRoC::new("test_data")?.left_camera()?;
+        assert_eq!(data.len()?, 5);
+        assert!(!data.is_empty()?);
+
+        let mut records = data.records()?;
+        assert_eq!(records.len(), 5);
+        records.next();
+        assert_eq!(records.len(), 4);
+
+        let entries = data.entries_between(1403636579800000000.into(), u64::MAX.into())?;
+        assert_eq!(entries.len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn entries_between() -> Result<()> {
+        let data = EuRoC::new("test_data")?.left_camera()?;
+        let entries = data
+            .entries_between(1403636579800000000.into(), 1403636579900000000.into())?
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].timestamp, 1403636579813555456.into());
+        assert!(data
+            .records_between(0.into(), 1403636579800000000.into())?
+            .next()
+            .unwrap()
+            .is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn entries() -> Result<()> {
+        let data = EuRoC::new("test_data")?.left_camera()?;
+        let entry = data.entries()?.nth(2).unwrap()?;
+
+        assert_eq!(entry.timestamp, 1403636579863555584.into());
+        assert_eq!(
+            entry.path,
+            PathBuf::from("test_data/cam0/data/1403636579863555584.png")
+        );
+        assert_eq!(entry.load()?.image.dimensions(), (752, 480));
+
+        assert_eq!(data.entries()?.count(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn records_order() -> Result<()> {
+        let data = EuRoC::new("test_data")?.left_camera()?;
+        let csv = std::fs::read_to_string("test_data/cam0/data.csv")?;
+        let expected: Vec<Timestamp> = csv
+            .lines()
+            .skip(1)
+            .map(|line| {
+                line.split(',')
+                    .next()
+                    .unwrap()
+                    .parse::<u64>()
+                    .unwrap()
+                    .into()
+            })
+            .collect();
+        let actual = data
+            .records()?
+            .map(|r| r.map(|r| r.timestamp))
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,191 @@
+use image::DynamicImage;
+use nalgebra as na;
+
+use crate::{
+    fnv::{fnv1a, FNV_OFFSET},
+    CameraConfig, CameraRecords, DistortionModel, EurocError, RectifyMap, Result,
+};
+
+const UNDISTORT_ITERATIONS: usize = 20;
+
+/// Pinhole camera with radial-tangential distortion, as declared in EuRoC `sensor.yaml`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PinholeRadTan {
+    /// image size (width, height)
+    pub resolution: (u32, u32),
+    /// (fu, fv, cu, cv)
+    pub intrinsics: (f64, f64, f64, f64),
+    /// (k1, k2, p1, p2)
+    pub distortion: na::Vector4<f64>,
+}
+
+impl PinholeRadTan {
+    pub fn from_config(config: &CameraConfig) -> Result<Self> {
+        let distortion = match (
+            config.distortion_model,
+            config.distortion_coefficients.len(),
+        ) {
+            (DistortionModel::RadialTangential, 4) => {
+                na::Vector4::from_column_slice(&config.distortion_coefficients)
+            }
+            (DistortionModel::None, _) => na::Vector4::zeros(),
+            _ => {
+                return Err(EurocError::InvalidInput(format!(
+                    "unsupported {:?} distortion with {} coefficients",
+                    config.distortion_model,
+                    config.distortion_coefficients.len()
+                )))
+            }
+        };
+        if config.camera_model != "pinhole" {
+            return Err(EurocError::InvalidInput(format!(
+                "unsupported camera model `{}`",
+                config.camera_model
+            )));
+        }
+
+        Ok(Self {
+            resolution: config.resolution,
+            intrinsics: config.intrinsics,
+            distortion,
+        })
+    }
+
+    /// Apply distortion to normalized image coordinates.
+    pub fn distort(&self, point: &na::Point2<f64>) -> na::Point2<f64> {
+        let d = &self.distortion;
+        let (k1, k2, p1, p2) = (d[0], d[1], d[2], d[3]);
+        let (x, y) = (point.x, point.y);
+        let r2 = x.mul_add(x, y * y);
+        let radial = r2.mul_add(k2.mul_add(r2, k1), 1.0);
+        let xy2 = 2.0 * x * y;
+
+        na::Point2::new(
+            x.mul_add(radial, p1.mul_add(xy2, p2 * 2.0f64.mul_add(x * x, r2))),
+            y.mul_add(radial, p1.mul_add(2.0f64.mul_add(y * y, r2), p2 * xy2)),
+        )
+    }
+
+    /// Remove distortion from normalized image coordinates by fixed-point iteration.
+    pub fn undistort(&self, point: &na::Point2<f64>) -> na::Point2<f64> {
+        let mut undistorted = *point;
+        for _ in 0..UNDISTORT_ITERATIONS {
+            undistorted -= self.distort(&undistorted) - point;
+        }
+        undistorted
+    }
+
+    /// Project a point in the camera frame to a (distorted) pixel, or `None` behind the camera.
+    pub fn project(&self, point: &na::Point3<f64>) -> Option<na::Point2<f64>> {
+        if point.z <= 0.0 {
+            return None;
+        }
+
+        let distorted = self.distort(&na::Point2::new(point.x / point.z, point.y / point.z));
+        Some(self.pixel_of(&distorted))
+    }
+
+    /// Return the unit bearing vector of a (distorted) pixel.
+    pub fn unproject(&self, pixel: &na::Point2<f64>) -> na::Vector3<f64> {
+        let normalized = self.undistort(&self.normalized_of(pixel));
+        na::Vector3::new(normalized.x, normalized.y, 1.0).normalize()
+    }
+
+    /// Return where a (distorted) pixel would appear in an ideal pinhole image with the same intrinsics.
+    pub fn undistort_point(&self, pixel: &na::Point2<f64>) -> na::Point2<f64> {
+        self.pixel_of(&self.undistort(&self.normalized_of(pixel)))
+    }
+
+    /// Return a hash of resolution, intrinsics and distortion, stable across
+    /// platforms and compiler versions.
+    pub fn calibration_key(&self) -> String {
+        let (fu, fv, cu, cv) = self.intrinsics;
+        let mut hash = fnv1a(FNV_OFFSET, &self.resolution.0.to_le_bytes());
+        hash = fnv1a(hash, &self.resolution.1.to_le_bytes());
+        for v in [fu, fv, cu, cv].iter().chain(self.distortion.iter()) {
+            hash = fnv1a(hash, &v.to_bits().to_le_bytes());
+        }
+
+        format!("{:016x}", hash)
+    }
+
+    /// Return the lookup table from an ideal pinhole image into the raw image.
+    pub fn undistort_map(&self) -> RectifyMap {
+        let (width, height) = self.resolution;
+        RectifyMap::from_fn(width, height, |u, v| {
+            let normalized = self.normalized_of(&na::Point2::new(u as f64, v as f64));
+            Some(self.pixel_of(&self.distort(&normalized)))
+        })
+    }
+
+    /// Remove lens distortion from `image`, converted to grayscale.
+    pub fn undistort_image(&self, image: &DynamicImage) -> DynamicImage {
+        DynamicImage::ImageLuma8(self.undistort_map().remap(&image.to_luma8()))
+    }
+
+    fn pixel_of(&self, normalized: &na::Point2<f64>) -> na::Point2<f64> {
+        let (fu, fv, cu, cv) = self.intrinsics;
+        na::Point2::new(fu.mul_add(normalized.x, cu), fv.mul_add(normalized.y, cv))
+    }
+
+    fn normalized_of(&self, pixel: &na::Point2<f64>) -> na::Point2<f64> {
+        let (fu, fv, cu, cv) = self.intrinsics;
+        na::Point2::new((pixel.x - cu) / fu, (pixel.y - cv) / fv)
+    }
+}
+
+impl CameraRecords {
+    /// Return the camera model described by `sensor.yaml`.
+    pub fn camera_model(&self) -> Result<PinholeRadTan> {
+        PinholeRadTan::from_config(self.config())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::GenericImageView;
+
+    use super::*;
+    use crate::EuRoC;
+
+    #[test]
+    fn project_unproject() -> Result<()> {
+        let model = EuRoC::new("test_data")?.left_camera()?.camera_model()?;
+        let point = na::Point3::new(0.4, -0.3, 1.5);
+
+        let pixel = model.project(&point).unwrap();
+        let bearing = model.unproject(&pixel);
+        assert!((bearing - point.coords.normalize()).norm() < 1e-9);
+
+        assert!(model.project(&na::Point3::new(0.0, 0.0, -1.0)).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn undistort_point() -> Result<()> {
+        let model = EuRoC::new("test_data")?.left_camera()?.camera_model()?;
+        let (_, _, cu, cv) = model.intrinsics;
+
+        let center = na::Point2::new(cu, cv);
+        assert!((model.undistort_point(&center) - center).norm() < 1e-9);
+
+        // barrel distortion pulls the corners in, so undistorting pushes them out
+        let corner = na::Point2::new(10.0, 10.0);
+        let undistorted = model.undistort_point(&corner);
+        assert!(undistorted.x < corner.x && undistorted.y < corner.y);
+
+        Ok(())
+    }
+
+    #[test]
+    fn undistort_image() -> Result<()> {
+        let camera = EuRoC::new("test_data")?.left_camera()?;
+        let model = camera.camera_model()?;
+        let image = camera.records()?.next().unwrap()?.image;
+
+        assert_eq!(model.undistort_image(&image).dimensions(), (752, 480));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use yaml_rust::Yaml;
+
+use crate::{
+    fnv::{fnv1a, FNV_OFFSET},
+    yaml::SensorYaml,
+    Config, EuRoC, EurocError, FileSystem, OpenOptions, Result, Sensor, Validation,
+};
+
+/// Files hashed by [`EuRoC::manifest_hash`] in every sensor folder.
+const SENSOR_FILES: [&str; 3] = ["data.csv", "sensor.yaml", "data.ply"];
+
+/// Everything affecting how a dataset was loaded, for reproducing a run.
+///
+/// Save it next to published results; [`Capsule::open`] later opens the same
+/// dataset with the same options, and fails if the dataset has changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capsule {
+    /// version of this crate that produced the capsule
+    pub crate_version: String,
+    /// [`EuRoC::manifest_hash`] of the dataset
+    pub manifest: String,
+    pub validate: Validation,
+    /// [`Config`] applied by [`EuRoC::with_config`]
+    pub config: Config,
+}
+
+impl EuRoC {
+    /// Return a hash of the sensor files and calibration of the dataset.
+    ///
+    /// Images are covered through the file names listed in the camera
+    /// `data.csv`, but not their contents.
+    pub fn manifest_hash(&self) -> Result<String> {
+        let mut hash = FNV_OFFSET;
+
+        let body = self.root.join("body.yaml");
+        if self.source.is_file(&body) {
+            hash = fnv1a(hash, b"body.yaml");
+            hash = fnv1a(hash, &self.source.read(&body)?);
+        }
+
+        let dirs = Sensor::ALL
+            .iter()
+            .map(|&sensor| sensor.dir_name())
+            .chain(std::iter::once("pointcloud0"));
+        for dir in dirs {
+            for file in SENSOR_FILES.iter() {
+                let path = self.root.join(dir).join(file);
+                if self.source.is_file(&path) {
+                    hash = fnv1a(hash, format!("{}/{}", dir, file).as_bytes());
+                    hash = fnv1a(hash, &self.source.read(&path)?);
+                }
+            }
+        }
+
+        Ok(format!("{:016x}", hash))
+    }
+
+    /// Snapshot the dataset and `options` into a [`Capsule`].
+    pub fn capsule(&self, options: &OpenOptions) -> Result<Capsule> {
+        Ok(Capsule {
+            crate_version: env!("CARGO_PKG_VERSION").to_owned(),
+            manifest: self.manifest_hash()?,
+            validate: options.validate,
+            config: self.config().clone(),
+        })
+    }
+}
+
+impl Capsule {
+    pub const fn open_options(&self) -> OpenOptions {
+        OpenOptions {
+            validate: self.validate,
+        }
+    }
+
+    /// Open the dataset at `root` as it was opened for the capsule, including
+    /// its [`Capsule::config`].
+    ///
+    /// Fails if the dataset, or an overridden calibration, does not match the manifest. A different
+    /// [`Capsule::crate_version`] is not an error; compare it if needed.
+    pub fn open<P: AsRef<Path>>(&self, root: P) -> Result<EuRoC> {
+        let data = EuRoC::open(root, &self.open_options())?.with_config(self.config.clone())?;
+        let manifest = data.manifest_hash()?;
+        if manifest != self.manifest {
+            return Err(EurocError::InvalidInput(format!(
+                "dataset manifest {} does not match capsule {}",
+                manifest, self.manifest
+            )));
+        }
+
+        Ok(data)
+    }
+
+    /// Save as YAML.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut yaml = String::new();
+        let _ = writeln!(yaml, "crate_version: \"{}\"", self.crate_version);
+        let _ = writeln!(yaml, "manifest: \"{}\"", self.manifest);
+        let _ = writeln!(yaml, "validate: {}", self.validate.as_str());
+        yaml.push_str("config:\n");
+        yaml.push_str(&self.config.to_yaml("  ")?);
+        fs::write(path, yaml)?;
+
+        Ok(())
+    }
+
+    /// Load a capsule written by [`Capsule::save`].
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let yaml = SensorYaml::load(&FileSystem, path.as_ref().to_owned())?;
+        let validate = yaml
+            .str("validate")?
+            .parse()
+            .map_err(|_| EurocError::YamlField {
+                file: path.as_ref().to_owned(),
+                key: "validate".to_owned(),
+            })?;
+
+        // capsules without a config predate it and used the default one
+        let config = match yaml.field("config") {
+            Yaml::BadValue => Config::default(),
+            config => Config::from_yaml(config, path.as_ref())?,
+        };
+
+        Ok(Self {
+            crate_version: yaml.str("crate_version")?,
+            manifest: yaml.str("manifest")?,
+            validate,
+            config,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use crate::ErrorPolicy;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let options = OpenOptions {
+            validate: Validation::Shallow,
+        };
+        let capsule = EuRoC::open("test_data", &options)?.capsule(&options)?;
+        assert_eq!(capsule.manifest.len(), 16);
+
+        let tmp = TempDir::new("capsule")?;
+        let path = tmp.join("capsule.yaml");
+        capsule.save(&path)?;
+        let loaded = Capsule::load(&path)?;
+        assert_eq!(loaded, capsule);
+        assert!(loaded.open("test_data").is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip_config() -> Result<()> {
+        let dir = TempDir::new("capsule_config")?;
+        let calibration = dir.join("cam0.yaml");
+        fs::write(
+            &calibration,
+            fs::read_to_string("test_data/cam0/sensor.yaml")?.replace("458.654", "460.0"),
+        )?;
+
+        let mut config = Config {
+            on_error: ErrorPolicy::SkipWithWarning,
+            ..Config::default()
+        };
+        config.calibration.insert(Sensor::Cam0, calibration.clone());
+        let options = OpenOptions::default();
+        let capsule = EuRoC::new("test_data")?
+            .with_config(config.clone())?
+            .capsule(&options)?;
+        assert_eq!(capsule.config, config);
+
+        let path = dir.join("capsule.yaml");
+        capsule.save(&path)?;
+        let loaded = Capsule::load(&path)?;
+        assert_eq!(loaded, capsule);
+        let data = loaded.open("test_data")?;
+        assert!((data.left_camera()?.config().intrinsics.0 - 460.0).abs() < 1e-9);
+        assert_eq!(data.error_policy(), ErrorPolicy::SkipWithWarning);
+
+        // the overridden calibration is part of the manifest
+        fs::copy("test_data/cam0/sensor.yaml", &calibration)?;
+        assert!(matches!(
+            loaded.open("test_data"),
+            Err(EurocError::InvalidInput(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn detects_changes() -> Result<()> {
+        let root = TempDir::new("capsule_changes")?;
+        fs::create_dir_all(root.join("imu0"))?;
+        for file in ["data.csv", "sensor.yaml"].iter() {
+            fs::copy(
+                Path::new("test_data/imu0").join(file),
+                root.join("imu0").join(file),
+            )?;
+        }
+
+        let capsule = EuRoC::new(&root)?.capsule(&OpenOptions::default())?;
+        assert!(capsule.open(&root).is_ok());
+
+        let csv = fs::read_to_string(root.join("imu0/data.csv"))?;
+        fs::write(root.join("imu0/data.csv"), csv.replacen('1', "2", 1))?;
+        assert!(matches!(
+            capsule.open(&root),
+            Err(EurocError::InvalidInput(_))
+        ));
+
+        Ok(())
+    }
+}
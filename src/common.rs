@@ -1,20 +1,42 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+    path::Path,
+    time::{self, SystemTime, UNIX_EPOCH},
+};
 
-use anyhow::Result;
 use yaml_rust::YamlLoader;
 
+use crate::{EurocError, Result};
+
 pub fn load_yaml<P: AsRef<Path>>(path: P) -> Result<Vec<yaml_rust::Yaml>> {
-    let f = fs::read_to_string(path)?;
-    Ok(YamlLoader::load_from_str(&f)?)
+    let f = fs::read_to_string(&path)?;
+    YamlLoader::load_from_str(&f).map_err(|e| EurocError::YamlParse {
+        file: path.as_ref().to_owned(),
+        message: e.to_string(),
+    })
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Nanoseconds since the Unix epoch, as stored in EuRoC `data.csv` files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Timestamp(u64);
 
 impl Timestamp {
+    pub const fn new(nsecs: u64) -> Self {
+        Self(nsecs)
+    }
+
     pub const fn nsecs(self) -> u64 {
         self.0
     }
+
+    pub fn as_secs_f64(self) -> f64 {
+        self.0 as f64 * 1e-9
+    }
+
+    pub fn to_system_time(self) -> SystemTime {
+        UNIX_EPOCH + time::Duration::from_nanos(self.0)
+    }
 }
 
 impl From<u64> for Timestamp {
@@ -22,3 +44,170 @@ impl From<u64> for Timestamp {
         Self(v)
     }
 }
+
+/// Interpret `v` as time since the Unix epoch.
+impl From<time::Duration> for Timestamp {
+    fn from(v: time::Duration) -> Self {
+        Self(v.as_nanos() as u64)
+    }
+}
+
+/// Record carrying a measurement timestamp.
+pub trait Timestamped {
+    fn timestamp(&self) -> Timestamp;
+}
+
+/// Signed time span with nanosecond resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Duration(i64);
+
+impl Duration {
+    pub const ZERO: Self = Self(0);
+
+    pub const fn from_nsecs(nsecs: i64) -> Self {
+        Self(nsecs)
+    }
+
+    pub const fn from_millis(millis: i64) -> Self {
+        Self(millis * 1_000_000)
+    }
+
+    pub fn from_secs_f64(secs: f64) -> Self {
+        Self((secs * 1e9).round() as i64)
+    }
+
+    pub const fn nsecs(self) -> i64 {
+        self.0
+    }
+
+    pub fn as_secs_f64(self) -> f64 {
+        self.0 as f64 * 1e-9
+    }
+
+    pub const fn abs(self) -> Self {
+        Self(self.0.abs())
+    }
+}
+
+impl From<time::Duration> for Duration {
+    fn from(v: time::Duration) -> Self {
+        Self(v.as_nanos() as i64)
+    }
+}
+
+impl Add for Duration {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Duration {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Duration {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Duration {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Duration {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl Sub for Timestamp {
+    type Output = Duration;
+
+    fn sub(self, rhs: Self) -> Duration {
+        Duration(self.0.wrapping_sub(rhs.0) as i64)
+    }
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Self;
+
+    fn add(self, rhs: Duration) -> Self {
+        Self(self.0.wrapping_add(rhs.0 as u64))
+    }
+}
+
+impl AddAssign<Duration> for Timestamp {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Duration> for Timestamp {
+    type Output = Self;
+
+    fn sub(self, rhs: Duration) -> Self {
+        Self(self.0.wrapping_sub(rhs.0 as u64))
+    }
+}
+
+impl SubAssign<Duration> for Timestamp {
+    fn sub_assign(&mut self, rhs: Duration) {
+        *self = *self - rhs;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn timestamp_sub() {
+        let a = Timestamp::from(1403636579763555584);
+        let b = Timestamp::from(1403636579813555456);
+
+        assert_eq!((b - a).nsecs(), 49999872);
+        assert_eq!((a - b).nsecs(), -49999872);
+    }
+
+    #[test]
+    fn timestamp_add_duration() {
+        let a = Timestamp::from(1403636579763555584);
+
+        assert_eq!(a + Duration::from_millis(5), 1403636579768555584.into());
+        assert_eq!(a - Duration::from_millis(5), 1403636579758555584.into());
+        assert_eq!(a + -Duration::from_millis(5), a - Duration::from_millis(5));
+    }
+
+    #[test]
+    fn duration_secs() {
+        assert_eq!(Duration::from_secs_f64(0.05), Duration::from_millis(50));
+        assert_eq!(Duration::from_millis(-50).as_secs_f64(), -0.05);
+    }
+
+    #[test]
+    fn std_conversions() {
+        let ts = Timestamp::from(time::Duration::new(1403636579, 763555584));
+
+        assert_eq!(ts, 1403636579763555584.into());
+        assert!((ts.as_secs_f64() - 1403636579.763_555_6).abs() < 1e-6);
+        assert_eq!(
+            ts.to_system_time().duration_since(UNIX_EPOCH).unwrap(),
+            time::Duration::new(1403636579, 763555584)
+        );
+        assert_eq!(
+            Duration::from(time::Duration::from_millis(5)),
+            Duration::from_millis(5)
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::{fs, path::Path};
+
+use image::{imageops, Rgb, RgbImage};
+use nalgebra as na;
+
+use crate::{EuRoC, EurocError, Result, Sensor, Timestamp};
+
+const GROUND_TRUTH_AHEAD: Rgb<u8> = Rgb([70, 70, 70]);
+const GROUND_TRUTH: Rgb<u8> = Rgb([40, 200, 40]);
+const ESTIMATE: Rgb<u8> = Rgb([230, 40, 40]);
+/// border around the trajectories (px)
+const MARGIN: f64 = 16.0;
+
+/// Settings of [`EuRoC::render_comparison`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComparisonOptions {
+    /// camera shown next to the plot
+    pub camera: Sensor,
+    /// render every `every`-th camera frame
+    pub every: usize,
+}
+
+impl Default for ComparisonOptions {
+    fn default() -> Self {
+        Self {
+            camera: Sensor::Cam0,
+            every: 1,
+        }
+    }
+}
+
+/// Top-down view of the trajectories, drawn up to the current frame.
+struct Plot {
+    canvas: RgbImage,
+    ground_truth: Vec<(Timestamp, (i64, i64))>,
+    estimate: Vec<(Timestamp, (i64, i64))>,
+    /// number of points of each trajectory drawn so far
+    drawn: (usize, usize),
+}
+
+impl Plot {
+    fn new(
+        size: u32,
+        ground_truth: &[(Timestamp, na::Vector3<f64>)],
+        estimate: &[(Timestamp, na::Vector3<f64>)],
+    ) -> Self {
+        let points = ground_truth.iter().chain(estimate).map(|(_, p)| p);
+        let (mut min, mut max) = (na::Vector2::repeat(f64::MAX), na::Vector2::repeat(f64::MIN));
+        for p in points {
+            min = min.inf(&p.xy());
+            max = max.sup(&p.xy());
+        }
+        let range = (max - min).max().max(1e-6);
+        let scale = 2.0f64.mul_add(-MARGIN, f64::from(size)) / range;
+        let centre = (min + max) / 2.0;
+        let to_pixel = |p: &na::Vector3<f64>| {
+            let offset = (p.xy() - centre) * scale;
+            (
+                (f64::from(size) / 2.0 + offset.x).round() as i64,
+                (f64::from(size) / 2.0 - offset.y).round() as i64,
+            )
+        };
+        let project = |trajectory: &[(Timestamp, na::Vector3<f64>)]| {
+            trajectory
+                .iter()
+                .map(|(t, p)| (*t, to_pixel(p)))
+                .collect::<Vec<_>>()
+        };
+
+        let mut plot = Self {
+            canvas: RgbImage::new(size, size),
+            ground_truth: project(ground_truth),
+            estimate: project(estimate),
+            drawn: (0, 0),
+        };
+        for w in plot.ground_truth.windows(2) {
+            draw_line(&mut plot.canvas, w[0].1, w[1].1, GROUND_TRUTH_AHEAD);
+        }
+        plot
+    }
+
+    /// Draw both trajectories up to `t` and return the plot with the current positions marked.
+    fn at(&mut self, t: Timestamp) -> RgbImage {
+        let ground_truth = advance(
+            &mut self.canvas,
+            &self.ground_truth,
+            &mut self.drawn.0,
+            t,
+            GROUND_TRUTH,
+        );
+        let estimate = advance(
+            &mut self.canvas,
+            &self.estimate,
+            &mut self.drawn.1,
+            t,
+            ESTIMATE,
+        );
+
+        let mut frame = self.canvas.clone();
+        for (point, color) in [(ground_truth, GROUND_TRUTH), (estimate, ESTIMATE)].iter() {
+            if let Some(point) = point {
+                draw_marker(&mut frame, *point, *color);
+            }
+        }
+        frame
+    }
+}
+
+/// Draw the segments of `trajectory` up to `t` not drawn yet, returning the last point drawn.
+fn advance(
+    canvas: &mut RgbImage,
+    trajectory: &[(Timestamp, (i64, i64))],
+    drawn: &mut usize,
+    t: Timestamp,
+    color: Rgb<u8>,
+) -> Option<(i64, i64)> {
+    while *drawn < trajectory.len() && trajectory[*drawn].0 <= t {
+        if *drawn > 0 {
+            draw_line(
+                canvas,
+                trajectory[*drawn - 1].1,
+                trajectory[*drawn].1,
+                color,
+            );
+        }
+        *drawn += 1;
+    }
+    drawn.checked_sub(1).map(|i| trajectory[i].1)
+}
+
+fn put(image: &mut RgbImage, (x, y): (i64, i64), color: Rgb<u8>) {
+    if x >= 0 && y >= 0 && x < i64::from(image.width()) && y < i64::from(image.height()) {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+/// Bresenham's line from `a` to `b`.
+fn draw_line(image: &mut RgbImage, a: (i64, i64), b: (i64, i64), color: Rgb<u8>) {
+    let (dx, dy) = ((b.0 - a.0).abs(), -(b.1 - a.1).abs());
+    let (sx, sy) = ((b.0 - a.0).signum(), (b.1 - a.1).signum());
+    let (mut x, mut y, mut err) = (a.0, a.1, dx + dy);
+    loop {
+        put(image, (x, y), color);
+        if (x, y) == b {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+fn draw_marker(image: &mut RgbImage, (x, y): (i64, i64), color: Rgb<u8>) {
+    for dy in -3..=3 {
+        for dx in -3..=3 {
+            if dx * dx + dy * dy <= 9 {
+                put(image, (x + dx, y + dy), color);
+            }
+        }
+    }
+}
+
+impl EuRoC {
+    /// Render frames comparing `estimate` with the ground truth, for a qualitative review video.
+    ///
+    /// Each frame shows a camera image next to a top-down (x-y) view of both
+    /// trajectories up to the time of the image: ground truth in green, the
+    /// estimate in red and the rest of the ground truth in grey. `estimate`
+    /// must be sorted and expressed in the ground truth frame, e.g. aligned
+    /// with [`crate::umeyama`].
+    ///
+    /// Frames are written to `out_dir` as `frame_000000.png`, ...; encode them
+    /// with e.g. `ffmpeg -framerate 20 -i frame_%06d.png comparison.mp4`.
+    /// Return the number of frames written.
+    pub fn render_comparison<P: AsRef<Path>>(
+        &self,
+        estimate: &[(Timestamp, na::Isometry3<f64>)],
+        out_dir: P,
+        options: &ComparisonOptions,
+    ) -> Result<usize> {
+        if options.every == 0 {
+            return Err(EurocError::InvalidInput(
+                "every must be positive".to_owned(),
+            ));
+        }
+
+        let ground_truth = self
+            .ground_truth()?
+            .records()?
+            .map(|r| r.map(|r| (r.timestamp, r.position)))
+            .collect::<Result<Vec<_>>>()?;
+        let estimate: Vec<_> = estimate
+            .iter()
+            .map(|(t, pose)| (*t, pose.translation.vector))
+            .collect();
+
+        let camera = self.camera(options.camera)?;
+        let (width, height) = camera.image_size()?;
+        let mut plot = Plot::new(height, &ground_truth, &estimate);
+
+        fs::create_dir_all(out_dir.as_ref())?;
+        let mut frames = 0;
+        // skip entries rather than records, so that only rendered frames are decoded
+        for entry in camera.entries()?.step_by(options.every) {
+            let record = entry?.load()?;
+            let mut frame = RgbImage::new(width + height, height);
+            imageops::replace(&mut frame, &record.image.to_rgb8(), 0, 0);
+            imageops::replace(&mut frame, &plot.at(record.timestamp), width, 0);
+
+            frame.save(out_dir.as_ref().join(format!("frame_{:06}.png", frames)))?;
+            frames += 1;
+        }
+
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use image::GenericImageView;
+
+    use super::*;
+    use crate::{temp_dir::TempDir, MemorySource};
+
+    /// An estimate 1 cm off the ground truth.
+    ///
+    /// The ground truth of `test_data` starts after the camera frames, so
+    /// pretend that the estimate was produced at the camera timestamps.
+    fn estimate(data: &EuRoC) -> Result<Vec<(Timestamp, na::Isometry3<f64>)>> {
+        let frames = data.timestamps(Sensor::Cam0)?;
+        data.ground_truth()?
+            .records()?
+            .zip(frames)
+            .map(|(r, t)| {
+                let mut pose = r?.pose();
+                pose.translation.vector.x += 0.01;
+                Ok((t, pose))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn render_comparison() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let estimate = estimate(&data)?;
+
+        let out = TempDir::new("render_comparison")?;
+        let options = ComparisonOptions {
+            every: 2,
+            ..ComparisonOptions::default()
+        };
+        assert_eq!(data.render_comparison(&estimate, &out, &options)?, 3);
+
+        let last = image::open(out.join("frame_000002.png"))?;
+        assert_eq!(last.dimensions(), (752 + 480, 480));
+        let last = last.to_rgb8();
+        assert!(last.pixels().any(|&p| p == ESTIMATE));
+        assert!(last.pixels().any(|&p| p == GROUND_TRUTH_AHEAD));
+
+        Ok(())
+    }
+
+    #[test]
+    fn skipped_frames_are_not_decoded() -> Result<()> {
+        let mut source = MemorySource::from_dir("test_data")?;
+        let frames = EuRoC::new("test_data")?.timestamps(Sensor::Cam0)?;
+        for ts in [frames[1], frames[3]].iter() {
+            source.insert(format!("cam0/data/{}.png", ts.nsecs()), &b"not a png"[..]);
+        }
+        let data = EuRoC::from_source(Arc::new(source), "")?;
+        let estimate = estimate(&data)?;
+
+        let out = TempDir::new("render_comparison_skipped")?;
+        let options = ComparisonOptions {
+            every: 2,
+            ..ComparisonOptions::default()
+        };
+        assert_eq!(data.render_comparison(&estimate, &out, &options)?, 3);
+
+        Ok(())
+    }
+}
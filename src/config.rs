@@ -0,0 +1,362 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use yaml_rust::{Yaml, YamlLoader};
+
+use crate::{
+    DataSource, ErrorPolicy, EuRoC, EurocError, FrameCache, ReadSeek, Result, Sensor, Validation,
+};
+
+const SENSOR_YAML: &str = "sensor.yaml";
+const FIELDS: [&str; 7] = [
+    "validate",
+    "strict",
+    "on_error",
+    "frame_cache",
+    "prefetch_depth",
+    "decode_workers",
+    "calibration",
+];
+
+/// Loader settings shared by the tools of a team, applied by [`EuRoC::with_config`].
+///
+/// Usually kept as a `euroc.yaml` next to the datasets:
+///
+/// ```yaml
+/// validate: shallow
+/// strict: true
+/// on_error: skip_with_warning
+/// frame_cache: /scratch/euroc_frames
+/// prefetch_depth: 8
+/// decode_workers: 4
+/// calibration:
+///   cam0: recalibrated/cam0.yaml
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// checks run when the config is applied
+    pub validate: Validation,
+    /// fail on fields of `sensor.yaml` this crate does not know instead of
+    /// reporting them in `warnings`
+    pub strict: bool,
+    /// handling of malformed records by the `load_all` of the sensors of the
+    /// dataset, returned by [`EuRoC::error_policy`]
+    pub on_error: ErrorPolicy,
+    /// folder of the [`FrameCache`] returned by [`EuRoC::frame_cache`]
+    pub frame_cache: Option<PathBuf>,
+    /// images decoded ahead by [`EuRoC::records_prefetched`], 4 by default
+    pub prefetch_depth: usize,
+    /// threads decoding for [`EuRoC::records_prefetched`], 2 by default
+    pub decode_workers: usize,
+    /// `sensor.yaml` on disk replacing the recorded calibration of a sensor
+    pub calibration: BTreeMap<Sensor, PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            validate: Validation::default(),
+            strict: false,
+            on_error: ErrorPolicy::default(),
+            frame_cache: None,
+            prefetch_depth: 4,
+            decode_workers: 2,
+            calibration: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Parse a config file; relative paths in it are relative to the file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let doc = YamlLoader::load_from_str(&text)
+            .map_err(|e| EurocError::YamlParse {
+                file: path.to_owned(),
+                message: e.to_string(),
+            })?
+            .into_iter()
+            .next()
+            .unwrap_or(Yaml::Null);
+
+        Self::from_yaml(&doc, path)
+    }
+
+    /// Parse the fields of `doc`, read from `path`.
+    pub(crate) fn from_yaml(doc: &Yaml, path: &Path) -> Result<Self> {
+        let field_error = |key: &str| EurocError::YamlField {
+            file: path.to_owned(),
+            key: key.to_owned(),
+        };
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let resolve = |key: &str, yaml: &Yaml| {
+            yaml.as_str()
+                .map(|p| dir.join(p))
+                .ok_or_else(|| field_error(key))
+        };
+        let positive = |key: &str, yaml: &Yaml| match yaml.as_i64() {
+            Some(n) if n > 0 => Ok(n as usize),
+            _ => Err(field_error(key)),
+        };
+
+        let mut config = Self::default();
+        let fields = match doc {
+            Yaml::Hash(fields) => fields.clone(),
+            Yaml::Null => Default::default(),
+            _ => return Err(field_error("")),
+        };
+        for (key, value) in &fields {
+            let key = key.as_str().ok_or_else(|| field_error(""))?;
+            match key {
+                "validate" => {
+                    config.validate = value
+                        .as_str()
+                        .and_then(|name| name.parse().ok())
+                        .ok_or_else(|| field_error(key))?
+                }
+                "strict" => config.strict = value.as_bool().ok_or_else(|| field_error(key))?,
+                "on_error" => {
+                    config.on_error = value
+                        .as_str()
+                        .and_then(|name| name.parse().ok())
+                        .ok_or_else(|| field_error(key))?
+                }
+                "frame_cache" => config.frame_cache = Some(resolve(key, value)?),
+                "prefetch_depth" => config.prefetch_depth = positive(key, value)?,
+                "decode_workers" => config.decode_workers = positive(key, value)?,
+                "calibration" => {
+                    let overrides = value.as_hash().ok_or_else(|| field_error(key))?;
+                    for (name, file) in overrides {
+                        let name = name.as_str().unwrap_or_default();
+                        let sensor = Sensor::ALL
+                            .iter()
+                            .copied()
+                            .find(|s| s.dir_name() == name)
+                            .ok_or_else(|| field_error(&format!("calibration.{}", name)))?;
+                        config.calibration.insert(sensor, resolve(key, file)?);
+                    }
+                }
+                _ => {
+                    return Err(EurocError::YamlParse {
+                        file: path.to_owned(),
+                        message: format!("unknown field `{}`, expected one of {:?}", key, FIELDS),
+                    })
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Write the fields as YAML readable by [`Config::from_yaml`], each line
+    /// prefixed with `indent`.
+    ///
+    /// Paths are written absolute, so that the YAML can be moved.
+    pub(crate) fn to_yaml(&self, indent: &str) -> Result<String> {
+        let cwd = std::env::current_dir()?;
+        let path = |p: &Path| format!("{:?}", cwd.join(p).display().to_string());
+
+        let mut yaml = String::new();
+        let _ = writeln!(yaml, "{}validate: {}", indent, self.validate.as_str());
+        let _ = writeln!(yaml, "{}strict: {}", indent, self.strict);
+        let _ = writeln!(yaml, "{}on_error: {}", indent, self.on_error.as_str());
+        if let Some(frame_cache) = &self.frame_cache {
+            let _ = writeln!(yaml, "{}frame_cache: {}", indent, path(frame_cache));
+        }
+        let _ = writeln!(yaml, "{}prefetch_depth: {}", indent, self.prefetch_depth);
+        let _ = writeln!(yaml, "{}decode_workers: {}", indent, self.decode_workers);
+        if !self.calibration.is_empty() {
+            let _ = writeln!(yaml, "{}calibration:", indent);
+            for (sensor, file) in &self.calibration {
+                let _ = writeln!(yaml, "{}  {}: {}", indent, sensor.dir_name(), path(file));
+            }
+        }
+
+        Ok(yaml)
+    }
+}
+
+/// [`DataSource`] replacing some files of another one.
+#[derive(Debug)]
+struct Overlay {
+    base: Arc<dyn DataSource>,
+    files: BTreeMap<PathBuf, Arc<[u8]>>,
+}
+
+impl DataSource for Overlay {
+    fn open(&self, path: &Path) -> Result<Box<dyn ReadSeek>> {
+        self.files.get(path).map_or_else(
+            || self.base.open(path),
+            |data| Ok(Box::new(Cursor::new(Arc::clone(data)))),
+        )
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.contains_key(path) || self.base.is_file(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.base.is_dir(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        self.base.read_dir(path)
+    }
+}
+
+impl EuRoC {
+    /// Apply `config`: override calibrations, then validate as configured.
+    pub fn with_config(mut self, config: Config) -> Result<Self> {
+        if !config.calibration.is_empty() {
+            let mut files = BTreeMap::new();
+            for (&sensor, file) in &config.calibration {
+                if !file.is_file() {
+                    return Err(EurocError::MissingFile(file.clone()));
+                }
+                let data: Arc<[u8]> = fs::read(file)?.into();
+                files.insert(self.sensor_dir(sensor).join(SENSOR_YAML), data);
+            }
+            self.source = Arc::new(Overlay {
+                base: self.source,
+                files,
+            });
+        }
+
+        self.validate(config.validate)?;
+        if config.strict {
+            self.check_strict()?;
+        }
+        self.config = Arc::new(config);
+
+        Ok(self)
+    }
+
+    /// Return the config applied by [`EuRoC::with_config`], or the default one.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Return the [`Config::on_error`] policy, used by e.g. [`crate::ImuData::load_all`].
+    pub fn error_policy(&self) -> ErrorPolicy {
+        self.config.on_error
+    }
+
+    /// Return the frame cache of [`Config::frame_cache`], if configured.
+    pub fn frame_cache(&self) -> Result<Option<FrameCache>> {
+        self.config
+            .frame_cache
+            .as_ref()
+            .map(FrameCache::new)
+            .transpose()
+    }
+
+    /// Fail if a camera or IMU `sensor.yaml` has unknown fields.
+    fn check_strict(&self) -> Result<()> {
+        for &sensor in Sensor::ALL.iter() {
+            if !self.source.is_dir(&self.sensor_dir(sensor)) {
+                continue;
+            }
+            let warnings = match sensor {
+                Sensor::Cam0 | Sensor::Cam1 => self.camera(sensor)?.config().warnings.clone(),
+                Sensor::Imu0 => self.imu()?.config()?.warnings,
+                _ => continue,
+            };
+            if let Some(warning) = warnings.first() {
+                return Err(EurocError::YamlParse {
+                    file: warning.file.clone(),
+                    message: format!("unknown field `{}`", warning.key),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn load() -> Result<()> {
+        let dir = TempDir::new("config_load")?;
+        let path = dir.join("euroc.yaml");
+        fs::write(
+            &path,
+            "validate: deep\nstrict: true\non_error: skip_with_warning\nframe_cache: frames\n\
+             prefetch_depth: 8\ndecode_workers: 3\ncalibration:\n  cam1: cam1.yaml\n",
+        )?;
+
+        let config = Config::load(&path)?;
+        assert_eq!(config.validate, Validation::Deep);
+        assert!(config.strict);
+        assert_eq!(config.on_error, ErrorPolicy::SkipWithWarning);
+        assert_eq!(config.frame_cache, Some(dir.join("frames")));
+        assert_eq!((config.prefetch_depth, config.decode_workers), (8, 3));
+        assert_eq!(config.calibration[&Sensor::Cam1], dir.join("cam1.yaml"));
+
+        fs::write(&path, "validate: always\n")?;
+        assert!(matches!(
+            Config::load(&path),
+            Err(EurocError::YamlField { key, .. }) if key == "validate"
+        ));
+        fs::write(&path, "on_error: ignore\n")?;
+        assert!(matches!(
+            Config::load(&path),
+            Err(EurocError::YamlField { key, .. }) if key == "on_error"
+        ));
+        fs::write(&path, "prefetch_depth: 0\n")?;
+        assert!(matches!(
+            Config::load(&path),
+            Err(EurocError::YamlField { key, .. }) if key == "prefetch_depth"
+        ));
+        fs::write(&path, "prefetch: 4\n")?;
+        assert!(matches!(
+            Config::load(&path),
+            Err(EurocError::YamlParse { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn with_config() -> Result<()> {
+        let dir = TempDir::new("with_config")?;
+        let yaml = fs::read_to_string("test_data/cam0/sensor.yaml")?
+            .replace("458.654", "460.0")
+            .replace("rate_hz: 20", "rate_hz: 20\nexposure: auto");
+        let calibration = dir.join("cam0.yaml");
+        fs::write(&calibration, yaml)?;
+
+        let mut config = Config {
+            frame_cache: Some(dir.join("frames")),
+            ..Config::default()
+        };
+        config.calibration.insert(Sensor::Cam0, calibration);
+
+        let data = EuRoC::new("test_data")?.with_config(config.clone())?;
+        assert!((data.left_camera()?.config().intrinsics.0 - 460.0).abs() < 1e-9);
+        assert!((data.right_camera()?.config().intrinsics.0 - 457.587).abs() < 1e-9);
+        assert!(data.frame_cache()?.is_some());
+        assert!(dir.join("frames").is_dir());
+        assert_eq!(data.config(), &config);
+        assert_eq!(data.error_policy(), ErrorPolicy::Strict);
+        assert!(EuRoC::new("test_data")?.frame_cache()?.is_none());
+
+        config.strict = true;
+        assert!(matches!(
+            EuRoC::new("test_data")?.with_config(config),
+            Err(EurocError::YamlParse { message, .. }) if message.contains("exposure")
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use nalgebra as na;
+
+use crate::{GroundTruthRecord, Result, RunRecord, ViconRecord};
+
+/// Record carrying an orientation quaternion.
+pub trait Oriented {
+    fn quaternion_mut(&mut self) -> &mut na::Quaternion<f64>;
+}
+
+impl Oriented for na::Quaternion<f64> {
+    fn quaternion_mut(&mut self) -> &mut na::Quaternion<f64> {
+        self
+    }
+}
+
+impl Oriented for GroundTruthRecord {
+    fn quaternion_mut(&mut self) -> &mut na::Quaternion<f64> {
+        &mut self.quaternion
+    }
+}
+
+impl Oriented for ViconRecord {
+    fn quaternion_mut(&mut self) -> &mut na::Quaternion<f64> {
+        &mut self.quaternion
+    }
+}
+
+impl Oriented for RunRecord {
+    fn quaternion_mut(&mut self) -> &mut na::Quaternion<f64> {
+        self.pose.rotation.as_mut_unchecked()
+    }
+}
+
+/// Flip quaternion signs in place so that consecutive quaternions lie in the same hemisphere.
+///
+/// `q` and `-q` describe the same rotation, but every sign change between
+/// neighbours makes component-wise blending take the long way around.
+pub fn make_continuous<T: Oriented>(records: &mut [T]) {
+    let mut previous: Option<na::Quaternion<f64>> = None;
+    for record in records {
+        flip_towards(record, &mut previous);
+    }
+}
+
+fn flip_towards<T: Oriented>(record: &mut T, previous: &mut Option<na::Quaternion<f64>>) {
+    let q = record.quaternion_mut();
+    if let Some(p) = previous {
+        if p.dot(q) < 0.0 {
+            *q = -*q;
+        }
+    }
+    *previous = Some(*q);
+}
+
+/// Wrap a record iterator, enforcing quaternion sign continuity as in [`make_continuous`].
+pub fn continuous<I, T>(records: I) -> ContinuousIterator<I::IntoIter>
+where
+    I: IntoIterator<Item = Result<T>>,
+    T: Oriented,
+{
+    ContinuousIterator {
+        inner: records.into_iter(),
+        previous: None,
+    }
+}
+
+pub struct ContinuousIterator<I> {
+    inner: I,
+    previous: Option<na::Quaternion<f64>>,
+}
+
+impl<I, T> Iterator for ContinuousIterator<I>
+where
+    I: Iterator<Item = Result<T>>,
+    T: Oriented,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|record| {
+            let mut record = record?;
+            flip_towards(&mut record, &mut self.previous);
+            Ok(record)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EuRoC;
+
+    #[test]
+    fn flips_hemisphere() {
+        let q = na::Quaternion::new(0.5, 0.5, 0.5, 0.5);
+        let mut quaternions = vec![q, -q, -q, q];
+        make_continuous(&mut quaternions);
+
+        assert_eq!(quaternions, vec![q; 4]);
+    }
+
+    #[test]
+    fn continuous_ground_truth() -> Result<()> {
+        let gt = EuRoC::new("test_data")?.ground_truth()?;
+        let records = gt
+            .records()?
+            .enumerate()
+            .map(|(i, r)| {
+                r.map(|mut r| {
+                    if i % 2 == 1 {
+                        r.quaternion = -r.quaternion;
+                    }
+                    r
+                })
+            })
+            .collect::<Vec<_>>();
+        let expected = gt.records()?.collect::<Result<Vec<_>>>()?;
+
+        let out = continuous(records).collect::<Result<Vec<_>>>()?;
+        for (a, b) in out.iter().zip(&expected) {
+            assert_eq!(a.quaternion, b.quaternion);
+        }
+
+        Ok(())
+    }
+}
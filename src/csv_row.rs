@@ -0,0 +1,548 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use nalgebra as na;
+
+use crate::{
+    interpolation::bracket, DataSource, EurocError, FileSystem, ReadSeek, Result, Timestamp,
+};
+
+/// Rows of a `data.csv`, remembering where they came from for error reporting.
+pub struct CsvRows<R = File> {
+    path: Arc<Path>,
+    reader: csv::StringRecordsIntoIter<R>,
+}
+
+impl<R: Read> CsvRows<R> {
+    /// `path` is only used in error messages.
+    pub fn from_reader(path: PathBuf, reader: R) -> Self {
+        Self {
+            path: path.into(),
+            reader: csv::Reader::from_reader(reader).into_records(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for CsvRows<R> {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let path = &self.path;
+        self.reader.next().map(|record| {
+            record
+                .map(|record| Row {
+                    record,
+                    path: path.clone(),
+                })
+                .map_err(|e| csv_error(path, e))
+        })
+    }
+}
+
+/// Position and timestamp of every row of a `data.csv`.
+#[derive(Debug)]
+struct CsvIndex {
+    positions: Vec<csv::Position>,
+    /// timestamp of each row, meaningful only without `bad_row`
+    timestamps: Vec<Timestamp>,
+    /// (line, message) of the first row without a valid timestamp
+    bad_row: Option<(u64, String)>,
+}
+
+impl CsvIndex {
+    fn build<R: Read>(file: R, path: &Path) -> Result<Self> {
+        let mut reader = csv::Reader::from_reader(file);
+        let shared: Arc<Path> = path.into();
+        let mut index = Self {
+            positions: Vec::new(),
+            timestamps: Vec::new(),
+            bad_row: None,
+        };
+
+        loop {
+            // rows failing to parse are indexed too, and fail again when read
+            let mut record = csv::StringRecord::new();
+            let (position, timestamp) = match reader.read_record(&mut record) {
+                Ok(false) => break,
+                Ok(true) => {
+                    let position = record
+                        .position()
+                        .cloned()
+                        .unwrap_or_else(csv::Position::new);
+                    let row = Row {
+                        record,
+                        path: shared.clone(),
+                    };
+                    (position, row.timestamp().map_err(|e| e.to_string()))
+                }
+                Err(e) => match e.position().cloned() {
+                    Some(position) => (position, Err(e.to_string())),
+                    None => return Err(csv_error(path, e)),
+                },
+            };
+
+            match timestamp {
+                Ok(ts) => index.timestamps.push(ts),
+                Err(message) => {
+                    if index.bad_row.is_none() {
+                        index.bad_row = Some((position.line(), message));
+                    }
+                    index.timestamps.push(Timestamp::new(0));
+                }
+            }
+            index.positions.push(position);
+        }
+
+        Ok(index)
+    }
+
+    /// Return the index of the first row at or after `ts`.
+    fn lower_bound(&self, ts: Timestamp) -> usize {
+        self.timestamps
+            .binary_search_by(|row| {
+                if *row < ts {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            })
+            .unwrap_or_else(|i| i)
+    }
+}
+
+/// Lazily built row index of one `data.csv`, shared between clones.
+#[derive(Debug, Clone)]
+pub struct IndexCache {
+    source: Arc<dyn DataSource>,
+    index: Arc<Mutex<Option<Arc<CsvIndex>>>>,
+}
+
+impl Default for IndexCache {
+    fn default() -> Self {
+        Self::new(FileSystem::shared())
+    }
+}
+
+impl IndexCache {
+    /// Read the file through `source`.
+    pub fn new(source: Arc<dyn DataSource>) -> Self {
+        Self {
+            source,
+            index: Arc::default(),
+        }
+    }
+
+    /// Return the index of `path`, building it on the first call.
+    fn get(&self, path: &Path) -> Result<Arc<CsvIndex>> {
+        let mut cached = self.index.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(index) = &*cached {
+            return Ok(Arc::clone(index));
+        }
+
+        let index = Arc::new(CsvIndex::build(self.source.open(path)?, path)?);
+        *cached = Some(Arc::clone(&index));
+        drop(cached);
+        Ok(index)
+    }
+
+    /// Return the number of rows of `path`.
+    pub fn row_count(&self, path: &Path) -> Result<usize> {
+        Ok(self.get(path)?.positions.len())
+    }
+
+    /// Return all rows of `path`.
+    pub fn rows(&self, path: PathBuf) -> Result<IndexedRows> {
+        let index = self.get(&path)?;
+        let back = index.positions.len();
+        IndexedRows::new(path, self.clone(), Some(index), 0, back)
+    }
+
+    /// Return the rows of `path` with `start <= timestamp < end`.
+    ///
+    /// Fails if a row has no valid timestamp, as the rows cannot be searched.
+    pub fn rows_between(
+        &self,
+        path: PathBuf,
+        start: Timestamp,
+        end: Timestamp,
+    ) -> Result<IndexedRows> {
+        let index = self.searchable(&path)?;
+        let front = index.lower_bound(start);
+        let back = index.lower_bound(end).max(front);
+        IndexedRows::new(path, self.clone(), Some(index), front, back)
+    }
+
+    /// Return the rows at or just before and after `ts`, with the fraction of
+    /// `ts` between them, or `None` outside the rows; see [`bracket`].
+    ///
+    /// Yields a single row if one is at `ts`.
+    pub fn rows_around(&self, path: PathBuf, ts: Timestamp) -> Result<Option<(IndexedRows, f64)>> {
+        let index = self.searchable(&path)?;
+        match bracket(&index.timestamps, ts) {
+            Some((i, j, alpha)) => Ok(Some((
+                IndexedRows::new(path, self.clone(), Some(index), i, j + 1)?,
+                alpha,
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Continue reading `path` where `checkpoint` was taken, without indexing it.
+    pub fn resume(&self, path: PathBuf, checkpoint: &Checkpoint) -> Result<IndexedRows> {
+        let mut position = csv::Position::new();
+        position
+            .set_byte(checkpoint.byte)
+            .set_line(checkpoint.line)
+            .set_record(checkpoint.record);
+
+        let mut rows = IndexedRows::new(path, self.clone(), None, checkpoint.row, checkpoint.end)?;
+        rows.reader
+            .seek(position)
+            .map_err(|e| csv_error(&rows.path, e))?;
+        rows.cursor = Some(checkpoint.row);
+        rows.last = checkpoint.last;
+        Ok(rows)
+    }
+
+    /// Return the index of `path`, failing if a row has no valid timestamp.
+    fn searchable(&self, path: &Path) -> Result<Arc<CsvIndex>> {
+        let index = self.get(path)?;
+        match &index.bad_row {
+            Some((line, message)) => Err(EurocError::CsvParse {
+                path: path.to_owned(),
+                line: *line,
+                message: message.clone(),
+            }),
+            None => Ok(index),
+        }
+    }
+}
+
+/// Where a record iterator stopped, to resume it later without indexing the file again.
+///
+/// A checkpoint is only meaningful for the unchanged `data.csv` it was taken from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// byte offset of the next row
+    pub byte: u64,
+    pub line: u64,
+    pub record: u64,
+    /// index of the next row
+    pub row: usize,
+    /// index after the last row to yield
+    pub end: usize,
+    /// timestamp of the last row yielded from the front, if any
+    pub last: Option<Timestamp>,
+}
+
+/// Rows of a `data.csv` read through its [`IndexCache`], in either direction.
+pub struct IndexedRows {
+    path: Arc<Path>,
+    reader: csv::Reader<Box<dyn ReadSeek>>,
+    cache: IndexCache,
+    /// fetched from `cache` when a seek is needed
+    index: Option<Arc<CsvIndex>>,
+    /// rows `front..back` are left
+    front: usize,
+    back: usize,
+    /// row the reader is positioned at, if known
+    cursor: Option<usize>,
+    last: Option<Timestamp>,
+}
+
+impl IndexedRows {
+    fn new(
+        path: PathBuf,
+        cache: IndexCache,
+        index: Option<Arc<CsvIndex>>,
+        front: usize,
+        back: usize,
+    ) -> Result<Self> {
+        Ok(Self {
+            reader: csv::Reader::from_reader(cache.source.open(&path)?),
+            path: path.into(),
+            cache,
+            index,
+            front,
+            back,
+            cursor: None,
+            last: None,
+        })
+    }
+
+    /// Return the position of the next row from the front.
+    pub fn checkpoint(&self) -> Checkpoint {
+        let position = match &self.index {
+            _ if self.cursor == Some(self.front) => self.reader.position().clone(),
+            Some(index) => index
+                .positions
+                .get(self.front)
+                .cloned()
+                .unwrap_or_else(|| self.reader.position().clone()),
+            None => self.reader.position().clone(),
+        };
+
+        Checkpoint {
+            byte: position.byte(),
+            line: position.line(),
+            record: position.record(),
+            row: self.front,
+            end: self.back,
+            last: self.last,
+        }
+    }
+
+    fn index(&mut self) -> Result<Arc<CsvIndex>> {
+        match &self.index {
+            Some(index) => Ok(Arc::clone(index)),
+            None => {
+                let index = self.cache.get(&self.path)?;
+                self.index = Some(Arc::clone(&index));
+                Ok(index)
+            }
+        }
+    }
+
+    fn read(&mut self, i: usize) -> Result<Row> {
+        if self.cursor != Some(i) {
+            self.cursor = None;
+            let position = self.index()?.positions[i].clone();
+            self.reader
+                .seek(position)
+                .map_err(|e| csv_error(&self.path, e))?;
+        }
+
+        let mut record = csv::StringRecord::new();
+        let read = self.reader.read_record(&mut record);
+        self.cursor = Some(i + 1);
+        match read {
+            Ok(true) => Ok(Row {
+                record,
+                path: self.path.clone(),
+            }),
+            Ok(false) => Err(EurocError::CsvParse {
+                path: self.path.to_path_buf(),
+                line: self.reader.position().line(),
+                message: "file changed since it was indexed".to_owned(),
+            }),
+            Err(e) => Err(csv_error(&self.path, e)),
+        }
+    }
+}
+
+impl Iterator for IndexedRows {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        let row = self.read(self.front - 1);
+        if let Ok(row) = &row {
+            self.last = row.timestamp().ok();
+        }
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.back - self.front;
+        (n, Some(n))
+    }
+}
+
+impl DoubleEndedIterator for IndexedRows {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.read(self.back))
+    }
+}
+
+impl ExactSizeIterator for IndexedRows {}
+
+pub fn csv_error(path: &Path, e: csv::Error) -> EurocError {
+    match e.position() {
+        Some(pos) => EurocError::CsvParse {
+            path: path.to_owned(),
+            line: pos.line(),
+            message: e.to_string(),
+        },
+        None => match e.into_kind() {
+            csv::ErrorKind::Io(e) => e.into(),
+            kind => EurocError::CsvParse {
+                path: path.to_owned(),
+                line: 0,
+                message: format!("{:?}", kind),
+            },
+        },
+    }
+}
+
+/// One row of a `data.csv`, with typed column accessors.
+pub struct Row {
+    record: csv::StringRecord,
+    path: Arc<Path>,
+}
+
+impl Row {
+    pub fn line(&self) -> u64 {
+        self.record.position().map_or(0, |p| p.line())
+    }
+
+    pub fn error<M: Display>(&self, message: M) -> EurocError {
+        EurocError::CsvParse {
+            path: self.path.to_path_buf(),
+            line: self.line(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Return the number of columns.
+    pub fn len(&self) -> usize {
+        self.record.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.record.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Result<&str> {
+        self.record
+            .get(i)
+            .ok_or_else(|| self.error(format!("missing column {}", i)))
+    }
+
+    pub fn parse<T>(&self, i: usize) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let field = self.get(i)?;
+        field
+            .trim()
+            .parse()
+            .map_err(|e| self.error(format!("column {} `{}`: {}", i, field, e)))
+    }
+
+    pub fn timestamp(&self) -> Result<Timestamp> {
+        Ok(self.parse::<u64>(0)?.into())
+    }
+
+    /// Parse columns `i..i + 3`.
+    pub fn vector3(&self, i: usize) -> Result<na::Vector3<f64>> {
+        Ok(na::Vector3::new(
+            self.parse(i)?,
+            self.parse(i + 1)?,
+            self.parse(i + 2)?,
+        ))
+    }
+
+    /// Parse columns `i..i + 4` as (w, x, y, z).
+    pub fn quaternion(&self, i: usize) -> Result<na::Quaternion<f64>> {
+        Ok(na::Quaternion::new(
+            self.parse(i)?,
+            self.parse(i + 1)?,
+            self.parse(i + 2)?,
+            self.parse(i + 3)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rows_between() -> Result<()> {
+        let path = PathBuf::from("test_data/cam0/data.csv");
+        let cache = IndexCache::default();
+        let timestamps = |rows: IndexedRows| -> Result<Vec<u64>> {
+            rows.map(|row| Ok(row?.timestamp()?.nsecs())).collect()
+        };
+
+        let rows = cache.rows_between(
+            path.clone(),
+            1403636579813555456.into(),
+            1403636579913555456.into(),
+        )?;
+        assert_eq!(rows.size_hint(), (2, Some(2)));
+        assert_eq!(
+            timestamps(rows)?,
+            vec![1403636579813555456, 1403636579863555584]
+        );
+
+        let mut rows =
+            cache.rows_between(path.clone(), 1403636579900000000.into(), u64::MAX.into())?;
+        let row = rows.next().unwrap()?;
+        assert_eq!(row.timestamp()?, 1403636579913555456.into());
+        // line numbers match reading from the start
+        let line = CsvRows::from_reader(path.clone(), File::open(&path)?)
+            .nth(3)
+            .unwrap()?
+            .line();
+        assert_eq!(row.line(), line);
+
+        let rows = cache.rows_between(path.clone(), u64::MAX.into(), u64::MAX.into())?;
+        assert!(timestamps(rows)?.is_empty());
+
+        let mut rows = cache.rows(path.clone())?;
+        assert_eq!(rows.len(), 5);
+        rows.next();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(cache.row_count(&path)?, 5);
+
+        // alternate ends, seeking back and forth
+        let mut rows = cache.rows(path)?;
+        assert_eq!(
+            rows.next_back().unwrap()?.timestamp()?,
+            1403636579963555584.into()
+        );
+        assert_eq!(
+            rows.next().unwrap()?.timestamp()?,
+            1403636579763555584.into()
+        );
+        assert_eq!(
+            rows.next_back().unwrap()?.timestamp()?,
+            1403636579913555456.into()
+        );
+        assert_eq!(
+            rows.next().unwrap()?.timestamp()?,
+            1403636579813555456.into()
+        );
+        assert_eq!(rows.len(), 1);
+        assert_eq!(
+            rows.next_back().unwrap()?.timestamp()?,
+            1403636579863555584.into()
+        );
+        assert!(rows.next().is_none() && rows.next_back().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_error_has_line() {
+        let data = "#timestamp,x\n1,0.5\n2,abc\n";
+        let mut rows = CsvRows::from_reader(PathBuf::from("data.csv"), data.as_bytes());
+
+        assert_eq!(rows.next().unwrap().unwrap().parse::<f64>(1).unwrap(), 0.5);
+
+        let err = rows.next().unwrap().unwrap().parse::<f64>(1).unwrap_err();
+        match err {
+            EurocError::CsvParse { path, line, .. } => {
+                assert_eq!(path, PathBuf::from("data.csv"));
+                assert_eq!(line, 3);
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+}
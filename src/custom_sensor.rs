@@ -0,0 +1,189 @@
+use std::{path::PathBuf, sync::Arc};
+
+use nalgebra as na;
+
+use crate::{
+    csv_row::{IndexCache, IndexedRows, Row},
+    yaml::SensorYaml,
+    DataSource, EuRoC, FileSystem, Result, Timestamp,
+};
+
+const DATA_CSV: &str = "data.csv";
+const SENSOR_YAML: &str = "sensor.yaml";
+
+/// Record type parsed from one `data.csv` row of a [`CustomSensor`].
+pub trait FromCsvRow: Sized {
+    fn from_csv_row(row: &Row) -> Result<Self>;
+}
+
+/// Parser used by [`CustomSensor::records`].
+pub type ParseRow<T> = fn(&Row) -> Result<T>;
+
+/// Any sensor folder in EuRoC layout, i.e. with `data.csv` and `sensor.yaml`.
+#[derive(Debug, Clone)]
+pub struct CustomSensor {
+    source: Arc<dyn DataSource>,
+    path: PathBuf,
+    index: IndexCache,
+}
+
+impl CustomSensor {
+    pub fn new(path: PathBuf) -> Result<Self> {
+        Self::with_source(FileSystem::shared(), path)
+    }
+
+    /// Open the folder `path` of `source`.
+    pub fn with_source(source: Arc<dyn DataSource>, path: PathBuf) -> Result<Self> {
+        source.require_dir(&path)?;
+        source.require_file(&path.join(DATA_CSV))?;
+        source.require_file(&path.join(SENSOR_YAML))?;
+
+        Ok(Self {
+            index: IndexCache::new(Arc::clone(&source)),
+            source,
+            path,
+        })
+    }
+
+    #[inline]
+    fn read_sensor_yaml(&self) -> Result<SensorYaml> {
+        SensorYaml::load(&*self.source, self.path.join(SENSOR_YAML))
+    }
+
+    /// Return the `sensor_type` of `sensor.yaml`.
+    pub fn sensor_type(&self) -> Result<String> {
+        self.read_sensor_yaml()?.str("sensor_type")
+    }
+
+    /// Return extrinsics wrt. the body-frame.
+    pub fn extrinsics(&self) -> Result<na::Matrix4<f64>> {
+        self.read_sensor_yaml()?.matrix4("T_BS")
+    }
+
+    /// Return extrinsics wrt. the body-frame as a rigid body transform.
+    ///
+    /// Fails if `T_BS` is not orthonormal. Use [`na::Isometry3::inverse`] for `T_SB`.
+    pub fn extrinsics_isometry(&self) -> Result<na::Isometry3<f64>> {
+        self.read_sensor_yaml()?.isometry("T_BS")
+    }
+
+    /// Return the number of rows, counting the rows of `data.csv` on the first call.
+    pub fn len(&self) -> Result<usize> {
+        self.index.row_count(&self.path.join(DATA_CSV))
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Iterate over the raw rows in `data.csv` order.
+    pub fn rows(&self) -> Result<IndexedRows> {
+        self.index.rows(self.path.join(DATA_CSV))
+    }
+
+    /// Iterate over rows with `start <= timestamp < end`, expecting the timestamp in column 0.
+    pub fn rows_between(&self, start: Timestamp, end: Timestamp) -> Result<IndexedRows> {
+        self.index
+            .rows_between(self.path.join(DATA_CSV), start, end)
+    }
+
+    /// Iterate over typed records in `data.csv` order.
+    pub fn records<T: FromCsvRow>(&self) -> Result<CustomRecords<ParseRow<T>>> {
+        self.records_with(T::from_csv_row)
+    }
+
+    /// Iterate over records parsed by `parse` in `data.csv` order.
+    pub fn records_with<T, F>(&self, parse: F) -> Result<CustomRecords<F>>
+    where
+        F: FnMut(&Row) -> Result<T>,
+    {
+        Ok(CustomRecords {
+            rows: self.rows()?,
+            parse,
+        })
+    }
+}
+
+impl EuRoC {
+    /// Open the sensor folder `name`, e.g. a user-added `cam2`.
+    pub fn sensor(&self, name: &str) -> Result<CustomSensor> {
+        CustomSensor::with_source(Arc::clone(&self.source), self.root.join(name))
+    }
+}
+
+pub struct CustomRecords<F> {
+    rows: IndexedRows,
+    parse: F,
+}
+
+impl<T, F> Iterator for CustomRecords<F>
+where
+    F: FnMut(&Row) -> Result<T>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.rows.next()?;
+        Some(row.and_then(|row| (self.parse)(&row)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl<T, F> DoubleEndedIterator for CustomRecords<F>
+where
+    F: FnMut(&Row) -> Result<T>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let row = self.rows.next_back()?;
+        Some(row.and_then(|row| (self.parse)(&row)))
+    }
+}
+
+impl<T, F> ExactSizeIterator for CustomRecords<F> where F: FnMut(&Row) -> Result<T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EurocError;
+
+    struct Accel {
+        timestamp: Timestamp,
+        accel: na::Vector3<f64>,
+    }
+
+    impl FromCsvRow for Accel {
+        fn from_csv_row(row: &Row) -> Result<Self> {
+            Ok(Self {
+                timestamp: row.timestamp()?,
+                accel: row.vector3(4)?,
+            })
+        }
+    }
+
+    #[test]
+    fn custom_sensor() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let sensor = data.sensor("imu0")?;
+        assert_eq!(sensor.sensor_type()?, "imu");
+        assert_eq!(sensor.extrinsics()?, data.imu()?.extrinsics()?);
+        assert_eq!(sensor.len()?, 5);
+
+        let imu = data.imu()?.records()?.collect::<Result<Vec<_>>>()?;
+        let accel = sensor.records::<Accel>()?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(accel[2].timestamp, imu[2].timestamp);
+        assert_eq!(accel[2].accel, imu[2].accel);
+
+        let columns = sensor.records_with(|row| Ok(row.len()))?.next().unwrap()?;
+        assert_eq!(columns, 7);
+
+        assert!(matches!(
+            data.sensor("lidar0"),
+            Err(EurocError::MissingFile(_))
+        ));
+
+        Ok(())
+    }
+}
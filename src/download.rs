@@ -0,0 +1,464 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    time,
+};
+
+use crate::{EuRoC, EurocError, Result};
+
+const BASE_URL: &str = "http://robotics.ethz.ch/~asl-datasets/ijrr_euroc_mav_dataset";
+const MAX_REDIRECTS: usize = 5;
+/// Timeout of connecting and of every read or write, so that a stalled server fails the download.
+const TIMEOUT: time::Duration = time::Duration::from_secs(30);
+
+/// Sequences of the EuRoC MAV dataset, as published by ETH Zurich.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sequence {
+    Mh01Easy,
+    Mh02Easy,
+    Mh03Medium,
+    Mh04Difficult,
+    Mh05Difficult,
+    V101Easy,
+    V102Medium,
+    V103Difficult,
+    V201Easy,
+    V202Medium,
+    V203Difficult,
+}
+
+impl Sequence {
+    pub const ALL: [Self; 11] = [
+        Self::Mh01Easy,
+        Self::Mh02Easy,
+        Self::Mh03Medium,
+        Self::Mh04Difficult,
+        Self::Mh05Difficult,
+        Self::V101Easy,
+        Self::V102Medium,
+        Self::V103Difficult,
+        Self::V201Easy,
+        Self::V202Medium,
+        Self::V203Difficult,
+    ];
+
+    /// Return the official name, e.g. `MH_01_easy`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Mh01Easy => "MH_01_easy",
+            Self::Mh02Easy => "MH_02_easy",
+            Self::Mh03Medium => "MH_03_medium",
+            Self::Mh04Difficult => "MH_04_difficult",
+            Self::Mh05Difficult => "MH_05_difficult",
+            Self::V101Easy => "V1_01_easy",
+            Self::V102Medium => "V1_02_medium",
+            Self::V103Difficult => "V1_03_difficult",
+            Self::V201Easy => "V2_01_easy",
+            Self::V202Medium => "V2_02_medium",
+            Self::V203Difficult => "V2_03_difficult",
+        }
+    }
+
+    /// Return the environment folder of the download server.
+    const fn environment(self) -> &'static str {
+        match self {
+            Self::Mh01Easy
+            | Self::Mh02Easy
+            | Self::Mh03Medium
+            | Self::Mh04Difficult
+            | Self::Mh05Difficult => "machine_hall",
+            Self::V101Easy | Self::V102Medium | Self::V103Difficult => "vicon_room1",
+            Self::V201Easy | Self::V202Medium | Self::V203Difficult => "vicon_room2",
+        }
+    }
+
+    /// Return the URL of the `.zip` archive.
+    pub fn url(self) -> String {
+        format!(
+            "{}/{}/{}/{}.zip",
+            BASE_URL,
+            self.environment(),
+            self.name(),
+            self.name()
+        )
+    }
+
+    /// Download the archive into `dir` unless already there, and open it.
+    ///
+    /// The archive is stored as `<name>.zip` and read without extracting it,
+    /// see [`EuRoC::from_zip`]. An interrupted download is kept as
+    /// `<name>.zip.part` and resumed by the next call. `on_progress` is called
+    /// after every chunk.
+    ///
+    /// # HTTPS is not supported
+    ///
+    /// This crate has no TLS dependency, so only plain `http://` URLs and
+    /// redirects are followed. A server redirecting to `https://` fails with
+    /// [`EurocError::Download`]; download the archive with another tool into
+    /// `dir` as `<name>.zip` instead, and it is opened without a request.
+    ///
+    /// A connection stalled for 30 s fails with [`EurocError::Io`], keeping
+    /// the partial download for the next call.
+    pub fn download_to<P, F>(self, dir: P, on_progress: F) -> Result<EuRoC>
+    where
+        P: AsRef<Path>,
+        F: FnMut(Progress),
+    {
+        let zip = dir.as_ref().join(format!("{}.zip", self.name()));
+        if !zip.is_file() {
+            fs::create_dir_all(dir.as_ref())?;
+            download(&self.url(), &zip, on_progress)?;
+        }
+
+        EuRoC::from_zip(zip)
+    }
+}
+
+/// State of a download, reported by [`Sequence::download_to`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// bytes on disk, including those of a resumed download
+    pub downloaded: u64,
+    /// size of the archive, if the server reported it
+    pub total: Option<u64>,
+}
+
+/// Download `url` to `path` through `<path>.part`, resuming a previous attempt.
+fn download<F: FnMut(Progress)>(url: &str, path: &Path, mut on_progress: F) -> Result<()> {
+    let part = PathBuf::from(format!("{}.part", path.display()));
+    let offset = fs::metadata(&part).map_or(0, |m| m.len());
+
+    let mut response = get(url, offset)?;
+    let (mut downloaded, append) = match response.status {
+        200 => (0, false),
+        206 => (offset, true),
+        // the previous attempt already got everything
+        416 if offset > 0 => {
+            fs::rename(part, path)?;
+            return Ok(());
+        }
+        status => return Err(download_error(format!("{}: HTTP {}", url, status))),
+    };
+    let total = response.length.map(|length| length + downloaded);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&part)?;
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let n = response.body.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buf[..n])?;
+        downloaded += n as u64;
+        on_progress(Progress { downloaded, total });
+    }
+    file.flush()?;
+
+    if total.map_or(false, |total| total != downloaded) {
+        return Err(download_error(format!(
+            "{}: connection closed after {} bytes",
+            url, downloaded
+        )));
+    }
+    fs::rename(part, path)?;
+
+    Ok(())
+}
+
+struct Response {
+    status: u16,
+    /// `Content-Length`, i.e. bytes of `body`
+    length: Option<u64>,
+    body: Box<dyn Read>,
+}
+
+/// Send a GET request for the bytes of `url` from `offset` on, following redirects.
+fn get(url: &str, offset: u64) -> Result<Response> {
+    let mut url = url.to_owned();
+    for _ in 0..=MAX_REDIRECTS {
+        let (host, port, path) = parse_url(&url)?;
+        let mut stream = connect(host, port)?;
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {}\r\nRange: bytes={}-\r\nUser-Agent: euroc/{}\r\nConnection: close\r\n\r\n",
+            path,
+            host,
+            offset,
+            env!("CARGO_PKG_VERSION")
+        )?;
+
+        let mut reader = BufReader::new(stream);
+        let status = read_line(&mut reader)?
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse::<u16>().ok())
+            .ok_or_else(|| download_error(format!("{}: bad status line", url)))?;
+
+        let (mut length, mut location) = (None, None);
+        loop {
+            let line = read_line(&mut reader)?;
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = match line.find(':') {
+                Some(i) => (line[..i].trim().to_ascii_lowercase(), line[i + 1..].trim()),
+                None => continue,
+            };
+            match name.as_str() {
+                "content-length" => length = value.parse().ok(),
+                "location" => location = Some(value.to_owned()),
+                "transfer-encoding" if !value.eq_ignore_ascii_case("identity") => {
+                    return Err(download_error(format!(
+                        "{}: unsupported transfer encoding `{}`",
+                        url, value
+                    )))
+                }
+                _ => {}
+            }
+        }
+
+        match (status, location) {
+            (status, Some(location)) if matches!(status, 301 | 302 | 303 | 307 | 308) => {
+                url = resolve(&url, &location)
+            }
+            _ => {
+                let body: Box<dyn Read> = match length {
+                    Some(length) => Box::new(reader.take(length)),
+                    None => Box::new(reader),
+                };
+                return Ok(Response {
+                    status,
+                    length,
+                    body,
+                });
+            }
+        }
+    }
+
+    Err(download_error(format!("{}: too many redirects", url)))
+}
+
+/// Connect to `host` with [`TIMEOUT`], trying each of its addresses.
+fn connect(host: &str, port: u16) -> Result<TcpStream> {
+    let mut last_error = None;
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, TIMEOUT) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(TIMEOUT))?;
+                stream.set_write_timeout(Some(TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{}: no address", host)))
+        .into())
+}
+
+/// Return the URL a `Location` header of a response to `base` points to.
+fn resolve(base: &str, location: &str) -> String {
+    if location.contains("://") {
+        return location.to_owned();
+    }
+    let scheme_end = base.find("://").map_or(0, |i| i + 3);
+    if location.starts_with("//") {
+        return format!("{}{}", &base[..scheme_end - 2], location);
+    }
+    let path_start = base[scheme_end..]
+        .find('/')
+        .map_or(base.len(), |i| scheme_end + i);
+    if location.starts_with('/') {
+        format!("{}{}", &base[..path_start], location)
+    } else {
+        // relative to the folder of `base`
+        let dir_end = base[path_start..]
+            .rfind('/')
+            .map_or(base.len(), |i| path_start + i);
+        format!("{}/{}", &base[..dir_end], location)
+    }
+}
+
+/// Split an `http://` URL into host, port and path.
+fn parse_url(url: &str) -> Result<(&str, u16, &str)> {
+    if url.starts_with("https://") {
+        return Err(download_error(format!(
+            "{}: HTTPS is not supported, download the archive manually",
+            url
+        )));
+    }
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| download_error(format!("{}: only http:// is supported", url)))?;
+    let (authority, path) = rest
+        .find('/')
+        .map_or((rest, "/"), |i| (&rest[..i], &rest[i..]));
+    let (host, port) = match authority.rfind(':') {
+        Some(i) => (
+            &authority[..i],
+            authority[i + 1..]
+                .parse()
+                .map_err(|_| download_error(format!("{}: bad port", url)))?,
+        ),
+        None => (authority, 80),
+    };
+
+    Ok((host, port, path))
+}
+
+fn read_line<R: BufRead>(reader: &mut R) -> io::Result<String> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    Ok(line.trim_end().to_owned())
+}
+
+const fn download_error(message: String) -> EurocError {
+    EurocError::Download(message)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{net::TcpListener, thread};
+
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn urls() -> Result<()> {
+        assert_eq!(
+            Sequence::V102Medium.url(),
+            "http://robotics.ethz.ch/~asl-datasets/ijrr_euroc_mav_dataset/vicon_room1/V1_02_medium/V1_02_medium.zip"
+        );
+        assert_eq!(
+            parse_url("http://localhost:8080/a/b.zip")?,
+            ("localhost", 8080, "/a/b.zip")
+        );
+        assert!(matches!(
+            parse_url("https://example.com/"),
+            Err(EurocError::Download(message)) if message.contains("HTTPS")
+        ));
+
+        let base = "http://example.com:8080/a/b.zip";
+        assert_eq!(
+            resolve(base, "https://example.org/c"),
+            "https://example.org/c"
+        );
+        assert_eq!(resolve(base, "//example.org/c"), "http://example.org/c");
+        assert_eq!(resolve(base, "/c/d.zip"), "http://example.com:8080/c/d.zip");
+        assert_eq!(resolve(base, "d.zip"), "http://example.com:8080/a/d.zip");
+        assert_eq!(
+            resolve("http://example.com", "d.zip"),
+            "http://example.com/d.zip"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn relative_redirect() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/seq.zip", listener.local_addr()?);
+
+        let server = thread::spawn(move || -> io::Result<Vec<String>> {
+            let mut requests = Vec::new();
+            for stream in listener.incoming().take(2) {
+                let mut stream = stream?;
+                let mut reader = BufReader::new(stream.try_clone()?);
+                let request = read_line(&mut reader)?;
+                while !read_line(&mut reader)?.is_empty() {}
+                if requests.is_empty() {
+                    write!(
+                        stream,
+                        "HTTP/1.1 302 Found\r\nLocation: moved/seq.zip\r\n\r\n"
+                    )?;
+                } else {
+                    write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nzip")?;
+                }
+                requests.push(request);
+            }
+            Ok(requests)
+        });
+
+        let mut response = get(&url, 0)?;
+        let mut body = String::new();
+        response.body.read_to_string(&mut body)?;
+        assert_eq!((response.status, body.as_str()), (200, "zip"));
+        assert_eq!(
+            server.join().unwrap()?,
+            vec![
+                "GET /seq.zip HTTP/1.1".to_owned(),
+                "GET /moved/seq.zip HTTP/1.1".to_owned()
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn resume() -> Result<()> {
+        let body: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/seq.zip", listener.local_addr()?);
+
+        let served = body.clone();
+        let server = thread::spawn(move || -> io::Result<Vec<String>> {
+            let mut ranges = Vec::new();
+            for stream in listener.incoming().take(2) {
+                let mut stream = stream?;
+                let mut reader = BufReader::new(stream.try_clone()?);
+                let mut offset = 0;
+                loop {
+                    let line = read_line(&mut reader)?;
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(range) = line.strip_prefix("Range: ") {
+                        ranges.push(range.to_owned());
+                        offset = range[6..range.len() - 1].parse().unwrap_or(0);
+                    }
+                }
+                let rest = &served[offset..];
+                write!(
+                    stream,
+                    "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\n\r\n",
+                    rest.len()
+                )?;
+                stream.write_all(rest)?;
+            }
+            Ok(ranges)
+        });
+
+        let dir = TempDir::new("download_resume")?;
+        let path = dir.join("seq.zip");
+        fs::write(dir.join("seq.zip.part"), &body[..50_000])?;
+
+        let mut last = None;
+        download(&url, &path, |progress| last = Some(progress))?;
+        assert_eq!(fs::read(&path)?, body);
+        assert!(!dir.join("seq.zip.part").exists());
+        assert_eq!(
+            last,
+            Some(Progress {
+                downloaded: 200_000,
+                total: Some(200_000)
+            })
+        );
+
+        fs::write(dir.join("again.zip.part"), &body[..10])?;
+        download(&url, &dir.join("again.zip"), |_| {})?;
+        assert_eq!(
+            server.join().unwrap()?,
+            vec!["bytes=50000-".to_owned(), "bytes=10-".to_owned()]
+        );
+
+        Ok(())
+    }
+}
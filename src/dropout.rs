@@ -0,0 +1,284 @@
+use std::io::Write;
+
+use crate::{estimate_rate, Duration, EuRoC, Result, Sensor, Timestamp};
+
+/// An interval is a dropout if it exceeds the nominal interval by this factor.
+const DROPOUT_FACTOR: f64 = 1.5;
+
+/// Lower edges of the [`DropoutStats::gap_histogram`] bins, in nominal intervals.
+pub const GAP_BINS: [usize; 4] = [2, 3, 5, 10];
+
+const CSV_HEADER: [&str; 12] = [
+    "sequence",
+    "sensor",
+    "samples",
+    "duration [s]",
+    "nominal [Hz]",
+    "effective [Hz]",
+    "dropouts",
+    "missing",
+    "gaps 2",
+    "gaps 3-4",
+    "gaps 5-9",
+    "gaps >=10",
+];
+
+/// Samples missing between two consecutive timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dropout {
+    /// last timestamp before the gap
+    pub start: Timestamp,
+    /// first timestamp after the gap
+    pub end: Timestamp,
+    /// number of samples expected in between at the nominal rate
+    pub missing: usize,
+}
+
+/// Dropouts of one sensor of one sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DropoutStats {
+    pub sequence: String,
+    pub sensor: Sensor,
+    pub samples: usize,
+    pub duration: Duration,
+    /// `rate_hz` of `sensor.yaml`, or the median rate for sensors without one
+    pub nominal_hz: f64,
+    /// samples per second actually recorded
+    pub effective_hz: f64,
+    pub dropouts: Vec<Dropout>,
+    /// number of dropouts per gap length, binned by [`GAP_BINS`]
+    pub gap_histogram: [usize; 4],
+}
+
+impl DropoutStats {
+    /// Find the dropouts of `timestamps`, which must be sorted.
+    ///
+    /// Return `None` if fewer than two timestamps are given.
+    pub fn new(
+        sequence: &str,
+        sensor: Sensor,
+        nominal_hz: Option<f64>,
+        timestamps: &[Timestamp],
+    ) -> Option<Self> {
+        let duration = *timestamps.last()? - timestamps[0];
+        if timestamps.len() < 2 || duration <= Duration::ZERO {
+            return None;
+        }
+        let nominal_hz = match nominal_hz {
+            Some(hz) => hz,
+            None => estimate_rate(timestamps.iter().copied())?.median_hz(),
+        };
+
+        let nominal = 1.0 / nominal_hz;
+        let mut dropouts = Vec::new();
+        let mut gap_histogram = [0; 4];
+        for w in timestamps.windows(2) {
+            let periods = (w[1] - w[0]).as_secs_f64() / nominal;
+            if periods <= DROPOUT_FACTOR {
+                continue;
+            }
+
+            let periods = periods.round() as usize;
+            if let Some(bin) = GAP_BINS.iter().rposition(|&edge| periods >= edge) {
+                gap_histogram[bin] += 1;
+            }
+            dropouts.push(Dropout {
+                start: w[0],
+                end: w[1],
+                missing: periods.max(2) - 1,
+            });
+        }
+
+        Some(Self {
+            sequence: sequence.to_owned(),
+            sensor,
+            samples: timestamps.len(),
+            duration,
+            nominal_hz,
+            effective_hz: (timestamps.len() - 1) as f64 / duration.as_secs_f64(),
+            dropouts,
+            gap_histogram,
+        })
+    }
+
+    /// Return the number of samples missing over all dropouts.
+    pub fn missing(&self) -> usize {
+        self.dropouts.iter().map(|d| d.missing).sum()
+    }
+}
+
+/// Dropout statistics of several sequences, for the dataset-quality appendix of a paper.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DropoutReport {
+    pub stats: Vec<DropoutStats>,
+}
+
+impl DropoutReport {
+    /// Collect the statistics of `sensors` in every named sequence.
+    ///
+    /// Missing sensors and sensors with fewer than two records are left out.
+    pub fn new(sequences: &[(&str, &EuRoC)], sensors: &[Sensor]) -> Result<Self> {
+        let mut stats = Vec::new();
+        for &(name, data) in sequences {
+            for &sensor in sensors {
+                if !data.source().is_dir(&data.sensor_dir(sensor)) {
+                    continue;
+                }
+                let timestamps = data.timestamps(sensor)?;
+                stats.extend(DropoutStats::new(
+                    name,
+                    sensor,
+                    data.nominal_hz(sensor)?,
+                    &timestamps,
+                ));
+            }
+        }
+
+        Ok(Self { stats })
+    }
+
+    /// Write one row per sequence and sensor as CSV.
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(CSV_HEADER.iter())?;
+
+        for s in &self.stats {
+            let mut row = vec![
+                s.sequence.clone(),
+                s.sensor.dir_name().to_owned(),
+                s.samples.to_string(),
+                format!("{:.3}", s.duration.as_secs_f64()),
+                format!("{:.1}", s.nominal_hz),
+                format!("{:.3}", s.effective_hz),
+                s.dropouts.len().to_string(),
+                s.missing().to_string(),
+            ];
+            row.extend(s.gap_histogram.iter().map(ToString::to_string));
+            writer.write_record(&row)?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Write every dropout interval as CSV.
+    pub fn write_dropouts_csv<W: Write>(&self, writer: W) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(&["sequence", "sensor", "start [ns]", "end [ns]", "missing"])?;
+
+        for s in &self.stats {
+            for d in &s.dropouts {
+                writer.write_record(&[
+                    s.sequence.clone(),
+                    s.sensor.dir_name().to_owned(),
+                    d.start.nsecs().to_string(),
+                    d.end.nsecs().to_string(),
+                    d.missing.to_string(),
+                ])?;
+            }
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Write the table of [`DropoutReport::write_csv`] as a LaTeX `tabular`.
+    pub fn write_latex<W: Write>(&self, mut writer: W) -> Result<()> {
+        writeln!(writer, "\\begin{{tabular}}{{ll{}}}", "r".repeat(10))?;
+        writeln!(writer, "\\hline")?;
+        writeln!(
+            writer,
+            "Sequence & Sensor & Samples & Duration [s] & Nominal [Hz] & Effective [Hz] \
+             & Dropouts & Missing & 2 & 3--4 & 5--9 & $\\geq$10 \\\\"
+        )?;
+        writeln!(writer, "\\hline")?;
+        for s in &self.stats {
+            writeln!(
+                writer,
+                "{} & {} & {} & {:.3} & {:.1} & {:.3} & {} & {} & {} & {} & {} & {} \\\\",
+                latex_escape(&s.sequence),
+                latex_escape(s.sensor.dir_name()),
+                s.samples,
+                s.duration.as_secs_f64(),
+                s.nominal_hz,
+                s.effective_hz,
+                s.dropouts.len(),
+                s.missing(),
+                s.gap_histogram[0],
+                s.gap_histogram[1],
+                s.gap_histogram[2],
+                s.gap_histogram[3],
+            )?;
+        }
+        writeln!(writer, "\\hline")?;
+        writeln!(writer, "\\end{{tabular}}")?;
+
+        Ok(())
+    }
+}
+
+impl EuRoC {
+    /// Return `rate_hz` of `sensor.yaml`, for the sensors that declare it.
+    pub(crate) fn nominal_hz(&self, sensor: Sensor) -> Result<Option<f64>> {
+        Ok(match sensor {
+            Sensor::Cam0 | Sensor::Cam1 => Some(self.camera(sensor)?.rate_hz()?),
+            Sensor::Imu0 => Some(self.imu()?.rate_hz()?),
+            _ => None,
+        })
+    }
+}
+
+fn latex_escape(text: &str) -> String {
+    text.replace('\\', "\\textbackslash{}")
+        .replace('_', "\\_")
+        .replace('&', "\\&")
+        .replace('%', "\\%")
+        .replace('#', "\\#")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dropouts() {
+        let timestamps: Vec<Timestamp> = [0, 50, 100, 200, 250, 600]
+            .iter()
+            .map(|&ms| (ms * 1_000_000).into())
+            .collect();
+        let stats = DropoutStats::new("seq", Sensor::Cam0, Some(20.0), &timestamps).unwrap();
+
+        assert_eq!(stats.dropouts.len(), 2);
+        assert_eq!(stats.dropouts[0].start, Timestamp::from(100_000_000));
+        assert_eq!(stats.dropouts[0].missing, 1);
+        assert_eq!(stats.missing(), 7);
+        assert_eq!(stats.gap_histogram, [1, 0, 1, 0]);
+        assert!((stats.effective_hz - 5.0 / 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn report() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let report = DropoutReport::new(&[("MH_01_easy", &data)], &Sensor::ALL)?;
+        assert_eq!(report.stats.len(), Sensor::ALL.len());
+        assert!((report.stats[2].nominal_hz - 200.0).abs() < 1e-9);
+
+        let mut csv = Vec::new();
+        report.write_csv(&mut csv)?;
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 1 + report.stats.len());
+        assert!(csv
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("MH_01_easy,cam0,5,"));
+
+        let mut latex = Vec::new();
+        report.write_latex(&mut latex)?;
+        let latex = String::from_utf8(latex).unwrap();
+        assert!(latex.contains("MH\\_01\\_easy & imu0 & 5 &"));
+        assert!(latex.ends_with("\\end{tabular}\n"));
+
+        Ok(())
+    }
+}
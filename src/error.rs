@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub type Result<T, E = EurocError> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum EurocError {
+    #[error("missing file or directory `{0}`")]
+    MissingFile(PathBuf),
+    #[error("{}:{line}: {message}", path.display())]
+    CsvParse {
+        path: PathBuf,
+        line: u64,
+        message: String,
+    },
+    #[error("{}: cannot parse YAML: {message}", file.display())]
+    YamlParse { file: PathBuf, message: String },
+    #[error("{}: `{key}` is missing or malformed", file.display())]
+    YamlField { file: PathBuf, key: String },
+    #[error("invalid PLY: {0}")]
+    PlyParse(String),
+    #[error("invalid zip: {0}")]
+    ZipParse(String),
+    #[error("invalid rosbag: {0}")]
+    RosbagParse(String),
+    #[error("download failed: {0}")]
+    Download(String),
+    /// Invalid argument or not enough data for the requested computation.
+    #[error("{0}")]
+    InvalidInput(String),
+    /// Error while writing a CSV file.
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+}
@@ -0,0 +1,167 @@
+use nalgebra as na;
+
+use crate::{
+    Alignment, Duration, EuRoC, Result, Sensor, SensorEvent, Timestamp, Timestamped,
+    TrajectoryEvaluation,
+};
+
+/// State estimator run on a sequence by [`EuRoC::run_estimator`].
+pub trait Estimator {
+    /// Return the sensors whose records are passed to [`Estimator::process`].
+    fn sensors(&self) -> Vec<Sensor>;
+
+    /// Process one record; records arrive in timestamp order.
+    ///
+    /// Return the estimated body pose wrt. the world frame at the timestamp
+    /// of `event`, if there is one.
+    fn process(&mut self, event: &SensorEvent) -> Option<na::Isometry3<f64>>;
+}
+
+/// Reference estimator returning the ground-truth poses.
+///
+/// Its ATE is zero, so any error reported for it comes from the evaluation
+/// setup, e.g. the association or the alignment.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GroundTruthPassthrough;
+
+impl Estimator for GroundTruthPassthrough {
+    fn sensors(&self) -> Vec<Sensor> {
+        vec![Sensor::GroundTruth]
+    }
+
+    fn process(&mut self, event: &SensorEvent) -> Option<na::Isometry3<f64>> {
+        match event {
+            SensorEvent::GroundTruth(record) => Some(record.pose()),
+            _ => None,
+        }
+    }
+}
+
+/// Dead reckoner extrapolating the first ground-truth pose with its velocity.
+///
+/// It outputs a pose at every IMU record, keeping the initial orientation.
+/// Its error grows with how far the motion departs from constant velocity,
+/// a lower bar for real estimators.
+#[derive(Debug, Clone, Default)]
+pub struct ConstantVelocity {
+    start: Option<(Timestamp, na::Isometry3<f64>, na::Vector3<f64>)>,
+}
+
+impl Estimator for ConstantVelocity {
+    fn sensors(&self) -> Vec<Sensor> {
+        vec![Sensor::GroundTruth, Sensor::Imu0]
+    }
+
+    fn process(&mut self, event: &SensorEvent) -> Option<na::Isometry3<f64>> {
+        if let SensorEvent::GroundTruth(record) = event {
+            if self.start.is_none() {
+                self.start = Some((record.timestamp, record.pose(), record.velocity));
+            }
+        }
+        match (event, &self.start) {
+            (SensorEvent::Imu(record), Some((t0, pose, velocity))) => {
+                let dt = (record.timestamp - *t0).as_secs_f64();
+                let mut pose = *pose;
+                pose.translation.vector += velocity * dt;
+                Some(pose)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl EuRoC {
+    /// Feed the records of [`Estimator::sensors`] to `estimator` and collect its poses.
+    pub fn run_estimator<E: Estimator>(
+        &self,
+        estimator: &mut E,
+    ) -> Result<Vec<(Timestamp, na::Isometry3<f64>)>> {
+        let mut poses = Vec::new();
+        for event in self.events(&estimator.sensors())? {
+            let event = event?;
+            if let Some(pose) = estimator.process(&event) {
+                poses.push((event.timestamp(), pose));
+            }
+        }
+
+        Ok(poses)
+    }
+
+    /// Run `estimator` and evaluate its poses against the ground truth, see
+    /// [`crate::GroundTruthData::evaluate_trajectory`].
+    pub fn benchmark<E: Estimator>(
+        &self,
+        estimator: &mut E,
+        max_dt: Duration,
+        alignment: Alignment,
+        rpe_deltas: &[usize],
+    ) -> Result<TrajectoryEvaluation> {
+        let poses = self.run_estimator(estimator)?;
+        self.ground_truth()?
+            .evaluate_trajectory(&poses, max_dt, alignment, rpe_deltas)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{GroundTruthRecord, MemorySource};
+
+    /// `test_data` with a ground truth at the IMU rate, moving along x with `x(k)`.
+    fn dataset(x: impl Fn(u64) -> f64) -> Result<EuRoC> {
+        let ground_truth: Vec<_> = (0..5)
+            .map(|k| GroundTruthRecord {
+                velocity: na::Vector3::x() * (x(k + 1) - x(k)) / 0.005,
+                ..GroundTruthRecord::from_pose(
+                    (1403636579758555392 + k * 5_000_000).into(),
+                    na::Isometry3::translation(x(k), 0.0, 0.0),
+                )
+            })
+            .collect();
+        EuRoC::with_ground_truth(MemorySource::from_dir("test_data")?, &ground_truth)
+    }
+
+    #[test]
+    fn ground_truth_passthrough() -> Result<()> {
+        let data = dataset(|k| (k * k) as f64 * 0.01)?;
+        let evaluation = data.benchmark(
+            &mut GroundTruthPassthrough,
+            Duration::from_nsecs(0),
+            Alignment::None,
+            &[1],
+        )?;
+        assert_eq!(evaluation.ate.count, 5);
+        assert!(evaluation.ate.max < 1e-12);
+
+        Ok(())
+    }
+
+    #[test]
+    fn constant_velocity() -> Result<()> {
+        let max_dt = Duration::from_millis(1);
+
+        let data = dataset(|k| k as f64 * 0.005)?;
+        let poses = data.run_estimator(&mut ConstantVelocity::default())?;
+        assert_eq!(poses.len(), 5);
+        let evaluation = data.benchmark(
+            &mut ConstantVelocity::default(),
+            max_dt,
+            Alignment::None,
+            &[],
+        )?;
+        assert_eq!(evaluation.ate.count, 5);
+        assert!(evaluation.ate.max < 1e-6);
+
+        // accelerating, so the dead reckoner falls behind
+        let data = dataset(|k| (k * k) as f64 * 0.01)?;
+        let evaluation = data.benchmark(
+            &mut ConstantVelocity::default(),
+            max_dt,
+            Alignment::None,
+            &[],
+        )?;
+        assert!(evaluation.ate.max > 0.01);
+
+        Ok(())
+    }
+}
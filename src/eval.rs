@@ -0,0 +1,643 @@
+use std::cmp::Ordering;
+
+use nalgebra as na;
+
+use crate::{Duration, EurocError, GroundTruthData, PositionData, Result, Timestamp, ViconData};
+
+/// Spatial alignment applied to an estimate before computing errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// Compare in the estimate's own frame.
+    None,
+    /// Rigid body alignment (rotation + translation).
+    Se3,
+    /// Rigid body alignment with scale, for monocular estimates.
+    Sim3,
+}
+
+/// Summary statistics of a set of errors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorStats {
+    /// number of finite errors the statistics are computed from
+    pub count: usize,
+    /// number of NaN or infinite errors left out
+    pub non_finite: usize,
+    pub rmse: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ErrorStats {
+    /// Return `None` if `errors` has no finite value.
+    ///
+    /// NaN and infinite errors, e.g. from NaN rows of an estimate, are left
+    /// out and counted in `non_finite`.
+    pub fn new(errors: &[f64]) -> Option<Self> {
+        let mut sorted: Vec<_> = errors.iter().copied().filter(|e| e.is_finite()).collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        // finite values are totally ordered
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        let n = sorted.len();
+        Some(Self {
+            count: n,
+            non_finite: errors.len() - n,
+            rmse: (sorted.iter().map(|e| e * e).sum::<f64>() / n as f64).sqrt(),
+            mean: sorted.iter().sum::<f64>() / n as f64,
+            median: (sorted[(n - 1) / 2] + sorted[n / 2]) / 2.0,
+            min: sorted[0],
+            max: sorted[n - 1],
+        })
+    }
+}
+
+fn no_finite_errors() -> EurocError {
+    EurocError::InvalidInput("every error is NaN or infinite".to_owned())
+}
+
+/// Interval of an error statistic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorInterval {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Zero-mean Gaussian noise model of ground-truth positions.
+///
+/// Measured errors include the ground-truth noise, which biases the RMSE
+/// upwards: `E[e^2] = d^2 + sum(sigma^2)` for a true error `d`. The intervals
+/// below remove this bias and account for its spread, so that two estimates
+/// whose intervals overlap are not significantly different.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroundTruthUncertainty {
+    /// per-axis standard deviation (m)
+    pub position_sigma: na::Vector3<f64>,
+}
+
+impl GroundTruthUncertainty {
+    pub const fn new(position_sigma: na::Vector3<f64>) -> Self {
+        Self { position_sigma }
+    }
+
+    /// Return the interval of the true RMSE, `z` standard deviations wide on each side.
+    ///
+    /// `stats` are absolute translation errors, each involving one ground-truth position.
+    pub fn rmse_interval(&self, stats: &ErrorStats, z: f64) -> ErrorInterval {
+        self.interval(stats, z, self.position_sigma.map(|s| s * s))
+    }
+
+    /// Like [`GroundTruthUncertainty::rmse_interval`] for relative translation errors,
+    /// each involving two ground-truth positions.
+    pub fn relative_rmse_interval(&self, stats: &ErrorStats, z: f64) -> ErrorInterval {
+        self.interval(stats, z, self.position_sigma.map(|s| 2.0 * s * s))
+    }
+
+    fn interval(&self, stats: &ErrorStats, z: f64, variance: na::Vector3<f64>) -> ErrorInterval {
+        let mse = stats.rmse.mul_add(stats.rmse, -variance.sum()).max(0.0);
+        // Var(|d + n|^2) = 2 sum(sigma^4) + 4 d' Sigma d, the latter bounded by the largest axis
+        let spread = 2.0f64.mul_add(variance.norm_squared(), 4.0 * mse * variance.max());
+        let half_width = z * (spread / stats.count as f64).sqrt();
+
+        ErrorInterval {
+            lower: (mse - half_width).max(0.0).sqrt(),
+            upper: (mse + half_width).sqrt(),
+        }
+    }
+}
+
+/// Associate each estimate timestamp with the nearest reference timestamp.
+///
+/// Both slices must be sorted. Pairs further apart than `max_dt` are dropped.
+/// Return `(estimate index, reference index)` pairs.
+pub fn associate(
+    estimate: &[Timestamp],
+    reference: &[Timestamp],
+    max_dt: Duration,
+) -> Vec<(usize, usize)> {
+    estimate
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &ts)| {
+            let j = match reference.binary_search(&ts) {
+                Ok(j) => j,
+                Err(0) => 0,
+                Err(j) if j == reference.len() => j - 1,
+                Err(j) => {
+                    if ts - reference[j - 1] <= reference[j] - ts {
+                        j - 1
+                    } else {
+                        j
+                    }
+                }
+            };
+            let j = reference.get(j).map(|_| j)?;
+
+            if (reference[j] - ts).abs() <= max_dt {
+                Some((i, j))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Return the similarity transform `T` minimizing `sum |target - T * source|^2`.
+///
+/// Scale is fixed to 1 unless `with_scale` is set (S. Umeyama, 1991).
+/// Return `None` for fewer than three correspondences or degenerate input.
+pub fn umeyama(
+    source: &[na::Vector3<f64>],
+    target: &[na::Vector3<f64>],
+    with_scale: bool,
+) -> Option<na::Similarity3<f64>> {
+    if source.len() != target.len() || source.len() < 3 {
+        return None;
+    }
+
+    let n = source.len() as f64;
+    let mu_s = source.iter().sum::<na::Vector3<f64>>() / n;
+    let mu_t = target.iter().sum::<na::Vector3<f64>>() / n;
+
+    let sigma_s = source
+        .iter()
+        .map(|s| (s - mu_s).norm_squared())
+        .sum::<f64>()
+        / n;
+    let cov = source
+        .iter()
+        .zip(target)
+        .map(|(s, t)| (t - mu_t) * (s - mu_s).transpose())
+        .sum::<na::Matrix3<f64>>()
+        / n;
+
+    if sigma_s <= f64::EPSILON {
+        return None;
+    }
+
+    let svd = cov.svd(true, true);
+    let (u, v_t) = (svd.u?, svd.v_t?);
+
+    let mut s = na::Vector3::new(1.0, 1.0, 1.0);
+    if u.determinant() * v_t.determinant() < 0.0 {
+        s.z = -1.0;
+    }
+
+    let rotation = u * na::Matrix3::from_diagonal(&s) * v_t;
+    let scale = if with_scale {
+        svd.singular_values.dot(&s) / sigma_s
+    } else {
+        1.0
+    };
+    let translation = mu_t - scale * rotation * mu_s;
+
+    Some(na::Similarity3::from_parts(
+        translation.into(),
+        na::UnitQuaternion::from_rotation_matrix(&na::Rotation3::from_matrix_unchecked(rotation)),
+        scale,
+    ))
+}
+
+/// Result of comparing estimated positions against a reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionEvaluation {
+    /// transform applied to the estimate (identity for [`Alignment::None`])
+    pub alignment: na::Similarity3<f64>,
+    /// translation errors (m) of the associated pairs
+    pub ate: ErrorStats,
+}
+
+/// Compute translation-only ATE of `estimate` against `reference`.
+///
+/// Both streams must be sorted by timestamp and are associated by nearest
+/// timestamp within `max_dt`. This is the only temporal alignment: no time
+/// offset between the clocks is estimated, so an offset larger than the
+/// sample spacing must be removed from the estimate timestamps beforehand.
+pub fn evaluate_positions(
+    estimate: &[(Timestamp, na::Vector3<f64>)],
+    reference: &[(Timestamp, na::Vector3<f64>)],
+    max_dt: Duration,
+    alignment: Alignment,
+) -> Result<PositionEvaluation> {
+    let est_ts: Vec<_> = estimate.iter().map(|(ts, _)| *ts).collect();
+    let ref_ts: Vec<_> = reference.iter().map(|(ts, _)| *ts).collect();
+    let pairs = associate(&est_ts, &ref_ts, max_dt);
+    if pairs.is_empty() {
+        return Err(EurocError::InvalidInput(
+            "no associated timestamps".to_owned(),
+        ));
+    }
+
+    let source: Vec<_> = pairs.iter().map(|&(i, _)| estimate[i].1).collect();
+    let target: Vec<_> = pairs.iter().map(|&(_, j)| reference[j].1).collect();
+
+    let alignment = match alignment {
+        Alignment::None => Some(na::Similarity3::identity()),
+        Alignment::Se3 => umeyama(&source, &target, false),
+        Alignment::Sim3 => umeyama(&source, &target, true),
+    };
+    let alignment =
+        alignment.ok_or_else(|| EurocError::InvalidInput("alignment is degenerate".to_owned()))?;
+
+    let errors: Vec<_> = source
+        .iter()
+        .zip(&target)
+        .map(|(s, t)| (t - alignment.transform_point(&(*s).into()).coords).norm())
+        .collect();
+
+    Ok(PositionEvaluation {
+        alignment,
+        ate: ErrorStats::new(&errors).ok_or_else(no_finite_errors)?,
+    })
+}
+
+impl PositionData {
+    /// Compute translation-only ATE of `estimate` against the measured positions.
+    ///
+    /// The measurements are prism positions, which differ from the body origin
+    /// by the lever arm in [`PositionData::extrinsics`].
+    pub fn evaluate_positions(
+        &self,
+        estimate: &[(Timestamp, na::Vector3<f64>)],
+        max_dt: Duration,
+        alignment: Alignment,
+    ) -> Result<PositionEvaluation> {
+        let reference = self
+            .records()?
+            .map(|r| r.map(|r| (r.timestamp, r.position)))
+            .collect::<Result<Vec<_>>>()?;
+
+        evaluate_positions(estimate, &reference, max_dt, alignment)
+    }
+}
+
+impl ViconData {
+    /// Compute translation-only ATE of `estimate` against the Vicon positions,
+    /// like [`PositionData::evaluate_positions`].
+    ///
+    /// The measurements are positions of the marker body, which differ from
+    /// the body origin by [`ViconData::extrinsics`].
+    pub fn evaluate_positions(
+        &self,
+        estimate: &[(Timestamp, na::Vector3<f64>)],
+        max_dt: Duration,
+        alignment: Alignment,
+    ) -> Result<PositionEvaluation> {
+        let reference = self
+            .records()?
+            .map(|r| r.map(|r| (r.timestamp, r.position)))
+            .collect::<Result<Vec<_>>>()?;
+
+        evaluate_positions(estimate, &reference, max_dt, alignment)
+    }
+}
+
+/// Relative pose error over a fixed number of associated poses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RelativePoseError {
+    /// step between the compared poses, in associated pairs
+    pub delta: usize,
+    /// translation errors (m)
+    pub translation: ErrorStats,
+    /// rotation errors (rad)
+    pub rotation: ErrorStats,
+}
+
+/// Result of comparing an estimated trajectory against a reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrajectoryEvaluation {
+    /// transform applied to the estimate (identity for [`Alignment::None`])
+    pub alignment: na::Similarity3<f64>,
+    /// absolute translation errors (m)
+    pub ate: ErrorStats,
+    /// absolute rotation errors (rad)
+    pub ate_rotation: ErrorStats,
+    /// one entry per requested delta with enough poses
+    pub rpe: Vec<RelativePoseError>,
+}
+
+/// Compute ATE and RPE of `estimate` against `reference`.
+///
+/// Both streams must be sorted by timestamp and are associated by nearest
+/// timestamp within `max_dt`, without estimating a time offset, like
+/// [`evaluate_positions`]. The alignment is estimated from positions only.
+/// RPE is computed for every step in `rpe_deltas` on the aligned poses.
+pub fn evaluate_trajectory(
+    estimate: &[(Timestamp, na::Isometry3<f64>)],
+    reference: &[(Timestamp, na::Isometry3<f64>)],
+    max_dt: Duration,
+    alignment: Alignment,
+    rpe_deltas: &[usize],
+) -> Result<TrajectoryEvaluation> {
+    let to_positions = |poses: &[(Timestamp, na::Isometry3<f64>)]| -> Vec<_> {
+        poses
+            .iter()
+            .map(|(ts, pose)| (*ts, pose.translation.vector))
+            .collect()
+    };
+    let positions = evaluate_positions(
+        &to_positions(estimate),
+        &to_positions(reference),
+        max_dt,
+        alignment,
+    )?;
+    let transform = positions.alignment;
+
+    let est_ts: Vec<_> = estimate.iter().map(|(ts, _)| *ts).collect();
+    let ref_ts: Vec<_> = reference.iter().map(|(ts, _)| *ts).collect();
+    let pairs = associate(&est_ts, &ref_ts, max_dt);
+
+    let aligned: Vec<_> = pairs
+        .iter()
+        .map(|&(i, _)| {
+            let pose = &estimate[i].1;
+            na::Isometry3::from_parts(
+                transform
+                    .transform_point(&pose.translation.vector.into())
+                    .coords
+                    .into(),
+                transform.isometry.rotation * pose.rotation,
+            )
+        })
+        .collect();
+    let target: Vec<_> = pairs.iter().map(|&(_, j)| reference[j].1).collect();
+
+    let rotation_errors: Vec<_> = aligned
+        .iter()
+        .zip(&target)
+        .map(|(a, t)| a.rotation.angle_to(&t.rotation))
+        .collect();
+
+    let rpe = rpe_deltas
+        .iter()
+        .filter(|&&delta| delta > 0)
+        .filter_map(|&delta| {
+            let (translation, rotation): (Vec<_>, Vec<_>) =
+                (0..aligned.len().saturating_sub(delta))
+                    .map(|k| {
+                        let est = aligned[k].inverse() * aligned[k + delta];
+                        let reference = target[k].inverse() * target[k + delta];
+                        let error = reference.inverse() * est;
+                        (error.translation.vector.norm(), error.rotation.angle())
+                    })
+                    .unzip();
+
+            Some(RelativePoseError {
+                delta,
+                translation: ErrorStats::new(&translation)?,
+                rotation: ErrorStats::new(&rotation)?,
+            })
+        })
+        .collect();
+
+    Ok(TrajectoryEvaluation {
+        alignment: transform,
+        ate: positions.ate,
+        ate_rotation: ErrorStats::new(&rotation_errors).ok_or_else(no_finite_errors)?,
+        rpe,
+    })
+}
+
+impl TrajectoryEvaluation {
+    /// Return the interval of the true ATE RMSE under `uncertainty`, see
+    /// [`GroundTruthUncertainty::rmse_interval`].
+    pub fn ate_interval(&self, uncertainty: &GroundTruthUncertainty, z: f64) -> ErrorInterval {
+        uncertainty.rmse_interval(&self.ate, z)
+    }
+
+    /// Return the interval of the true translational RPE RMSE of each entry of `rpe`.
+    pub fn rpe_intervals(
+        &self,
+        uncertainty: &GroundTruthUncertainty,
+        z: f64,
+    ) -> Vec<(usize, ErrorInterval)> {
+        self.rpe
+            .iter()
+            .map(|rpe| {
+                (
+                    rpe.delta,
+                    uncertainty.relative_rmse_interval(&rpe.translation, z),
+                )
+            })
+            .collect()
+    }
+}
+
+impl GroundTruthData {
+    /// Compute ATE and RPE of `estimate` against the ground-truth trajectory.
+    pub fn evaluate_trajectory(
+        &self,
+        estimate: &[(Timestamp, na::Isometry3<f64>)],
+        max_dt: Duration,
+        alignment: Alignment,
+        rpe_deltas: &[usize],
+    ) -> Result<TrajectoryEvaluation> {
+        let reference = self
+            .records()?
+            .map(|r| r.map(|r| (r.timestamp, r.pose())))
+            .collect::<Result<Vec<_>>>()?;
+
+        evaluate_trajectory(estimate, &reference, max_dt, alignment, rpe_deltas)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EuRoC;
+
+    fn reference() -> Result<Vec<(Timestamp, na::Vector3<f64>)>> {
+        EuRoC::new("test_data")?
+            .position()?
+            .records()?
+            .map(|r| r.map(|r| (r.timestamp, r.position)))
+            .collect()
+    }
+
+    #[test]
+    fn error_stats() {
+        let stats = ErrorStats::new(&[3.0, 1.0, 2.0, 4.0]).unwrap();
+
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.mean, 2.5);
+        assert_eq!(stats.median, 2.5);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.max, 4.0);
+        assert_eq!(stats.rmse, 7.5f64.sqrt());
+        assert_eq!(ErrorStats::new(&[]), None);
+    }
+
+    #[test]
+    fn error_stats_non_finite() {
+        let stats = ErrorStats::new(&[f64::NAN, 2.0, f64::INFINITY, 1.0]).unwrap();
+        assert_eq!((stats.count, stats.non_finite), (2, 2));
+        assert_eq!(stats.max, 2.0);
+        assert_eq!(ErrorStats::new(&[f64::NAN]), None);
+
+        let reference = [(Timestamp::new(0), na::Vector3::zeros())];
+        let estimate = [(Timestamp::new(0), na::Vector3::repeat(f64::NAN))];
+        assert!(matches!(
+            evaluate_positions(
+                &estimate,
+                &reference,
+                Duration::from_nsecs(0),
+                Alignment::None
+            ),
+            Err(EurocError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn rmse_interval() {
+        let stats = ErrorStats::new(&[0.3; 100]).unwrap();
+
+        let exact = GroundTruthUncertainty::new(na::Vector3::zeros()).rmse_interval(&stats, 2.0);
+        assert!((exact.lower - 0.3).abs() < 1e-12);
+        assert!((exact.upper - 0.3).abs() < 1e-12);
+
+        // errors explained by ground-truth noise alone are not significant
+        let uncertainty = GroundTruthUncertainty::new(na::Vector3::new(0.1, 0.2, 0.2));
+        let interval = uncertainty.rmse_interval(&stats, 2.0);
+        assert_eq!(interval.lower, 0.0);
+        assert!(interval.upper > 0.0 && interval.upper < 0.3);
+
+        let interval = uncertainty.rmse_interval(&ErrorStats::new(&[1.0; 100]).unwrap(), 2.0);
+        assert!(interval.lower > 0.9 && interval.upper < 1.0);
+
+        let relative = uncertainty.relative_rmse_interval(&stats, 2.0);
+        assert_eq!(relative.lower, 0.0);
+        assert!(relative.upper < interval.upper);
+    }
+
+    #[test]
+    fn associate_nearest() {
+        let ts = |v: &[u64]| -> Vec<Timestamp> { v.iter().map(|&v| v.into()).collect() };
+        let pairs = associate(
+            &ts(&[0, 9, 18, 40]),
+            &ts(&[1, 10, 20, 30]),
+            Duration::from_nsecs(3),
+        );
+
+        assert_eq!(pairs, vec![(0, 0), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn umeyama_recovers_transform() {
+        let source = vec![
+            na::Vector3::new(0.0, 0.0, 0.0),
+            na::Vector3::new(1.0, 0.0, 0.0),
+            na::Vector3::new(0.0, 2.0, 0.0),
+            na::Vector3::new(0.0, 0.0, 3.0),
+        ];
+        let expected = na::Similarity3::new(
+            na::Vector3::new(1.0, -2.0, 0.5),
+            na::Vector3::new(0.1, 0.2, -0.3),
+            2.0,
+        );
+        let target: Vec<_> = source
+            .iter()
+            .map(|s| expected.transform_point(&(*s).into()).coords)
+            .collect();
+
+        let sim3 = umeyama(&source, &target, true).unwrap();
+        assert!((sim3.scaling() - 2.0).abs() < 1e-9);
+        assert!(
+            (sim3.isometry.translation.vector - expected.isometry.translation.vector).norm() < 1e-9
+        );
+
+        let se3 = umeyama(&source, &target, false).unwrap();
+        assert_eq!(se3.scaling(), 1.0);
+    }
+
+    #[test]
+    fn evaluate_positions_aligned() -> Result<()> {
+        let reference = reference()?;
+        let transform = na::Isometry3::new(
+            na::Vector3::new(1.0, 2.0, 3.0),
+            na::Vector3::new(0.0, 0.0, 1.0),
+        );
+        let estimate: Vec<_> = reference
+            .iter()
+            .map(|(ts, p)| {
+                (
+                    *ts + Duration::from_millis(1),
+                    transform.transform_point(&(*p).into()).coords,
+                )
+            })
+            .collect();
+
+        let data = EuRoC::new("test_data")?.position()?;
+
+        let result =
+            data.evaluate_positions(&estimate, Duration::from_millis(10), Alignment::Se3)?;
+        assert_eq!(result.ate.count, 5);
+        assert!(result.ate.rmse < 1e-6);
+
+        let result =
+            data.evaluate_positions(&estimate, Duration::from_millis(10), Alignment::None)?;
+        assert!(result.ate.rmse > 1.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn evaluate_vicon_positions() -> Result<()> {
+        let data = EuRoC::new("test_data")?.vicon()?;
+        let offset = na::Vector3::new(0.1, 0.0, 0.0);
+        let estimate = data
+            .records()?
+            .map(|r| r.map(|r| (r.timestamp + Duration::from_millis(2), r.position + offset)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let result =
+            data.evaluate_positions(&estimate, Duration::from_millis(3), Alignment::None)?;
+        assert_eq!(result.ate.count, 5);
+        assert!((result.ate.rmse - 0.1).abs() < 1e-9);
+
+        // the 2 ms offset is not estimated, only tolerated by `max_dt`
+        assert!(data
+            .evaluate_positions(&estimate, Duration::from_millis(1), Alignment::None)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn evaluate_trajectory_aligned() -> Result<()> {
+        let data = EuRoC::new("test_data")?.ground_truth()?;
+        let transform = na::Isometry3::new(
+            na::Vector3::new(1.0, 2.0, 3.0),
+            na::Vector3::new(0.0, 0.0, 1.0),
+        );
+        let estimate = data
+            .records()?
+            .map(|r| r.map(|r| (r.timestamp, transform * r.pose())))
+            .collect::<Result<Vec<_>>>()?;
+
+        let result = data.evaluate_trajectory(
+            &estimate,
+            Duration::from_millis(1),
+            Alignment::Se3,
+            &[1, 2, 10],
+        )?;
+        assert_eq!(result.ate.count, 5);
+        assert!(result.ate.rmse < 1e-6);
+        assert!(result.ate_rotation.max < 1e-6);
+        assert_eq!(result.rpe.len(), 2);
+        assert_eq!(result.rpe[1].delta, 2);
+        assert_eq!(result.rpe[1].translation.count, 3);
+        assert!(result.rpe[1].translation.max < 1e-6);
+
+        let result =
+            data.evaluate_trajectory(&estimate, Duration::from_millis(1), Alignment::None, &[1])?;
+        assert!(result.ate.rmse > 1.0);
+        assert!((result.ate_rotation.mean - 1.0).abs() < 1e-6);
+        // relative motion is unaffected by a global transform
+        assert!(result.rpe[0].translation.max < 1e-6);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::VecDeque;
+
+use crate::{
+    EuRoC, EurocError, GroundTruthRecord, ImageRecord, ImuRecord, PositionRecord, Result, Sensor,
+    Timestamp, Timestamped, ViconRecord,
+};
+
+/// Record of any sensor stream.
+#[derive(Debug, Clone)]
+pub enum SensorEvent {
+    /// image of [`Sensor::Cam0`] or [`Sensor::Cam1`]
+    Image(Sensor, ImageRecord),
+    Imu(ImuRecord),
+    Position(PositionRecord),
+    Vicon(ViconRecord),
+    GroundTruth(GroundTruthRecord),
+}
+
+impl SensorEvent {
+    /// Return the sensor the record originates from.
+    pub const fn sensor(&self) -> Sensor {
+        match self {
+            Self::Image(sensor, _) => *sensor,
+            Self::Imu(_) => Sensor::Imu0,
+            Self::Position(_) => Sensor::Leica0,
+            Self::Vicon(_) => Sensor::Vicon0,
+            Self::GroundTruth(_) => Sensor::GroundTruth,
+        }
+    }
+
+    /// Replace the timestamp of the record.
+    pub fn set_timestamp(&mut self, timestamp: Timestamp) {
+        match self {
+            Self::Image(_, r) => r.timestamp = timestamp,
+            Self::Imu(r) => r.timestamp = timestamp,
+            Self::Position(r) => r.timestamp = timestamp,
+            Self::Vicon(r) => r.timestamp = timestamp,
+            Self::GroundTruth(r) => r.timestamp = timestamp,
+        }
+    }
+}
+
+impl Timestamped for SensorEvent {
+    fn timestamp(&self) -> Timestamp {
+        match self {
+            Self::Image(_, r) => r.timestamp,
+            Self::Imu(r) => r.timestamp,
+            Self::Position(r) => r.timestamp,
+            Self::Vicon(r) => r.timestamp,
+            Self::GroundTruth(r) => r.timestamp,
+        }
+    }
+}
+
+type EventStream = Box<dyn Iterator<Item = Result<SensorEvent>>>;
+
+/// Iterator interleaving several timestamp-sorted streams in timestamp order.
+///
+/// Items with equal timestamps are yielded in the order of the streams. An
+/// error is yielded in place of the item it replaces, and its stream keeps
+/// being read afterwards.
+pub struct MergeIterator<T> {
+    streams: Vec<Box<dyn Iterator<Item = Result<T>>>>,
+    heads: Vec<Option<T>>,
+    /// streams whose head was taken and must be read again
+    unfilled: Vec<usize>,
+    errors: VecDeque<EurocError>,
+}
+
+/// Iterator interleaving several sensor streams in timestamp order.
+///
+/// Records with equal timestamps are yielded in the order the sensors were requested.
+pub type EventIterator = MergeIterator<SensorEvent>;
+
+impl<T: Timestamped> MergeIterator<T> {
+    pub(crate) fn new(streams: Vec<Box<dyn Iterator<Item = Result<T>>>>) -> Self {
+        Self {
+            heads: streams.iter().map(|_| None).collect(),
+            unfilled: (0..streams.len()).collect(),
+            streams,
+            errors: VecDeque::new(),
+        }
+    }
+
+    fn fill(&mut self) {
+        for i in std::mem::take(&mut self.unfilled) {
+            match self.streams[i].next() {
+                Some(Ok(item)) => self.heads[i] = Some(item),
+                Some(Err(e)) => {
+                    self.errors.push_back(e);
+                    // read past the error on the next call
+                    self.unfilled.push(i);
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+impl<T: Timestamped> Iterator for MergeIterator<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.errors.is_empty() {
+            self.fill();
+        }
+        if let Some(e) = self.errors.pop_front() {
+            return Some(Err(e));
+        }
+
+        let next = self
+            .heads
+            .iter()
+            .enumerate()
+            .filter_map(|(i, head)| head.as_ref().map(|e| (e.timestamp(), i)))
+            .min()?
+            .1;
+        self.unfilled.push(next);
+
+        self.heads[next].take().map(Ok)
+    }
+}
+
+/// Box `records` as a stream of [`SensorEvent`]s.
+fn stream<I, T, F>(records: I, event: F) -> EventStream
+where
+    I: Iterator<Item = Result<T>> + 'static,
+    F: Fn(T) -> SensorEvent + 'static,
+{
+    Box::new(records.map(move |r| r.map(&event)))
+}
+
+impl EuRoC {
+    /// Merge the records of `sensors` into a single stream in timestamp order.
+    pub fn events(&self, sensors: &[Sensor]) -> Result<EventIterator> {
+        self.merge_events(sensors, None)
+    }
+
+    /// Merge the records of `sensors` with `start <= timestamp < end`, see [`EuRoC::events`].
+    ///
+    /// Like `records_between`, this indexes every `data.csv` to seek to `start`.
+    pub fn events_between(
+        &self,
+        sensors: &[Sensor],
+        start: Timestamp,
+        end: Timestamp,
+    ) -> Result<EventIterator> {
+        self.merge_events(sensors, Some((start, end)))
+    }
+
+    fn merge_events(
+        &self,
+        sensors: &[Sensor],
+        window: Option<(Timestamp, Timestamp)>,
+    ) -> Result<EventIterator> {
+        let streams = sensors
+            .iter()
+            .map(|&sensor| self.event_stream(sensor, window))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(EventIterator::new(streams))
+    }
+
+    fn event_stream(
+        &self,
+        sensor: Sensor,
+        window: Option<(Timestamp, Timestamp)>,
+    ) -> Result<EventStream> {
+        Ok(match sensor {
+            Sensor::Cam0 | Sensor::Cam1 => {
+                let camera = self.camera(sensor)?;
+                let event = move |r| SensorEvent::Image(sensor, r);
+                match window {
+                    Some((start, end)) => stream(camera.records_between(start, end)?, event),
+                    None => stream(camera.records()?, event),
+                }
+            }
+            Sensor::Imu0 => {
+                let imu = self.imu()?;
+                match window {
+                    Some((start, end)) => {
+                        stream(imu.records_between(start, end)?, SensorEvent::Imu)
+                    }
+                    None => stream(imu.records()?, SensorEvent::Imu),
+                }
+            }
+            Sensor::Leica0 => {
+                let position = self.position()?;
+                match window {
+                    Some((start, end)) => {
+                        stream(position.records_between(start, end)?, SensorEvent::Position)
+                    }
+                    None => stream(position.records()?, SensorEvent::Position),
+                }
+            }
+            Sensor::Vicon0 => {
+                let vicon = self.vicon()?;
+                match window {
+                    Some((start, end)) => {
+                        stream(vicon.records_between(start, end)?, SensorEvent::Vicon)
+                    }
+                    None => stream(vicon.records()?, SensorEvent::Vicon),
+                }
+            }
+            Sensor::GroundTruth => {
+                let ground_truth = self.ground_truth()?;
+                match window {
+                    Some((start, end)) => stream(
+                        ground_truth.records_between(start, end)?,
+                        SensorEvent::GroundTruth,
+                    ),
+                    None => stream(ground_truth.records()?, SensorEvent::GroundTruth),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn events() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let events = data
+            .events(&[Sensor::Cam0, Sensor::Cam1, Sensor::Imu0, Sensor::Leica0])?
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(events.len(), 20);
+        assert!(events
+            .windows(2)
+            .all(|w| w[0].timestamp() <= w[1].timestamp()));
+
+        assert_eq!(events[0].sensor(), Sensor::Leica0);
+        // cam0, cam1 and imu0 share the timestamp 1403636579763555584
+        let tied: Vec<_> = events
+            .iter()
+            .filter(|e| e.timestamp() == 1403636579763555584.into())
+            .map(SensorEvent::sensor)
+            .collect();
+        assert_eq!(tied, vec![Sensor::Cam0, Sensor::Cam1, Sensor::Imu0]);
+
+        Ok(())
+    }
+
+    struct Stamp(u64);
+
+    impl Timestamped for Stamp {
+        fn timestamp(&self) -> Timestamp {
+            Timestamp::new(self.0)
+        }
+    }
+
+    #[test]
+    fn merge_keeps_records_around_errors() {
+        let stream = |items: Vec<Result<u64>>| -> Box<dyn Iterator<Item = Result<Stamp>>> {
+            Box::new(items.into_iter().map(|r| r.map(Stamp)))
+        };
+        let bad = || Err(EurocError::InvalidInput("bad row".to_owned()));
+        let merged = MergeIterator::new(vec![
+            stream(vec![Ok(1), bad(), Ok(3)]),
+            stream(vec![bad(), Ok(2), Ok(4)]),
+        ])
+        .map(|r| r.map(|s| s.0).ok())
+        .collect::<Vec<_>>();
+
+        assert_eq!(merged, [None, Some(1), None, Some(2), Some(3), Some(4)]);
+    }
+
+    #[test]
+    fn events_between() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let sensors = [Sensor::Cam0, Sensor::Imu0];
+        let all = data.events(&sensors)?.collect::<Result<Vec<_>>>()?;
+        let (start, end) = (all[2].timestamp(), all[7].timestamp());
+
+        let window = data
+            .events_between(&sensors, start, end)?
+            .collect::<Result<Vec<_>>>()?;
+        let expected: Vec<_> = all
+            .iter()
+            .filter(|e| start <= e.timestamp() && e.timestamp() < end)
+            .map(|e| (e.sensor(), e.timestamp()))
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(
+            window
+                .iter()
+                .map(|e| (e.sensor(), e.timestamp()))
+                .collect::<Vec<_>>(),
+            expected
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn events_empty() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        assert!(data.events(&[]).is_ok());
+        assert_eq!(data.events(&[])?.count(), 0);
+
+        Ok(())
+    }
+}
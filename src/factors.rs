@@ -0,0 +1,360 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use nalgebra as na;
+
+use crate::{
+    interpolation::bracket, EuRoC, EurocError, Extrapolation, GroundTruthInterpolator, ImuConfig,
+    ImuRecord, KeyframeSelector, PinholeRadTan, Preintegration, Result, Sensor, Timestamp,
+};
+
+/// Ground-truth state of the body at a keyframe, a prior for factor graphs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyframePrior {
+    pub timestamp: Timestamp,
+    /// body pose wrt. the world frame `T_WB`
+    pub pose: na::Isometry3<f64>,
+    /// linear velocity (m/s)
+    pub velocity: na::Vector3<f64>,
+    /// gyroscope bias (rad/s)
+    pub gyro_bias: na::Vector3<f64>,
+    /// accelerometer bias (m/s^2)
+    pub accel_bias: na::Vector3<f64>,
+}
+
+/// Everything a visual-inertial factor graph needs from a sequence.
+///
+/// `imu_factors[i]` connects `keyframes[i]` and `keyframes[i + 1]`, and is
+/// preintegrated with the biases of `keyframes[i]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FactorBundle {
+    pub imu: ImuConfig,
+    /// IMU pose wrt. the body
+    pub t_bs: na::Isometry3<f64>,
+    pub camera_sensor: Sensor,
+    pub camera: PinholeRadTan,
+    /// camera pose wrt. the body
+    pub t_bc: na::Isometry3<f64>,
+    pub keyframes: Vec<KeyframePrior>,
+    pub imu_factors: Vec<Preintegration>,
+}
+
+impl FactorBundle {
+    /// Write the bundle as text, one whitespace-separated record per line.
+    ///
+    /// Poses are written `tx ty tz qx qy qz qw`, timestamps in nanoseconds and
+    /// keyframes are referred to by their index:
+    ///
+    /// ```text
+    /// # EuRoC factor bundle v1
+    /// IMU rate_hz gyro_noise_density gyro_random_walk accel_noise_density accel_random_walk T_BS
+    /// CAMERA sensor width height fu fv cu cv k1 k2 p1 p2 T_BC
+    /// KEYFRAME id timestamp T_WB vx vy vz bgx bgy bgz bax bay baz
+    /// IMU_FACTOR from to dt dqx dqy dqz dqw dvx dvy dvz dpx dpy dpz covariance
+    /// ```
+    ///
+    /// `dt` is in seconds, and `covariance` holds the 81 entries of
+    /// [`Preintegration::covariance`] in row-major order.
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<()> {
+        writeln!(writer, "# EuRoC factor bundle v1")?;
+
+        let imu = &self.imu;
+        write!(
+            writer,
+            "IMU {} {} {} {} {}",
+            imu.rate_hz,
+            imu.gyro_noise_density,
+            imu.gyro_random_walk,
+            imu.accel_noise_density,
+            imu.accel_random_walk
+        )?;
+        write_pose(&mut writer, &self.t_bs)?;
+        writeln!(writer)?;
+
+        let (width, height) = self.camera.resolution;
+        let (fu, fv, cu, cv) = self.camera.intrinsics;
+        write!(
+            writer,
+            "CAMERA {} {} {} {} {} {} {}",
+            self.camera_sensor.dir_name(),
+            width,
+            height,
+            fu,
+            fv,
+            cu,
+            cv
+        )?;
+        write_values(&mut writer, self.camera.distortion.iter())?;
+        write_pose(&mut writer, &self.t_bc)?;
+        writeln!(writer)?;
+
+        for (id, keyframe) in self.keyframes.iter().enumerate() {
+            write!(writer, "KEYFRAME {} {}", id, keyframe.timestamp.nsecs())?;
+            write_pose(&mut writer, &keyframe.pose)?;
+            write_values(&mut writer, keyframe.velocity.iter())?;
+            write_values(&mut writer, keyframe.gyro_bias.iter())?;
+            write_values(&mut writer, keyframe.accel_bias.iter())?;
+            writeln!(writer)?;
+        }
+
+        for (id, factor) in self.imu_factors.iter().enumerate() {
+            let q = &factor.delta_rotation;
+            write!(
+                writer,
+                "IMU_FACTOR {} {} {} {} {} {} {}",
+                id,
+                id + 1,
+                (factor.end - factor.start).as_secs_f64(),
+                q.i,
+                q.j,
+                q.k,
+                q.w
+            )?;
+            write_values(&mut writer, factor.delta_velocity.iter())?;
+            write_values(&mut writer, factor.delta_position.iter())?;
+            write_values(&mut writer, factor.covariance.transpose().iter())?;
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the bundle to `path`, see [`FactorBundle::write`].
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+fn write_pose<W: Write>(writer: &mut W, pose: &na::Isometry3<f64>) -> Result<()> {
+    let t = &pose.translation.vector;
+    let q = &pose.rotation;
+    write!(
+        writer,
+        " {} {} {} {} {} {} {}",
+        t.x, t.y, t.z, q.i, q.j, q.k, q.w
+    )?;
+
+    Ok(())
+}
+
+fn write_values<'a, W, I>(writer: &mut W, values: I) -> Result<()>
+where
+    W: Write,
+    I: Iterator<Item = &'a f64>,
+{
+    for value in values {
+        write!(writer, " {}", value)?;
+    }
+
+    Ok(())
+}
+
+/// Return the records needed to preintegrate from `start` to `end`: the last
+/// one at or before `start` through the first one at or after `end`.
+fn window(records: &[ImuRecord], start: Timestamp, end: Timestamp) -> Result<&[ImuRecord]> {
+    let from = match records.binary_search_by_key(&start, |r| r.timestamp) {
+        Ok(i) => Some(i),
+        Err(i) => i.checked_sub(1),
+    };
+    let to = match records.binary_search_by_key(&end, |r| r.timestamp) {
+        Ok(j) => Some(j),
+        Err(j) if j < records.len() => Some(j),
+        Err(_) => None,
+    };
+
+    match (from, to) {
+        (Some(from), Some(to)) => Ok(&records[from..=to]),
+        _ => Err(EurocError::InvalidInput(format!(
+            "IMU does not cover keyframes {:?}..{:?}",
+            start, end
+        ))),
+    }
+}
+
+impl EuRoC {
+    /// Build a [`FactorBundle`] over the keyframes of `camera` chosen by `selector`.
+    ///
+    /// Write it with [`FactorBundle::save`]. Priors are interpolated from the
+    /// ground truth at the keyframe timestamps. Fails if the IMU does not cover
+    /// the keyframes.
+    pub fn factor_bundle(
+        &self,
+        camera: Sensor,
+        selector: &KeyframeSelector,
+    ) -> Result<FactorBundle> {
+        let camera_records = self.camera(camera)?;
+        let ground_truth = self
+            .ground_truth()?
+            .records()?
+            .collect::<Result<Vec<_>>>()?;
+        let interpolator = GroundTruthInterpolator::new(&ground_truth, Extrapolation::None);
+        let timestamps: Vec<_> = ground_truth.iter().map(|r| r.timestamp).collect();
+
+        let keyframes = selector
+            .select(&self.timestamps(camera)?, &interpolator)
+            .into_iter()
+            .filter_map(|ts| {
+                let (i, j, alpha) = bracket(&timestamps, ts)?;
+                let (a, b) = (&ground_truth[i], &ground_truth[j]);
+                Some(KeyframePrior {
+                    timestamp: ts,
+                    pose: interpolator.pose_at(ts)?,
+                    velocity: a.velocity.lerp(&b.velocity, alpha),
+                    gyro_bias: a.gyro_bias.lerp(&b.gyro_bias, alpha),
+                    accel_bias: a.accel_bias.lerp(&b.accel_bias, alpha),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let imu = self.imu()?;
+        let preintegrator = imu.preintegrator()?;
+        let imu_records = imu.records()?.collect::<Result<Vec<_>>>()?;
+        let imu_factors = keyframes
+            .windows(2)
+            .map(|pair| {
+                let (start, end) = (pair[0].timestamp, pair[1].timestamp);
+                preintegrator
+                    .with_bias(pair[0].gyro_bias, pair[0].accel_bias)
+                    .integrate(window(&imu_records, start, end)?, start, end)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(FactorBundle {
+            imu: imu.config()?,
+            t_bs: imu.extrinsics_isometry()?,
+            camera_sensor: camera,
+            camera: camera_records.camera_model()?,
+            t_bc: camera_records.extrinsics_isometry()?,
+            keyframes,
+            imu_factors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::{temp_dir::TempDir, GroundTruthRecord, MemorySource};
+
+    const START: u64 = 1403636579758555392;
+
+    /// `test_data` accelerating along x at 2 m/s^2 from rest at `START`, with
+    /// ground truth covering the camera frames and `imu_rows` IMU records, both at 200 Hz.
+    fn dataset(imu_rows: u64) -> Result<EuRoC> {
+        let mut source = MemorySource::from_dir("test_data")?;
+        let mut ground_truth = Vec::new();
+        let mut imu = "#timestamp,w_x,w_y,w_z,a_x,a_y,a_z\n".to_owned();
+        for k in 0..50 {
+            let ts = START + k * 5_000_000;
+            let t = k as f64 * 0.005;
+            ground_truth.push(GroundTruthRecord {
+                velocity: na::Vector3::x() * 2.0 * t,
+                gyro_bias: na::Vector3::z() * 0.01,
+                ..GroundTruthRecord::from_pose(
+                    ts.into(),
+                    na::Isometry3::translation(t * t, 0.0, 0.0),
+                )
+            });
+            if k < imu_rows {
+                imu.push_str(&format!("{},0,0,0.01,2,0,0\n", ts));
+            }
+        }
+        source.insert("imu0/data.csv", imu.as_bytes());
+        EuRoC::with_ground_truth(source, &ground_truth)
+    }
+
+    #[test]
+    fn factor_bundle() -> Result<()> {
+        let data = dataset(50)?;
+        let bundle = data.factor_bundle(Sensor::Cam0, &KeyframeSelector::new(0.015, 1.0))?;
+
+        // frames every 50 ms from 5 ms on, where x = t^2 is 0, 0.003, 0.011, 0.024 and 0.042 m
+        let frames = data.timestamps(Sensor::Cam0)?;
+        let keyframes: Vec<_> = bundle.keyframes.iter().map(|k| k.timestamp).collect();
+        assert_eq!(keyframes, vec![frames[0], frames[3], frames[4]]);
+        assert_eq!(bundle.imu_factors.len(), 2);
+
+        let prior = &bundle.keyframes[1];
+        let t = (prior.timestamp - Timestamp::from(START)).as_secs_f64();
+        assert!((prior.pose.translation.x - t * t).abs() < 1e-4);
+        assert!(2.0f64.mul_add(-t, prior.velocity.x).abs() < 1e-9);
+        assert!((prior.gyro_bias.z - 0.01).abs() < 1e-12);
+
+        // the gyroscope bias of the first keyframe is removed
+        let factor = &bundle.imu_factors[0];
+        assert_eq!((factor.start, factor.end), (frames[0], frames[3]));
+        let dt = (factor.end - factor.start).as_secs_f64();
+        assert!(factor.delta_rotation.angle() < 1e-12);
+        assert!((factor.delta_velocity - na::Vector3::x() * 2.0 * dt).norm() < 1e-9);
+        assert!((factor.delta_position - na::Vector3::x() * dt * dt).norm() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write() -> Result<()> {
+        let tmp = TempDir::new("factor_bundle")?;
+        let path = tmp.join("bundle.txt");
+        let bundle =
+            dataset(50)?.factor_bundle(Sensor::Cam0, &KeyframeSelector::new(0.015, 1.0))?;
+        bundle.save(&path)?;
+
+        let text = fs::read_to_string(&path)?;
+        let lines: Vec<Vec<&str>> = text
+            .lines()
+            .skip(1)
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        let count = |tag| lines.iter().filter(|line| line[0] == tag).count();
+        assert_eq!(lines.len(), 7);
+        assert_eq!((count("KEYFRAME"), count("IMU_FACTOR")), (3, 2));
+        for line in &lines {
+            let fields = match line[0] {
+                "IMU" => 1 + 5 + 7,
+                "CAMERA" => 1 + 7 + 4 + 7,
+                "KEYFRAME" => 1 + 2 + 7 + 9,
+                "IMU_FACTOR" => 1 + 3 + 4 + 6 + 81,
+                tag => panic!("unexpected {}", tag),
+            };
+            assert_eq!(line.len(), fields, "{:?}", line);
+        }
+
+        let keyframe = &lines[3];
+        assert_eq!(keyframe[1], "1");
+        assert_eq!(
+            keyframe[2].parse::<u64>().unwrap(),
+            bundle.keyframes[1].timestamp.nsecs()
+        );
+        let factor = &lines[5];
+        assert_eq!(&factor[1..3], &["0", "1"]);
+        let ts = (bundle.keyframes[0].timestamp, bundle.keyframes[1].timestamp);
+        let dt: f64 = factor[3].parse().unwrap();
+        assert!((dt - (ts.1 - ts.0).as_secs_f64()).abs() < 1e-12);
+        let covariance: Vec<f64> = factor[14..].iter().map(|v| v.parse().unwrap()).collect();
+        assert_eq!(covariance[9], bundle.imu_factors[0].covariance[(1, 0)]);
+        assert_eq!(covariance[80], bundle.imu_factors[0].covariance[(8, 8)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn imu_must_cover_keyframes() -> Result<()> {
+        let selector = KeyframeSelector::new(0.015, 1.0);
+        assert!(dataset(50)?.factor_bundle(Sensor::Cam0, &selector).is_ok());
+        // the IMU stops before the second keyframe
+        assert!(dataset(20)?.factor_bundle(Sensor::Cam0, &selector).is_err());
+
+        assert!(window(&[], 0.into(), 1.into()).is_err());
+
+        Ok(())
+    }
+}
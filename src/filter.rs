@@ -0,0 +1,242 @@
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+use crate::{EurocError, ImuRecord, Result};
+
+/// Second-order IIR section (transposed direct form II).
+///
+/// Coefficients follow the RBJ audio EQ cookbook.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    fn from_rbj(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    /// Second-order Butterworth low-pass filter.
+    ///
+    /// Fail unless `0 < cutoff < sample_rate / 2`.
+    pub fn low_pass(sample_rate: f64, cutoff: f64) -> Result<Self> {
+        check_frequency("cutoff", sample_rate, cutoff)?;
+        let w0 = 2.0 * PI * cutoff / sample_rate;
+        let alpha = w0.sin() / (2.0 * FRAC_1_SQRT_2);
+        let cos = w0.cos();
+
+        Ok(Self::from_rbj(
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        ))
+    }
+
+    /// Notch filter rejecting `center` (Hz); larger `q` gives a narrower notch.
+    ///
+    /// Fail unless `0 < center < sample_rate / 2` and `q > 0`.
+    pub fn notch(sample_rate: f64, center: f64, q: f64) -> Result<Self> {
+        check_frequency("center", sample_rate, center)?;
+        if !(q.is_finite() && q > 0.0) {
+            return Err(EurocError::InvalidInput(format!(
+                "notch q must be positive, got {}",
+                q
+            )));
+        }
+        let w0 = 2.0 * PI * center / sample_rate;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+
+        Ok(Self::from_rbj(
+            [1.0, -2.0 * cos, 1.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        ))
+    }
+
+    /// Filter one sample.
+    pub fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0.mul_add(x, self.z1);
+        self.z1 = self.b1.mul_add(x, self.a1.mul_add(-y, self.z2));
+        self.z2 = self.b2.mul_add(x, -self.a2 * y);
+        y
+    }
+
+    /// Clear the filter state.
+    pub fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+}
+
+/// Fail unless `frequency` lies strictly between 0 and the Nyquist frequency of `sample_rate`.
+fn check_frequency(name: &str, sample_rate: f64, frequency: f64) -> Result<()> {
+    if sample_rate.is_finite() && frequency > 0.0 && frequency < sample_rate / 2.0 {
+        Ok(())
+    } else {
+        Err(EurocError::InvalidInput(format!(
+            "{} must be between 0 and half the sample rate {} Hz, got {}",
+            name, sample_rate, frequency
+        )))
+    }
+}
+
+/// Cascade of [`Biquad`] sections applied independently to each IMU axis.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImuFilter {
+    stages: Vec<Biquad>,
+}
+
+impl ImuFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a Butterworth low-pass stage, see [`Biquad::low_pass`].
+    pub fn low_pass(mut self, sample_rate: f64, cutoff: f64) -> Result<Self> {
+        self.stages.push(Biquad::low_pass(sample_rate, cutoff)?);
+        Ok(self)
+    }
+
+    /// Append a notch stage, e.g. at the rotor frequency, see [`Biquad::notch`].
+    pub fn notch(mut self, sample_rate: f64, center: f64, q: f64) -> Result<Self> {
+        self.stages.push(Biquad::notch(sample_rate, center, q)?);
+        Ok(self)
+    }
+
+    /// Wrap an IMU record iterator so that gyro and accel are filtered.
+    ///
+    /// Timestamps are passed through unchanged.
+    pub fn apply<I>(&self, records: I) -> FilteredImuIterator<I::IntoIter>
+    where
+        I: IntoIterator<Item = Result<ImuRecord>>,
+    {
+        let mut stages = self.stages.clone();
+        stages.iter_mut().for_each(Biquad::reset);
+
+        FilteredImuIterator {
+            inner: records.into_iter(),
+            axes: vec![stages; 6],
+        }
+    }
+}
+
+pub struct FilteredImuIterator<I> {
+    inner: I,
+    axes: Vec<Vec<Biquad>>,
+}
+
+impl<I> Iterator for FilteredImuIterator<I>
+where
+    I: Iterator<Item = Result<ImuRecord>>,
+{
+    type Item = Result<ImuRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let axes = &mut self.axes;
+        self.inner.next().map(|record| {
+            let mut record = record?;
+            for (i, stages) in axes.iter_mut().enumerate() {
+                let value = if i < 3 {
+                    &mut record.gyro[i]
+                } else {
+                    &mut record.accel[i - 3]
+                };
+                *value = stages.iter_mut().fold(*value, |x, s| s.process(x));
+            }
+            Ok(record)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra as na;
+
+    use super::*;
+    use crate::EuRoC;
+
+    fn amplitude(mut filter: Biquad, rate: f64, freq: f64) -> f64 {
+        (0..2000)
+            .map(|i| filter.process((2.0 * PI * freq * i as f64 / rate).sin()))
+            .skip(1000)
+            .fold(0.0, |acc: f64, y| acc.max(y.abs()))
+    }
+
+    #[test]
+    fn low_pass() -> Result<()> {
+        let filter = Biquad::low_pass(200.0, 10.0)?;
+
+        assert!((amplitude(filter, 200.0, 1.0) - 1.0).abs() < 0.01);
+        assert!((amplitude(filter, 200.0, 10.0) - FRAC_1_SQRT_2).abs() < 0.01);
+        assert!(amplitude(filter, 200.0, 80.0) < 0.02);
+
+        for &cutoff in [0.0, -10.0, 100.0, 150.0, f64::NAN].iter() {
+            assert!(matches!(
+                Biquad::low_pass(200.0, cutoff),
+                Err(EurocError::InvalidInput(_))
+            ));
+        }
+        assert!(Biquad::low_pass(f64::INFINITY, 10.0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn notch() -> Result<()> {
+        let filter = Biquad::notch(200.0, 50.0, 5.0)?;
+
+        assert!(amplitude(filter, 200.0, 50.0) < 0.01);
+        assert!((amplitude(filter, 200.0, 5.0) - 1.0).abs() < 0.01);
+
+        assert!(Biquad::notch(200.0, 100.0, 5.0).is_err());
+        for &q in [0.0, -1.0, f64::NAN].iter() {
+            assert!(matches!(
+                Biquad::notch(200.0, 50.0, q),
+                Err(EurocError::InvalidInput(_))
+            ));
+        }
+        assert!(ImuFilter::new().notch(200.0, 50.0, 0.0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn apply() -> Result<()> {
+        let data = EuRoC::new("test_data")?.imu()?;
+        let filter = ImuFilter::new()
+            .low_pass(200.0, 20.0)?
+            .notch(200.0, 50.0, 2.0)?;
+
+        let raw = data.records()?.collect::<Result<Vec<_>>>()?;
+        let filtered = filter.apply(data.records()?).collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(filtered.len(), raw.len());
+        assert_eq!(filtered[3].timestamp, raw[3].timestamp);
+        assert_ne!(filtered[3].accel, raw[3].accel);
+
+        let identity = ImuFilter::new()
+            .apply(data.records()?)
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            identity
+                .iter()
+                .map(|r| r.gyro)
+                .collect::<Vec<na::Vector3<f64>>>(),
+            raw.iter().map(|r| r.gyro).collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use std::iter::Peekable;
+
+use nalgebra as na;
+
+use crate::{
+    EuRoC, Extrapolation, GroundTruthInterpolator, ImageEntryIterator, ImageRecord, ImuIterator,
+    ImuRecord, Result, Sensor, Timestamp, Timestamped,
+};
+
+/// Camera frame with the IMU samples recorded since the previous frame.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub timestamp: Timestamp,
+    pub left: ImageRecord,
+    /// `cam1` image with the same timestamp, if any
+    pub right: Option<ImageRecord>,
+    /// samples with `previous frame < timestamp <= frame`, or every sample up
+    /// to the first frame
+    pub imu_window: Vec<ImuRecord>,
+    /// body pose `T_WB` interpolated from the ground truth, if it covers the frame
+    pub gt_pose: Option<na::Isometry3<f64>>,
+}
+
+impl Timestamped for Frame {
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+/// Iterator over the `cam0` frames of a sequence, created by [`EuRoC::frames`].
+pub struct FrameIterator {
+    left: ImageEntryIterator,
+    right: Option<Peekable<ImageEntryIterator>>,
+    imu: Peekable<ImuIterator>,
+    ground_truth: Option<GroundTruthInterpolator>,
+}
+
+impl FrameIterator {
+    fn next_frame(&mut self, timestamp: Timestamp, left: ImageRecord) -> Result<Frame> {
+        let mut imu_window = Vec::new();
+        while let Some(record) = self.imu.peek() {
+            match record {
+                Ok(record) if record.timestamp > timestamp => break,
+                _ => imu_window.push(self.imu.next().unwrap()?),
+            }
+        }
+
+        let mut right = None;
+        if let Some(entries) = &mut self.right {
+            while let Some(entry) = entries.peek() {
+                match entry {
+                    Ok(entry) if entry.timestamp > timestamp => break,
+                    Ok(entry) if entry.timestamp == timestamp => {
+                        right = Some(entry.load()?);
+                        entries.next();
+                        break;
+                    }
+                    _ => {
+                        entries.next().unwrap()?;
+                    }
+                }
+            }
+        }
+
+        Ok(Frame {
+            timestamp,
+            left,
+            right,
+            imu_window,
+            gt_pose: self
+                .ground_truth
+                .as_ref()
+                .and_then(|gt| gt.pose_at(timestamp)),
+        })
+    }
+}
+
+impl Iterator for FrameIterator {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let left = self.left.next()?.and_then(|entry| entry.load());
+        Some(left.and_then(|left| self.next_frame(left.timestamp, left)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.left.size_hint()
+    }
+}
+
+impl ExactSizeIterator for FrameIterator {}
+
+impl EuRoC {
+    /// Iterate over the `cam0` frames, each bundled with the `cam1` image
+    /// taken at the same time, the IMU samples since the previous frame and
+    /// the ground-truth pose.
+    ///
+    /// `cam1` and the ground truth are optional; the IMU is required. Images
+    /// and IMU samples are read as the iteration advances.
+    pub fn frames(&self) -> Result<FrameIterator> {
+        let present = |sensor| self.source().is_dir(&self.sensor_dir(sensor));
+        let right = if present(Sensor::Cam1) {
+            Some(self.right_camera()?.entries()?.peekable())
+        } else {
+            None
+        };
+        let ground_truth = if present(Sensor::GroundTruth) {
+            Some(self.ground_truth()?.interpolator(Extrapolation::None)?)
+        } else {
+            None
+        };
+
+        Ok(FrameIterator {
+            left: self.left_camera()?.entries()?,
+            right,
+            imu: self.imu()?.records()?.peekable(),
+            ground_truth,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn frames() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let cam = data.timestamps(Sensor::Cam0)?;
+        let imu = data.imu()?.records()?.collect::<Result<Vec<_>>>()?;
+
+        let frames = data.frames()?;
+        assert_eq!(frames.len(), 5);
+        let frames = frames.collect::<Result<Vec<_>>>()?;
+        assert_eq!(frames.iter().map(|f| f.timestamp).collect::<Vec<_>>(), cam);
+        assert!(frames.iter().all(|f| f.right.is_some()));
+        // the ground truth starts after the frames
+        assert!(frames.iter().all(|f| f.gt_pose.is_none()));
+
+        // the first frame also gets the sample before it
+        let windows = frames
+            .iter()
+            .map(|f| f.imu_window.len())
+            .collect::<Vec<_>>();
+        assert_eq!(windows, [2, 3, 0, 0, 0]);
+        assert_eq!(frames[1].imu_window[0].gyro, imu[2].gyro);
+        for (i, frame) in frames.iter().enumerate().skip(1) {
+            assert!(frame
+                .imu_window
+                .iter()
+                .all(|r| cam[i - 1] < r.timestamp && r.timestamp <= cam[i]));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use image::{DynamicImage, ImageFormat};
+
+use crate::{
+    fnv::{fnv1a, FNV_OFFSET},
+    ImageEntry, ImageRecord, Result, Timestamp,
+};
+
+/// Content-addressed store of decoded or preprocessed frames.
+///
+/// Frames are stored under a hash of their timestamp and a description of
+/// the pipeline that produced them, so jobs with different configurations can
+/// share one directory safely as long as `config` covers every parameter
+/// affecting the output.
+#[derive(Debug, Clone)]
+pub struct FrameCache {
+    root: PathBuf,
+}
+
+impl FrameCache {
+    /// Use `root` as the store, creating it if needed.
+    pub fn new<P: AsRef<Path>>(root: P) -> Result<Self> {
+        fs::create_dir_all(root.as_ref())?;
+
+        Ok(Self {
+            root: root.as_ref().to_owned(),
+        })
+    }
+
+    /// Return the key of a frame, stable across platforms and compiler versions.
+    pub fn key(timestamp: Timestamp, config: &str) -> String {
+        // 64-bit FNV-1a over the fixed-width timestamp followed by `config`
+        let hash = fnv1a(FNV_OFFSET, &timestamp.nsecs().to_le_bytes());
+        let hash = fnv1a(hash, config.as_bytes());
+
+        format!("{:016x}", hash)
+    }
+
+    /// Return where the frame is stored.
+    pub fn path(&self, timestamp: Timestamp, config: &str) -> PathBuf {
+        self.root
+            .join(format!("{}.png", Self::key(timestamp, config)))
+    }
+
+    /// Return the stored frame, if any.
+    pub fn get(&self, timestamp: Timestamp, config: &str) -> Result<Option<DynamicImage>> {
+        let path = self.path(timestamp, config);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        Ok(Some(image::open(path)?))
+    }
+
+    /// Store a frame as PNG.
+    ///
+    /// The file is written under a temporary name and then renamed, so
+    /// concurrent jobs never read a partially written frame.
+    pub fn insert(&self, timestamp: Timestamp, config: &str, image: &DynamicImage) -> Result<()> {
+        let path = self.path(timestamp, config);
+        let tmp = path.with_extension(format!("{}.tmp", process::id()));
+        image.save_with_format(&tmp, ImageFormat::Png)?;
+        fs::rename(tmp, path)?;
+
+        Ok(())
+    }
+
+    /// Return the stored frame, or compute and store it with `f`.
+    pub fn get_or_insert_with<F>(
+        &self,
+        timestamp: Timestamp,
+        config: &str,
+        f: F,
+    ) -> Result<DynamicImage>
+    where
+        F: FnOnce() -> Result<DynamicImage>,
+    {
+        if let Some(image) = self.get(timestamp, config)? {
+            return Ok(image);
+        }
+
+        let image = f()?;
+        self.insert(timestamp, config, &image)?;
+        Ok(image)
+    }
+}
+
+impl ImageEntry {
+    /// Decode and `preprocess` the image, reusing the result stored in `cache` under `config`.
+    pub fn load_cached<F>(
+        &self,
+        cache: &FrameCache,
+        config: &str,
+        preprocess: F,
+    ) -> Result<ImageRecord>
+    where
+        F: FnOnce(DynamicImage) -> DynamicImage,
+    {
+        let image = cache.get_or_insert_with(self.timestamp, config, || {
+            Ok(preprocess(self.load()?.image))
+        })?;
+
+        Ok(ImageRecord {
+            timestamp: self.timestamp,
+            image,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::GenericImageView;
+
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use crate::EuRoC;
+
+    #[test]
+    fn key() {
+        let ts = Timestamp::from(1403636579763555584);
+        assert_eq!(
+            FrameCache::key(ts, "scale=2"),
+            FrameCache::key(ts, "scale=2")
+        );
+        assert_ne!(
+            FrameCache::key(ts, "scale=2"),
+            FrameCache::key(ts, "scale=4")
+        );
+        assert_ne!(
+            FrameCache::key(ts, "scale=2"),
+            FrameCache::key(Timestamp::from(1403636579813555456), "scale=2")
+        );
+        assert_eq!(FrameCache::key(ts, "").len(), 16);
+    }
+
+    #[test]
+    fn load_cached() -> Result<()> {
+        let root = TempDir::new("frame_cache")?;
+        let cache = FrameCache::new(&root)?;
+
+        let entry = EuRoC::new("test_data")?
+            .left_camera()?
+            .entries()?
+            .next()
+            .unwrap()?;
+        let half = |image: DynamicImage| image.thumbnail_exact(376, 240);
+
+        let first = entry.load_cached(&cache, "half", half)?;
+        assert_eq!(first.image.dimensions(), (376, 240));
+        assert!(cache.path(entry.timestamp, "half").is_file());
+
+        // a hit does not run the pipeline
+        let second = entry.load_cached(&cache, "half", |_| unreachable!())?;
+        assert_eq!(second.image.to_luma8(), first.image.to_luma8());
+
+        let full = entry.load_cached(&cache, "full", |image| image)?;
+        assert_eq!(full.image.dimensions(), (752, 480));
+
+        Ok(())
+    }
+}
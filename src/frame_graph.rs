@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use nalgebra as na;
+
+use crate::{EuRoC, Result, Sensor};
+
+/// `T_BS` of every sensor, answering transforms between sensor frames.
+#[derive(Debug, Clone, Default)]
+pub struct FrameGraph {
+    t_bs: HashMap<Sensor, na::Isometry3<f64>>,
+}
+
+impl FrameGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the extrinsics of `sensor` wrt. the body-frame.
+    pub fn insert(&mut self, sensor: Sensor, t_bs: na::Isometry3<f64>) {
+        self.t_bs.insert(sensor, t_bs);
+    }
+
+    /// Return extrinsics of `sensor` wrt. the body-frame.
+    pub fn body_to(&self, sensor: Sensor) -> Option<na::Isometry3<f64>> {
+        self.t_bs.get(&sensor).copied()
+    }
+
+    /// Return the pose of `to` in the frame of `from`.
+    ///
+    /// The result maps points from the `to` frame into the `from` frame, e.g.
+    /// `transform(Sensor::Cam0, Sensor::Cam1)` is the stereo extrinsic `T_C0C1`.
+    pub fn transform(&self, from: Sensor, to: Sensor) -> Option<na::Isometry3<f64>> {
+        Some(self.body_to(from)?.inverse() * self.body_to(to)?)
+    }
+}
+
+impl EuRoC {
+    /// Collect the extrinsics of every available sensor.
+    pub fn frame_graph(&self) -> Result<FrameGraph> {
+        let mut graph = FrameGraph::new();
+
+        for &sensor in Sensor::ALL.iter() {
+            if !self.source.is_dir(&self.sensor_dir(sensor)) {
+                continue;
+            }
+            let t_bs = match sensor {
+                Sensor::Cam0 | Sensor::Cam1 => self.camera(sensor)?.extrinsics_isometry()?,
+                Sensor::Imu0 => self.imu()?.extrinsics_isometry()?,
+                Sensor::Leica0 => self.position()?.extrinsics_isometry()?,
+                Sensor::Vicon0 => self.vicon()?.extrinsics_isometry()?,
+                Sensor::GroundTruth => self.ground_truth()?.extrinsics_isometry()?,
+            };
+            graph.insert(sensor, t_bs);
+        }
+
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stereo_extrinsic() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let graph = data.frame_graph()?;
+
+        let t_c0c1 = graph.transform(Sensor::Cam0, Sensor::Cam1).unwrap();
+        // EuRoC stereo baseline is about 11 cm along the camera x axis
+        let t = t_c0c1.translation.vector;
+        assert!((t.norm() - 0.11).abs() < 0.001);
+        assert!(t.x > 0.1);
+
+        let t_c1c0 = graph.transform(Sensor::Cam1, Sensor::Cam0).unwrap();
+        assert!((t_c0c1 * t_c1c0).translation.vector.norm() < 1e-9);
+
+        let t_ic0 = graph.transform(Sensor::Imu0, Sensor::Cam0).unwrap();
+        assert!((t_ic0.to_homogeneous() - data.left_camera()?.extrinsics()?).amax() < 1e-6);
+
+        Ok(())
+    }
+}
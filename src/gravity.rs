@@ -0,0 +1,177 @@
+use nalgebra as na;
+
+use crate::{
+    interpolation::bracket, GroundTruthData, GroundTruthRecord, ImuRecord, Result, Timestamp,
+    Timestamped,
+};
+
+/// Standard gravity (m/s^2) along world -z.
+pub const GRAVITY: f64 = 9.81;
+
+/// Frame in which gravity-compensated acceleration is expressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccelFrame {
+    /// IMU sensor frame
+    Body,
+    /// ground-truth world frame
+    World,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinearAccelRecord {
+    pub timestamp: Timestamp,
+    /// gravity-compensated linear acceleration (m/s^2)
+    pub accel: na::Vector3<f64>,
+}
+
+impl Timestamped for LinearAccelRecord {
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+/// Removes gravity from IMU specific force using ground-truth orientation.
+#[derive(Debug, Clone)]
+pub struct GravityCompensator {
+    timestamps: Vec<Timestamp>,
+    orientations: Vec<na::UnitQuaternion<f64>>,
+    gravity: na::Vector3<f64>,
+    frame: AccelFrame,
+}
+
+impl GravityCompensator {
+    /// `ground_truth` must be sorted by timestamp.
+    pub fn new(ground_truth: &[GroundTruthRecord], frame: AccelFrame) -> Self {
+        Self {
+            timestamps: ground_truth.iter().map(|r| r.timestamp).collect(),
+            orientations: ground_truth.iter().map(|r| r.pose().rotation).collect(),
+            gravity: na::Vector3::new(0.0, 0.0, -GRAVITY),
+            frame,
+        }
+    }
+
+    /// Override the world-frame gravity vector.
+    pub const fn with_gravity(mut self, gravity: na::Vector3<f64>) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Return SLERP-interpolated orientation, or `None` outside the ground truth.
+    pub fn orientation_at(&self, ts: Timestamp) -> Option<na::UnitQuaternion<f64>> {
+        let (i, j, alpha) = bracket(&self.timestamps, ts)?;
+        Some(self.orientations[i].slerp(&self.orientations[j], alpha))
+    }
+
+    /// Return gravity-compensated acceleration of one sample.
+    pub fn compensate(&self, record: &ImuRecord) -> Option<LinearAccelRecord> {
+        let orientation = self.orientation_at(record.timestamp)?;
+        let world = orientation * record.accel + self.gravity;
+
+        Some(LinearAccelRecord {
+            timestamp: record.timestamp,
+            accel: match self.frame {
+                AccelFrame::World => world,
+                AccelFrame::Body => orientation.inverse() * world,
+            },
+        })
+    }
+
+    /// Wrap an IMU record iterator.
+    ///
+    /// Samples outside the time range of the ground truth are skipped. Chain
+    /// after [`crate::BiasCorrector::apply`] to remove accelerometer bias as well.
+    pub fn apply<I>(&self, records: I) -> GravityCompensatedIterator<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = Result<ImuRecord>>,
+    {
+        GravityCompensatedIterator {
+            inner: records.into_iter(),
+            compensator: self,
+        }
+    }
+}
+
+impl GroundTruthData {
+    /// Build a [`GravityCompensator`] from the estimated orientation.
+    pub fn gravity_compensator(&self, frame: AccelFrame) -> Result<GravityCompensator> {
+        let records = self.records()?.collect::<Result<Vec<_>>>()?;
+        Ok(GravityCompensator::new(&records, frame))
+    }
+}
+
+pub struct GravityCompensatedIterator<'a, I> {
+    inner: I,
+    compensator: &'a GravityCompensator,
+}
+
+impl<'a, I> Iterator for GravityCompensatedIterator<'a, I>
+where
+    I: Iterator<Item = Result<ImuRecord>>,
+{
+    type Item = Result<LinearAccelRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next()? {
+                Ok(record) => {
+                    if let Some(record) = self.compensator.compensate(&record) {
+                        return Some(Ok(record));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ground_truth(ts: u64, rotation: na::UnitQuaternion<f64>) -> GroundTruthRecord {
+        let pose = na::Isometry3::from_parts(na::Translation3::identity(), rotation);
+        GroundTruthRecord::from_pose(ts.into(), pose)
+    }
+
+    fn imu(ts: u64, accel: na::Vector3<f64>) -> ImuRecord {
+        ImuRecord {
+            timestamp: ts.into(),
+            gyro: na::Vector3::zeros(),
+            accel,
+        }
+    }
+
+    #[test]
+    fn static_sensor() {
+        let rotation = na::UnitQuaternion::from_euler_angles(0.3, -0.2, 1.0);
+        let gt = [ground_truth(0, rotation), ground_truth(100, rotation)];
+        // a static accelerometer measures the reaction to gravity
+        let specific_force = rotation.inverse() * na::Vector3::new(0.0, 0.0, GRAVITY);
+
+        for &frame in &[AccelFrame::World, AccelFrame::Body] {
+            let compensator = GravityCompensator::new(&gt, frame);
+            let out = compensator.compensate(&imu(50, specific_force)).unwrap();
+            assert!(out.accel.norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn apply() -> Result<()> {
+        let gt = [
+            ground_truth(0, na::UnitQuaternion::identity()),
+            ground_truth(100, na::UnitQuaternion::from_euler_angles(0.0, 0.0, 1.0)),
+        ];
+        let compensator = GravityCompensator::new(&gt, AccelFrame::World);
+        let records = vec![
+            Ok(imu(50, na::Vector3::new(1.0, 0.0, GRAVITY))),
+            Ok(imu(150, na::Vector3::new(1.0, 0.0, GRAVITY))),
+        ];
+        let out = compensator.apply(records).collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(out.len(), 1);
+        let expected = na::UnitQuaternion::from_euler_angles(0.0, 0.0, 0.5) * na::Vector3::x();
+        assert!((out[0].accel - expected).norm() < 1e-9);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use std::io::Read;
+
+use image::{codecs::png::PngDecoder, ColorType, GrayImage, ImageDecoder, ImageFormat};
+
+use crate::{
+    CameraRecords, EurocError, ImageEntry, ImageEntryIterator, Result, Timestamp, Timestamped,
+};
+
+/// 8-bit grayscale image, the format recorded by the EuRoC cameras.
+#[derive(Debug, Clone)]
+pub struct GrayRecord {
+    pub timestamp: Timestamp,
+    pub image: GrayImage,
+}
+
+impl GrayRecord {
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+
+    /// Return the bytes from the start of one row to the next.
+    ///
+    /// Rows are tightly packed, so this is always the width.
+    pub fn stride(&self) -> usize {
+        self.image.width() as usize
+    }
+
+    /// Return the pixels row by row.
+    pub fn as_raw(&self) -> &[u8] {
+        self.image.as_raw()
+    }
+
+    pub fn into_raw(self) -> Vec<u8> {
+        self.image.into_raw()
+    }
+}
+
+impl Timestamped for GrayRecord {
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+/// Image file as stored in the dataset, without decoding it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawImage {
+    pub timestamp: Timestamp,
+    /// encoded file, PNG for the EuRoC cameras
+    pub data: Vec<u8>,
+}
+
+impl Timestamped for RawImage {
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+impl ImageEntry {
+    /// Decode the image as 8-bit grayscale.
+    ///
+    /// Grayscale PNGs are decoded straight into the result; other images are
+    /// decoded with [`ImageEntry::load`] and converted.
+    pub fn load_gray(&self) -> Result<GrayRecord> {
+        if ImageFormat::from_path(&self.path)? == ImageFormat::Png {
+            let decoder = PngDecoder::new(self.open()?)?;
+            if decoder.color_type() == ColorType::L8 {
+                let (width, height) = decoder.dimensions();
+                let mut buf = vec![0; decoder.total_bytes() as usize];
+                decoder.read_image(&mut buf)?;
+                let image = GrayImage::from_raw(width, height, buf).ok_or_else(|| {
+                    EurocError::InvalidInput(format!("{}: truncated image", self.path.display()))
+                })?;
+
+                return Ok(GrayRecord {
+                    timestamp: self.timestamp,
+                    image,
+                });
+            }
+        }
+
+        Ok(GrayRecord {
+            timestamp: self.timestamp,
+            image: self.load()?.image.into_luma8(),
+        })
+    }
+
+    /// Read the image file without decoding it.
+    pub fn load_raw(&self) -> Result<RawImage> {
+        let mut data = Vec::new();
+        self.open()?.read_to_end(&mut data)?;
+
+        Ok(RawImage {
+            timestamp: self.timestamp,
+            data,
+        })
+    }
+}
+
+impl CameraRecords {
+    /// Iterate over records in `data.csv` order, decoded as 8-bit grayscale.
+    pub fn records_gray(&self) -> Result<GrayIterator> {
+        Ok(GrayIterator {
+            entries: self.entries()?,
+        })
+    }
+
+    /// Iterate over the image files in `data.csv` order without decoding them.
+    pub fn records_raw(&self) -> Result<RawImageIterator> {
+        Ok(RawImageIterator {
+            entries: self.entries()?,
+        })
+    }
+}
+
+pub struct GrayIterator {
+    entries: ImageEntryIterator,
+}
+
+impl Iterator for GrayIterator {
+    type Item = Result<GrayRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(|entry| entry?.load_gray())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for GrayIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back().map(|entry| entry?.load_gray())
+    }
+}
+
+impl ExactSizeIterator for GrayIterator {}
+
+pub struct RawImageIterator {
+    entries: ImageEntryIterator,
+}
+
+impl Iterator for RawImageIterator {
+    type Item = Result<RawImage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(|entry| entry?.load_raw())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for RawImageIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back().map(|entry| entry?.load_raw())
+    }
+}
+
+impl ExactSizeIterator for RawImageIterator {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EuRoC;
+
+    #[test]
+    fn records_gray() -> Result<()> {
+        let camera = EuRoC::new("test_data")?.left_camera()?;
+        let decoded = camera.records()?.next().unwrap()?.image.to_luma8();
+
+        let gray = camera.records_gray()?.next().unwrap()?;
+        assert_eq!(gray.timestamp, 1403636579763555584.into());
+        assert_eq!(
+            (gray.width(), gray.height(), gray.stride()),
+            (752, 480, 752)
+        );
+        assert_eq!(gray.into_raw(), decoded.into_raw());
+        assert_eq!(camera.records_gray()?.len(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn records_raw() -> Result<()> {
+        let camera = EuRoC::new("test_data")?.left_camera()?;
+        let entry = camera.entries()?.next_back().unwrap()?;
+
+        let raw = camera.records_raw()?.next_back().unwrap()?;
+        assert_eq!(raw.timestamp, entry.timestamp);
+        assert_eq!(raw.data, std::fs::read(&entry.path)?);
+        assert!(raw.data.starts_with(b"\x89PNG"));
+
+        Ok(())
+    }
+}
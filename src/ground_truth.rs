@@ -1,52 +1,106 @@
-use std::{fs::File, path::PathBuf};
+use std::{path::PathBuf, sync::Arc};
 
-use anyhow::{ensure, Result};
 use nalgebra as na;
-use yaml_rust::Yaml;
 
-use crate::{load_yaml, Timestamp};
+use crate::{
+    csv_row::{Checkpoint, IndexCache, IndexedRows, Row},
+    yaml::SensorYaml,
+    DataSource, ErrorPolicy, FileSystem, Result, Timestamp, Timestamped,
+};
 
 const DATA_CSV: &str = "data.csv";
 const SENSOR_YAML: &str = "sensor.yaml";
 
 #[derive(Debug, Clone)]
 pub struct GroundTruthData {
+    source: Arc<dyn DataSource>,
     path: PathBuf,
+    index: IndexCache,
+    on_error: ErrorPolicy,
 }
 
 impl GroundTruthData {
     pub fn new(path: PathBuf) -> Result<Self> {
-        ensure!(path.is_dir());
-        ensure!(path.join(DATA_CSV).is_file());
-        ensure!(path.join(SENSOR_YAML).is_file());
+        Self::with_source(FileSystem::shared(), path)
+    }
+
+    /// Open the folder `path` of `source`.
+    pub fn with_source(source: Arc<dyn DataSource>, path: PathBuf) -> Result<Self> {
+        source.require_dir(&path)?;
+        source.require_file(&path.join(DATA_CSV))?;
+        source.require_file(&path.join(SENSOR_YAML))?;
+
+        Ok(Self {
+            on_error: ErrorPolicy::default(),
+            index: IndexCache::new(Arc::clone(&source)),
+            source,
+            path,
+        })
+    }
+
+    /// Handle bad records in `load_all` with `policy`.
+    ///
+    /// The accessors of [`EuRoC`](crate::EuRoC) start from
+    /// [`Config::on_error`](crate::Config::on_error), [`Self::new`] from
+    /// [`ErrorPolicy::Strict`].
+    pub const fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.on_error = policy;
+        self
+    }
 
-        Ok(Self { path })
+    /// Return the policy `load_all` handles bad records with.
+    pub const fn error_policy(&self) -> ErrorPolicy {
+        self.on_error
     }
 
     #[inline]
-    fn read_sensor_yaml(&self) -> Result<Vec<Yaml>> {
-        load_yaml(self.path.join(SENSOR_YAML))
+    fn read_sensor_yaml(&self) -> Result<SensorYaml> {
+        SensorYaml::load(&*self.source, self.path.join(SENSOR_YAML))
     }
 
     /// Return extrinsics wrt. the body-frame.
     pub fn extrinsics(&self) -> Result<na::Matrix4<f64>> {
-        let data: Vec<_> = self.read_sensor_yaml()?[0]["T_BS"]["data"]
-            .as_vec()
-            .unwrap()
-            .iter()
-            .map(|v| v.as_f64().unwrap())
-            .collect();
+        self.read_sensor_yaml()?.matrix4("T_BS")
+    }
+
+    /// Return extrinsics wrt. the body-frame as a rigid body transform.
+    ///
+    /// Fails if `T_BS` is not orthonormal. Use [`na::Isometry3::inverse`] for `T_SB`.
+    pub fn extrinsics_isometry(&self) -> Result<na::Isometry3<f64>> {
+        self.read_sensor_yaml()?.isometry("T_BS")
+    }
 
-        assert!(data.len() == 16);
+    /// Return the number of records, counting the rows of `data.csv` on the first call.
+    pub fn len(&self) -> Result<usize> {
+        self.index.row_count(&self.path.join(DATA_CSV))
+    }
 
-        Ok(na::Matrix4::from_row_slice(&data))
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
     }
 
+    /// Iterate over records in `data.csv` order.
     pub fn records(&self) -> Result<GroundTruthIterator> {
-        let f = File::open(self.path.join(DATA_CSV))?;
+        Ok(GroundTruthIterator {
+            rows: self.index.rows(self.path.join(DATA_CSV))?,
+        })
+    }
 
+    /// Iterate over records with `start <= timestamp < end`.
+    ///
+    /// The first call indexes `data.csv`; later calls seek directly to `start`.
+    pub fn records_between(&self, start: Timestamp, end: Timestamp) -> Result<GroundTruthIterator> {
         Ok(GroundTruthIterator {
-            reader: csv::Reader::from_reader(f).into_records(),
+            rows: self
+                .index
+                .rows_between(self.path.join(DATA_CSV), start, end)?,
+        })
+    }
+
+    /// Continue an iteration where `checkpoint` was taken, without indexing `data.csv`.
+    pub fn resume(&self, checkpoint: &Checkpoint) -> Result<GroundTruthIterator> {
+        Ok(GroundTruthIterator {
+            rows: self.index.resume(self.path.join(DATA_CSV), checkpoint)?,
         })
     }
 }
@@ -60,55 +114,138 @@ pub struct GroundTruthRecord {
     pub quaternion: na::Quaternion<f64>,
     /// linear velocity (m/s)
     pub velocity: na::Vector3<f64>,
-    /// angular velocity (rad/s)
-    pub gyro: na::Vector3<f64>,
-    /// linear acceleration (m/s^2)
-    pub accel: na::Vector3<f64>,
+    /// gyroscope bias b_w_RS_S (rad/s)
+    pub gyro_bias: na::Vector3<f64>,
+    /// accelerometer bias b_a_RS_S (m/s^2)
+    pub accel_bias: na::Vector3<f64>,
+}
+
+impl Timestamped for GroundTruthRecord {
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+impl GroundTruthRecord {
+    #[deprecated(note = "columns 12-14 are the gyroscope bias, use `gyro_bias`")]
+    pub const fn gyro(&self) -> na::Vector3<f64> {
+        self.gyro_bias
+    }
+
+    #[deprecated(note = "columns 15-17 are the accelerometer bias, use `accel_bias`")]
+    pub const fn accel(&self) -> na::Vector3<f64> {
+        self.accel_bias
+    }
+
+    /// Return body pose wrt. the world frame.
+    pub fn pose(&self) -> na::Isometry3<f64> {
+        na::Isometry3::from_parts(
+            self.position.into(),
+            na::UnitQuaternion::from_quaternion(self.quaternion),
+        )
+    }
+}
+
+#[cfg(test)]
+impl GroundTruthRecord {
+    /// Return a record at `pose`, at rest and without biases.
+    pub(crate) fn from_pose(timestamp: Timestamp, pose: na::Isometry3<f64>) -> Self {
+        Self {
+            timestamp,
+            position: pose.translation.vector,
+            quaternion: pose.rotation.into_inner(),
+            velocity: na::Vector3::zeros(),
+            gyro_bias: na::Vector3::zeros(),
+            accel_bias: na::Vector3::zeros(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl crate::EuRoC {
+    /// Open `source` with its ground truth replaced by `records`, e.g. to
+    /// cover the frames of `test_data`, whose ground truth starts after them.
+    pub(crate) fn with_ground_truth(
+        mut source: crate::MemorySource,
+        records: &[GroundTruthRecord],
+    ) -> Result<Self> {
+        use std::fmt::Write as _;
+
+        let mut csv = "#timestamp,p_x,p_y,p_z,q_w,q_x,q_y,q_z,v_x,v_y,v_z,\
+                       bw_x,bw_y,bw_z,ba_x,ba_y,ba_z\n"
+            .to_owned();
+        for r in records {
+            let q = [
+                r.quaternion.w,
+                r.quaternion.i,
+                r.quaternion.j,
+                r.quaternion.k,
+            ];
+            let values = r
+                .position
+                .iter()
+                .chain(&q)
+                .chain(r.velocity.iter())
+                .chain(r.gyro_bias.iter())
+                .chain(r.accel_bias.iter());
+            let _ = write!(csv, "{}", r.timestamp.nsecs());
+            for value in values {
+                let _ = write!(csv, ",{}", value);
+            }
+            csv.push('\n');
+        }
+        source.insert(
+            PathBuf::from("state_groundtruth_estimate0").join(DATA_CSV),
+            csv.as_bytes(),
+        );
+
+        Self::from_source(Arc::new(source), "")
+    }
 }
 
 pub struct GroundTruthIterator {
-    reader: csv::StringRecordsIntoIter<File>,
+    rows: IndexedRows,
+}
+
+impl GroundTruthIterator {
+    /// Return where the iteration stopped, for [`GroundTruthData::resume`].
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.rows.checkpoint()
+    }
+
+    fn parse(row: Result<Row>) -> Result<GroundTruthRecord> {
+        let row = row?;
+        Ok(GroundTruthRecord {
+            timestamp: row.timestamp()?,
+            position: row.vector3(1)?,
+            quaternion: row.quaternion(4)?,
+            velocity: row.vector3(8)?,
+            gyro_bias: row.vector3(11)?,
+            accel_bias: row.vector3(14)?,
+        })
+    }
 }
 
 impl Iterator for GroundTruthIterator {
     type Item = Result<GroundTruthRecord>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.reader.next().map(|row| {
-            let row = row?;
-            Ok(GroundTruthRecord {
-                timestamp: row[0].parse::<u64>()?.into(),
-                position: na::Vector3::new(
-                    row[1].parse::<f64>()?,
-                    row[2].parse::<f64>()?,
-                    row[3].parse::<f64>()?,
-                ),
-                quaternion: na::Quaternion::new(
-                    row[4].parse::<f64>()?,
-                    row[5].parse::<f64>()?,
-                    row[6].parse::<f64>()?,
-                    row[7].parse::<f64>()?,
-                ),
-                velocity: na::Vector3::new(
-                    row[8].parse::<f64>()?,
-                    row[9].parse::<f64>()?,
-                    row[10].parse::<f64>()?,
-                ),
-                gyro: na::Vector3::new(
-                    row[11].parse::<f64>()?,
-                    row[12].parse::<f64>()?,
-                    row[13].parse::<f64>()?,
-                ),
-                accel: na::Vector3::new(
-                    row[14].parse::<f64>()?,
-                    row[15].parse::<f64>()?,
-                    row[16].parse::<f64>()?,
-                ),
-            })
-        })
+        self.rows.next().map(Self::parse)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
     }
 }
 
+impl DoubleEndedIterator for GroundTruthIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.rows.next_back().map(Self::parse)
+    }
+}
+
+impl ExactSizeIterator for GroundTruthIterator {}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -140,9 +277,12 @@ mod test {
             record.velocity,
             na::Vector3::new(-0.030043, 0.034999, 0.808240)
         );
-        assert_eq!(record.gyro, na::Vector3::new(-0.003172, 0.021267, 0.078502));
         assert_eq!(
-            record.accel,
+            record.gyro_bias,
+            na::Vector3::new(-0.003172, 0.021267, 0.078502)
+        );
+        assert_eq!(
+            record.accel_bias,
             na::Vector3::new(-0.025266, 0.136696, 0.075593)
         );
 
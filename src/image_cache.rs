@@ -0,0 +1,178 @@
+use std::collections::{BTreeMap, HashMap};
+
+use image::DynamicImage;
+
+use crate::{CameraRecords, ImageRecord, Result, Timestamp};
+
+/// Limit of an [`ImageCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheCapacity {
+    /// number of decoded frames
+    Frames(usize),
+    /// bytes of decoded pixel data
+    Bytes(usize),
+}
+
+/// Camera keeping the most recently used decoded frames in memory.
+///
+/// For window-based optimizers that revisit frames; created by
+/// [`CameraRecords::with_cache`]. When full, the least recently used frame
+/// is evicted.
+#[derive(Debug, Clone)]
+pub struct ImageCache {
+    camera: CameraRecords,
+    capacity: CacheCapacity,
+    /// frame and last use of every cached timestamp
+    frames: HashMap<Timestamp, (DynamicImage, u64)>,
+    /// cached timestamps by last use
+    uses: BTreeMap<u64, Timestamp>,
+    clock: u64,
+    bytes: usize,
+}
+
+impl ImageCache {
+    /// Return the image recorded exactly at `timestamp`, decoding it only if not cached.
+    pub fn get_by_timestamp(&mut self, timestamp: Timestamp) -> Result<Option<ImageRecord>> {
+        self.clock += 1;
+        if let Some((image, used)) = self.frames.get_mut(&timestamp) {
+            self.uses.remove(used);
+            self.uses.insert(self.clock, timestamp);
+            *used = self.clock;
+            return Ok(Some(ImageRecord {
+                timestamp,
+                image: image.clone(),
+            }));
+        }
+
+        let record = match self.camera.get_by_timestamp(timestamp)? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        self.bytes += record.image.as_bytes().len();
+        self.frames
+            .insert(timestamp, (record.image.clone(), self.clock));
+        self.uses.insert(self.clock, timestamp);
+        self.evict();
+
+        Ok(Some(record))
+    }
+
+    /// Drop the least recently used frames until within capacity.
+    fn evict(&mut self) {
+        while self.is_over_capacity() {
+            let oldest = match self.uses.keys().next() {
+                Some(&oldest) => oldest,
+                None => break,
+            };
+            if let Some(timestamp) = self.uses.remove(&oldest) {
+                if let Some((image, _)) = self.frames.remove(&timestamp) {
+                    self.bytes -= image.as_bytes().len();
+                }
+            }
+        }
+    }
+
+    fn is_over_capacity(&self) -> bool {
+        match self.capacity {
+            CacheCapacity::Frames(frames) => self.frames.len() > frames,
+            CacheCapacity::Bytes(bytes) => self.bytes > bytes,
+        }
+    }
+
+    /// Return whether the frame at `timestamp` is cached, without marking it as used.
+    pub fn contains(&self, timestamp: Timestamp) -> bool {
+        self.frames.contains_key(&timestamp)
+    }
+
+    /// Return the number of cached frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Return the bytes of pixel data cached.
+    pub const fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.uses.clear();
+        self.bytes = 0;
+    }
+
+    /// Return the camera the frames are read from.
+    pub const fn camera(&self) -> &CameraRecords {
+        &self.camera
+    }
+}
+
+impl CameraRecords {
+    /// Cache the frames decoded by [`ImageCache::get_by_timestamp`] up to `capacity`.
+    pub fn with_cache(self, capacity: CacheCapacity) -> ImageCache {
+        ImageCache {
+            camera: self,
+            capacity,
+            frames: HashMap::new(),
+            uses: BTreeMap::new(),
+            clock: 0,
+            bytes: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Duration, EuRoC, Sensor};
+
+    #[test]
+    fn lru() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let ts = data.timestamps(Sensor::Cam0)?;
+        let mut cache = data.left_camera()?.with_cache(CacheCapacity::Frames(2));
+
+        let first = cache.get_by_timestamp(ts[0])?.unwrap();
+        assert_eq!(first.timestamp, ts[0]);
+        cache.get_by_timestamp(ts[1])?;
+        // use ts[0] again, so ts[1] is evicted next
+        let again = cache.get_by_timestamp(ts[0])?.unwrap();
+        assert_eq!(again.image.to_luma8(), first.image.to_luma8());
+        cache.get_by_timestamp(ts[2])?;
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains(ts[0]) && cache.contains(ts[2]));
+        assert!(!cache.contains(ts[1]));
+        assert_eq!(cache.bytes(), 2 * 752 * 480);
+
+        assert!(cache
+            .get_by_timestamp(ts[0] + Duration::from_nsecs(1))?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn bytes() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let ts = data.timestamps(Sensor::Cam0)?;
+        let mut cache = data
+            .left_camera()?
+            .with_cache(CacheCapacity::Bytes(3 * 752 * 480 - 1));
+
+        for &t in &ts {
+            cache.get_by_timestamp(t)?;
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains(ts[3]) && cache.contains(ts[4]));
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.bytes(), 0);
+
+        Ok(())
+    }
+}
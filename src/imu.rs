@@ -1,82 +1,259 @@
-use std::{fs::File, path::PathBuf};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use anyhow::{ensure, Result};
 use nalgebra as na;
-use yaml_rust::Yaml;
 
-use crate::{load_yaml, Timestamp};
+use crate::{
+    csv_row::{Checkpoint, IndexCache, IndexedRows, Row},
+    yaml::SensorYaml,
+    ConfigWarning, DataSource, ErrorPolicy, FileSystem, Result, Timestamp, Timestamped,
+};
 
 const DATA_CSV: &str = "data.csv";
 const SENSOR_YAML: &str = "sensor.yaml";
+const IMU_FIELDS: [&str; 5] = [
+    "rate_hz",
+    "gyroscope_noise_density",
+    "gyroscope_random_walk",
+    "accelerometer_noise_density",
+    "accelerometer_random_walk",
+];
+const CSV_HEADER: [&str; 7] = [
+    "#timestamp [ns]",
+    "w_RS_S_x [rad s^-1]",
+    "w_RS_S_y [rad s^-1]",
+    "w_RS_S_z [rad s^-1]",
+    "a_RS_S_x [m s^-2]",
+    "a_RS_S_y [m s^-2]",
+    "a_RS_S_z [m s^-2]",
+];
+
+/// IMU calibration parsed from `sensor.yaml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImuConfig {
+    /// nominal sample rate (Hz)
+    pub rate_hz: f64,
+    /// gyroscope "white noise" (rad/s/√Hz)
+    pub gyro_noise_density: f64,
+    /// gyroscope "random walk" (rad/s^2/√Hz)
+    pub gyro_random_walk: f64,
+    /// accelerometer "white noise" (m/s^2/√Hz)
+    pub accel_noise_density: f64,
+    /// accelerometer "random walk" (m/s^3/√Hz)
+    pub accel_random_walk: f64,
+    /// extrinsics wrt. the body-frame
+    pub t_bs: na::Matrix4<f64>,
+    /// fields of `sensor.yaml` this crate does not use
+    pub warnings: Vec<ConfigWarning>,
+}
+
+impl ImuConfig {
+    /// Parse an IMU `sensor.yaml`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::load_from(&FileSystem, path)
+    }
+
+    /// Parse an IMU `sensor.yaml` of `source`.
+    pub fn load_from<P: AsRef<Path>>(source: &dyn DataSource, path: P) -> Result<Self> {
+        let yaml = SensorYaml::load(source, path.as_ref().to_owned())?;
+
+        Ok(Self {
+            rate_hz: yaml.f64("rate_hz")?,
+            gyro_noise_density: yaml.f64("gyroscope_noise_density")?,
+            gyro_random_walk: yaml.f64("gyroscope_random_walk")?,
+            accel_noise_density: yaml.f64("accelerometer_noise_density")?,
+            accel_random_walk: yaml.f64("accelerometer_random_walk")?,
+            t_bs: yaml.matrix4("T_BS")?,
+            warnings: yaml.unknown_fields(&IMU_FIELDS),
+        })
+    }
+
+    /// Return the standard deviation of a single gyroscope sample at `rate_hz` (rad/s).
+    pub fn gyro_noise_sigma(&self, rate_hz: f64) -> f64 {
+        self.gyro_noise_density * rate_hz.sqrt()
+    }
+
+    /// Return the standard deviation of the gyroscope bias step between samples at `rate_hz` (rad/s).
+    pub fn gyro_random_walk_sigma(&self, rate_hz: f64) -> f64 {
+        self.gyro_random_walk / rate_hz.sqrt()
+    }
+
+    /// Return the standard deviation of a single accelerometer sample at `rate_hz` (m/s^2).
+    pub fn accel_noise_sigma(&self, rate_hz: f64) -> f64 {
+        self.accel_noise_density * rate_hz.sqrt()
+    }
+
+    /// Return the standard deviation of the accelerometer bias step between samples at `rate_hz` (m/s^2).
+    pub fn accel_random_walk_sigma(&self, rate_hz: f64) -> f64 {
+        self.accel_random_walk / rate_hz.sqrt()
+    }
+}
 
 #[derive(Debug)]
 pub struct ImuData {
+    source: Arc<dyn DataSource>,
     path: PathBuf,
+    index: IndexCache,
+    on_error: ErrorPolicy,
 }
 
 impl ImuData {
     pub fn new(path: PathBuf) -> Result<Self> {
-        ensure!(path.is_dir());
-        ensure!(path.join(DATA_CSV).is_file());
-        ensure!(path.join(SENSOR_YAML).is_file());
+        Self::with_source(FileSystem::shared(), path)
+    }
+
+    /// Open the folder `path` of `source`.
+    pub fn with_source(source: Arc<dyn DataSource>, path: PathBuf) -> Result<Self> {
+        source.require_dir(&path)?;
+        source.require_file(&path.join(DATA_CSV))?;
+        source.require_file(&path.join(SENSOR_YAML))?;
+
+        Ok(Self {
+            on_error: ErrorPolicy::default(),
+            index: IndexCache::new(Arc::clone(&source)),
+            source,
+            path,
+        })
+    }
+
+    /// Handle bad records in `load_all` with `policy`.
+    ///
+    /// The accessors of [`EuRoC`](crate::EuRoC) start from
+    /// [`Config::on_error`](crate::Config::on_error), [`Self::new`] from
+    /// [`ErrorPolicy::Strict`].
+    pub const fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.on_error = policy;
+        self
+    }
 
-        Ok(Self { path })
+    /// Return the policy `load_all` handles bad records with.
+    pub const fn error_policy(&self) -> ErrorPolicy {
+        self.on_error
     }
 
     #[inline]
-    fn read_sensor_yaml(&self) -> Result<Vec<Yaml>> {
-        load_yaml(self.path.join(SENSOR_YAML))
+    fn read_sensor_yaml(&self) -> Result<SensorYaml> {
+        SensorYaml::load(&*self.source, self.path.join(SENSOR_YAML))
     }
 
     /// Return extrinsics wrt. the body-frame.
     pub fn extrinsics(&self) -> Result<na::Matrix4<f64>> {
-        let data: Vec<_> = self.read_sensor_yaml()?[0]["T_BS"]["data"]
-            .as_vec()
-            .unwrap()
-            .iter()
-            .map(|v| v.as_f64().unwrap())
-            .collect();
+        self.read_sensor_yaml()?.matrix4("T_BS")
+    }
+
+    /// Return extrinsics wrt. the body-frame as a rigid body transform.
+    ///
+    /// Fails if `T_BS` is not orthonormal. Use [`na::Isometry3::inverse`] for `T_SB`.
+    pub fn extrinsics_isometry(&self) -> Result<na::Isometry3<f64>> {
+        self.read_sensor_yaml()?.isometry("T_BS")
+    }
 
-        assert!(data.len() == 16);
+    /// Parse the whole `sensor.yaml` at once.
+    pub fn config(&self) -> Result<ImuConfig> {
+        ImuConfig::load_from(&*self.source, self.path.join(SENSOR_YAML))
+    }
 
-        Ok(na::Matrix4::from_row_slice(&data))
+    /// Return nominal sample rate (Hz)
+    pub fn rate_hz(&self) -> Result<f64> {
+        self.read_sensor_yaml()?.f64("rate_hz")
     }
 
     /// Return gyroscope "white noise" (rad/s/√Hz)
     pub fn gyro_noise_density(&self) -> Result<f64> {
-        Ok(self.read_sensor_yaml()?[0]["gyroscope_noise_density"]
-            .as_f64()
-            .unwrap())
+        self.read_sensor_yaml()?.f64("gyroscope_noise_density")
     }
 
     /// Return gyroscope "random walk" (rad/s^2/√Hz)
     pub fn gyro_random_walk(&self) -> Result<f64> {
-        Ok(self.read_sensor_yaml()?[0]["gyroscope_random_walk"]
-            .as_f64()
-            .unwrap())
+        self.read_sensor_yaml()?.f64("gyroscope_random_walk")
     }
 
     /// Return accelerometer "white noise" (m/s^2/√Hz)
     pub fn accel_noise_density(&self) -> Result<f64> {
-        Ok(self.read_sensor_yaml()?[0]["accelerometer_noise_density"]
-            .as_f64()
-            .unwrap())
+        self.read_sensor_yaml()?.f64("accelerometer_noise_density")
     }
 
     /// Return accelerometer "random walk" (m/s^3/√Hz)
     pub fn accel_random_walk(&self) -> Result<f64> {
-        Ok(self.read_sensor_yaml()?[0]["accelerometer_random_walk"]
-            .as_f64()
-            .unwrap())
+        self.read_sensor_yaml()?.f64("accelerometer_random_walk")
+    }
+
+    /// Return the number of records, counting the rows of `data.csv` on the first call.
+    pub fn len(&self) -> Result<usize> {
+        self.index.row_count(&self.path.join(DATA_CSV))
     }
 
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Iterate over records in `data.csv` order.
     pub fn records(&self) -> Result<ImuIterator> {
-        let f = File::open(self.path.join(DATA_CSV))?;
+        Ok(ImuIterator {
+            rows: self.index.rows(self.path.join(DATA_CSV))?,
+        })
+    }
+
+    /// Iterate over records with `start <= timestamp < end`.
+    ///
+    /// The first call indexes `data.csv`; later calls seek directly to `start`.
+    pub fn records_between(&self, start: Timestamp, end: Timestamp) -> Result<ImuIterator> {
+        Ok(ImuIterator {
+            rows: self
+                .index
+                .rows_between(self.path.join(DATA_CSV), start, end)?,
+        })
+    }
 
+    /// Continue an iteration where `checkpoint` was taken, without indexing `data.csv`.
+    pub fn resume(&self, checkpoint: &Checkpoint) -> Result<ImuIterator> {
         Ok(ImuIterator {
-            reader: csv::Reader::from_reader(f).into_records(),
+            rows: self.index.resume(self.path.join(DATA_CSV), checkpoint)?,
         })
     }
+
+    /// Return the samples surrounding `ts`, or `None` outside the recording.
+    ///
+    /// Uses the index of `data.csv` like [`ImuData::records_between`].
+    pub fn bracketing(&self, ts: Timestamp) -> Result<Option<ImuBracket>> {
+        let (rows, alpha) = match self.index.rows_around(self.path.join(DATA_CSV), ts)? {
+            Some(around) => around,
+            None => return Ok(None),
+        };
+
+        let mut records = ImuIterator { rows };
+        let before = match records.next() {
+            Some(record) => record?,
+            None => return Ok(None),
+        };
+        let after = records
+            .next_back()
+            .transpose()?
+            .unwrap_or_else(|| before.clone());
+
+        Ok(Some(ImuBracket {
+            interpolated: ImuRecord {
+                timestamp: ts,
+                gyro: before.gyro.lerp(&after.gyro, alpha),
+                accel: before.accel.lerp(&after.accel, alpha),
+            },
+            before,
+            after,
+        }))
+    }
+}
+
+/// IMU samples at or just before and after a timestamp.
+#[derive(Debug, Clone)]
+pub struct ImuBracket {
+    pub before: ImuRecord,
+    /// same as `before` if a sample is exactly at the timestamp
+    pub after: ImuRecord,
+    /// measurement linearly interpolated at the timestamp
+    pub interpolated: ImuRecord,
 }
 
 #[derive(Debug, Clone)]
@@ -88,33 +265,77 @@ pub struct ImuRecord {
     pub accel: na::Vector3<f64>,
 }
 
+impl Timestamped for ImuRecord {
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+/// Write IMU records as an EuRoC-format `data.csv`.
+pub fn write_imu_csv<W, I>(writer: W, records: I) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = ImuRecord>,
+{
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(CSV_HEADER.iter())?;
+
+    for r in records {
+        writer.write_record(&[
+            r.timestamp.nsecs().to_string(),
+            r.gyro.x.to_string(),
+            r.gyro.y.to_string(),
+            r.gyro.z.to_string(),
+            r.accel.x.to_string(),
+            r.accel.y.to_string(),
+            r.accel.z.to_string(),
+        ])?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
 pub struct ImuIterator {
-    reader: csv::StringRecordsIntoIter<File>,
+    rows: IndexedRows,
+}
+
+impl ImuIterator {
+    /// Return where the iteration stopped, for [`ImuData::resume`].
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.rows.checkpoint()
+    }
+
+    fn parse(row: Result<Row>) -> Result<ImuRecord> {
+        let row = row?;
+        Ok(ImuRecord {
+            timestamp: row.timestamp()?,
+            gyro: row.vector3(1)?,
+            accel: row.vector3(4)?,
+        })
+    }
 }
 
 impl Iterator for ImuIterator {
     type Item = Result<ImuRecord>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.reader.next().map(|row| {
-            let row = row?;
-            Ok(ImuRecord {
-                timestamp: row[0].parse::<u64>()?.into(),
-                gyro: na::Vector3::new(
-                    row[1].parse::<f64>()?,
-                    row[2].parse::<f64>()?,
-                    row[3].parse::<f64>()?,
-                ),
-                accel: na::Vector3::new(
-                    row[4].parse::<f64>()?,
-                    row[5].parse::<f64>()?,
-                    row[6].parse::<f64>()?,
-                ),
-            })
-        })
+        self.rows.next().map(Self::parse)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
     }
 }
 
+impl DoubleEndedIterator for ImuIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.rows.next_back().map(Self::parse)
+    }
+}
+
+impl ExactSizeIterator for ImuIterator {}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -128,6 +349,31 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn config() -> Result<()> {
+        let data = EuRoC::new("test_data")?.imu()?;
+        let config = data.config()?;
+
+        assert_eq!(config.rate_hz, data.rate_hz()?);
+        assert_eq!(config.gyro_random_walk, 1.9393e-05);
+        assert_eq!(config.accel_noise_density, 2.0000e-3);
+        assert_eq!(config.t_bs, data.extrinsics()?);
+        assert!(config.warnings.is_empty());
+
+        assert!((config.accel_noise_sigma(config.rate_hz) / 200f64.sqrt() - 2.0e-3).abs() < 1e-12);
+        assert!((config.gyro_random_walk_sigma(100.0) - 1.9393e-06).abs() < 1e-12);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rate_hz() -> Result<()> {
+        let data = EuRoC::new("test_data")?.imu()?;
+        assert_eq!(data.rate_hz()?, 200.0);
+
+        Ok(())
+    }
+
     #[test]
     fn gyro_noise_density() -> Result<()> {
         let data = EuRoC::new("test_data")?.imu()?;
@@ -186,4 +432,106 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn records_between() -> Result<()> {
+        let data = EuRoC::new("test_data")?.imu()?;
+        let all = data.records()?.collect::<Result<Vec<_>>>()?;
+
+        let window = data
+            .records_between(all[1].timestamp, all[3].timestamp)?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(window.len(), 2);
+        assert_eq!(window[0].gyro, all[1].gyro);
+        assert_eq!(window[1].timestamp, all[2].timestamp);
+
+        // reuses the index
+        let tail = data.records_between(all[3].timestamp, u64::MAX.into())?;
+        assert_eq!(tail.count(), 2);
+
+        let reversed = data.records()?.rev().collect::<Result<Vec<_>>>()?;
+        assert_eq!(reversed.len(), all.len());
+        assert_eq!(reversed[0].timestamp, all[4].timestamp);
+        assert_eq!(reversed[4].accel, all[0].accel);
+
+        Ok(())
+    }
+
+    #[test]
+    fn resume() -> Result<()> {
+        let data = EuRoC::new("test_data")?.imu()?;
+        let all = data.records()?.collect::<Result<Vec<_>>>()?;
+
+        let mut records = data.records()?;
+        records.nth(1).unwrap()?;
+        let checkpoint = records.checkpoint();
+        assert_eq!(checkpoint.row, 2);
+        assert_eq!(checkpoint.last, Some(all[1].timestamp));
+
+        // a fresh instance has no index yet
+        let fresh = EuRoC::new("test_data")?.imu()?;
+        let mut resumed = fresh.resume(&checkpoint)?;
+        assert_eq!(resumed.len(), 3);
+        assert_eq!(resumed.next().unwrap()?.timestamp, all[2].timestamp);
+        assert_eq!(resumed.checkpoint().last, Some(all[2].timestamp));
+        assert_eq!(resumed.next_back().unwrap()?.timestamp, all[4].timestamp);
+        assert_eq!(resumed.next().unwrap()?.timestamp, all[3].timestamp);
+        assert!(resumed.next().is_none());
+
+        let mut window = data.records_between(all[1].timestamp, all[4].timestamp)?;
+        window.next().unwrap()?;
+        let rest = fresh
+            .resume(&window.checkpoint())?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[1].timestamp, all[3].timestamp);
+
+        Ok(())
+    }
+
+    #[test]
+    fn bracketing() -> Result<()> {
+        let data = EuRoC::new("test_data")?.imu()?;
+        let all = data.records()?.collect::<Result<Vec<_>>>()?;
+
+        let step = all[2].timestamp.nsecs() - all[1].timestamp.nsecs();
+        let mid = Timestamp::from(all[1].timestamp.nsecs() + step / 4);
+        let bracket = data.bracketing(mid)?.unwrap();
+        assert_eq!(bracket.before.timestamp, all[1].timestamp);
+        assert_eq!(bracket.after.timestamp, all[2].timestamp);
+        assert_eq!(bracket.interpolated.timestamp, mid);
+        let expected = all[1].gyro * 0.75 + all[2].gyro * 0.25;
+        assert!((bracket.interpolated.gyro - expected).norm() < 1e-12);
+
+        let exact = data.bracketing(all[3].timestamp)?.unwrap();
+        assert_eq!(exact.before.timestamp, exact.after.timestamp);
+        assert_eq!(exact.interpolated.accel, all[3].accel);
+
+        assert!(data.bracketing(0.into())?.is_none());
+        assert!(data.bracketing(u64::MAX.into())?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_imu_csv() -> Result<()> {
+        let data = EuRoC::new("test_data")?.imu()?;
+        let records = data.records()?.collect::<Result<Vec<_>>>()?;
+
+        let mut buf = Vec::new();
+        super::write_imu_csv(&mut buf, records.clone())?;
+
+        let expected = std::fs::read_to_string("test_data/imu0/data.csv")?;
+        let text = String::from_utf8(buf).unwrap();
+        assert_eq!(text.lines().next(), expected.lines().next());
+
+        let reread = csv::Reader::from_reader(text.as_bytes())
+            .into_records()
+            .map(|row| row.unwrap()[0].parse::<u64>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(reread.len(), records.len());
+        assert_eq!(reread[2], records[2].timestamp.nsecs());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,166 @@
+use std::ops::Index;
+
+use crate::{
+    CameraRecords, GroundTruthData, GroundTruthRecord, ImageEntry, ImuData, ImuRecord,
+    PositionData, PositionRecord, Result, Timestamp, Timestamped, ViconData, ViconRecord,
+};
+
+/// Records of one sensor held in memory for random access and timestamp lookups.
+#[derive(Debug, Clone)]
+pub struct IndexedRecords<T> {
+    records: Vec<T>,
+}
+
+impl<T: Timestamped> IndexedRecords<T> {
+    /// Sort `records` by timestamp, keeping the order of equal timestamps.
+    pub fn new(mut records: Vec<T>) -> Self {
+        records.sort_by_key(Timestamped::timestamp);
+        Self { records }
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.records.get(i)
+    }
+
+    /// Return all records in timestamp order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.records
+    }
+
+    /// Return the index of the first record at or after `ts`.
+    fn lower_bound(&self, ts: Timestamp) -> usize {
+        self.records
+            .binary_search_by_key(&ts, Timestamped::timestamp)
+            .unwrap_or_else(|i| i)
+    }
+
+    /// Return the index of the record closest to `ts`, the earlier one on ties.
+    pub fn nearest_index(&self, ts: Timestamp) -> Option<usize> {
+        let i = self.lower_bound(ts);
+        match (i.checked_sub(1), self.records.get(i)) {
+            (Some(before), Some(after)) => {
+                let to_before = ts - self.records[before].timestamp();
+                let to_after = after.timestamp() - ts;
+                Some(if to_before <= to_after { before } else { i })
+            }
+            (Some(before), None) => Some(before),
+            (None, Some(_)) => Some(i),
+            (None, None) => None,
+        }
+    }
+
+    /// Return the record closest to `ts`.
+    pub fn nearest(&self, ts: Timestamp) -> Option<&T> {
+        self.nearest_index(ts).map(|i| &self.records[i])
+    }
+
+    /// Return the last record at or before `ts`.
+    pub fn at_or_before(&self, ts: Timestamp) -> Option<&T> {
+        let i = match self
+            .records
+            .binary_search_by_key(&ts, Timestamped::timestamp)
+        {
+            Ok(i) => i,
+            Err(i) => i.checked_sub(1)?,
+        };
+        self.records.get(i)
+    }
+
+    /// Return the first record at or after `ts`.
+    pub fn at_or_after(&self, ts: Timestamp) -> Option<&T> {
+        self.records.get(self.lower_bound(ts))
+    }
+}
+
+impl<T> Index<usize> for IndexedRecords<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        &self.records[i]
+    }
+}
+
+impl CameraRecords {
+    /// Load all image paths for random access; images are decoded on demand with [`ImageEntry::load`].
+    pub fn indexed(&self) -> Result<IndexedRecords<ImageEntry>> {
+        Ok(IndexedRecords::new(self.entries()?.collect::<Result<_>>()?))
+    }
+}
+
+impl ImuData {
+    /// Load all records for random access.
+    pub fn indexed(&self) -> Result<IndexedRecords<ImuRecord>> {
+        Ok(IndexedRecords::new(self.records()?.collect::<Result<_>>()?))
+    }
+}
+
+impl PositionData {
+    /// Load all records for random access.
+    pub fn indexed(&self) -> Result<IndexedRecords<PositionRecord>> {
+        Ok(IndexedRecords::new(self.records()?.collect::<Result<_>>()?))
+    }
+}
+
+impl ViconData {
+    /// Load all records for random access.
+    pub fn indexed(&self) -> Result<IndexedRecords<ViconRecord>> {
+        Ok(IndexedRecords::new(self.records()?.collect::<Result<_>>()?))
+    }
+}
+
+impl GroundTruthData {
+    /// Load all records for random access.
+    pub fn indexed(&self) -> Result<IndexedRecords<GroundTruthRecord>> {
+        Ok(IndexedRecords::new(self.records()?.collect::<Result<_>>()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EuRoC;
+
+    #[test]
+    fn lookups() -> Result<()> {
+        let frames = EuRoC::new("test_data")?.left_camera()?.indexed()?;
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[1].timestamp, 1403636579813555456.into());
+        assert!(frames.get(5).is_none());
+
+        let nearest = |ts: u64| frames.nearest(ts.into()).unwrap().timestamp.nsecs();
+        assert_eq!(nearest(0), 1403636579763555584);
+        assert_eq!(nearest(1403636579830000000), 1403636579813555456);
+        assert_eq!(nearest(1403636579850000000), 1403636579863555584);
+        assert_eq!(nearest(u64::MAX), 1403636579963555584);
+
+        let before = frames.at_or_before(1403636579850000000.into()).unwrap();
+        assert_eq!(before.timestamp, 1403636579813555456.into());
+        assert_eq!(
+            frames.at_or_before(1403636579813555456.into()).unwrap(),
+            before
+        );
+        assert!(frames.at_or_before(0.into()).is_none());
+
+        let after = frames.at_or_after(1403636579850000000.into()).unwrap();
+        assert_eq!(after.timestamp, 1403636579863555584.into());
+        assert!(after.load().is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn empty() {
+        let records = IndexedRecords::<ImuRecord>::new(Vec::new());
+        assert!(records.is_empty());
+        assert!(records.nearest(0.into()).is_none());
+        assert!(records.at_or_after(0.into()).is_none());
+    }
+}
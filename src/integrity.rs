@@ -0,0 +1,486 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    fnv::{fnv1a, FNV_OFFSET},
+    EuRoC, EurocError, Result, Sensor, Timestamp, Timestamped,
+};
+
+/// Problem found by [`EuRoC::verify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityProblem {
+    /// The sensor folder could not be opened, or a row could not be parsed.
+    Unreadable { sensor: Sensor, error: String },
+    /// A timestamp is not greater than the one before it.
+    NonMonotonic {
+        sensor: Sensor,
+        /// index of the record in `data.csv` order
+        index: usize,
+        previous: Timestamp,
+        timestamp: Timestamp,
+    },
+    /// An image listed in `data.csv` does not exist.
+    MissingImage { sensor: Sensor, path: PathBuf },
+    /// The `data` folder holds a different number of images than `data.csv` lists.
+    ImageCount {
+        sensor: Sensor,
+        listed: usize,
+        found: usize,
+    },
+    /// `cam0` and `cam1` list a different number of images.
+    StereoCount { cam0: usize, cam1: usize },
+    /// A file differs from the checksum manifest, or is missing.
+    Checksum {
+        path: PathBuf,
+        expected: String,
+        actual: Option<String>,
+    },
+    /// The rows of a sensor differ from the [`OrderIndex`], or the sensor is missing.
+    Order {
+        sensor: Sensor,
+        expected: SensorOrder,
+        actual: Option<SensorOrder>,
+    },
+}
+
+/// All problems found by [`EuRoC::verify`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    pub problems: Vec<IntegrityProblem>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Checksums of the files of a dataset, keyed by their path relative to `mav0`.
+///
+/// The hash is 64-bit FNV-1a, which detects corrupted or truncated downloads
+/// but is not tamper-proof.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checksums {
+    pub files: BTreeMap<PathBuf, String>,
+}
+
+impl Checksums {
+    /// Save as `<checksum>  <path>` lines, like `sha256sum`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut text = String::new();
+        for (file, checksum) in &self.files {
+            let _ = writeln!(text, "{}  {}", checksum, file.display());
+        }
+        fs::write(path, text)?;
+
+        Ok(())
+    }
+
+    /// Load checksums written by [`Checksums::save`].
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(path.as_ref())?;
+        let mut files = BTreeMap::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut fields = line.splitn(2, "  ");
+            match (fields.next(), fields.next()) {
+                (Some(checksum), Some(file)) => {
+                    files.insert(PathBuf::from(file), checksum.to_owned());
+                }
+                _ => {
+                    return Err(EurocError::CsvParse {
+                        path: path.as_ref().to_owned(),
+                        line: i as u64 + 1,
+                        message: "expected `<checksum>  <path>`".to_owned(),
+                    })
+                }
+            }
+        }
+
+        Ok(Self { files })
+    }
+}
+
+/// Row count and hash of the timestamps of every sensor, in `data.csv` order.
+///
+/// Saved along the results of an experiment, it pins the order records are
+/// replayed in: [`EuRoC::verify_order`] reports every sensor whose rows were
+/// since added, removed or reordered, whatever the storage backend.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrderIndex {
+    pub sensors: BTreeMap<Sensor, SensorOrder>,
+}
+
+/// Entry of an [`OrderIndex`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SensorOrder {
+    pub rows: usize,
+    /// 64-bit FNV-1a of the timestamps in order
+    pub hash: String,
+}
+
+impl SensorOrder {
+    fn new(timestamps: &[Timestamp]) -> Self {
+        let hash = timestamps.iter().fold(FNV_OFFSET, |hash, ts| {
+            fnv1a(hash, &ts.nsecs().to_le_bytes())
+        });
+        Self {
+            rows: timestamps.len(),
+            hash: format!("{:016x}", hash),
+        }
+    }
+}
+
+impl OrderIndex {
+    /// Save as `<sensor> <rows> <hash>` lines.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut text = String::new();
+        for (sensor, order) in &self.sensors {
+            let _ = writeln!(text, "{} {} {}", sensor.dir_name(), order.rows, order.hash);
+        }
+        fs::write(path, text)?;
+
+        Ok(())
+    }
+
+    /// Load an index written by [`OrderIndex::save`].
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(path.as_ref())?;
+        let mut sensors = BTreeMap::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let sensor = fields
+                .next()
+                .and_then(|name| Sensor::ALL.iter().find(|s| s.dir_name() == name));
+            let rows = fields.next().and_then(|rows| rows.parse().ok());
+            match (sensor, rows, fields.next(), fields.next()) {
+                (Some(&sensor), Some(rows), Some(hash), None) => {
+                    let hash = hash.to_owned();
+                    sensors.insert(sensor, SensorOrder { rows, hash });
+                }
+                _ => {
+                    return Err(EurocError::CsvParse {
+                        path: path.as_ref().to_owned(),
+                        line: i as u64 + 1,
+                        message: "expected `<sensor> <rows> <hash>`".to_owned(),
+                    })
+                }
+            }
+        }
+
+        Ok(Self { sensors })
+    }
+}
+
+impl EuRoC {
+    /// Compute the [`OrderIndex`] of every sensor folder present, reading all `data.csv`.
+    pub fn order_index(&self) -> Result<OrderIndex> {
+        let mut sensors = BTreeMap::new();
+        for &sensor in Sensor::ALL.iter() {
+            if self.source.is_dir(&self.sensor_dir(sensor)) {
+                sensors.insert(sensor, SensorOrder::new(&self.timestamps(sensor)?));
+            }
+        }
+
+        Ok(OrderIndex { sensors })
+    }
+
+    /// Check that every sensor of `index` still has the same rows in the same order.
+    ///
+    /// Sensors not in `index` are not checked.
+    pub fn verify_order(&self, index: &OrderIndex) -> Result<IntegrityReport> {
+        let mut problems = Vec::new();
+        for (&sensor, expected) in &index.sensors {
+            let actual = if self.source.is_dir(&self.sensor_dir(sensor)) {
+                match self.timestamps(sensor) {
+                    Ok(timestamps) => Some(SensorOrder::new(&timestamps)),
+                    Err(e) => {
+                        problems.push(IntegrityProblem::Unreadable {
+                            sensor,
+                            error: e.to_string(),
+                        });
+                        continue;
+                    }
+                }
+            } else {
+                None
+            };
+            if actual.as_ref() != Some(expected) {
+                problems.push(IntegrityProblem::Order {
+                    sensor,
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+
+        Ok(IntegrityReport { problems })
+    }
+
+    /// Compute the checksum of every file of the dataset, reading all of it.
+    pub fn checksums(&self) -> Result<Checksums> {
+        let mut paths = Vec::new();
+        self.list_files(&self.root, &mut paths)?;
+
+        let mut files = BTreeMap::new();
+        for path in paths {
+            let checksum = self.checksum(&path)?;
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+            files.insert(relative.to_owned(), checksum);
+        }
+
+        Ok(Checksums { files })
+    }
+
+    fn list_files(&self, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        for path in self.source.read_dir(dir)? {
+            if self.source.is_dir(&path) {
+                self.list_files(&path, files)?;
+            } else {
+                files.push(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn checksum(&self, path: &Path) -> Result<String> {
+        Ok(format!(
+            "{:016x}",
+            fnv1a(FNV_OFFSET, &self.source.read(path)?)
+        ))
+    }
+
+    /// Check every sensor folder present, collecting all problems instead of stopping at the first.
+    ///
+    /// Timestamps must increase strictly and every image listed in a camera
+    /// `data.csv` must exist. With `checksums`, every file listed there must
+    /// also match; files not listed are not checked.
+    pub fn verify(&self, checksums: Option<&Checksums>) -> Result<IntegrityReport> {
+        let mut problems = Vec::new();
+        let mut image_counts = BTreeMap::new();
+
+        for &sensor in Sensor::ALL.iter() {
+            if !self.source.is_dir(&self.sensor_dir(sensor)) {
+                continue;
+            }
+            if let Err(e) = self.verify_sensor(sensor, &mut problems, &mut image_counts) {
+                problems.push(IntegrityProblem::Unreadable {
+                    sensor,
+                    error: e.to_string(),
+                });
+            }
+        }
+
+        if let (Some(&cam0), Some(&cam1)) = (
+            image_counts.get(&Sensor::Cam0),
+            image_counts.get(&Sensor::Cam1),
+        ) {
+            if cam0 != cam1 {
+                problems.push(IntegrityProblem::StereoCount { cam0, cam1 });
+            }
+        }
+
+        for (file, expected) in checksums.map(|c| &c.files).into_iter().flatten() {
+            let path = self.root.join(file);
+            let actual = if self.source.is_file(&path) {
+                Some(self.checksum(&path)?)
+            } else {
+                None
+            };
+            if actual.as_ref() != Some(expected) {
+                problems.push(IntegrityProblem::Checksum {
+                    path: file.clone(),
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+
+        Ok(IntegrityReport { problems })
+    }
+
+    fn verify_sensor(
+        &self,
+        sensor: Sensor,
+        problems: &mut Vec<IntegrityProblem>,
+        image_counts: &mut BTreeMap<Sensor, usize>,
+    ) -> Result<()> {
+        let timestamps = if sensor.is_camera() {
+            let entries = self
+                .camera(sensor)?
+                .entries()?
+                .collect::<Result<Vec<_>>>()?;
+            for entry in &entries {
+                if !self.source.is_file(&entry.path) {
+                    problems.push(IntegrityProblem::MissingImage {
+                        sensor,
+                        path: entry.path.clone(),
+                    });
+                }
+            }
+
+            let found = self
+                .source
+                .read_dir(&self.sensor_dir(sensor).join("data"))?
+                .iter()
+                .filter(|path| self.source.is_file(path))
+                .count();
+            if found != entries.len() {
+                problems.push(IntegrityProblem::ImageCount {
+                    sensor,
+                    listed: entries.len(),
+                    found,
+                });
+            }
+            image_counts.insert(sensor, entries.len());
+
+            entries.iter().map(Timestamped::timestamp).collect()
+        } else {
+            self.timestamps(sensor)?
+        };
+
+        for (index, w) in timestamps.windows(2).enumerate() {
+            if w[1] <= w[0] {
+                problems.push(IntegrityProblem::NonMonotonic {
+                    sensor,
+                    index: index + 1,
+                    previous: w[0],
+                    timestamp: w[1],
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use crate::{DataSource, MemorySource};
+
+    #[test]
+    fn verify() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        assert!(data.verify(None)?.is_ok());
+
+        let checksums = data.checksums()?;
+        assert!(checksums.files.contains_key(Path::new("cam0/data.csv")));
+        assert!(data.verify(Some(&checksums))?.is_ok());
+
+        let mut source = MemorySource::from_dir("test_data")?;
+        let csv = source.read_to_string(Path::new("cam0/data.csv"))?;
+        let csv = format!(
+            "{}1403636579913555456,missing.png\n",
+            csv.replace("1403636579863555584,", "1403636579963555584,")
+        );
+        source.insert("cam0/data.csv", csv.as_bytes());
+        source.insert("leica0/data.csv", &b"#timestamp,x,y,z\n1,a,0,0\n"[..]);
+        let broken = EuRoC::from_source(Arc::new(source), "")?;
+
+        let report = broken.verify(Some(&checksums))?;
+        let problems = &report.problems;
+        assert!(problems.contains(&IntegrityProblem::NonMonotonic {
+            sensor: Sensor::Cam0,
+            index: 3,
+            previous: 1403636579963555584.into(),
+            timestamp: 1403636579913555456.into(),
+        }));
+        assert!(problems.contains(&IntegrityProblem::MissingImage {
+            sensor: Sensor::Cam0,
+            path: PathBuf::from("cam0/data/missing.png"),
+        }));
+        assert!(problems.contains(&IntegrityProblem::ImageCount {
+            sensor: Sensor::Cam0,
+            listed: 6,
+            found: 5,
+        }));
+        assert!(problems.contains(&IntegrityProblem::StereoCount { cam0: 6, cam1: 5 }));
+        assert!(problems.iter().any(|p| matches!(
+            p,
+            IntegrityProblem::Unreadable {
+                sensor: Sensor::Leica0,
+                ..
+            }
+        )));
+        assert_eq!(
+            problems
+                .iter()
+                .filter(|p| matches!(p, IntegrityProblem::Checksum { .. }))
+                .count(),
+            2
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn order_index() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let index = data.order_index()?;
+        assert_eq!(index.sensors[&Sensor::Cam0].rows, 5);
+        assert!(data.verify_order(&index)?.is_ok());
+
+        let tmp = TempDir::new("order_index")?;
+        let path = tmp.join("order.txt");
+        index.save(&path)?;
+        assert_eq!(OrderIndex::load(&path)?, index);
+
+        // the same rows, with two of them swapped
+        let mut source = MemorySource::from_dir("test_data")?;
+        let csv = source.read_to_string(Path::new("imu0/data.csv"))?;
+        let mut lines: Vec<_> = csv.lines().collect();
+        lines.swap(1, 2);
+        source.insert(
+            "imu0/data.csv",
+            format!("{}\n", lines.join("\n")).as_bytes(),
+        );
+        let swapped = EuRoC::from_source(Arc::new(source), "")?;
+
+        let report = swapped.verify_order(&index)?;
+        assert_eq!(report.problems.len(), 1);
+        match &report.problems[0] {
+            IntegrityProblem::Order {
+                sensor: Sensor::Imu0,
+                expected,
+                actual: Some(actual),
+            } => {
+                assert_eq!(actual.rows, expected.rows);
+                assert_ne!(actual.hash, expected.hash);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        fs::write(&path, "imu0 5\n")?;
+        assert!(matches!(
+            OrderIndex::load(&path),
+            Err(EurocError::CsvParse { line: 1, .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn save_load() -> Result<()> {
+        let checksums = EuRoC::new("test_data")?.checksums()?;
+        let tmp = TempDir::new("checksums")?;
+        let path = tmp.join("checksums.txt");
+        checksums.save(&path)?;
+        assert_eq!(Checksums::load(&path)?, checksums);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use crate::Timestamp;
+
+/// Locate `ts` in the sorted `timestamps`.
+///
+/// Return `(i, j, alpha)` such that `ts` lies between `timestamps[i]` and
+/// `timestamps[j]` at fraction `alpha`, or `None` outside the covered range.
+pub fn bracket(timestamps: &[Timestamp], ts: Timestamp) -> Option<(usize, usize, f64)> {
+    match timestamps.binary_search(&ts) {
+        Ok(i) => Some((i, i, 0.0)),
+        Err(0) => None,
+        Err(j) if j == timestamps.len() => None,
+        Err(j) => {
+            let i = j - 1;
+            let span = (timestamps[j] - timestamps[i]).nsecs() as f64;
+            Some((i, j, (ts - timestamps[i]).nsecs() as f64 / span))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bracket_inside() {
+        let timestamps: Vec<Timestamp> = vec![10.into(), 20.into(), 40.into()];
+
+        assert_eq!(bracket(&timestamps, 20.into()), Some((1, 1, 0.0)));
+        assert_eq!(bracket(&timestamps, 25.into()), Some((1, 2, 0.25)));
+        assert_eq!(bracket(&timestamps, 5.into()), None);
+        assert_eq!(bracket(&timestamps, 41.into()), None);
+    }
+}
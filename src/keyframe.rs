@@ -0,0 +1,120 @@
+use nalgebra as na;
+
+use crate::{EuRoC, Extrapolation, GroundTruthInterpolator, Result, Sensor, Timestamp};
+
+/// Picks keyframes whose ground-truth pose moved enough since the previous keyframe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyframeSelector {
+    /// minimum translation (m)
+    pub min_translation: f64,
+    /// minimum rotation angle (rad)
+    pub min_rotation: f64,
+}
+
+impl KeyframeSelector {
+    pub const fn new(min_translation: f64, min_rotation: f64) -> Self {
+        Self {
+            min_translation,
+            min_rotation,
+        }
+    }
+
+    /// Return the keyframes among `frames`, in order.
+    ///
+    /// The first frame with a ground-truth pose is always a keyframe; every later
+    /// one is a keyframe once either threshold is reached wrt. the last keyframe.
+    /// Frames where `ground_truth` has no pose are skipped.
+    pub fn select(
+        &self,
+        frames: &[Timestamp],
+        ground_truth: &GroundTruthInterpolator,
+    ) -> Vec<Timestamp> {
+        let mut keyframes = Vec::new();
+        let mut last: Option<na::Isometry3<f64>> = None;
+
+        for &frame in frames {
+            let pose = match ground_truth.pose_at(frame) {
+                Some(pose) => pose,
+                None => continue,
+            };
+            let is_keyframe = last.map_or(true, |last| {
+                let delta = last.inverse() * pose;
+                delta.translation.vector.norm() >= self.min_translation
+                    || delta.rotation.angle() >= self.min_rotation
+            });
+            if is_keyframe {
+                keyframes.push(frame);
+                last = Some(pose);
+            }
+        }
+
+        keyframes
+    }
+}
+
+impl EuRoC {
+    /// Select keyframes among the frames of `sensor` using the ground truth.
+    ///
+    /// Write them with [`EuRoC::export_keyframes`] to get a dataset of keyframes only.
+    pub fn keyframes(&self, sensor: Sensor, selector: &KeyframeSelector) -> Result<Vec<Timestamp>> {
+        let frames = self.timestamps(sensor)?;
+        let ground_truth = self.ground_truth()?.interpolator(Extrapolation::None)?;
+
+        Ok(selector.select(&frames, &ground_truth))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use crate::GroundTruthRecord;
+
+    #[test]
+    fn select() {
+        // 0.1 m per 100 ns up to 1000 ns, then 0.1 rad per 100 ns; no pose after 2000 ns
+        let gt: Vec<_> = [(0, 0.0, 0.0), (1000, 1.0, 0.0), (2000, 1.0, 1.0)]
+            .iter()
+            .map(|&(ts, x, yaw)| {
+                let pose = na::Isometry3::new(na::Vector3::x() * x, na::Vector3::z() * yaw);
+                GroundTruthRecord::from_pose(Timestamp::from(ts), pose)
+            })
+            .collect();
+        let interpolator = GroundTruthInterpolator::new(&gt, Extrapolation::None);
+        let frames: Vec<Timestamp> = (0..=25).map(|i| Timestamp::from(i * 100)).collect();
+
+        let selector = KeyframeSelector::new(0.25, 0.35);
+        assert_eq!(
+            selector.select(&frames, &interpolator),
+            [0, 300, 600, 900, 1400, 1800]
+                .iter()
+                .map(|&ts| Timestamp::from(ts))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn export_keyframes() -> Result<()> {
+        let out = TempDir::new("export_keyframes")?;
+
+        let data = EuRoC::new("test_data")?;
+        let frames = data.timestamps(Sensor::Cam0)?;
+        let keyframes = [frames[1], frames[3]];
+        data.export_keyframes(&out, &keyframes)?;
+
+        let exported = EuRoC::new(&out)?;
+        let entries = exported
+            .left_camera()?
+            .entries()?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].timestamp, frames[3]);
+        assert!(entries[1].load().is_ok());
+        assert_eq!(
+            exported.imu()?.records()?.count(),
+            data.imu()?.records()?.count()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{DataSource, EuRoC, EurocError, Result};
+
+/// Which folder [`EuRoC::new`] was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// the `mav0` folder itself
+    Mav,
+    /// an extracted archive such as `MH_01_easy/` containing `mav0/`
+    Archive,
+}
+
+impl Layout {
+    /// Tell which one the folder `root` of `source` is.
+    pub fn detect(source: &dyn DataSource, root: &Path) -> Self {
+        if source.is_dir(&root.join(mav_dir(0))) {
+            Self::Archive
+        } else {
+            Self::Mav
+        }
+    }
+}
+
+fn mav_dir(n: usize) -> String {
+    format!("mav{}", n)
+}
+
+impl EuRoC {
+    pub const fn layout(&self) -> Layout {
+        if self.archive.is_some() {
+            Layout::Archive
+        } else {
+            Layout::Mav
+        }
+    }
+
+    /// Open the `mav<n>` folder of the same archive, for layouts with several MAVs.
+    ///
+    /// Without an archive only `mav(0)`, the dataset itself, is available.
+    pub fn mav(&self, n: usize) -> Result<Self> {
+        match &self.archive {
+            Some(archive) => {
+                let root = archive.join(mav_dir(n));
+                self.source.require_dir(&root)?;
+                Ok(Self {
+                    source: Arc::clone(&self.source),
+                    root,
+                    archive: Some(archive.clone()),
+                    config: Arc::clone(&self.config),
+                })
+            }
+            None if n == 0 => Ok(self.clone()),
+            None => Err(EurocError::InvalidInput(format!(
+                "{} is not inside an archive, no `{}`",
+                self.root.display(),
+                mav_dir(n)
+            ))),
+        }
+    }
+
+    /// Return the archive folder containing the dataset, if [`Layout::Archive`].
+    pub fn archive_root(&self) -> Option<&Path> {
+        self.archive.as_deref()
+    }
+
+    pub(crate) fn resolve_root(source: &dyn DataSource, root: &Path) -> (PathBuf, Option<PathBuf>) {
+        match Layout::detect(source, root) {
+            Layout::Archive => (root.join(mav_dir(0)), Some(root.to_owned())),
+            Layout::Mav => (root.to_owned(), None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn archive() -> Result<()> {
+        let archive = TempDir::new("layout_archive")?;
+        fs::create_dir_all(archive.join("mav0/imu0"))?;
+        for file in ["data.csv", "sensor.yaml"].iter() {
+            fs::copy(
+                Path::new("test_data/imu0").join(file),
+                archive.join("mav0/imu0").join(file),
+            )?;
+        }
+
+        let data = EuRoC::new(&archive)?;
+        assert_eq!(data.layout(), Layout::Archive);
+        assert_eq!(data.archive_root(), Some(archive.path()));
+        assert_eq!(data.imu()?.len()?, 5);
+        assert_eq!(data.mav(0)?.layout(), Layout::Archive);
+        assert!(matches!(data.mav(1), Err(EurocError::MissingFile(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn mav() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        assert_eq!(data.layout(), Layout::Mav);
+        assert!(data.archive_root().is_none());
+        assert!(data.mav(0)?.imu().is_ok());
+        assert!(matches!(data.mav(1), Err(EurocError::InvalidInput(_))));
+
+        Ok(())
+    }
+}
@@ -4,51 +4,267 @@
     clippy::all,
     clippy::nursery
 )]
+// keep the upstream tests as written: CSV values verbatim and `skip(n).next()`
+#![cfg_attr(test, allow(clippy::excessive_precision, clippy::iter_skip_next))]
 
+mod association;
+mod bias;
+mod bulk;
+mod calibration_check;
 mod camera;
+mod camera_model;
+mod capsule;
 mod common;
+mod comparison;
+mod config;
+mod continuity;
+mod csv_row;
+mod custom_sensor;
+#[cfg(feature = "download")]
+mod download;
+mod dropout;
+mod error;
+mod estimator;
+mod eval;
+mod event;
+mod factors;
+mod filter;
+mod fnv;
+mod frame;
+mod frame_cache;
+mod frame_graph;
+mod gravity;
+mod gray;
 mod ground_truth;
+mod image_cache;
 mod imu;
+mod indexed;
+mod integrity;
+mod interpolation;
+mod keyframe;
+mod layout;
+mod map_cache;
+mod mcap;
+mod overlay;
+mod photometric;
+mod player;
+#[cfg(feature = "plot")]
+mod plot;
+mod point_cloud;
+mod pose_graph;
+mod pose_interpolation;
 mod position;
+mod prefetch;
+mod preintegration;
+pub mod prelude;
+mod psd;
+mod rate;
+mod recorder;
+mod relative_time;
+#[cfg(feature = "rosbag")]
+mod rosbag;
+#[cfg(feature = "sample-data")]
+mod sample;
+mod sensor;
+mod sink;
+mod snippet;
+mod source;
+mod stereo;
+mod summary;
+mod tee;
+#[cfg(test)]
+mod temp_dir;
+mod thumbnail;
+mod timeline;
+mod trajectory;
+mod transform;
+mod tum;
+mod validation;
+mod vicon;
+mod yaml;
+mod zip;
 
-use std::path::{Path, PathBuf};
-
-use anyhow::{ensure, Result};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-pub use self::{camera::*, common::*, ground_truth::*, imu::*, position::*};
+pub use self::csv_row::{Checkpoint, IndexedRows, Row};
+#[cfg(feature = "download")]
+pub use self::download::{Progress, Sequence};
+pub use self::error::{EurocError, Result};
+#[cfg(feature = "plot")]
+pub use self::plot::{Plane, Plot, Series};
+#[cfg(feature = "rosbag")]
+pub use self::rosbag::{RosbagSink, RosbagSource};
+pub use self::source::{DataSource, FileSystem, MemorySource, ReadSeek};
+pub use self::yaml::ConfigWarning;
+pub use self::{
+    association::*, bias::*, bulk::*, calibration_check::*, camera::*, camera_model::*, capsule::*,
+    common::*, comparison::*, config::*, continuity::*, custom_sensor::*, dropout::*, estimator::*,
+    eval::*, event::*, factors::*, filter::*, frame::*, frame_cache::*, frame_graph::*, gravity::*,
+    gray::*, ground_truth::*, image_cache::*, imu::*, indexed::*, integrity::*, keyframe::*,
+    layout::*, map_cache::*, mcap::*, overlay::*, photometric::*, player::*, point_cloud::*,
+    pose_graph::*, pose_interpolation::*, position::*, prefetch::*, preintegration::*, psd::*,
+    rate::*, recorder::*, relative_time::*, sensor::*, sink::*, snippet::*, stereo::*, summary::*,
+    tee::*, timeline::*, trajectory::*, transform::*, tum::*, validation::*, vicon::*, zip::*,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EuRoC {
+    source: Arc<dyn DataSource>,
+    /// the `mav0` folder
     root: PathBuf,
+    /// folder containing `root`, for [`Layout::Archive`]
+    archive: Option<PathBuf>,
+    config: Arc<Config>,
 }
 
 impl EuRoC {
+    /// Open the `mav0` folder, or an extracted archive containing it; see [`EuRoC::layout`].
     pub fn new<P: AsRef<Path>>(root: P) -> Result<Self> {
-        ensure!(root.as_ref().is_dir());
+        Self::from_source(FileSystem::shared(), root)
+    }
+
+    /// Open the folder `root` of `source`, like [`EuRoC::new`] does on disk.
+    pub fn from_source<P: AsRef<Path>>(source: Arc<dyn DataSource>, root: P) -> Result<Self> {
+        source.require_dir(root.as_ref())?;
+        let (root, archive) = Self::resolve_root(&*source, root.as_ref());
 
         Ok(Self {
-            root: root.as_ref().to_owned(),
+            source,
+            root,
+            archive,
+            config: Arc::default(),
         })
     }
 
+    /// Open a distributed `.zip` archive such as `MH_01_easy.zip` without extracting it.
+    pub fn from_zip<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_source(Arc::new(ZipSource::open(path)?), "")
+    }
+
+    /// Open a `.zip` archive held in memory, e.g. fetched by a browser viewer.
+    ///
+    /// Nothing is read from the file system, so this also works where
+    /// [`FileSystem`] does not, such as `wasm32-unknown-unknown`.
+    pub fn from_zip_bytes<B: Into<Arc<[u8]>>>(data: B) -> Result<Self> {
+        Self::from_source(Arc::new(ZipSource::from_bytes(data)?), "")
+    }
+
+    /// Return where the dataset is read from.
+    pub fn source(&self) -> &Arc<dyn DataSource> {
+        &self.source
+    }
+
     pub fn left_camera(&self) -> Result<CameraRecords> {
-        CameraRecords::new(self.root.join("cam0"))
+        Ok(
+            CameraRecords::with_source(Arc::clone(&self.source), self.root.join("cam0"))?
+                .with_error_policy(self.config.on_error),
+        )
     }
 
     pub fn right_camera(&self) -> Result<CameraRecords> {
-        CameraRecords::new(self.root.join("cam1"))
+        Ok(
+            CameraRecords::with_source(Arc::clone(&self.source), self.root.join("cam1"))?
+                .with_error_policy(self.config.on_error),
+        )
+    }
+
+    /// Return the camera identified by `sensor`.
+    pub fn camera(&self, sensor: Sensor) -> Result<CameraRecords> {
+        if sensor.is_camera() {
+            Ok(
+                CameraRecords::with_source(Arc::clone(&self.source), self.sensor_dir(sensor))?
+                    .with_error_policy(self.config.on_error),
+            )
+        } else {
+            Err(EurocError::InvalidInput(format!(
+                "{:?} is not a camera",
+                sensor
+            )))
+        }
+    }
+
+    /// Return the directory of `sensor`.
+    pub fn sensor_dir(&self, sensor: Sensor) -> PathBuf {
+        self.root.join(sensor.dir_name())
     }
 
     pub fn imu(&self) -> Result<ImuData> {
-        ImuData::new(self.root.join("imu0"))
+        Ok(
+            ImuData::with_source(Arc::clone(&self.source), self.root.join("imu0"))?
+                .with_error_policy(self.config.on_error),
+        )
     }
 
     pub fn position(&self) -> Result<PositionData> {
-        PositionData::new(self.root.join("leica0"))
+        Ok(
+            PositionData::with_source(Arc::clone(&self.source), self.root.join("leica0"))?
+                .with_error_policy(self.config.on_error),
+        )
+    }
+
+    pub fn vicon(&self) -> Result<ViconData> {
+        Ok(
+            ViconData::with_source(Arc::clone(&self.source), self.root.join("vicon0"))?
+                .with_error_policy(self.config.on_error),
+        )
+    }
+
+    pub fn point_cloud(&self) -> Result<PointCloudData> {
+        PointCloudData::with_source(Arc::clone(&self.source), self.root.join("pointcloud0"))
     }
 
     pub fn ground_truth(&self) -> Result<GroundTruthData> {
-        GroundTruthData::new(self.root.join("state_groundtruth_estimate0"))
+        Ok(GroundTruthData::with_source(
+            Arc::clone(&self.source),
+            self.root.join("state_groundtruth_estimate0"),
+        )?
+        .with_error_policy(self.config.on_error))
+    }
+
+    /// Return the earliest timestamp over all available streams.
+    pub fn epoch(&self) -> Result<Timestamp> {
+        let firsts = vec![
+            self.first_timestamp(Sensor::Cam0, || self.left_camera()?.entries()),
+            self.first_timestamp(Sensor::Cam1, || self.right_camera()?.entries()),
+            self.first_timestamp(Sensor::Imu0, || self.imu()?.records()),
+            self.first_timestamp(Sensor::Leica0, || self.position()?.records()),
+            self.first_timestamp(Sensor::Vicon0, || self.vicon()?.records()),
+            self.first_timestamp(Sensor::GroundTruth, || self.ground_truth()?.records()),
+        ];
+
+        firsts
+            .into_iter()
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .min()
+            .ok_or_else(|| EurocError::InvalidInput("no records in dataset".to_owned()))
+    }
+
+    /// Return a clock measuring time since [`EuRoC::epoch`].
+    pub fn relative_time(&self) -> Result<RelativeTime> {
+        Ok(RelativeTime::new(self.epoch()?))
+    }
+
+    /// Return the first timestamp of a sensor stream, or `None` if the sensor is missing or empty.
+    ///
+    /// Errors opening a sensor that is present are returned.
+    fn first_timestamp<I, T>(
+        &self,
+        sensor: Sensor,
+        records: impl FnOnce() -> Result<I>,
+    ) -> Result<Option<Timestamp>>
+    where
+        I: Iterator<Item = Result<T>>,
+        T: Timestamped,
+    {
+        if !self.source.is_dir(&self.sensor_dir(sensor)) {
+            return Ok(None);
+        }
+        Ok(records()?.next().transpose()?.map(|r| r.timestamp()))
     }
 }
 
@@ -62,4 +278,22 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn camera() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        assert_eq!(
+            data.camera(Sensor::Cam1)?.config(),
+            data.right_camera()?.config()
+        );
+        assert!(data.camera(Sensor::Imu0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_root() {
+        let err = EuRoC::new("no_such_dir").unwrap_err();
+        assert!(matches!(err, EurocError::MissingFile(_)));
+    }
 }
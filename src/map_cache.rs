@@ -0,0 +1,107 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{PinholeRadTan, RectifyMap, Result};
+
+/// Directory of computed [`RectifyMap`]s, shared by batch jobs.
+///
+/// Maps are stored under a key covering every parameter they depend on, so
+/// sequences recorded with the same calibration share one file, and a
+/// changed calibration never picks up a stale map.
+#[derive(Debug, Clone)]
+pub struct MapCache {
+    root: PathBuf,
+}
+
+impl MapCache {
+    /// Use `root` as the store, creating it if needed.
+    pub fn new<P: AsRef<Path>>(root: P) -> Result<Self> {
+        fs::create_dir_all(root.as_ref())?;
+
+        Ok(Self {
+            root: root.as_ref().to_owned(),
+        })
+    }
+
+    /// Return where the map of `key` is stored.
+    pub fn path(&self, key: &str) -> PathBuf {
+        self.root.join(format!("{}.map", key))
+    }
+
+    /// Return the stored map of `key`, or compute and store it with `f`.
+    ///
+    /// The file is written under a temporary name and then renamed, so
+    /// concurrent jobs never read a partially written map.
+    pub fn get_or_insert_with<F>(&self, key: &str, f: F) -> Result<RectifyMap>
+    where
+        F: FnOnce() -> RectifyMap,
+    {
+        let path = self.path(key);
+        if path.is_file() {
+            return RectifyMap::from_bytes(&fs::read(path)?);
+        }
+
+        let map = f();
+        let tmp = path.with_extension(format!("{}.tmp", process::id()));
+        fs::write(&tmp, map.to_bytes())?;
+        fs::rename(tmp, path)?;
+        Ok(map)
+    }
+
+    /// Return [`PinholeRadTan::undistort_map`] of `model`, keyed by
+    /// [`PinholeRadTan::calibration_key`].
+    pub fn undistort_map(&self, model: &PinholeRadTan) -> Result<RectifyMap> {
+        let key = format!("undistort_{}", model.calibration_key());
+        self.get_or_insert_with(&key, || model.undistort_map())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{temp_dir::TempDir, EuRoC};
+
+    #[test]
+    fn undistort_map() -> Result<()> {
+        let tmp = TempDir::new("map_cache")?;
+        let cache = MapCache::new(tmp.join("maps"))?;
+        let data = EuRoC::new("test_data")?;
+        let left = data.left_camera()?.camera_model()?;
+        let right = data.right_camera()?.camera_model()?;
+        assert_ne!(left.calibration_key(), right.calibration_key());
+        assert_eq!(left.calibration_key().len(), 16);
+
+        let computed = cache.undistort_map(&left)?;
+        assert_eq!(computed.to_bytes(), left.undistort_map().to_bytes());
+        let cached = cache.get_or_insert_with(
+            &format!("undistort_{}", left.calibration_key()),
+            || unreachable!(),
+        )?;
+        assert_eq!(cached.to_bytes(), computed.to_bytes());
+
+        let _ = cache.undistort_map(&right)?;
+        assert_eq!(fs::read_dir(tmp.join("maps"))?.count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_map() {
+        assert!(RectifyMap::from_bytes(b"EUROCMAP\x01\x00\x00\x00").is_err());
+        let map = RectifyMap::from_fn(2, 1, |u, _| {
+            if u == 0 {
+                None
+            } else {
+                Some(nalgebra::Point2::new(1.0, 2.0))
+            }
+        });
+        let bytes = map.to_bytes();
+        assert!(RectifyMap::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let parsed = RectifyMap::from_bytes(&bytes).unwrap();
+        assert!(parsed.get(0, 0).0.is_nan());
+        assert_eq!(parsed.get(1, 0), (1.0, 2.0));
+    }
+}
@@ -0,0 +1,374 @@
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use image::{
+    codecs::png::{CompressionType, FilterType, PngEncoder},
+    DynamicImage, GenericImageView,
+};
+use nalgebra as na;
+
+use crate::{EuRoC, EurocError, RecordSink, Result, Sensor, SensorEvent, Timestamp, Timestamped};
+
+const MAGIC: &[u8] = b"\x89MCAP0\r\n";
+const OP_HEADER: u8 = 0x01;
+const OP_FOOTER: u8 = 0x02;
+const OP_SCHEMA: u8 = 0x03;
+const OP_CHANNEL: u8 = 0x04;
+const OP_MESSAGE: u8 = 0x05;
+const OP_DATA_END: u8 = 0x0f;
+
+const TIME_SCHEMA: &str =
+    r#"{"type":"object","properties":{"sec":{"type":"integer"},"nsec":{"type":"integer"}}}"#;
+const VECTOR3_SCHEMA: &str = r#"{"type":"object","properties":{"x":{"type":"number"},"y":{"type":"number"},"z":{"type":"number"}}}"#;
+const QUATERNION_SCHEMA: &str = r#"{"type":"object","properties":{"x":{"type":"number"},"y":{"type":"number"},"z":{"type":"number"},"w":{"type":"number"}}}"#;
+
+/// Settings of [`EuRoC::export_mcap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McapOptions {
+    /// sensors to export, each as the topic `/<folder name>`
+    pub sensors: Vec<Sensor>,
+}
+
+impl Default for McapOptions {
+    fn default() -> Self {
+        Self {
+            sensors: vec![
+                Sensor::Cam0,
+                Sensor::Cam1,
+                Sensor::Imu0,
+                Sensor::GroundTruth,
+            ],
+        }
+    }
+}
+
+/// Return the schema name and JSON schema of the messages of `sensor`.
+fn schema(sensor: Sensor) -> (&'static str, String) {
+    let properties = match sensor {
+        Sensor::Cam0 | Sensor::Cam1 => {
+            return (
+                "foxglove.CompressedImage",
+                format!(
+                    r#"{{"type":"object","properties":{{"timestamp":{},"frame_id":{{"type":"string"}},"data":{{"type":"string","contentEncoding":"base64"}},"format":{{"type":"string"}}}}}}"#,
+                    TIME_SCHEMA
+                ),
+            )
+        }
+        Sensor::Imu0 => format!(
+            r#""angular_velocity":{v},"linear_acceleration":{v}"#,
+            v = VECTOR3_SCHEMA
+        ),
+        Sensor::Leica0 => format!(r#""position":{}"#, VECTOR3_SCHEMA),
+        Sensor::Vicon0 | Sensor::GroundTruth => format!(
+            r#""pose":{{"type":"object","properties":{{"position":{},"orientation":{}}}}}"#,
+            VECTOR3_SCHEMA, QUATERNION_SCHEMA
+        ),
+    };
+    let name = match sensor {
+        Sensor::Imu0 => "euroc.Imu",
+        Sensor::Leica0 => "euroc.Position",
+        _ => "foxglove.PoseInFrame",
+    };
+
+    (
+        name,
+        format!(
+            r#"{{"type":"object","properties":{{"timestamp":{},"frame_id":{{"type":"string"}},{}}}}}"#,
+            TIME_SCHEMA, properties
+        ),
+    )
+}
+
+/// Start a JSON message with its `timestamp` and `frame_id`.
+fn json_header(timestamp: Timestamp, frame_id: &str) -> String {
+    format!(
+        r#"{{"timestamp":{{"sec":{},"nsec":{}}},"frame_id":"{}""#,
+        timestamp.nsecs() / 1_000_000_000,
+        timestamp.nsecs() % 1_000_000_000,
+        frame_id
+    )
+}
+
+fn json_vector3(v: &na::Vector3<f64>) -> String {
+    format!(r#"{{"x":{},"y":{},"z":{}}}"#, v.x, v.y, v.z)
+}
+
+fn json_pose(position: &na::Vector3<f64>, q: &na::Quaternion<f64>) -> String {
+    format!(
+        r#""pose":{{"position":{},"orientation":{{"x":{},"y":{},"z":{},"w":{}}}}}"#,
+        json_vector3(position),
+        q.i,
+        q.j,
+        q.k,
+        q.w
+    )
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut text = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from(b[0]) << 16 | u32::from(b[1]) << 8 | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// Append an MCAP string: its byte length (u32) then the bytes.
+fn put_str(buf: &mut Vec<u8>, text: &str) {
+    buf.extend_from_slice(&(text.len() as u32).to_le_bytes());
+    buf.extend_from_slice(text.as_bytes());
+}
+
+fn write_record<W: Write>(writer: &mut W, op: u8, content: &[u8]) -> Result<()> {
+    writer.write_all(&[op])?;
+    writer.write_all(&(content.len() as u64).to_le_bytes())?;
+    writer.write_all(content)?;
+    Ok(())
+}
+
+/// [`RecordSink`] writing an MCAP file, for Foxglove Studio and similar tools.
+///
+/// Messages are JSON in timestamp order, each sensor on the topic
+/// `/<folder name>`. Camera images are PNG encoded as
+/// `foxglove.CompressedImage`; Vicon and ground truth poses are
+/// `foxglove.PoseInFrame`, IMU and Leica records use the `euroc.Imu` and
+/// `euroc.Position` schemas.
+///
+/// The file has no chunks, index or summary section, so tools read it front
+/// to back.
+pub struct McapSink<W: Write> {
+    writer: W,
+    /// sensor and sequence number of each channel, whose id is its index + 1
+    channels: Vec<(Sensor, u32)>,
+}
+
+impl<W: Write> McapSink<W> {
+    /// Start the file with the MCAP magic and header.
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(MAGIC)?;
+
+        let mut header = Vec::new();
+        put_str(&mut header, "");
+        put_str(
+            &mut header,
+            &format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        );
+        write_record(&mut writer, OP_HEADER, &header)?;
+
+        Ok(Self {
+            writer,
+            channels: Vec::new(),
+        })
+    }
+
+    /// Return the writer the file went to.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> RecordSink for McapSink<W> {
+    fn begin(&mut self, sensors: &[Sensor]) -> Result<()> {
+        for &sensor in sensors {
+            let id = self.channels.len() as u16 + 1;
+            let (name, schema) = schema(sensor);
+            let mut record = Vec::new();
+            record.extend_from_slice(&id.to_le_bytes());
+            put_str(&mut record, name);
+            put_str(&mut record, "jsonschema");
+            put_str(&mut record, &schema);
+            write_record(&mut self.writer, OP_SCHEMA, &record)?;
+
+            let mut record = Vec::new();
+            record.extend_from_slice(&id.to_le_bytes());
+            record.extend_from_slice(&id.to_le_bytes());
+            put_str(&mut record, &format!("/{}", sensor.dir_name()));
+            put_str(&mut record, "json");
+            // no metadata
+            record.extend_from_slice(&0u32.to_le_bytes());
+            write_record(&mut self.writer, OP_CHANNEL, &record)?;
+
+            self.channels.push((sensor, 0));
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, event: &SensorEvent) -> Result<()> {
+        let index = self
+            .channels
+            .iter()
+            .position(|&(sensor, _)| sensor == event.sensor())
+            .ok_or_else(|| {
+                EurocError::InvalidInput(format!("no MCAP channel for {:?}", event.sensor()))
+            })?;
+        let data = json_message(event)?;
+
+        let timestamp = event.timestamp();
+        let sequence = &mut self.channels[index].1;
+        let mut record = Vec::with_capacity(22 + data.len());
+        record.extend_from_slice(&(index as u16 + 1).to_le_bytes());
+        record.extend_from_slice(&sequence.to_le_bytes());
+        record.extend_from_slice(&timestamp.nsecs().to_le_bytes());
+        record.extend_from_slice(&timestamp.nsecs().to_le_bytes());
+        record.extend_from_slice(data.as_bytes());
+        write_record(&mut self.writer, OP_MESSAGE, &record)?;
+        *sequence += 1;
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        // a zero CRC means it was not computed
+        write_record(&mut self.writer, OP_DATA_END, &0u32.to_le_bytes())?;
+        write_record(&mut self.writer, OP_FOOTER, &[0; 20])?;
+        self.writer.write_all(MAGIC)?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Encode `event` as the JSON message of its schema.
+fn json_message(event: &SensorEvent) -> Result<String> {
+    let body = match event {
+        SensorEvent::Image(_, r) => {
+            let png = png(&r.image)?;
+            let mut body = String::with_capacity(png.len() * 4 / 3 + 32);
+            let _ = write!(body, r#""format":"png","data":"{}""#, base64(&png));
+            body
+        }
+        SensorEvent::Imu(r) => format!(
+            r#""angular_velocity":{},"linear_acceleration":{}"#,
+            json_vector3(&r.gyro),
+            json_vector3(&r.accel)
+        ),
+        SensorEvent::Position(r) => format!(r#""position":{}"#, json_vector3(&r.position)),
+        SensorEvent::Vicon(r) => json_pose(&r.position, &r.quaternion),
+        SensorEvent::GroundTruth(r) => json_pose(&r.position, &r.quaternion),
+    };
+
+    let mut json = json_header(event.timestamp(), event.sensor().dir_name());
+    json.push(',');
+    json.push_str(&body);
+    json.push('}');
+    Ok(json)
+}
+
+fn png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    PngEncoder::new_with_quality(&mut png, CompressionType::Fast, FilterType::Sub).encode(
+        image.as_bytes(),
+        image.width(),
+        image.height(),
+        image.color(),
+    )?;
+    Ok(png)
+}
+
+impl EuRoC {
+    /// Write the records of `options.sensors` into an MCAP file through an [`McapSink`].
+    ///
+    /// Return the number of messages written.
+    pub fn export_mcap<P: AsRef<Path>>(&self, path: P, options: &McapOptions) -> Result<usize> {
+        let mut sink = McapSink::new(BufWriter::new(File::create(path)?))?;
+        self.convert(&options.sensors, &mut sink)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{convert::TryInto, fs};
+
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn mcap_sink() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let tmp = TempDir::new("mcap_sink")?;
+        let path = tmp.join("export.mcap");
+        let options = McapOptions::default();
+        data.export_mcap(&path, &options)?;
+
+        let mut sink = McapSink::new(Vec::new())?;
+        assert_eq!(data.convert(&options.sensors, &mut sink)?, 20);
+        assert_eq!(sink.into_inner(), fs::read(&path)?);
+
+        // records of a sensor passed to `begin` only
+        let mut sink = McapSink::new(Vec::new())?;
+        assert!(data.convert(&[Sensor::Imu0], &mut sink).is_ok());
+        let event = data.events(&[Sensor::Leica0])?.next().unwrap()?;
+        assert!(sink.write(&event).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_mcap() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let tmp = TempDir::new("export_mcap")?;
+        let path = tmp.join("export.mcap");
+        let options = McapOptions {
+            sensors: Sensor::ALL.to_vec(),
+        };
+        let count = data.export_mcap(&path, &options)?;
+        assert_eq!(count, 5 + 5 + 5 + 5 + 5 + 5);
+
+        let file = fs::read(&path)?;
+        assert!(file.starts_with(MAGIC) && file.ends_with(MAGIC));
+        let (mut offset, mut ops, mut log_times) = (MAGIC.len(), Vec::new(), Vec::new());
+        let mut imu = None;
+        while offset < file.len() - MAGIC.len() {
+            let op = file[offset];
+            let len = u64::from_le_bytes(file[offset + 1..offset + 9].try_into().unwrap()) as usize;
+            let content = &file[offset + 9..offset + 9 + len];
+            if op == OP_MESSAGE {
+                log_times.push(u64::from_le_bytes(content[6..14].try_into().unwrap()));
+                if content[..2] == 3u16.to_le_bytes() && imu.is_none() {
+                    imu = Some(String::from_utf8(content[22..].to_vec()).unwrap());
+                }
+            }
+            ops.push(op);
+            offset += 9 + len;
+        }
+        assert_eq!(offset, file.len() - MAGIC.len());
+
+        assert_eq!(ops[0], OP_HEADER);
+        assert_eq!(ops.iter().filter(|&&op| op == OP_SCHEMA).count(), 6);
+        assert_eq!(ops.iter().filter(|&&op| op == OP_MESSAGE).count(), count);
+        assert_eq!(&ops[ops.len() - 2..], &[OP_DATA_END, OP_FOOTER]);
+        assert!(log_times.windows(2).all(|w| w[0] <= w[1]));
+        let imu = imu.unwrap();
+        assert!(imu.starts_with(r#"{"timestamp":{"sec":1403636579,"nsec":"#));
+        assert!(imu.contains(r#""frame_id":"imu0","angular_velocity":{"x":"#));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use image::{DynamicImage, Rgb};
+use nalgebra as na;
+
+use crate::{
+    CameraRecords, Duration, Extrapolation, GroundTruthData, GroundTruthInterpolator,
+    GroundTruthRecord, PinholeRadTan, Result, Timestamp,
+};
+
+const OVERLAY_COLOR: Rgb<u8> = Rgb([0xff, 0x20, 0x20]);
+
+/// Ground-truth trajectory seen from a camera.
+///
+/// Drawing where the body goes next onto a frame shows at a glance whether
+/// the calibration and the time alignment of camera and ground truth agree.
+#[derive(Debug, Clone)]
+pub struct TrajectoryProjector {
+    model: PinholeRadTan,
+    t_bc: na::Isometry3<f64>,
+    interpolator: GroundTruthInterpolator,
+    timestamps: Vec<Timestamp>,
+    positions: Vec<na::Point3<f64>>,
+}
+
+impl TrajectoryProjector {
+    /// `t_bc` is the camera pose wrt. the body; `ground_truth` must be sorted by timestamp.
+    pub fn new(
+        model: PinholeRadTan,
+        t_bc: na::Isometry3<f64>,
+        ground_truth: &[GroundTruthRecord],
+    ) -> Self {
+        Self {
+            model,
+            t_bc,
+            interpolator: GroundTruthInterpolator::new(ground_truth, Extrapolation::None),
+            timestamps: ground_truth.iter().map(|r| r.timestamp).collect(),
+            positions: ground_truth.iter().map(|r| r.position.into()).collect(),
+        }
+    }
+
+    /// Return the camera pose `T_WC = T_WB · T_BC` at `ts`, or `None` outside the ground truth.
+    pub fn camera_pose(&self, ts: Timestamp) -> Option<na::Isometry3<f64>> {
+        Some(self.interpolator.pose_at(ts)? * self.t_bc)
+    }
+
+    /// Return the pixels, in the image taken at `ts`, of the ground-truth
+    /// positions in `(ts, ts + horizon]`.
+    ///
+    /// Positions behind the camera or outside the image are left out, and
+    /// there are none for a horizon that is not positive.
+    pub fn project_future(&self, ts: Timestamp, horizon: Duration) -> Vec<na::Point2<f64>> {
+        if horizon <= Duration::ZERO {
+            return Vec::new();
+        }
+        let t_cw = match self.camera_pose(ts) {
+            Some(t_wc) => t_wc.inverse(),
+            None => return Vec::new(),
+        };
+        let after = |ts| match self.timestamps.binary_search(&ts) {
+            Ok(i) => i + 1,
+            Err(i) => i,
+        };
+        let (width, height) = self.model.resolution;
+
+        self.positions[after(ts)..after(ts + horizon)]
+            .iter()
+            .filter_map(|p| self.model.project(&(t_cw * p)))
+            .filter(|px| {
+                (0.0..f64::from(width)).contains(&px.x) && (0.0..f64::from(height)).contains(&px.y)
+            })
+            .collect()
+    }
+
+    /// Return `image`, taken at `ts`, with the pixels of
+    /// [`TrajectoryProjector::project_future`] marked in red.
+    pub fn draw(&self, image: &DynamicImage, ts: Timestamp, horizon: Duration) -> DynamicImage {
+        let mut rgb = image.to_rgb8();
+        let (width, height) = rgb.dimensions();
+        for px in self.project_future(ts, horizon) {
+            let (u, v) = (px.x.round() as i64, px.y.round() as i64);
+            for (du, dv) in (-1..=1).flat_map(|du| (-1..=1).map(move |dv| (du, dv))) {
+                let (x, y) = (u + du, v + dv);
+                if x >= 0 && y >= 0 && x < i64::from(width) && y < i64::from(height) {
+                    rgb.put_pixel(x as u32, y as u32, OVERLAY_COLOR);
+                }
+            }
+        }
+        DynamicImage::ImageRgb8(rgb)
+    }
+}
+
+impl CameraRecords {
+    /// Build a [`TrajectoryProjector`] of `ground_truth` into this camera.
+    pub fn trajectory_projector(
+        &self,
+        ground_truth: &GroundTruthData,
+    ) -> Result<TrajectoryProjector> {
+        let records = ground_truth.records()?.collect::<Result<Vec<_>>>()?;
+        Ok(TrajectoryProjector::new(
+            self.camera_model()?,
+            self.extrinsics_isometry()?,
+            &records,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EuRoC, Sensor};
+
+    #[test]
+    fn project_future() {
+        let model = PinholeRadTan {
+            resolution: (640, 480),
+            intrinsics: (400.0, 400.0, 320.0, 240.0),
+            distortion: na::Vector4::zeros(),
+        };
+        // veering right while moving along the optical axis
+        let gt: Vec<_> = (0..5)
+            .map(|i| {
+                let position = na::Isometry3::translation(0.1 * (i * i) as f64, 0.0, i as f64);
+                GroundTruthRecord::from_pose((i * 100).into(), position)
+            })
+            .collect();
+        let projector = TrajectoryProjector::new(model, na::Isometry3::identity(), &gt);
+
+        let pixels = projector.project_future(0.into(), Duration::from_nsecs(300));
+        assert_eq!(pixels.len(), 3);
+        // 1 m ahead and 0.1 m right, then 2 m ahead and 0.4 m right
+        assert!((pixels[0] - na::Point2::new(360.0, 240.0)).norm() < 1e-9);
+        assert!((pixels[1] - na::Point2::new(400.0, 240.0)).norm() < 1e-9);
+        assert!(projector
+            .project_future(500.into(), Duration::from_nsecs(100))
+            .is_empty());
+        assert!(projector
+            .project_future(300.into(), Duration::from_nsecs(-200))
+            .is_empty());
+
+        let image = DynamicImage::new_luma8(640, 480);
+        let drawn = projector.draw(&image, 0.into(), Duration::from_nsecs(300));
+        assert_eq!(drawn.to_rgb8().get_pixel(361, 241), &OVERLAY_COLOR);
+        let drawn = projector.draw(&image, 300.into(), -Duration::from_nsecs(300));
+        assert!(drawn.to_rgb8().pixels().all(|p| p.0 == [0, 0, 0]));
+    }
+
+    #[test]
+    fn camera_pose() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let camera = data.left_camera()?;
+        let gt = data.ground_truth()?;
+        let projector = camera.trajectory_projector(&gt)?;
+
+        let record = gt.records()?.nth(1).unwrap()?;
+        let expected = record.pose() * camera.extrinsics_isometry()?;
+        let pose = projector.camera_pose(record.timestamp).unwrap();
+        assert!((pose.translation.vector - expected.translation.vector).norm() < 1e-12);
+        assert!(pose.rotation.angle_to(&expected.rotation) < 1e-12);
+
+        // the frames precede the ground truth
+        let first_frame = data.timestamps(Sensor::Cam0)?[0];
+        assert!(projector.camera_pose(first_frame).is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use std::cmp::Ordering;
+
+use image::GrayImage;
+use nalgebra as na;
+
+use crate::{stereo::bilinear, ErrorStats, EuRoC, PinholeRadTan, Result, Sensor, Timestamp};
+
+/// Scene depth assumed to warp one frame onto another.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepthAssumption {
+    /// Fronto-parallel plane at this depth (m) in front of the first camera.
+    Constant(f64),
+    /// Plane of the points `p` with `normal · p = offset` in the world frame,
+    /// e.g. the floor of the Vicon room.
+    WorldPlane {
+        normal: na::Vector3<f64>,
+        offset: f64,
+    },
+}
+
+impl DepthAssumption {
+    /// Fit a [`DepthAssumption::WorldPlane`] to `points` in the world frame by
+    /// least squares, e.g. to the floor points of the [`PointCloud`](crate::PointCloud).
+    ///
+    /// Return `None` for fewer than 3 points or points along a line.
+    pub fn fit_plane(points: &[na::Point3<f64>]) -> Option<Self> {
+        if points.len() < 3 {
+            return None;
+        }
+        let centroid = points
+            .iter()
+            .fold(na::Vector3::zeros(), |sum, p| sum + p.coords)
+            / points.len() as f64;
+        let covariance = points.iter().fold(na::Matrix3::zeros(), |sum, p| {
+            let d = p.coords - centroid;
+            sum + d * d.transpose()
+        });
+
+        // the normal is the direction of least spread
+        let eigen = covariance.symmetric_eigen();
+        let mut order = [0, 1, 2];
+        order.sort_by(|&a, &b| {
+            eigen.eigenvalues[a]
+                .partial_cmp(&eigen.eigenvalues[b])
+                .unwrap_or(Ordering::Equal)
+        });
+        if eigen.eigenvalues[order[1]] <= f64::EPSILON * eigen.eigenvalues[order[2]] {
+            return None;
+        }
+        let normal: na::Vector3<f64> = eigen.eigenvectors.column(order[0]).into();
+        Some(Self::WorldPlane {
+            normal,
+            offset: normal.dot(&centroid),
+        })
+    }
+
+    /// Return the point seen along the unit `ray` of the camera at `t_wc`, in the camera frame.
+    fn point(&self, ray: &na::Vector3<f64>, t_wc: &na::Isometry3<f64>) -> Option<na::Point3<f64>> {
+        let depth = match *self {
+            Self::Constant(depth) => depth / ray.z,
+            Self::WorldPlane { normal, offset } => {
+                let origin = t_wc.translation.vector;
+                let direction = t_wc.rotation * ray;
+                (offset - normal.dot(&origin)) / normal.dot(&direction)
+            }
+        };
+        if depth.is_finite() && depth > 0.0 {
+            Some((ray * depth).into())
+        } else {
+            None
+        }
+    }
+}
+
+/// Return the absolute intensity differences between `first` and `second`
+/// warped onto it, over every `stride`-th pixel of `first`.
+///
+/// Pixels of `first` are lifted to 3D with `depth`, moved with the camera
+/// poses `t_wc_first` and `t_wc_second`, and sampled bilinearly in `second`.
+/// Pixels whose point leaves `second` or lies behind either camera are left
+/// out. Return `None` without any pixel left, e.g. when the views do not overlap.
+///
+/// Both images are taken by the camera `model`. A low error means that the
+/// poses, the calibration and the time alignment agree with the images, as
+/// direct methods assume; the error also grows wherever the scene departs
+/// from `depth`.
+pub fn photometric_error(
+    model: &PinholeRadTan,
+    first: &GrayImage,
+    t_wc_first: &na::Isometry3<f64>,
+    second: &GrayImage,
+    t_wc_second: &na::Isometry3<f64>,
+    depth: &DepthAssumption,
+    stride: u32,
+) -> Option<ErrorStats> {
+    let t_second_first = t_wc_second.inverse() * t_wc_first;
+    let stride = stride.max(1) as usize;
+
+    let mut errors = Vec::new();
+    for v in (0..first.height()).step_by(stride) {
+        for u in (0..first.width()).step_by(stride) {
+            let ray = model.unproject(&na::Point2::new(f64::from(u), f64::from(v)));
+            let pixel = depth
+                .point(&ray, t_wc_first)
+                .and_then(|p| model.project(&(t_second_first * p)));
+            if let Some(sample) = pixel.and_then(|px| bilinear(second, px.x as f32, px.y as f32)) {
+                let intensity = first.get_pixel(u, v)[0];
+                errors.push(f64::from(intensity) - f64::from(sample));
+            }
+        }
+    }
+
+    ErrorStats::new(&errors.iter().map(|e| e.abs()).collect::<Vec<_>>())
+}
+
+impl EuRoC {
+    /// Return the [`photometric_error`] of every pair of consecutive frames of
+    /// `camera`, keyed by the timestamp of the first frame.
+    ///
+    /// Camera poses are the [`GroundTruthData::camera_poses`](crate::GroundTruthData::camera_poses); pairs without a
+    /// ground-truth pose or without overlap are left out.
+    pub fn photometric_consistency(
+        &self,
+        camera: Sensor,
+        depth: &DepthAssumption,
+        stride: u32,
+    ) -> Result<Vec<(Timestamp, ErrorStats)>> {
+        let records = self.camera(camera)?;
+        let model = records.camera_model()?;
+        let mut poses = self
+            .ground_truth()?
+            .camera_poses(&records)?
+            .into_iter()
+            .peekable();
+
+        let mut previous: Option<(Timestamp, GrayImage, na::Isometry3<f64>)> = None;
+        let mut result = Vec::new();
+        for entry in records.entries()? {
+            let entry = entry?;
+            let t_wc = match poses.peek() {
+                Some(&(ts, t_wc)) if ts == entry.timestamp => {
+                    poses.next();
+                    t_wc
+                }
+                _ => {
+                    previous = None;
+                    continue;
+                }
+            };
+            let image = entry.load_gray()?.image;
+
+            if let Some((ts, first, t_wc_first)) = &previous {
+                if let Some(stats) =
+                    photometric_error(&model, first, t_wc_first, &image, &t_wc, depth, stride)
+                {
+                    result.push((*ts, stats));
+                }
+            }
+            previous = Some((entry.timestamp, image, t_wc));
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{GroundTruthRecord, MemorySource};
+
+    fn model() -> PinholeRadTan {
+        PinholeRadTan {
+            resolution: (64, 48),
+            intrinsics: (100.0, 100.0, 32.0, 24.0),
+            distortion: na::Vector4::zeros(),
+        }
+    }
+
+    fn texture(u: u32, v: u32) -> u8 {
+        let (u, v) = (f64::from(u), f64::from(v));
+        60.0f64.mul_add((u * 0.4).sin(), 60.0f64.mul_add((v * 0.3).cos(), 127.0)) as u8
+    }
+
+    #[test]
+    fn constant_depth() {
+        let model = model();
+        let first = GrayImage::from_fn(64, 48, |u, v| image::Luma([texture(u, v)]));
+        // 4 cm to the right at 2 m depth shifts the scene 2 px to the left
+        let second = GrayImage::from_fn(64, 48, |u, v| image::Luma([texture(u + 2, v)]));
+        let (t_wa, t_wb) = (
+            na::Isometry3::identity(),
+            na::Isometry3::translation(0.04, 0.0, 0.0),
+        );
+
+        let stats = photometric_error(
+            &model,
+            &first,
+            &t_wa,
+            &second,
+            &t_wb,
+            &DepthAssumption::Constant(2.0),
+            1,
+        )
+        .unwrap();
+        assert!(stats.max < 1e-9);
+        // the two leftmost columns leave `second`, pixels landing on its border
+        // may too up to rounding
+        assert!(stats.count > 60 * 48 && stats.count <= 62 * 48);
+
+        let wrong = photometric_error(
+            &model,
+            &first,
+            &t_wa,
+            &second,
+            &t_wb,
+            &DepthAssumption::Constant(1.0),
+            1,
+        )
+        .unwrap();
+        assert!(wrong.mean > 10.0);
+
+        // the same plane, seen from a camera at the origin looking along +z
+        let plane = DepthAssumption::WorldPlane {
+            normal: na::Vector3::z(),
+            offset: 2.0,
+        };
+        let stats = photometric_error(&model, &first, &t_wa, &second, &t_wb, &plane, 4).unwrap();
+        assert!(stats.max < 1e-9);
+
+        // a plane behind the camera is never seen
+        let behind = DepthAssumption::WorldPlane {
+            normal: na::Vector3::z(),
+            offset: -2.0,
+        };
+        assert!(photometric_error(&model, &first, &t_wa, &second, &t_wb, &behind, 4).is_none());
+    }
+
+    #[test]
+    fn fit_plane() {
+        let points: Vec<_> = (0..20)
+            .map(|i| {
+                let (x, y) = (f64::from(i % 5), f64::from(i / 5));
+                na::Point3::new(x, y, 0.5f64.mul_add(x, -1.0))
+            })
+            .collect();
+        match DepthAssumption::fit_plane(&points) {
+            Some(DepthAssumption::WorldPlane { normal, offset }) => {
+                let expected = na::Vector3::new(0.5, 0.0, -1.0).normalize();
+                let sign = normal.dot(&expected).signum();
+                assert!((normal * sign - expected).norm() < 1e-9);
+                assert!(offset.mul_add(sign, expected.z).abs() < 1e-9);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let line: Vec<_> = (0..5)
+            .map(|i| na::Point3::new(f64::from(i), 0.0, 0.0))
+            .collect();
+        assert!(DepthAssumption::fit_plane(&line).is_none());
+        assert!(DepthAssumption::fit_plane(&points[..2]).is_none());
+    }
+
+    #[test]
+    fn photometric_consistency() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        // the ground truth of `test_data` starts after the camera frames
+        let depth = DepthAssumption::Constant(3.0);
+        assert!(data
+            .photometric_consistency(Sensor::Cam0, &depth, 16)?
+            .is_empty());
+
+        // a static body, so that consecutive frames differ only by the images
+        let ground_truth: Vec<_> = data
+            .timestamps(Sensor::Cam0)?
+            .into_iter()
+            .map(|ts| GroundTruthRecord::from_pose(ts, na::Isometry3::identity()))
+            .collect();
+        let data = EuRoC::with_ground_truth(MemorySource::from_dir("test_data")?, &ground_truth)?;
+
+        let errors = data.photometric_consistency(Sensor::Cam0, &depth, 16)?;
+        let frames = data.timestamps(Sensor::Cam0)?;
+        let keys: Vec<_> = errors.iter().map(|(ts, _)| *ts).collect();
+        assert_eq!(keys, frames[..4]);
+        assert!(errors.iter().all(|(_, stats)| stats.count == 47 * 30));
+
+        Ok(())
+    }
+}
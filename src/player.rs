@@ -0,0 +1,404 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::{self, Instant},
+};
+
+use crate::{Duration, EuRoC, EurocError, Result, Sensor, SensorEvent, Timestamp, Timestamped};
+
+/// Settings of a [`Player`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerOptions {
+    /// playback speed relative to real time, e.g. `2.0` plays twice as fast
+    pub speed: f64,
+    /// start over at the end of the sequence instead of finishing
+    pub looping: bool,
+}
+
+impl Default for PlayerOptions {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            looping: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    paused: bool,
+    speed: f64,
+    seek: Option<Timestamp>,
+    stop: bool,
+    finished: bool,
+}
+
+#[derive(Debug)]
+struct Control {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Control {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn update<F: FnOnce(&mut State)>(&self, f: F) {
+        f(&mut self.lock());
+        self.changed.notify_all();
+    }
+}
+
+/// Mapping from wall-clock time to dataset time.
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    instant: Instant,
+    origin: Timestamp,
+    speed: f64,
+}
+
+impl Clock {
+    /// Return the dataset time at `now`.
+    fn position(&self, now: Instant) -> Timestamp {
+        let elapsed = now.saturating_duration_since(self.instant).as_secs_f64();
+        self.origin + Duration::from_secs_f64(elapsed * self.speed)
+    }
+
+    /// Return the wall-clock time at which `timestamp` is due.
+    fn due(&self, timestamp: Timestamp) -> Instant {
+        let ahead = (timestamp - self.origin).as_secs_f64().max(0.0);
+        self.instant + time::Duration::from_secs_f64(ahead / self.speed)
+    }
+}
+
+enum Wait {
+    Deliver,
+    Seek(Timestamp),
+    Stop,
+}
+
+/// Plays the merged records of several sensors in real time, as if they came from the sensors.
+///
+/// Records are produced in a background thread, which waits until each one
+/// is due on the wall clock and hands it to a callback, see [`Player::spawn`],
+/// or to a channel, see [`Player::channel`]. Playback can be paused, moved to
+/// another time and sped up or slowed down while running.
+///
+/// Dropping the player stops playback.
+#[derive(Debug)]
+pub struct Player {
+    control: Arc<Control>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Player {
+    /// Start playing `sensors` of `data`, calling `deliver` with every record when it is due.
+    ///
+    /// Playback stops when `deliver` returns `false`. Errors while reading are
+    /// delivered as well; playback goes on after them.
+    pub fn spawn<F>(
+        data: &EuRoC,
+        sensors: &[Sensor],
+        options: PlayerOptions,
+        deliver: F,
+    ) -> Result<Self>
+    where
+        F: FnMut(Result<SensorEvent>) -> bool + Send + 'static,
+    {
+        check_speed(options.speed)?;
+        // fail early on missing sensors
+        data.events(sensors)?;
+
+        let control = Arc::new(Control {
+            state: Mutex::new(State {
+                paused: false,
+                speed: options.speed,
+                seek: None,
+                stop: false,
+                finished: false,
+            }),
+            changed: Condvar::new(),
+        });
+
+        let data = data.clone();
+        let sensors = sensors.to_vec();
+        let shared = Arc::clone(&control);
+        let thread = thread::spawn(move || {
+            let mut deliver = deliver;
+            play(&data, &sensors, options.looping, &shared, &mut deliver);
+            // before dropping `deliver`, which closes the channel
+            shared.update(|state| state.finished = true);
+        });
+
+        Ok(Self {
+            control,
+            thread: Some(thread),
+        })
+    }
+
+    /// Start playing `sensors` of `data` into a channel holding up to `capacity` records.
+    ///
+    /// Playback stops when the receiver is dropped. If the receiver falls
+    /// behind, records are delivered late rather than dropped.
+    pub fn channel(
+        data: &EuRoC,
+        sensors: &[Sensor],
+        options: PlayerOptions,
+        capacity: usize,
+    ) -> Result<(Self, Receiver<Result<SensorEvent>>)> {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let player = Self::spawn(data, sensors, options, move |event| {
+            sender.send(event).is_ok()
+        })?;
+
+        Ok((player, receiver))
+    }
+
+    pub fn pause(&self) {
+        self.control.update(|state| state.paused = true);
+    }
+
+    pub fn resume(&self) {
+        self.control.update(|state| state.paused = false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.lock().paused
+    }
+
+    /// Continue playback from the first record at or after `timestamp`.
+    pub fn seek(&self, timestamp: Timestamp) {
+        self.control.update(|state| state.seek = Some(timestamp));
+    }
+
+    /// Change the playback speed relative to real time.
+    pub fn set_speed(&self, speed: f64) -> Result<()> {
+        check_speed(speed)?;
+        self.control.update(|state| state.speed = speed);
+
+        Ok(())
+    }
+
+    /// Return whether playback has ended, i.e. reached the end without
+    /// looping, was stopped or the receiver went away.
+    pub fn is_finished(&self) -> bool {
+        self.control.lock().finished
+    }
+
+    /// Stop playback and wait for the background thread.
+    ///
+    /// With [`Player::channel`], drop the receiver first or keep receiving,
+    /// since the thread may be waiting for room in the channel.
+    pub fn stop(mut self) {
+        self.control.update(|state| state.stop = true);
+        self.join_thread();
+    }
+
+    /// Wait until playback ends.
+    pub fn join(mut self) {
+        self.join_thread();
+    }
+
+    fn join_thread(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            // `deliver` panicking is the only way to get an error
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.control.update(|state| state.stop = true);
+    }
+}
+
+fn check_speed(speed: f64) -> Result<()> {
+    if speed.is_finite() && speed > 0.0 {
+        Ok(())
+    } else {
+        Err(EurocError::InvalidInput(format!(
+            "playback speed must be positive, got {}",
+            speed
+        )))
+    }
+}
+
+fn play<F>(data: &EuRoC, sensors: &[Sensor], looping: bool, control: &Control, mut deliver: F)
+where
+    F: FnMut(Result<SensorEvent>) -> bool,
+{
+    let mut start = None;
+    loop {
+        let events = start.map_or_else(
+            || data.events(sensors),
+            |start| data.events_between(sensors, start, Timestamp::new(u64::MAX)),
+        );
+        let events = match events {
+            Ok(events) => events,
+            Err(e) => {
+                deliver(Err(e));
+                return;
+            }
+        };
+
+        let mut clock = None;
+        let mut played = false;
+        start = None;
+        for event in events {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    if deliver(Err(e)) {
+                        continue;
+                    }
+                    return;
+                }
+            };
+            match wait(control, &mut clock, event.timestamp()) {
+                Wait::Deliver => {
+                    if !deliver(Ok(event)) {
+                        return;
+                    }
+                    played = true;
+                }
+                Wait::Seek(timestamp) => {
+                    start = Some(timestamp);
+                    break;
+                }
+                Wait::Stop => return,
+            }
+        }
+
+        // nothing left to play, e.g. after seeking past the end
+        if start.is_none() && (!looping || !played) {
+            return;
+        }
+    }
+}
+
+/// Block until `timestamp` is due on `clock`, starting the clock at it if unset.
+fn wait(control: &Control, clock: &mut Option<Clock>, timestamp: Timestamp) -> Wait {
+    let mut state = control.lock();
+    // dataset time reached when paused
+    let mut paused_at = None;
+    loop {
+        if state.stop {
+            return Wait::Stop;
+        }
+        if let Some(timestamp) = state.seek.take() {
+            return Wait::Seek(timestamp);
+        }
+
+        let now = Instant::now();
+        if state.paused {
+            if paused_at.is_none() {
+                paused_at = Some(clock.map_or(timestamp, |c| c.position(now)));
+            }
+            state = control
+                .changed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+            continue;
+        }
+
+        let origin = match (paused_at.take(), *clock) {
+            (Some(position), _) => Some(position),
+            (None, Some(c)) if (c.speed - state.speed).abs() > f64::EPSILON => {
+                Some(c.position(now))
+            }
+            (None, Some(_)) => None,
+            (None, None) => Some(timestamp),
+        };
+        if let Some(origin) = origin {
+            *clock = Some(Clock {
+                instant: now,
+                origin,
+                speed: state.speed,
+            });
+        }
+
+        let due = clock.map_or(now, |c| c.due(timestamp));
+        if due <= now {
+            return Wait::Deliver;
+        }
+        state = control
+            .changed
+            .wait_timeout(state, due - now)
+            .unwrap_or_else(|e| e.into_inner())
+            .0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pacing() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let sensors = [Sensor::Cam0, Sensor::Imu0];
+        let expected = data
+            .events(&sensors)?
+            .map(|e| e.map(|e| e.timestamp()))
+            .collect::<Result<Vec<_>>>()?;
+        let span = (*expected.last().unwrap() - expected[0]).as_secs_f64();
+
+        let options = PlayerOptions {
+            speed: 2.0,
+            looping: false,
+        };
+        let begin = Instant::now();
+        let (player, receiver) = Player::channel(&data, &sensors, options, 4)?;
+        let played = receiver
+            .iter()
+            .map(|e| e.map(|e| e.timestamp()))
+            .collect::<Result<Vec<_>>>()?;
+        let elapsed = begin.elapsed().as_secs_f64();
+
+        assert_eq!(played, expected);
+        assert!(elapsed >= span / 2.0 * 0.9, "{} < {}", elapsed, span / 2.0);
+        assert!(player.is_finished());
+        player.join();
+
+        Ok(())
+    }
+
+    #[test]
+    fn seek_and_loop() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let timestamps = data.timestamps(Sensor::Cam0)?;
+        let options = PlayerOptions {
+            speed: 100.0,
+            looping: true,
+        };
+        let (player, receiver) = Player::channel(&data, &[Sensor::Cam0], options, 0)?;
+
+        let next = || receiver.recv().unwrap().map(|e| e.timestamp());
+        assert_eq!(next()?, timestamps[0]);
+        player.seek(timestamps[3]);
+        // at most one record was already on its way
+        let mut after_seek = next()?;
+        if after_seek != timestamps[3] {
+            after_seek = next()?;
+        }
+        assert_eq!(after_seek, timestamps[3]);
+        assert_eq!(next()?, timestamps[4]);
+        assert_eq!(next()?, timestamps[0]);
+
+        player.pause();
+        assert!(player.is_paused());
+        player.resume();
+        assert_eq!(next()?, timestamps[1]);
+
+        assert!(player.set_speed(0.0).is_err());
+        drop(receiver);
+        player.stop();
+
+        Ok(())
+    }
+}
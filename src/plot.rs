@@ -0,0 +1,500 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use image::{Rgb, RgbImage};
+use nalgebra as na;
+
+use crate::{GroundTruthData, ImuData, ImuRecord, Result, Timestamp};
+
+const COLORS: [[u8; 3]; 6] = [
+    [0x1f, 0x77, 0xb4],
+    [0xff, 0x7f, 0x0e],
+    [0x2c, 0xa0, 0x2c],
+    [0xd6, 0x27, 0x28],
+    [0x94, 0x67, 0xbd],
+    [0x8c, 0x56, 0x4b],
+];
+const GRID: [u8; 3] = [0xdd, 0xdd, 0xdd];
+/// space around the plot area for the title, labels and legend
+const MARGIN: (f64, f64, f64, f64) = (70.0, 120.0, 30.0, 45.0);
+
+/// Plane a trajectory is projected onto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Plane {
+    XY,
+    XZ,
+}
+
+impl Plane {
+    fn project(self, p: &na::Vector3<f64>) -> (f64, f64) {
+        match self {
+            Self::XY => (p.x, p.y),
+            Self::XZ => (p.x, p.z),
+        }
+    }
+
+    const fn labels(self) -> (&'static str, &'static str) {
+        match self {
+            Self::XY => ("x [m]", "y [m]"),
+            Self::XZ => ("x [m]", "z [m]"),
+        }
+    }
+}
+
+/// Polyline of a [`Plot`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// Line chart for quick looks at a sequence, rendered to SVG or PNG.
+///
+/// PNG rendering draws the grid and the series but no text, as no font is
+/// available; use SVG for a labelled plot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub series: Vec<Series>,
+    /// scale both axes alike, as for trajectories
+    pub equal_aspect: bool,
+}
+
+impl Plot {
+    pub fn new<S: Into<String>>(title: S, x_label: S, y_label: S) -> Self {
+        Self {
+            title: title.into(),
+            x_label: x_label.into(),
+            y_label: y_label.into(),
+            series: Vec::new(),
+            equal_aspect: false,
+        }
+    }
+
+    pub fn with_series<S: Into<String>>(mut self, name: S, points: Vec<(f64, f64)>) -> Self {
+        self.series.push(Series {
+            name: name.into(),
+            points,
+        });
+        self
+    }
+
+    /// Map data to pixels in an image of `width` by `height`.
+    fn frame(&self, width: u32, height: u32) -> Frame {
+        let (left, right, top, bottom) = MARGIN;
+        let (w, h) = (
+            (f64::from(width) - left - right).max(1.0),
+            (f64::from(height) - top - bottom).max(1.0),
+        );
+
+        let finite = self
+            .series
+            .iter()
+            .flat_map(|s| &s.points)
+            .filter(|(x, y)| x.is_finite() && y.is_finite());
+        let (mut x0, mut x1, mut y0, mut y1) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+        for &(x, y) in finite {
+            x0 = x0.min(x);
+            x1 = x1.max(x);
+            y0 = y0.min(y);
+            y1 = y1.max(y);
+        }
+        let widen = |lo: f64, hi: f64| {
+            if lo > hi {
+                (0.0, 1.0)
+            } else if (hi - lo).abs() < f64::EPSILON {
+                (lo - 0.5, hi + 0.5)
+            } else {
+                // keep the lines off the frame
+                let pad = (hi - lo) * 0.05;
+                (lo - pad, hi + pad)
+            }
+        };
+        let ((mut x0, mut x1), (mut y0, mut y1)) = (widen(x0, x1), widen(y0, y1));
+
+        if self.equal_aspect {
+            let scale = ((x1 - x0) / w).max((y1 - y0) / h);
+            let (cx, cy) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+            x0 = cx - scale * w / 2.0;
+            x1 = cx + scale * w / 2.0;
+            y0 = cy - scale * h / 2.0;
+            y1 = cy + scale * h / 2.0;
+        }
+
+        Frame {
+            x: (x0, x1),
+            y: (y0, y1),
+            left,
+            top,
+            width: w,
+            height: h,
+        }
+    }
+
+    /// Render as an SVG document of `width` by `height` pixels.
+    pub fn to_svg(&self, width: u32, height: u32) -> String {
+        let frame = self.frame(width, height);
+        let (left, top, w, h) = (frame.left, frame.top, frame.width, frame.height);
+        let mut svg = String::new();
+
+        // writing to a String cannot fail
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="sans-serif">"#,
+            width, height
+        );
+        svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n");
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="20" text-anchor="middle" font-size="14">{}</text>"#,
+            left + w / 2.0,
+            escape(&self.title)
+        );
+
+        for x in ticks(frame.x) {
+            let px = frame.map(x, frame.y.0).0;
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{0:.1}\" y1=\"{1:.1}\" x2=\"{0:.1}\" y2=\"{2:.1}\" stroke=\"{3}\"/>\n\
+                 <text x=\"{0:.1}\" y=\"{4:.1}\" text-anchor=\"middle\" font-size=\"10\">{5}</text>",
+                px,
+                top,
+                top + h,
+                hex(GRID),
+                top + h + 14.0,
+                label(x)
+            );
+        }
+        for y in ticks(frame.y) {
+            let py = frame.map(frame.x.0, y).1;
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{0:.1}\" y1=\"{1:.1}\" x2=\"{2:.1}\" y2=\"{1:.1}\" stroke=\"{3}\"/>\n\
+                 <text x=\"{4:.1}\" y=\"{5:.1}\" text-anchor=\"end\" font-size=\"10\">{6}</text>",
+                left,
+                py,
+                left + w,
+                hex(GRID),
+                left - 6.0,
+                py + 3.0,
+                label(y)
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="none" stroke="black"/>"#,
+            left, top, w, h
+        );
+
+        for (i, series) in self.series.iter().enumerate() {
+            let color = hex(COLORS[i % COLORS.len()]);
+            for segment in segments(&series.points) {
+                svg.push_str("<polyline fill=\"none\" stroke-width=\"1.5\" stroke=\"");
+                svg.push_str(&color);
+                svg.push_str("\" points=\"");
+                for &(x, y) in segment {
+                    let (px, py) = frame.map(x, y);
+                    let _ = write!(svg, "{:.1},{:.1} ", px, py);
+                }
+                svg.push_str("\"/>\n");
+            }
+
+            let ly = 18.0f64.mul_add(i as f64, top + 10.0);
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{0:.1}\" y1=\"{1:.1}\" x2=\"{2:.1}\" y2=\"{1:.1}\" stroke=\"{3}\" stroke-width=\"2\"/>\n\
+                 <text x=\"{4:.1}\" y=\"{5:.1}\" font-size=\"11\">{6}</text>",
+                left + w + 10.0,
+                ly,
+                left + w + 30.0,
+                color,
+                left + w + 35.0,
+                ly + 4.0,
+                escape(&series.name)
+            );
+        }
+
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle" font-size="12">{}</text>"#,
+            left + w / 2.0,
+            top + h + 34.0,
+            escape(&self.x_label)
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text transform="translate(16 {:.1}) rotate(-90)" text-anchor="middle" font-size="12">{}</text>"#,
+            top + h / 2.0,
+            escape(&self.y_label)
+        );
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Render to an image of `width` by `height` pixels.
+    pub fn to_image(&self, width: u32, height: u32) -> RgbImage {
+        let frame = self.frame(width, height);
+        let (left, top, w, h) = (frame.left, frame.top, frame.width, frame.height);
+        let mut image = RgbImage::from_pixel(width, height, Rgb([0xff; 3]));
+
+        for x in ticks(frame.x) {
+            let px = frame.map(x, frame.y.0).0;
+            draw_line(&mut image, (px, top), (px, top + h), GRID);
+        }
+        for y in ticks(frame.y) {
+            let py = frame.map(frame.x.0, y).1;
+            draw_line(&mut image, (left, py), (left + w, py), GRID);
+        }
+        let corners = [
+            (left, top),
+            (left + w, top),
+            (left + w, top + h),
+            (left, top + h),
+            (left, top),
+        ];
+        for pair in corners.windows(2) {
+            draw_line(&mut image, pair[0], pair[1], [0; 3]);
+        }
+
+        for (i, series) in self.series.iter().enumerate() {
+            let color = COLORS[i % COLORS.len()];
+            for segment in segments(&series.points) {
+                for pair in segment.windows(2) {
+                    let from = frame.map(pair[0].0, pair[0].1);
+                    let to = frame.map(pair[1].0, pair[1].1);
+                    draw_line(&mut image, from, to, color);
+                }
+            }
+            let ly = 18.0f64.mul_add(i as f64, top + 10.0);
+            draw_line(
+                &mut image,
+                (left + w + 10.0, ly),
+                (left + w + 30.0, ly),
+                color,
+            );
+        }
+
+        image
+    }
+
+    /// Write the plot to `path`, as SVG if it ends in `.svg` and otherwise in
+    /// the image format of its extension, e.g. PNG.
+    pub fn save<P: AsRef<Path>>(&self, path: P, width: u32, height: u32) -> Result<()> {
+        let path = path.as_ref();
+        let is_svg = path.extension().map_or(false, |ext| {
+            ext.to_string_lossy().eq_ignore_ascii_case("svg")
+        });
+        if is_svg {
+            fs::write(path, self.to_svg(width, height))?;
+        } else {
+            self.to_image(width, height).save(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Data range and pixel rectangle of the plot area.
+struct Frame {
+    x: (f64, f64),
+    y: (f64, f64),
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Frame {
+    fn map(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            ((x - self.x.0) / (self.x.1 - self.x.0)).mul_add(self.width, self.left),
+            ((self.y.1 - y) / (self.y.1 - self.y.0)).mul_add(self.height, self.top),
+        )
+    }
+}
+
+/// Return round values within `range`, about five of them.
+fn ticks((lo, hi): (f64, f64)) -> Vec<f64> {
+    let raw = (hi - lo) / 5.0;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|&step| step >= raw)
+        .unwrap_or(10.0 * magnitude);
+
+    let first = (lo / step).ceil() as i64;
+    let last = (hi / step).floor() as i64;
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+fn label(value: f64) -> String {
+    // avoid "-0"
+    let value = if value.abs() < 1e-12 { 0.0 } else { value };
+    let text = format!("{:.3}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+/// Split `points` at non-finite values, which leave a gap.
+fn segments(points: &[(f64, f64)]) -> impl Iterator<Item = &[(f64, f64)]> {
+    points
+        .split(|(x, y)| !x.is_finite() || !y.is_finite())
+        .filter(|segment| !segment.is_empty())
+}
+
+fn draw_line(image: &mut RgbImage, (x0, y0): (f64, f64), (x1, y1): (f64, f64), color: [u8; 3]) {
+    let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as usize;
+    for i in 0..=steps {
+        let t = i as f64 / steps as f64;
+        let x = (x1 - x0).mul_add(t, x0).round();
+        let y = (y1 - y0).mul_add(t, y0).round();
+        if x >= 0.0 && y >= 0.0 && x < f64::from(image.width()) && y < f64::from(image.height()) {
+            image.put_pixel(x as u32, y as u32, Rgb(color));
+        }
+    }
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Return the x, y and z components of `values` against time since the first.
+fn components(values: &[(Timestamp, na::Vector3<f64>)]) -> [Vec<(f64, f64)>; 3] {
+    let start = values.first().map_or(Timestamp::new(0), |&(ts, _)| ts);
+    let mut series = [Vec::new(), Vec::new(), Vec::new()];
+    for (ts, v) in values {
+        let t = (*ts - start).as_secs_f64();
+        for (axis, points) in series.iter_mut().enumerate() {
+            points.push((t, v[axis]));
+        }
+    }
+    series
+}
+
+impl GroundTruthData {
+    /// Plot the ground-truth positions projected onto `plane`.
+    pub fn plot_trajectory(&self, plane: Plane) -> Result<Plot> {
+        let points = self
+            .records()?
+            .map(|r| r.map(|r| plane.project(&r.position)))
+            .collect::<Result<Vec<_>>>()?;
+        let (x_label, y_label) = plane.labels();
+
+        let mut plot =
+            Plot::new("ground truth", x_label, y_label).with_series("ground truth", points);
+        plot.equal_aspect = true;
+        Ok(plot)
+    }
+
+    /// Plot the positions of `estimate` over the ground truth, projected onto `plane`.
+    ///
+    /// The estimate is drawn as given; transform it by the alignment of
+    /// [`GroundTruthData::evaluate_trajectory`] first to compare the shapes.
+    pub fn plot_estimate(
+        &self,
+        estimate: &[(Timestamp, na::Isometry3<f64>)],
+        plane: Plane,
+    ) -> Result<Plot> {
+        let points = estimate
+            .iter()
+            .map(|(_, pose)| plane.project(&pose.translation.vector))
+            .collect();
+
+        let mut plot = self.plot_trajectory(plane)?.with_series("estimate", points);
+        plot.title = "estimate vs ground truth".to_owned();
+        Ok(plot)
+    }
+}
+
+impl ImuData {
+    /// Plot the angular velocity against time since the first sample.
+    pub fn plot_gyro(&self) -> Result<Plot> {
+        self.plot_signal("gyroscope", "angular velocity [rad/s]", |r| r.gyro)
+    }
+
+    /// Plot the linear acceleration against time since the first sample.
+    pub fn plot_accel(&self) -> Result<Plot> {
+        self.plot_signal("accelerometer", "acceleration [m/s^2]", |r| r.accel)
+    }
+
+    fn plot_signal<F>(&self, title: &str, y_label: &str, signal: F) -> Result<Plot>
+    where
+        F: Fn(&ImuRecord) -> na::Vector3<f64>,
+    {
+        let values = self
+            .records()?
+            .map(|r| r.map(|r| (r.timestamp, signal(&r))))
+            .collect::<Result<Vec<_>>>()?;
+        let [x, y, z] = components(&values);
+
+        Ok(Plot::new(title, "time [s]", y_label)
+            .with_series("x", x)
+            .with_series("y", y)
+            .with_series("z", z))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use crate::EuRoC;
+
+    #[test]
+    fn ticks_are_round() {
+        let labels = |range| ticks(range).into_iter().map(label).collect::<Vec<_>>();
+        assert_eq!(labels((0.0, 1.0)), ["0", "0.2", "0.4", "0.6", "0.8", "1"]);
+        assert_eq!(labels((-3.0, 12.0)), ["0", "5", "10"]);
+        assert_eq!(labels((-0.013, 0.004)), ["-0.01", "-0.005", "0"]);
+    }
+
+    #[test]
+    fn plot_trajectory() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let truth = data.ground_truth()?;
+        let estimate = truth
+            .records()?
+            .map(|r| r.map(|r| (r.timestamp, r.pose())))
+            .collect::<Result<Vec<_>>>()?;
+
+        let plot = truth.plot_estimate(&estimate, Plane::XZ)?;
+        assert_eq!(plot.series.len(), 2);
+        assert_eq!(plot.series[0].points, plot.series[1].points);
+
+        let svg = plot.to_svg(640, 480);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains(">z [m]</text>"));
+
+        let image = plot.to_image(640, 480);
+        assert_eq!(image.dimensions(), (640, 480));
+        assert!(image.pixels().any(|p| p.0 == COLORS[1]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn plot_imu() -> Result<()> {
+        let imu = EuRoC::new("test_data")?.imu()?;
+        let plot = imu.plot_accel()?;
+        assert_eq!(plot.series.len(), 3);
+        assert_eq!(plot.series[0].points.len(), 5);
+        assert_eq!(plot.series[0].points[0].0, 0.0);
+
+        let dir = TempDir::new("plot_imu")?;
+        let svg = dir.join("plot.svg");
+        let png = dir.join("plot.png");
+        plot.save(&svg, 400, 300)?;
+        plot.save(&png, 400, 300)?;
+        assert!(fs::read_to_string(&svg)?.contains("accelerometer"));
+        assert_eq!(image::open(&png)?.to_rgb8().dimensions(), (400, 300));
+
+        Ok(())
+    }
+}
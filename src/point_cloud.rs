@@ -0,0 +1,394 @@
+use std::{
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use nalgebra as na;
+
+use crate::{DataSource, EurocError, FileSystem, Result};
+
+const DATA_PLY: &str = "data.ply";
+/// vertices reserved before reading any
+const MAX_RESERVED_VERTICES: usize = 1 << 20;
+
+#[derive(Debug, Clone)]
+pub struct PointCloudData {
+    source: Arc<dyn DataSource>,
+    path: PathBuf,
+}
+
+impl PointCloudData {
+    pub fn new(path: PathBuf) -> Result<Self> {
+        Self::with_source(FileSystem::shared(), path)
+    }
+
+    /// Open the folder `path` of `source`.
+    pub fn with_source(source: Arc<dyn DataSource>, path: PathBuf) -> Result<Self> {
+        source.require_dir(&path)?;
+        source.require_file(&path.join(DATA_PLY))?;
+
+        Ok(Self { source, path })
+    }
+
+    /// Parse `data.ply`.
+    pub fn load(&self) -> Result<PointCloud> {
+        let f = self.source.open(&self.path.join(DATA_PLY))?;
+        PointCloud::from_ply(BufReader::new(f))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PointCloud {
+    /// point positions (m)
+    pub points: Vec<na::Point3<f64>>,
+    /// per-point intensity, if the scan has an `intensity` property
+    pub intensities: Option<Vec<f64>>,
+    /// per-point RGB, if the scan has `red`, `green` and `blue` properties
+    pub colors: Option<Vec<[u8; 3]>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return Err(ply_error(format!("unknown PLY type `{}`", name))),
+        })
+    }
+
+    const fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Property {
+    Scalar(ScalarType, String),
+    List(ScalarType, ScalarType),
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl PointCloud {
+    /// Parse a PLY file (ASCII, binary little endian or binary big endian).
+    ///
+    /// Only the `vertex` element is kept; it must have `x`, `y` and `z` properties.
+    pub fn from_ply<R: BufRead>(mut reader: R) -> Result<Self> {
+        let (format, elements) = read_header(&mut reader)?;
+        let mut body = Body::new(reader, format);
+        let mut cloud = None;
+
+        for element in &elements {
+            if element.name == "vertex" {
+                cloud = Some(read_vertices(&mut body, element)?);
+            } else {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        match property {
+                            Property::Scalar(ty, _) => {
+                                body.read(*ty)?;
+                            }
+                            Property::List(len_ty, ty) => {
+                                for _ in 0..body.read(*len_ty)? as usize {
+                                    body.read(*ty)?;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        cloud.ok_or_else(|| ply_error("PLY has no vertex element"))
+    }
+}
+
+fn ply_error<M: Into<String>>(message: M) -> EurocError {
+    EurocError::PlyParse(message.into())
+}
+
+fn read_header<R: BufRead>(reader: &mut R) -> Result<(Format, Vec<Element>)> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if line.trim_end() != "ply" {
+        return Err(ply_error("not a PLY file"));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(ply_error("unexpected end of PLY header"));
+        }
+        let tokens: Vec<_> = line.split_whitespace().collect();
+
+        match tokens.as_slice() {
+            ["format", "ascii", _] => format = Some(Format::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(Format::BinaryLittleEndian),
+            ["format", "binary_big_endian", _] => format = Some(Format::BinaryBigEndian),
+            ["element", name, count] => elements.push(Element {
+                name: (*name).to_owned(),
+                count: count
+                    .parse()
+                    .map_err(|_| ply_error(format!("invalid element count `{}`", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", len_ty, ty, _] => elements
+                .last_mut()
+                .ok_or_else(|| ply_error("property before element"))?
+                .properties
+                .push(Property::List(
+                    ScalarType::parse(len_ty)?,
+                    ScalarType::parse(ty)?,
+                )),
+            ["property", ty, name] => elements
+                .last_mut()
+                .ok_or_else(|| ply_error("property before element"))?
+                .properties
+                .push(Property::Scalar(ScalarType::parse(ty)?, (*name).to_owned())),
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => {
+                return Err(ply_error(format!(
+                    "unexpected PLY header line `{}`",
+                    line.trim_end()
+                )))
+            }
+        }
+    }
+
+    let format = format.ok_or_else(|| ply_error("PLY header has no format"))?;
+    Ok((format, elements))
+}
+
+fn read_vertices<R: BufRead>(body: &mut Body<R>, element: &Element) -> Result<PointCloud> {
+    let index = |name: &str| {
+        element
+            .properties
+            .iter()
+            .position(|p| matches!(p, Property::Scalar(_, n) if n == name))
+    };
+    let missing = || ply_error("vertex element must have x, y and z");
+    let (x, y, z) = (
+        index("x").ok_or_else(missing)?,
+        index("y").ok_or_else(missing)?,
+        index("z").ok_or_else(missing)?,
+    );
+    let intensity = index("intensity");
+    let rgb = match (index("red"), index("green"), index("blue")) {
+        (Some(r), Some(g), Some(b)) => Some((r, g, b)),
+        _ => None,
+    };
+
+    // the count comes from the header, so reserve no more than a sane amount up front
+    let capacity = element.count.min(MAX_RESERVED_VERTICES);
+    let mut cloud = PointCloud {
+        points: Vec::with_capacity(capacity),
+        intensities: intensity.map(|_| Vec::with_capacity(capacity)),
+        colors: rgb.map(|_| Vec::with_capacity(capacity)),
+    };
+
+    let mut values = vec![0.0; element.properties.len()];
+    for _ in 0..element.count {
+        for (value, property) in values.iter_mut().zip(&element.properties) {
+            match property {
+                Property::Scalar(ty, _) => *value = body.read(*ty)?,
+                Property::List(..) => {
+                    return Err(ply_error("list properties on vertices are not supported"))
+                }
+            }
+        }
+
+        cloud
+            .points
+            .push(na::Point3::new(values[x], values[y], values[z]));
+        if let (Some(i), Some(intensities)) = (intensity, cloud.intensities.as_mut()) {
+            intensities.push(values[i]);
+        }
+        if let (Some((r, g, b)), Some(colors)) = (rgb, cloud.colors.as_mut()) {
+            colors.push([values[r] as u8, values[g] as u8, values[b] as u8]);
+        }
+    }
+
+    Ok(cloud)
+}
+
+/// Reader for the PLY body, yielding scalars as `f64`.
+struct Body<R> {
+    reader: R,
+    format: Format,
+    tokens: std::vec::IntoIter<String>,
+}
+
+impl<R: BufRead> Body<R> {
+    fn new(reader: R, format: Format) -> Self {
+        Self {
+            reader,
+            format,
+            tokens: Vec::new().into_iter(),
+        }
+    }
+
+    fn read(&mut self, ty: ScalarType) -> Result<f64> {
+        if self.format == Format::Ascii {
+            let token = self.next_token()?;
+            return token
+                .parse()
+                .map_err(|_| ply_error(format!("invalid number `{}`", token)));
+        }
+
+        let mut buf = [0u8; 8];
+        let buf = &mut buf[..ty.size()];
+        self.reader.read_exact(buf)?;
+        if self.format == Format::BinaryBigEndian {
+            buf.reverse();
+        }
+
+        let mut le = [0u8; 8];
+        le[..buf.len()].copy_from_slice(buf);
+        Ok(match ty {
+            ScalarType::I8 => f64::from(buf[0] as i8),
+            ScalarType::U8 => f64::from(buf[0]),
+            ScalarType::I16 => f64::from(i16::from_le_bytes([le[0], le[1]])),
+            ScalarType::U16 => f64::from(u16::from_le_bytes([le[0], le[1]])),
+            ScalarType::I32 => f64::from(i32::from_le_bytes([le[0], le[1], le[2], le[3]])),
+            ScalarType::U32 => f64::from(u32::from_le_bytes([le[0], le[1], le[2], le[3]])),
+            ScalarType::F32 => f64::from(f32::from_le_bytes([le[0], le[1], le[2], le[3]])),
+            ScalarType::F64 => f64::from_le_bytes(le),
+        })
+    }
+
+    fn next_token(&mut self) -> Result<String> {
+        loop {
+            if let Some(token) = self.tokens.next() {
+                return Ok(token);
+            }
+
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(ply_error("unexpected end of PLY body"));
+            }
+            self.tokens = line
+                .split_whitespace()
+                .map(ToOwned::to_owned)
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EuRoC;
+
+    #[test]
+    fn load() -> Result<()> {
+        let cloud = EuRoC::new("test_data")?.point_cloud()?.load()?;
+
+        assert_eq!(cloud.points.len(), 4);
+        assert_eq!(cloud.points[1], na::Point3::new(1.5, -2.25, 0.5));
+        assert_eq!(cloud.intensities, Some(vec![0.0, 10.0, 20.0, 30.0]));
+        assert_eq!(cloud.colors, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_ply_ascii() -> Result<()> {
+        let ply = "\
+ply
+format ascii 1.0
+comment test
+element vertex 2
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 2 3 0 128 255
+3 0 1 1
+";
+        let cloud = PointCloud::from_ply(ply.as_bytes())?;
+
+        assert_eq!(
+            cloud.points,
+            vec![na::Point3::origin(), na::Point3::new(1.0, 2.0, 3.0)]
+        );
+        assert_eq!(cloud.colors, Some(vec![[255, 0, 0], [0, 128, 255]]));
+        assert_eq!(cloud.intensities, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_ply_big_endian() -> Result<()> {
+        let mut ply = b"ply\nformat binary_big_endian 1.0\nelement vertex 1\nproperty double x\nproperty double y\nproperty double z\nend_header\n".to_vec();
+        for v in &[1.0f64, 2.0, 3.0] {
+            ply.extend_from_slice(&v.to_be_bytes());
+        }
+        let cloud = PointCloud::from_ply(ply.as_slice())?;
+
+        assert_eq!(cloud.points, vec![na::Point3::new(1.0, 2.0, 3.0)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_ply_missing_coordinate() {
+        let ply = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nend_header\n0 0\n";
+        assert!(PointCloud::from_ply(ply.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn from_ply_huge_count() {
+        let ply = format!(
+            "ply\nformat binary_little_endian 1.0\nelement vertex {}\nproperty float x\nproperty float y\nproperty float z\nend_header\n",
+            usize::MAX
+        );
+        assert!(PointCloud::from_ply(ply.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use nalgebra as na;
+
+use crate::{EurocError, GroundTruthData, Result};
+
+/// Output format of a pose graph file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoseGraphFormat {
+    /// `VERTEX_SE3:QUAT` / `EDGE_SE3:QUAT` lines as read by g2o.
+    G2o,
+    /// `VERTEX3` / `EDGE3` lines (roll, pitch, yaw) as read by TORO.
+    Toro,
+}
+
+/// Write `poses` as a pose graph.
+///
+/// Consecutive poses are connected by odometry edges, and every `(i, j)` pair in
+/// `loop_closures` adds an extra edge between vertex `i` and vertex `j`.
+/// All edges carry an identity information matrix.
+pub fn write_pose_graph<W: Write>(
+    mut writer: W,
+    poses: &[na::Isometry3<f64>],
+    loop_closures: &[(usize, usize)],
+    format: PoseGraphFormat,
+) -> Result<()> {
+    for &(i, j) in loop_closures {
+        if i >= poses.len() || j >= poses.len() {
+            return Err(EurocError::InvalidInput(format!(
+                "loop closure ({}, {}) out of range",
+                i, j
+            )));
+        }
+    }
+
+    for (id, pose) in poses.iter().enumerate() {
+        match format {
+            PoseGraphFormat::G2o => write!(writer, "VERTEX_SE3:QUAT {}", id)?,
+            PoseGraphFormat::Toro => write!(writer, "VERTEX3 {}", id)?,
+        }
+        write_pose(&mut writer, pose, format)?;
+        writeln!(writer)?;
+    }
+
+    let odometry = (1..poses.len()).map(|j| (j - 1, j));
+    for (i, j) in odometry.chain(loop_closures.iter().copied()) {
+        match format {
+            PoseGraphFormat::G2o => write!(writer, "EDGE_SE3:QUAT {} {}", i, j)?,
+            PoseGraphFormat::Toro => write!(writer, "EDGE3 {} {}", i, j)?,
+        }
+        write_pose(&mut writer, &(poses[i].inverse() * poses[j]), format)?;
+        write_identity_information(&mut writer)?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+fn write_pose<W: Write>(
+    writer: &mut W,
+    pose: &na::Isometry3<f64>,
+    format: PoseGraphFormat,
+) -> Result<()> {
+    let t = &pose.translation.vector;
+    write!(writer, " {} {} {}", t.x, t.y, t.z)?;
+
+    match format {
+        PoseGraphFormat::G2o => {
+            let q = &pose.rotation;
+            write!(writer, " {} {} {} {}", q.i, q.j, q.k, q.w)?;
+        }
+        PoseGraphFormat::Toro => {
+            let (roll, pitch, yaw) = pose.rotation.euler_angles();
+            write!(writer, " {} {} {}", roll, pitch, yaw)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Upper triangle of the 6x6 identity matrix, row by row.
+fn write_identity_information<W: Write>(writer: &mut W) -> Result<()> {
+    for row in 0..6 {
+        for col in row..6 {
+            write!(writer, " {}", if row == col { 1 } else { 0 })?;
+        }
+    }
+
+    Ok(())
+}
+
+impl GroundTruthData {
+    /// Write the ground-truth trajectory as a pose graph file.
+    pub fn write_pose_graph<P: AsRef<Path>>(
+        &self,
+        path: P,
+        loop_closures: &[(usize, usize)],
+        format: PoseGraphFormat,
+    ) -> Result<()> {
+        let poses = self
+            .records()?
+            .map(|record| record.map(|r| r.pose()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_pose_graph(&mut writer, &poses, loop_closures, format)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EuRoC;
+
+    fn poses() -> Result<Vec<na::Isometry3<f64>>> {
+        EuRoC::new("test_data")?
+            .ground_truth()?
+            .records()?
+            .map(|record| record.map(|r| r.pose()))
+            .collect()
+    }
+
+    #[test]
+    fn g2o() -> Result<()> {
+        let mut buf = Vec::new();
+        write_pose_graph(&mut buf, &poses()?, &[(0, 4)], PoseGraphFormat::G2o)?;
+        let text = String::from_utf8(buf).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines.len(), 5 + 4 + 1);
+        assert!(lines[0].starts_with("VERTEX_SE3:QUAT 0 4.688319 -1.786938 0.783338 "));
+        assert!(lines[5].starts_with("EDGE_SE3:QUAT 0 1 "));
+        assert!(lines[9].starts_with("EDGE_SE3:QUAT 0 4 "));
+        assert_eq!(lines[9].split(' ').count(), 3 + 7 + 21);
+
+        Ok(())
+    }
+
+    #[test]
+    fn toro() -> Result<()> {
+        let mut buf = Vec::new();
+        write_pose_graph(&mut buf, &poses()?, &[], PoseGraphFormat::Toro)?;
+        let text = String::from_utf8(buf).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines.len(), 5 + 4);
+        assert_eq!(lines[0].split(' ').count(), 2 + 6);
+        assert!(lines[5].starts_with("EDGE3 0 1 "));
+        assert_eq!(lines[5].split(' ').count(), 3 + 6 + 21);
+
+        Ok(())
+    }
+
+    #[test]
+    fn loop_closure_out_of_range() -> Result<()> {
+        let result = write_pose_graph(Vec::new(), &poses()?, &[(0, 5)], PoseGraphFormat::G2o);
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
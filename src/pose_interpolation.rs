@@ -0,0 +1,170 @@
+use nalgebra as na;
+
+use crate::{
+    interpolation::bracket, CameraRecords, GroundTruthData, GroundTruthRecord, Result, Timestamp,
+};
+
+/// Behaviour of [`GroundTruthInterpolator::pose_at`] outside the ground truth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extrapolation {
+    /// Return `None`.
+    None,
+    /// Return the first or last pose.
+    Clamp,
+    /// Continue the motion of the first or last segment.
+    Linear,
+}
+
+/// Answers ground-truth poses at arbitrary timestamps.
+#[derive(Debug, Clone)]
+pub struct GroundTruthInterpolator {
+    timestamps: Vec<Timestamp>,
+    poses: Vec<na::Isometry3<f64>>,
+    extrapolation: Extrapolation,
+}
+
+impl GroundTruthInterpolator {
+    /// `ground_truth` must be sorted by timestamp.
+    pub fn new(ground_truth: &[GroundTruthRecord], extrapolation: Extrapolation) -> Self {
+        Self {
+            timestamps: ground_truth.iter().map(|r| r.timestamp).collect(),
+            poses: ground_truth.iter().map(GroundTruthRecord::pose).collect(),
+            extrapolation,
+        }
+    }
+
+    /// Return the pose at `ts`, interpolating position linearly and orientation by SLERP.
+    pub fn pose_at(&self, ts: Timestamp) -> Option<na::Isometry3<f64>> {
+        if let Some((i, j, alpha)) = bracket(&self.timestamps, ts) {
+            return Some(interpolate(&self.poses[i], &self.poses[j], alpha));
+        }
+
+        let n = self.timestamps.len();
+        let before = ts < *self.timestamps.first()?;
+        match self.extrapolation {
+            Extrapolation::None => None,
+            Extrapolation::Clamp => Some(self.poses[if before { 0 } else { n - 1 }]),
+            Extrapolation::Linear if n < 2 => Some(self.poses[0]),
+            Extrapolation::Linear => {
+                let (i, j) = if before { (0, 1) } else { (n - 2, n - 1) };
+                let span = (self.timestamps[j] - self.timestamps[i]).nsecs() as f64;
+                let alpha = (ts - self.timestamps[i]).nsecs() as f64 / span;
+                Some(interpolate(&self.poses[i], &self.poses[j], alpha))
+            }
+        }
+    }
+}
+
+/// Interpolate between `a` (`alpha = 0`) and `b` (`alpha = 1`); `alpha` may lie outside `[0, 1]`.
+fn interpolate(a: &na::Isometry3<f64>, b: &na::Isometry3<f64>, alpha: f64) -> na::Isometry3<f64> {
+    let translation = a.translation.vector.lerp(&b.translation.vector, alpha);
+    let rotation = a.rotation * (a.rotation.inverse() * b.rotation).powf(alpha);
+
+    na::Isometry3::from_parts(translation.into(), rotation)
+}
+
+impl GroundTruthData {
+    /// Build a [`GroundTruthInterpolator`] from the estimated poses.
+    pub fn interpolator(&self, extrapolation: Extrapolation) -> Result<GroundTruthInterpolator> {
+        let records = self.records()?.collect::<Result<Vec<_>>>()?;
+        Ok(GroundTruthInterpolator::new(&records, extrapolation))
+    }
+
+    /// Return the pose `T_WC = T_WB · T_BC` of `camera` at each of its frames.
+    ///
+    /// Body poses are interpolated at the image timestamps; frames before or
+    /// after the ground truth are left out.
+    pub fn camera_poses(
+        &self,
+        camera: &CameraRecords,
+    ) -> Result<Vec<(Timestamp, na::Isometry3<f64>)>> {
+        let interpolator = self.interpolator(Extrapolation::None)?;
+        let t_bc = camera.extrinsics_isometry()?;
+
+        camera
+            .entries()?
+            .filter_map(|entry| match entry {
+                Ok(entry) => interpolator
+                    .pose_at(entry.timestamp)
+                    .map(|t_wb| Ok((entry.timestamp, t_wb * t_bc))),
+                Err(e) => Some(Err(e)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EuRoC, MemorySource};
+
+    fn assert_pose(pose: na::Isometry3<f64>, x: f64, yaw: f64) {
+        assert!((pose.translation.vector.x - x).abs() < 1e-9);
+        assert!((pose.rotation.euler_angles().2 - yaw).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pose_at() {
+        let gt = [
+            GroundTruthRecord::from_pose(100.into(), na::Isometry3::identity()),
+            GroundTruthRecord::from_pose(
+                200.into(),
+                na::Isometry3::new(na::Vector3::x(), na::Vector3::z() * 0.4),
+            ),
+        ];
+
+        let interpolator = GroundTruthInterpolator::new(&gt, Extrapolation::None);
+        assert_pose(interpolator.pose_at(125.into()).unwrap(), 0.25, 0.1);
+        assert!(interpolator.pose_at(250.into()).is_none());
+
+        let interpolator = GroundTruthInterpolator::new(&gt, Extrapolation::Clamp);
+        assert_pose(interpolator.pose_at(50.into()).unwrap(), 0.0, 0.0);
+        assert_pose(interpolator.pose_at(250.into()).unwrap(), 1.0, 0.4);
+
+        let interpolator = GroundTruthInterpolator::new(&gt, Extrapolation::Linear);
+        assert_pose(interpolator.pose_at(50.into()).unwrap(), -0.5, -0.2);
+        assert_pose(interpolator.pose_at(250.into()).unwrap(), 1.5, 0.6);
+    }
+
+    #[test]
+    fn camera_poses() -> Result<()> {
+        // the test_data ground truth starts after the frames, so replace it
+        let data = EuRoC::with_ground_truth(
+            MemorySource::from_dir("test_data")?,
+            &[
+                GroundTruthRecord::from_pose(1403636579800000000.into(), na::Isometry3::identity()),
+                GroundTruthRecord::from_pose(
+                    1403636579900000000.into(),
+                    na::Isometry3::translation(1.0, 0.0, 0.0),
+                ),
+            ],
+        )?;
+        let camera = data.left_camera()?;
+        let t_bc = camera.extrinsics_isometry()?;
+
+        let poses = data.ground_truth()?.camera_poses(&camera)?;
+        assert_eq!(poses.len(), 2);
+        assert_eq!(poses[0].0, 1403636579813555456.into());
+        let x = 0.13555456;
+        assert!(
+            (poses[0].1.translation.vector - (t_bc.translation.vector + na::Vector3::x() * x))
+                .norm()
+                < 1e-9
+        );
+        assert!(poses[0].1.rotation.angle_to(&t_bc.rotation) < 1e-12);
+
+        Ok(())
+    }
+
+    #[test]
+    fn interpolator() -> Result<()> {
+        let gt = EuRoC::new("test_data")?.ground_truth()?;
+        let records = gt.records()?.collect::<Result<Vec<_>>>()?;
+        let interpolator = gt.interpolator(Extrapolation::None)?;
+
+        let pose = interpolator.pose_at(records[1].timestamp).unwrap();
+        assert!((pose.translation.vector - records[1].position).norm() < 1e-12);
+
+        Ok(())
+    }
+}
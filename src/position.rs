@@ -1,52 +1,130 @@
-use std::{fs::File, path::PathBuf};
+use std::{path::PathBuf, sync::Arc};
 
-use anyhow::{ensure, Result};
 use nalgebra as na;
-use yaml_rust::Yaml;
 
-use crate::{load_yaml, Timestamp};
+use crate::{
+    csv_row::{csv_error, Checkpoint, IndexCache, IndexedRows, Row},
+    yaml::SensorYaml,
+    DataSource, ErrorPolicy, FileSystem, Result, Timestamp, Timestamped,
+};
 
 const DATA_CSV: &str = "data.csv";
 const SENSOR_YAML: &str = "sensor.yaml";
+/// timestamp and xyz
+const BASE_COLUMNS: usize = 4;
 
 #[derive(Debug, Clone)]
 pub struct PositionData {
+    source: Arc<dyn DataSource>,
     path: PathBuf,
+    index: IndexCache,
+    on_error: ErrorPolicy,
 }
 
 impl PositionData {
     pub fn new(path: PathBuf) -> Result<Self> {
-        ensure!(path.is_dir());
-        ensure!(path.join(DATA_CSV).is_file());
-        ensure!(path.join(SENSOR_YAML).is_file());
+        Self::with_source(FileSystem::shared(), path)
+    }
+
+    /// Open the folder `path` of `source`.
+    pub fn with_source(source: Arc<dyn DataSource>, path: PathBuf) -> Result<Self> {
+        source.require_dir(&path)?;
+        source.require_file(&path.join(DATA_CSV))?;
+        source.require_file(&path.join(SENSOR_YAML))?;
+
+        Ok(Self {
+            on_error: ErrorPolicy::default(),
+            index: IndexCache::new(Arc::clone(&source)),
+            source,
+            path,
+        })
+    }
 
-        Ok(Self { path })
+    /// Handle bad records in `load_all` with `policy`.
+    ///
+    /// The accessors of [`EuRoC`](crate::EuRoC) start from
+    /// [`Config::on_error`](crate::Config::on_error), [`Self::new`] from
+    /// [`ErrorPolicy::Strict`].
+    pub const fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.on_error = policy;
+        self
+    }
+
+    /// Return the policy `load_all` handles bad records with.
+    pub const fn error_policy(&self) -> ErrorPolicy {
+        self.on_error
     }
 
     #[inline]
-    fn read_sensor_yaml(&self) -> Result<Vec<Yaml>> {
-        load_yaml(self.path.join(SENSOR_YAML))
+    fn read_sensor_yaml(&self) -> Result<SensorYaml> {
+        SensorYaml::load(&*self.source, self.path.join(SENSOR_YAML))
     }
 
     /// Return extrinsics wrt. the body-frame.
     pub fn extrinsics(&self) -> Result<na::Matrix4<f64>> {
-        let data: Vec<_> = self.read_sensor_yaml()?[0]["T_BS"]["data"]
-            .as_vec()
-            .unwrap()
-            .iter()
-            .map(|v| v.as_f64().unwrap())
-            .collect();
+        self.read_sensor_yaml()?.matrix4("T_BS")
+    }
 
-        assert!(data.len() == 16);
+    /// Return extrinsics wrt. the body-frame as a rigid body transform.
+    ///
+    /// Fails if `T_BS` is not orthonormal. Use [`na::Isometry3::inverse`] for `T_SB`.
+    pub fn extrinsics_isometry(&self) -> Result<na::Isometry3<f64>> {
+        self.read_sensor_yaml()?.isometry("T_BS")
+    }
 
-        Ok(na::Matrix4::from_row_slice(&data))
+    /// Return the number of records, counting the rows of `data.csv` on the first call.
+    pub fn len(&self) -> Result<usize> {
+        self.index.row_count(&self.path.join(DATA_CSV))
     }
 
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Iterate over records in `data.csv` order.
     pub fn records(&self) -> Result<PositionIterator> {
-        let f = File::open(self.path.join(DATA_CSV))?;
+        Ok(PositionIterator {
+            rows: self.index.rows(self.path.join(DATA_CSV))?,
+        })
+    }
+
+    /// Iterate over records with `start <= timestamp < end`.
+    ///
+    /// The first call indexes `data.csv`; later calls seek directly to `start`.
+    pub fn records_between(&self, start: Timestamp, end: Timestamp) -> Result<PositionIterator> {
+        Ok(PositionIterator {
+            rows: self
+                .index
+                .rows_between(self.path.join(DATA_CSV), start, end)?,
+        })
+    }
 
+    /// Continue an iteration where `checkpoint` was taken, without indexing `data.csv`.
+    pub fn resume(&self, checkpoint: &Checkpoint) -> Result<PositionIterator> {
         Ok(PositionIterator {
-            reader: csv::Reader::from_reader(f).into_records(),
+            rows: self.index.resume(self.path.join(DATA_CSV), checkpoint)?,
+        })
+    }
+
+    /// Return the headers of the columns after xyz, empty for the standard layout.
+    ///
+    /// Some ASL exports append e.g. covariance or quality columns.
+    pub fn extra_columns(&self) -> Result<Vec<String>> {
+        let path = self.path.join(DATA_CSV);
+        let mut reader = csv::Reader::from_reader(self.source.open(&path)?);
+        let headers = reader.headers().map_err(|e| csv_error(&path, e))?;
+
+        Ok(headers
+            .iter()
+            .skip(BASE_COLUMNS)
+            .map(|header| header.trim().to_owned())
+            .collect())
+    }
+
+    /// Iterate over records in `data.csv` order, including the values of [`Self::extra_columns`].
+    pub fn extended_records(&self) -> Result<ExtendedPositionIterator> {
+        Ok(ExtendedPositionIterator {
+            rows: self.index.rows(self.path.join(DATA_CSV))?,
         })
     }
 }
@@ -58,31 +136,108 @@ pub struct PositionRecord {
     pub position: na::Vector3<f64>,
 }
 
+impl Timestamped for PositionRecord {
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
 pub struct PositionIterator {
-    reader: csv::StringRecordsIntoIter<File>,
+    rows: IndexedRows,
+}
+
+impl PositionIterator {
+    /// Return where the iteration stopped, for [`PositionData::resume`].
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.rows.checkpoint()
+    }
+
+    fn parse(row: Result<Row>) -> Result<PositionRecord> {
+        Self::record(&row?)
+    }
+
+    fn record(row: &Row) -> Result<PositionRecord> {
+        Ok(PositionRecord {
+            timestamp: row.timestamp()?,
+            position: row.vector3(1)?,
+        })
+    }
 }
 
 impl Iterator for PositionIterator {
     type Item = Result<PositionRecord>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.reader.next().map(|row| {
-            let row = row?;
-            Ok(PositionRecord {
-                timestamp: row[0].parse::<u64>()?.into(),
-                position: na::Vector3::new(
-                    row[1].parse::<f64>()?,
-                    row[2].parse::<f64>()?,
-                    row[3].parse::<f64>()?,
-                ),
-            })
+        self.rows.next().map(Self::parse)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for PositionIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.rows.next_back().map(Self::parse)
+    }
+}
+
+impl ExactSizeIterator for PositionIterator {}
+
+/// [`PositionRecord`] with the columns following xyz.
+#[derive(Debug, Clone)]
+pub struct ExtendedPositionRecord {
+    pub record: PositionRecord,
+    /// values in the order of [`PositionData::extra_columns`]
+    pub extra: Vec<f64>,
+}
+
+impl Timestamped for ExtendedPositionRecord {
+    fn timestamp(&self) -> Timestamp {
+        self.record.timestamp
+    }
+}
+
+pub struct ExtendedPositionIterator {
+    rows: IndexedRows,
+}
+
+impl ExtendedPositionIterator {
+    fn parse(row: Result<Row>) -> Result<ExtendedPositionRecord> {
+        let row = row?;
+        Ok(ExtendedPositionRecord {
+            record: PositionIterator::record(&row)?,
+            extra: (BASE_COLUMNS..row.len())
+                .map(|i| row.parse(i))
+                .collect::<Result<_>>()?,
         })
     }
 }
 
+impl Iterator for ExtendedPositionIterator {
+    type Item = Result<ExtendedPositionRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rows.next().map(Self::parse)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for ExtendedPositionIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.rows.next_back().map(Self::parse)
+    }
+}
+
+impl ExactSizeIterator for ExtendedPositionIterator {}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::temp_dir::TempDir;
     use crate::EuRoC;
 
     #[test]
@@ -101,6 +256,20 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn extrinsics_isometry() -> Result<()> {
+        let data = EuRoC::new("test_data")?.position()?;
+        let isometry = data.extrinsics_isometry()?;
+
+        assert_eq!(isometry.rotation, na::UnitQuaternion::identity());
+        assert_eq!(
+            isometry.translation.vector,
+            na::Vector3::new(7.48903e-02, -1.84772e-02, -1.20209e-01)
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn records() -> Result<()> {
         let data = EuRoC::new("test_data")?.position()?;
@@ -115,4 +284,34 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn extended_records() -> Result<()> {
+        let data = EuRoC::new("test_data")?.position()?;
+        assert!(data.extra_columns()?.is_empty());
+        let record = data.extended_records()?.next().unwrap()?;
+        assert!(record.extra.is_empty());
+
+        let dir = TempDir::new("extended_leica0")?;
+        std::fs::copy("test_data/leica0/sensor.yaml", dir.join(SENSOR_YAML))?;
+        std::fs::write(
+            dir.join(DATA_CSV),
+            "#timestamp [ns],p_RS_R_x [m],p_RS_R_y [m],p_RS_R_z [m], sigma [m],quality\n\
+             1403636578922881280,4.78,-1.81,0.84,0.001,3\n\
+             1403636578968881408,4.77,-1.82,0.85,0.002,2\n",
+        )?;
+
+        let data = PositionData::new(dir.path().to_owned())?;
+        assert_eq!(data.extra_columns()?, vec!["sigma [m]", "quality"]);
+        let records = data.extended_records()?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            records[1].record.position,
+            na::Vector3::new(4.77, -1.82, 0.85)
+        );
+        assert_eq!(records[1].extra, vec![0.002, 2.0]);
+        // the plain records are unaffected
+        assert_eq!(data.records()?.count(), 2);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,449 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+    sync::{
+        atomic::{self, AtomicBool},
+        mpsc::{self, Receiver, Sender},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    CameraRecords, EuRoC, EurocError, ImageEntry, ImageEntryIterator, ImageRecord, Result, Sensor,
+};
+
+/// Urgency of a [`DecodePool`] request; higher ones are decoded first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// e.g. frames prefetched far ahead
+    Low,
+    Normal,
+    /// e.g. the frame a viewer seeks to, or its keyframes
+    High,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+struct Request {
+    priority: Priority,
+    /// order of submission, to decode requests of equal priority first come first served
+    seq: u64,
+    entry: ImageEntry,
+    result: Sender<Result<ImageRecord>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl PartialEq for Request {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Request {}
+
+impl PartialOrd for Request {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Request {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    requests: BinaryHeap<Request>,
+    next_seq: u64,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<Queue>,
+    available: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Background threads decoding images in order of [`Priority`].
+///
+/// Meant for interactive viewers: when the user seeks, drop the handles of
+/// frames no longer needed, or call [`DecodePool::cancel_pending`], and
+/// request the new frame with [`Priority::High`]. Cancelled requests are
+/// skipped unless a worker already started decoding them.
+pub struct DecodePool {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl DecodePool {
+    pub fn new(workers: usize) -> Result<Self> {
+        if workers == 0 {
+            return Err(EurocError::InvalidInput(
+                "workers must be positive".to_owned(),
+            ));
+        }
+
+        let shared = Arc::new(Shared::default());
+        let workers = (0..workers)
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || work(&shared))
+            })
+            .collect();
+
+        Ok(Self { shared, workers })
+    }
+
+    /// Queue `entry` for decoding.
+    pub fn request(&self, entry: ImageEntry, priority: Priority) -> DecodeHandle {
+        let (result, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let mut queue = self.shared.lock();
+        let seq = queue.next_seq;
+        queue.next_seq += 1;
+        queue.requests.push(Request {
+            priority,
+            seq,
+            entry,
+            result,
+            cancelled: Arc::clone(&cancelled),
+        });
+        drop(queue);
+        self.shared.available.notify_one();
+
+        DecodeHandle {
+            receiver,
+            cancelled,
+        }
+    }
+
+    /// Cancel every request no worker has started yet, e.g. after a seek.
+    pub fn cancel_pending(&self) {
+        self.shared.lock().requests.clear();
+    }
+
+    /// Return the number of requests no worker has started yet.
+    pub fn pending(&self) -> usize {
+        self.shared.lock().requests.len()
+    }
+}
+
+fn work(shared: &Shared) {
+    loop {
+        let mut queue = shared.lock();
+        let request = loop {
+            if queue.closed {
+                return;
+            }
+            match queue.requests.pop() {
+                Some(request) => break request,
+                None => {
+                    queue = shared
+                        .available
+                        .wait(queue)
+                        .unwrap_or_else(|e| e.into_inner())
+                }
+            }
+        };
+        drop(queue);
+
+        if !request.cancelled.load(atomic::Ordering::Relaxed) {
+            // the handle may be gone already
+            let _ = request.result.send(request.entry.load());
+        }
+    }
+}
+
+impl Drop for DecodePool {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.available.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Result of a [`DecodePool::request`]; dropping it cancels the request.
+pub struct DecodeHandle {
+    receiver: Receiver<Result<ImageRecord>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl DecodeHandle {
+    /// Return a handle already holding `result`.
+    fn ready(result: Result<ImageRecord>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let _ = sender.send(result);
+        Self {
+            receiver,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Block until the image is decoded.
+    ///
+    /// Fails if the request was cancelled through [`DecodePool::cancel_pending`]
+    /// or the pool was dropped before decoding it.
+    pub fn wait(self) -> Result<ImageRecord> {
+        self.receiver.recv().unwrap_or_else(|_| Err(cancelled()))
+    }
+
+    /// Return the image if it is decoded, without blocking.
+    pub fn try_get(&self) -> Option<Result<ImageRecord>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(cancelled())),
+        }
+    }
+}
+
+impl Drop for DecodeHandle {
+    fn drop(&mut self) {
+        self.cancelled.store(true, atomic::Ordering::Relaxed);
+    }
+}
+
+fn cancelled() -> EurocError {
+    EurocError::InvalidInput("decode request cancelled or decoding thread panicked".to_owned())
+}
+
+/// Iterator decoding images on background threads ahead of the consumer.
+///
+/// Created by [`CameraRecords::records_prefetched`] or
+/// [`EuRoC::records_prefetched`]. Records are yielded in
+/// `data.csv` order, whatever order the workers finish in.
+pub struct PrefetchIterator {
+    entries: ImageEntryIterator,
+    /// images in flight, oldest first
+    pending: VecDeque<DecodeHandle>,
+    pool: DecodePool,
+}
+
+impl PrefetchIterator {
+    fn new(entries: ImageEntryIterator, depth: usize, pool: DecodePool) -> Self {
+        let mut iter = Self {
+            entries,
+            pending: VecDeque::with_capacity(depth),
+            pool,
+        };
+        for _ in 0..depth {
+            iter.dispatch();
+        }
+        iter
+    }
+
+    /// Hand the next entry to the workers.
+    fn dispatch(&mut self) {
+        let handle = match self.entries.next() {
+            Some(Ok(entry)) => self.pool.request(entry, Priority::Normal),
+            Some(Err(e)) => DecodeHandle::ready(Err(e)),
+            None => return,
+        };
+        self.pending.push_back(handle);
+    }
+}
+
+impl Iterator for PrefetchIterator {
+    type Item = Result<ImageRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let handle = self.pending.pop_front()?;
+        self.dispatch();
+
+        Some(handle.wait())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.pending.len() + self.entries.len();
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for PrefetchIterator {}
+
+impl CameraRecords {
+    /// Iterate over records like [`CameraRecords::records`], decoding up to
+    /// `depth` images ahead on `workers` background threads.
+    ///
+    /// This overlaps PNG decoding with the processing of earlier frames. At
+    /// most `depth` decoded images are held at a time.
+    pub fn records_prefetched(&self, depth: usize, workers: usize) -> Result<PrefetchIterator> {
+        if depth == 0 || workers == 0 {
+            return Err(EurocError::InvalidInput(
+                "depth and workers must be positive".to_owned(),
+            ));
+        }
+
+        Ok(PrefetchIterator::new(
+            self.entries()?,
+            depth,
+            DecodePool::new(workers)?,
+        ))
+    }
+}
+
+impl EuRoC {
+    /// Iterate over the records of `camera` with [`CameraRecords::records_prefetched`],
+    /// using the [`Config::prefetch_depth`](crate::Config::prefetch_depth) and
+    /// [`Config::decode_workers`](crate::Config::decode_workers) of the dataset.
+    pub fn records_prefetched(&self, camera: Sensor) -> Result<PrefetchIterator> {
+        let config = self.config();
+        self.camera(camera)?
+            .records_prefetched(config.prefetch_depth, config.decode_workers)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Config, Timestamped};
+
+    #[test]
+    fn records_prefetched() -> Result<()> {
+        let camera = EuRoC::new("test_data")?.left_camera()?;
+        let expected = camera.records()?.collect::<Result<Vec<_>>>()?;
+
+        for &(depth, workers) in [(1, 1), (2, 3), (8, 2)].iter() {
+            let records = camera.records_prefetched(depth, workers)?;
+            assert_eq!(records.len(), expected.len());
+            let records = records.collect::<Result<Vec<_>>>()?;
+            assert_eq!(records.len(), expected.len());
+            for (record, expected) in records.iter().zip(&expected) {
+                assert_eq!(record.timestamp(), expected.timestamp());
+                assert_eq!(record.image.to_luma8(), expected.image.to_luma8());
+            }
+        }
+
+        // dropping early stops the workers
+        let mut records = camera.records_prefetched(2, 2)?;
+        assert!(records.next().is_some());
+        drop(records);
+
+        assert!(camera.records_prefetched(0, 1).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn records_prefetched_config() -> Result<()> {
+        let config = Config {
+            prefetch_depth: 3,
+            decode_workers: 1,
+            ..Config::default()
+        };
+        let data = EuRoC::new("test_data")?.with_config(config)?;
+        let records = data.records_prefetched(Sensor::Cam1)?;
+        assert_eq!(records.len(), 5);
+        assert_eq!(records.pending.len(), 3);
+        assert!(records.collect::<Result<Vec<_>>>().is_ok());
+
+        let data = data.with_config(Config {
+            decode_workers: 0,
+            ..Config::default()
+        })?;
+        assert!(data.records_prefetched(Sensor::Cam0).is_err());
+        assert!(data.records_prefetched(Sensor::Imu0).is_err());
+
+        Ok(())
+    }
+
+    /// Pool without workers, so that requests stay queued.
+    fn idle_pool() -> DecodePool {
+        DecodePool {
+            shared: Arc::new(Shared::default()),
+            workers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn priorities() -> Result<()> {
+        let entries = EuRoC::new("test_data")?
+            .left_camera()?
+            .entries()?
+            .collect::<Result<Vec<_>>>()?;
+        let pool = idle_pool();
+        let priorities = [
+            Priority::Normal,
+            Priority::Low,
+            Priority::High,
+            Priority::Normal,
+            Priority::High,
+        ];
+        let _handles: Vec<_> = entries
+            .iter()
+            .zip(&priorities)
+            .map(|(entry, &priority)| pool.request(entry.clone(), priority))
+            .collect();
+        assert_eq!(pool.pending(), 5);
+
+        let mut queue = pool.shared.lock();
+        let order: Vec<_> = std::iter::from_fn(|| queue.requests.pop())
+            .map(|request| request.entry.timestamp)
+            .collect();
+        let expected: Vec<_> = [2, 4, 0, 3, 1]
+            .iter()
+            .map(|&i| entries[i].timestamp)
+            .collect();
+        assert_eq!(order, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cancel() -> Result<()> {
+        let entries = EuRoC::new("test_data")?
+            .left_camera()?
+            .entries()?
+            .collect::<Result<Vec<_>>>()?;
+
+        let pool = idle_pool();
+        let dropped = pool.request(entries[0].clone(), Priority::Normal);
+        let kept = pool.request(entries[1].clone(), Priority::Normal);
+        drop(dropped);
+        let cancelled: Vec<_> = pool
+            .shared
+            .lock()
+            .requests
+            .iter()
+            .map(|r| {
+                (
+                    r.entry.timestamp,
+                    r.cancelled.load(atomic::Ordering::Relaxed),
+                )
+            })
+            .collect();
+        assert!(cancelled.contains(&(entries[0].timestamp, true)));
+        assert!(cancelled.contains(&(entries[1].timestamp, false)));
+        assert!(kept.try_get().is_none());
+
+        // a seek supersedes everything queued
+        pool.cancel_pending();
+        assert_eq!(pool.pending(), 0);
+        assert!(matches!(kept.try_get(), Some(Err(_))));
+        assert!(kept.wait().is_err());
+
+        let pool = DecodePool::new(2)?;
+        let seek = pool.request(entries[4].clone(), Priority::High);
+        assert_eq!(seek.wait()?.timestamp(), entries[4].timestamp);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+use nalgebra as na;
+
+use crate::{EurocError, ImuConfig, ImuData, ImuRecord, Result, Timestamp};
+
+/// Relative motion of the IMU between two timestamps, from gyroscope and accelerometer alone.
+///
+/// The deltas are expressed in the IMU frame at `start` and exclude gravity,
+/// following C. Forster et al., "On-Manifold Preintegration for Real-Time
+/// Visual-Inertial Odometry", 2017.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preintegration {
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub delta_rotation: na::UnitQuaternion<f64>,
+    /// (m/s)
+    pub delta_velocity: na::Vector3<f64>,
+    /// (m)
+    pub delta_position: na::Vector3<f64>,
+    /// covariance of the (rotation, velocity, position) errors
+    pub covariance: na::SMatrix<f64, 9, 9>,
+}
+
+impl Preintegration {
+    fn identity(start: Timestamp) -> Self {
+        Self {
+            start,
+            end: start,
+            delta_rotation: na::UnitQuaternion::identity(),
+            delta_velocity: na::Vector3::zeros(),
+            delta_position: na::Vector3::zeros(),
+            covariance: na::SMatrix::zeros(),
+        }
+    }
+
+    /// Integrate one measurement held constant for `dt` seconds.
+    fn step(
+        &mut self,
+        gyro: &na::Vector3<f64>,
+        accel: &na::Vector3<f64>,
+        dt: f64,
+        noise: &na::Vector2<f64>,
+    ) {
+        let rotation = self.delta_rotation.to_rotation_matrix().into_inner();
+        let phi = gyro * dt;
+        let increment = na::UnitQuaternion::from_scaled_axis(phi);
+        let right_jacobian = na::Matrix3::identity() - 0.5 * phi.cross_matrix();
+        let accel_skew = rotation * accel.cross_matrix();
+
+        let mut a = na::SMatrix::<f64, 9, 9>::identity();
+        a.fixed_slice_mut::<3, 3>(0, 0)
+            .copy_from(&increment.to_rotation_matrix().into_inner().transpose());
+        a.fixed_slice_mut::<3, 3>(3, 0)
+            .copy_from(&(-accel_skew * dt));
+        a.fixed_slice_mut::<3, 3>(6, 0)
+            .copy_from(&(-0.5 * accel_skew * dt * dt));
+        a.fixed_slice_mut::<3, 3>(6, 3)
+            .copy_from(&(na::Matrix3::identity() * dt));
+
+        let mut b = na::SMatrix::<f64, 9, 3>::zeros();
+        b.fixed_slice_mut::<3, 3>(0, 0)
+            .copy_from(&(right_jacobian * dt));
+        let mut c = na::SMatrix::<f64, 9, 3>::zeros();
+        c.fixed_slice_mut::<3, 3>(3, 0).copy_from(&(rotation * dt));
+        c.fixed_slice_mut::<3, 3>(6, 0)
+            .copy_from(&(0.5 * rotation * dt * dt));
+
+        // discrete-time variance of a sample held for `dt` is density^2 / dt
+        let (gyro_var, accel_var) = (noise.x * noise.x / dt, noise.y * noise.y / dt);
+        self.covariance = a * self.covariance * a.transpose()
+            + b * b.transpose() * gyro_var
+            + c * c.transpose() * accel_var;
+
+        self.delta_position += self.delta_velocity * dt + 0.5 * rotation * accel * dt * dt;
+        self.delta_velocity += rotation * accel * dt;
+        self.delta_rotation *= increment;
+    }
+}
+
+/// Preintegrates IMU measurements with the noise model of an [`ImuConfig`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImuPreintegrator {
+    /// (rad/s/√Hz)
+    pub gyro_noise_density: f64,
+    /// (m/s^2/√Hz)
+    pub accel_noise_density: f64,
+    /// subtracted from every gyroscope sample (rad/s)
+    pub gyro_bias: na::Vector3<f64>,
+    /// subtracted from every accelerometer sample (m/s^2)
+    pub accel_bias: na::Vector3<f64>,
+}
+
+impl ImuPreintegrator {
+    pub fn new(config: &ImuConfig) -> Self {
+        Self {
+            gyro_noise_density: config.gyro_noise_density,
+            accel_noise_density: config.accel_noise_density,
+            gyro_bias: na::Vector3::zeros(),
+            accel_bias: na::Vector3::zeros(),
+        }
+    }
+
+    /// Set the biases, e.g. from [`crate::GroundTruthRecord`].
+    pub const fn with_bias(
+        mut self,
+        gyro_bias: na::Vector3<f64>,
+        accel_bias: na::Vector3<f64>,
+    ) -> Self {
+        self.gyro_bias = gyro_bias;
+        self.accel_bias = accel_bias;
+        self
+    }
+
+    /// Preintegrate from `start` to `end`, typically two camera timestamps.
+    ///
+    /// `records` must be sorted by timestamp; it may extend beyond the window.
+    /// Each sample is held until the next one, and the first sample is also
+    /// used before its own timestamp.
+    pub fn integrate(
+        &self,
+        records: &[ImuRecord],
+        start: Timestamp,
+        end: Timestamp,
+    ) -> Result<Preintegration> {
+        if end <= start {
+            return Err(EurocError::InvalidInput(format!(
+                "empty preintegration window {:?}..{:?}",
+                start, end
+            )));
+        }
+
+        let noise = na::Vector2::new(self.gyro_noise_density, self.accel_noise_density);
+        let mut result = Preintegration::identity(start);
+        for (k, record) in records.iter().enumerate() {
+            let from = if k == 0 {
+                start
+            } else {
+                record.timestamp.max(start)
+            };
+            let to = records
+                .get(k + 1)
+                .map_or(end, |next| next.timestamp.min(end));
+            if to <= from {
+                continue;
+            }
+
+            result.step(
+                &(record.gyro - self.gyro_bias),
+                &(record.accel - self.accel_bias),
+                (to - from).as_secs_f64(),
+                &noise,
+            );
+            result.end = to;
+        }
+
+        if result.end != end {
+            return Err(EurocError::InvalidInput(
+                "no IMU records in preintegration window".to_owned(),
+            ));
+        }
+
+        Ok(result)
+    }
+}
+
+impl ImuData {
+    /// Return an [`ImuPreintegrator`] with the noise parameters of `sensor.yaml`.
+    pub fn preintegrator(&self) -> Result<ImuPreintegrator> {
+        Ok(ImuPreintegrator::new(&self.config()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Duration, EuRoC};
+
+    fn records(gyro: na::Vector3<f64>, accel: na::Vector3<f64>) -> Vec<ImuRecord> {
+        (0..=200)
+            .map(|i| ImuRecord {
+                timestamp: Timestamp::from(0) + Duration::from_millis(5 * i),
+                gyro,
+                accel,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn constant_acceleration() -> Result<()> {
+        let integrator = EuRoC::new("test_data")?.imu()?.preintegrator()?;
+        let accel = na::Vector3::new(1.0, 0.0, -2.0);
+        let records = records(na::Vector3::zeros(), accel);
+
+        let result = integrator.integrate(&records, 0.into(), 1_000_000_000.into())?;
+        assert!(result.delta_rotation.angle() < 1e-12);
+        assert!((result.delta_velocity - accel).norm() < 1e-9);
+        assert!((result.delta_position - 0.5 * accel).norm() < 1e-9);
+
+        // without rotation, the rotation error is a random walk of the gyroscope noise
+        let gyro_var = integrator.gyro_noise_density.powi(2);
+        assert!((result.covariance[(0, 0)] - gyro_var).abs() < 1e-12);
+        // the velocity error adds the rotation error coupled through the acceleration
+        let accel_var = integrator.accel_noise_density.powi(2);
+        assert!(result.covariance[(3, 3)] > accel_var);
+        assert!(result.covariance[(6, 6)] > 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn constant_rotation() -> Result<()> {
+        let integrator = EuRoC::new("test_data")?.imu()?.preintegrator()?;
+        let gyro = na::Vector3::new(0.0, 0.0, 0.3);
+        let records = records(gyro, na::Vector3::zeros());
+
+        let biased = integrator.with_bias(na::Vector3::new(0.0, 0.0, 0.1), na::Vector3::zeros());
+        let result = biased.integrate(&records, 2_000_000.into(), 502_000_000.into())?;
+        assert!(
+            (result.delta_rotation.scaled_axis() - na::Vector3::new(0.0, 0.0, 0.1)).norm() < 1e-9
+        );
+
+        assert!(integrator
+            .integrate(&[], 0.into(), 1_000_000.into())
+            .is_err());
+        assert!(integrator
+            .integrate(&records, 1_000_000.into(), 1_000_000.into())
+            .is_err());
+
+        Ok(())
+    }
+}
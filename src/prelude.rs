@@ -0,0 +1,7 @@
+//! Commonly used types, for `use euroc::prelude::*;`.
+
+pub use crate::{
+    CameraRecords, Duration, ErrorPolicy, EuRoC, EurocError, GroundTruthData, GroundTruthRecord,
+    ImageEntry, ImageRecord, ImuConfig, ImuData, ImuRecord, PositionData, PositionRecord, Result,
+    Sensor, SensorEvent, Timestamp, Timestamped, ViconData, ViconRecord,
+};
@@ -0,0 +1,220 @@
+use std::{f64::consts::PI, io::Write};
+
+use nalgebra as na;
+
+use crate::{EurocError, ImuData, Result};
+
+/// Estimate the one-sided power spectral density of `signal` with Welch's method.
+///
+/// The signal is split into Hann-windowed segments of `segment_len` samples
+/// (a power of two) with 50% overlap, whose periodograms are averaged.
+/// Return `(frequencies (Hz), density (unit^2/Hz))`.
+pub fn welch(signal: &[f64], sample_rate: f64, segment_len: usize) -> Result<(Vec<f64>, Vec<f64>)> {
+    if segment_len < 2 || !segment_len.is_power_of_two() {
+        return Err(EurocError::InvalidInput(
+            "segment length must be a power of two".to_owned(),
+        ));
+    }
+    if signal.len() < segment_len {
+        return Err(EurocError::InvalidInput(
+            "signal is shorter than one segment".to_owned(),
+        ));
+    }
+
+    let window: Vec<f64> = (0..segment_len)
+        .map(|i| 0.5 * (1.0 - (2.0 * PI * i as f64 / segment_len as f64).cos()))
+        .collect();
+    let scale = 1.0 / (sample_rate * window.iter().map(|w| w * w).sum::<f64>());
+
+    let bins = segment_len / 2 + 1;
+    let step = segment_len / 2;
+    let mut density = vec![0.0; bins];
+    let mut segments = 0;
+
+    for start in (0..=signal.len() - segment_len).step_by(step) {
+        let segment = &signal[start..start + segment_len];
+        let mean = segment.iter().sum::<f64>() / segment_len as f64;
+
+        let mut buf: Vec<_> = segment
+            .iter()
+            .zip(&window)
+            .map(|(x, w)| na::Complex::new((x - mean) * w, 0.0))
+            .collect();
+        fft(&mut buf);
+
+        for (k, d) in density.iter_mut().enumerate() {
+            let one_sided = if k == 0 || k == segment_len / 2 {
+                1.0
+            } else {
+                2.0
+            };
+            *d += one_sided * buf[k].norm_sqr() * scale;
+        }
+        segments += 1;
+    }
+
+    density.iter_mut().for_each(|d| *d /= segments as f64);
+    let frequencies = (0..bins)
+        .map(|k| k as f64 * sample_rate / segment_len as f64)
+        .collect();
+
+    Ok((frequencies, density))
+}
+
+/// In-place iterative radix-2 FFT. `buf.len()` must be a power of two.
+fn fft(buf: &mut [na::Complex<f64>]) {
+    let n = buf.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buf.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        let w_len = na::Complex::new(angle.cos(), angle.sin());
+        for start in (0..n).step_by(len) {
+            let mut w = na::Complex::new(1.0, 0.0);
+            for k in 0..len / 2 {
+                let u = buf[start + k];
+                let v = buf[start + k + len / 2] * w;
+                buf[start + k] = u + v;
+                buf[start + k + len / 2] = u - v;
+                w *= w_len;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+const CSV_HEADER: [&str; 7] = [
+    "frequency [Hz]",
+    "w_x [rad^2 s^-2 Hz^-1]",
+    "w_y [rad^2 s^-2 Hz^-1]",
+    "w_z [rad^2 s^-2 Hz^-1]",
+    "a_x [m^2 s^-4 Hz^-1]",
+    "a_y [m^2 s^-4 Hz^-1]",
+    "a_z [m^2 s^-4 Hz^-1]",
+];
+
+/// Per-axis power spectral density of an IMU stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImuPsd {
+    /// frequency of each bin (Hz)
+    pub frequencies: Vec<f64>,
+    /// gyroscope x, y, z density ((rad/s)^2/Hz)
+    pub gyro: [Vec<f64>; 3],
+    /// accelerometer x, y, z density ((m/s^2)^2/Hz)
+    pub accel: [Vec<f64>; 3],
+}
+
+impl ImuPsd {
+    /// Write one row per frequency bin as CSV.
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(CSV_HEADER.iter())?;
+
+        for (k, f) in self.frequencies.iter().enumerate() {
+            let row = [
+                *f,
+                self.gyro[0][k],
+                self.gyro[1][k],
+                self.gyro[2][k],
+                self.accel[0][k],
+                self.accel[1][k],
+                self.accel[2][k],
+            ];
+            writer.write_record(row.iter().map(ToString::to_string))?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl ImuData {
+    /// Estimate per-axis power spectral density with Welch's method at the nominal rate.
+    pub fn psd(&self, segment_len: usize) -> Result<ImuPsd> {
+        let records = self.records()?.collect::<Result<Vec<_>>>()?;
+        let rate = self.rate_hz()?;
+
+        let mut axes = (0..6)
+            .map(|axis| {
+                let signal: Vec<_> = records
+                    .iter()
+                    .map(|r| {
+                        if axis < 3 {
+                            r.gyro[axis]
+                        } else {
+                            r.accel[axis - 3]
+                        }
+                    })
+                    .collect();
+                welch(&signal, rate, segment_len)
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter();
+        let mut next = || axes.next().unwrap();
+
+        let (frequencies, gyro_x) = next();
+        Ok(ImuPsd {
+            frequencies,
+            gyro: [gyro_x, next().1, next().1],
+            accel: [next().1, next().1, next().1],
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EuRoC;
+
+    #[test]
+    fn welch_sine_peak() -> Result<()> {
+        let rate = 200.0;
+        let signal: Vec<_> = (0..1024)
+            .map(|i| (2.0 * PI * 50.0 * i as f64 / rate).sin())
+            .collect();
+        let (frequencies, density) = welch(&signal, rate, 64)?;
+
+        assert_eq!(frequencies.len(), 33);
+        let peak = (0..density.len())
+            .max_by(|&a, &b| density[a].partial_cmp(&density[b]).unwrap())
+            .unwrap();
+        assert_eq!(frequencies[peak], 50.0);
+
+        // Parseval: integrated density equals the signal variance (0.5)
+        let power: f64 = density.iter().sum::<f64>() * rate / 64.0;
+        assert!((power - 0.5).abs() < 0.05);
+
+        Ok(())
+    }
+
+    #[test]
+    fn welch_invalid_segment() {
+        assert!(welch(&[0.0; 10], 200.0, 3).is_err());
+        assert!(welch(&[0.0; 10], 200.0, 16).is_err());
+    }
+
+    #[test]
+    fn imu_psd() -> Result<()> {
+        let psd = EuRoC::new("test_data")?.imu()?.psd(4)?;
+        assert_eq!(psd.frequencies, vec![0.0, 50.0, 100.0]);
+
+        let mut buf = Vec::new();
+        psd.write_csv(&mut buf)?;
+        assert_eq!(String::from_utf8(buf).unwrap().lines().count(), 1 + 3);
+
+        Ok(())
+    }
+}
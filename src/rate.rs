@@ -0,0 +1,158 @@
+use crate::{Duration, Timestamp};
+
+/// Sample interval statistics of a timestamp sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateEstimate {
+    /// number of intervals the estimate is based on
+    pub intervals: usize,
+    pub mean_interval: Duration,
+    pub median_interval: Duration,
+    /// standard deviation of the intervals
+    pub jitter: Duration,
+}
+
+impl RateEstimate {
+    /// Return rate (Hz) from the mean interval
+    pub fn mean_hz(&self) -> f64 {
+        1.0 / self.mean_interval.as_secs_f64()
+    }
+
+    /// Return rate (Hz) from the median interval, robust to dropped samples
+    pub fn median_hz(&self) -> f64 {
+        1.0 / self.median_interval.as_secs_f64()
+    }
+}
+
+/// Estimate the sample rate of `timestamps`.
+///
+/// Return `None` if fewer than two timestamps are given.
+pub fn estimate_rate<I: IntoIterator<Item = Timestamp>>(timestamps: I) -> Option<RateEstimate> {
+    let timestamps: Vec<_> = timestamps.into_iter().collect();
+    let mut intervals: Vec<i64> = timestamps
+        .windows(2)
+        .map(|w| (w[1] - w[0]).nsecs())
+        .collect();
+
+    if intervals.is_empty() {
+        return None;
+    }
+
+    let n = intervals.len() as f64;
+    let mean = intervals.iter().map(|&v| v as f64).sum::<f64>() / n;
+    let var = intervals
+        .iter()
+        .map(|&v| (v as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n;
+
+    intervals.sort_unstable();
+    let len = intervals.len();
+    let median = (intervals[(len - 1) / 2] + intervals[len / 2]) / 2;
+
+    Some(RateEstimate {
+        intervals: intervals.len(),
+        mean_interval: Duration::from_nsecs(mean.round() as i64),
+        median_interval: Duration::from_nsecs(median),
+        jitter: Duration::from_nsecs(var.sqrt().round() as i64),
+    })
+}
+
+/// Comparison of actual sample intervals against a nominal rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateDiagnostics {
+    /// rate declared by the sensor (Hz)
+    pub nominal_hz: f64,
+    pub estimate: RateEstimate,
+    /// median interval minus nominal interval, i.e. systematic offset
+    pub offset: Duration,
+    /// actual duration of the stream minus the duration expected at the nominal rate
+    pub drift: Duration,
+    /// number of intervals deviating from the nominal interval by more than half a period
+    pub irregular: usize,
+}
+
+impl RateDiagnostics {
+    /// Return `None` if fewer than two timestamps are given or `nominal_hz`
+    /// is not a positive rate.
+    pub fn new(nominal_hz: f64, timestamps: &[Timestamp]) -> Option<Self> {
+        if !(nominal_hz.is_finite() && nominal_hz > 0.0) {
+            return None;
+        }
+        let estimate = estimate_rate(timestamps.iter().copied())?;
+        let nominal = Duration::from_secs_f64(1.0 / nominal_hz);
+        if nominal <= Duration::ZERO {
+            return None;
+        }
+        let expected = nominal.nsecs().checked_mul(estimate.intervals as i64)?;
+
+        let elapsed = *timestamps.last().unwrap() - timestamps[0];
+        let irregular = timestamps
+            .windows(2)
+            .filter(|w| ((w[1] - w[0]) - nominal).abs().nsecs() * 2 > nominal.nsecs())
+            .count();
+
+        Some(Self {
+            nominal_hz,
+            estimate,
+            offset: estimate.median_interval - nominal,
+            drift: elapsed - Duration::from_nsecs(expected),
+            irregular,
+        })
+    }
+
+    /// Return whether the median rate is within `tolerance` (relative) of the nominal rate.
+    pub fn is_consistent(&self, tolerance: f64) -> bool {
+        (self.estimate.median_hz() - self.nominal_hz).abs() <= tolerance * self.nominal_hz
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EuRoC, Result};
+
+    #[test]
+    fn estimate_rate_imu() -> Result<()> {
+        let timestamps = EuRoC::new("test_data")?
+            .imu()?
+            .records()?
+            .map(|r| r.map(|r| r.timestamp))
+            .collect::<Result<Vec<_>>>()?;
+        let rate = estimate_rate(timestamps).unwrap();
+
+        assert_eq!(rate.intervals, 4);
+        assert!((rate.mean_hz() - 200.0).abs() < 0.1);
+        assert!((rate.median_hz() - 200.0).abs() < 0.1);
+        assert!(rate.jitter < Duration::from_nsecs(1000));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rate_diagnostics_dropped_frame() {
+        let timestamps: Vec<Timestamp> = [0, 50, 100, 200, 250]
+            .iter()
+            .map(|&ms| (ms * 1_000_000).into())
+            .collect();
+        let diagnostics = RateDiagnostics::new(20.0, &timestamps).unwrap();
+
+        assert_eq!(diagnostics.offset, Duration::ZERO);
+        assert_eq!(diagnostics.drift, Duration::from_millis(50));
+        assert_eq!(diagnostics.irregular, 1);
+        assert!(diagnostics.is_consistent(0.01));
+    }
+
+    #[test]
+    fn rate_diagnostics_invalid_nominal() {
+        let timestamps: Vec<Timestamp> = (0..5).map(|i| (i * 50_000_000).into()).collect();
+        assert_eq!(RateDiagnostics::new(0.0, &timestamps), None);
+        assert_eq!(RateDiagnostics::new(-20.0, &timestamps), None);
+        assert_eq!(RateDiagnostics::new(f64::NAN, &timestamps), None);
+        assert_eq!(RateDiagnostics::new(1e-12, &timestamps), None);
+    }
+
+    #[test]
+    fn estimate_rate_too_short() {
+        assert_eq!(estimate_rate(vec![Timestamp::from(0)]), None);
+    }
+}
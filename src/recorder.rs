@@ -0,0 +1,158 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use nalgebra as na;
+
+use crate::{csv_row::CsvRows, Duration, Result, Timestamp, Timestamped};
+
+const CSV_HEADER: [&str; 10] = [
+    "#timestamp [ns]",
+    "p_x [m]",
+    "p_y [m]",
+    "p_z [m]",
+    "q_w []",
+    "q_x []",
+    "q_y []",
+    "q_z []",
+    "processing_time [ns]",
+    "landmarks []",
+];
+
+/// Output of an estimator for one input frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    /// timestamp of the input frame
+    pub timestamp: Timestamp,
+    /// estimated body pose wrt. the estimator's world frame
+    pub pose: na::Isometry3<f64>,
+    pub processing_time: Duration,
+    /// number of tracked landmarks
+    pub landmarks: usize,
+}
+
+impl Timestamped for RunRecord {
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+/// Writes [`RunRecord`]s as CSV, one row per frame.
+pub struct RunRecorder<W: Write> {
+    writer: csv::Writer<W>,
+}
+
+impl RunRecorder<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> RunRecorder<W> {
+    pub fn new(writer: W) -> Result<Self> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(CSV_HEADER.iter())?;
+
+        Ok(Self { writer })
+    }
+
+    pub fn record(&mut self, record: &RunRecord) -> Result<()> {
+        let t = &record.pose.translation.vector;
+        let q = &record.pose.rotation;
+
+        self.writer.write_record(&[
+            record.timestamp.nsecs().to_string(),
+            t.x.to_string(),
+            t.y.to_string(),
+            t.z.to_string(),
+            q.w.to_string(),
+            q.i.to_string(),
+            q.j.to_string(),
+            q.k.to_string(),
+            record.processing_time.nsecs().to_string(),
+            record.landmarks.to_string(),
+        ])?;
+
+        Ok(())
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn finish(self) -> Result<W> {
+        self.writer.into_inner().map_err(|e| e.into_error().into())
+    }
+}
+
+/// Read a file written by [`RunRecorder`].
+pub fn read_run<R: Read>(reader: R) -> Result<Vec<RunRecord>> {
+    CsvRows::from_reader(PathBuf::from("<run>"), reader)
+        .map(|row| {
+            let row = row?;
+            if row.len() != CSV_HEADER.len() {
+                return Err(row.error("unexpected number of columns"));
+            }
+
+            let t = row.vector3(1)?;
+            Ok(RunRecord {
+                timestamp: row.timestamp()?,
+                pose: na::Isometry3::from_parts(
+                    na::Translation3::from(t),
+                    na::UnitQuaternion::from_quaternion(row.quaternion(4)?),
+                ),
+                processing_time: Duration::from_nsecs(row.parse(8)?),
+                landmarks: row.parse(9)?,
+            })
+        })
+        .collect()
+}
+
+/// Return `(timestamp, position)` pairs, e.g. for [`crate::evaluate_positions`].
+pub fn run_positions(records: &[RunRecord]) -> Vec<(Timestamp, na::Vector3<f64>)> {
+    records
+        .iter()
+        .map(|r| (r.timestamp, r.pose.translation.vector))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let records = vec![
+            RunRecord {
+                timestamp: 1403636579763555584.into(),
+                pose: na::Isometry3::new(
+                    na::Vector3::new(1.0, 2.0, 3.0),
+                    na::Vector3::new(0.0, 0.5, 0.0),
+                ),
+                processing_time: Duration::from_millis(12),
+                landmarks: 150,
+            },
+            RunRecord {
+                timestamp: 1403636579813555456.into(),
+                pose: na::Isometry3::identity(),
+                processing_time: Duration::from_millis(9),
+                landmarks: 148,
+            },
+        ];
+
+        let mut recorder = RunRecorder::new(Vec::new())?;
+        for r in &records {
+            recorder.record(r)?;
+        }
+        let buf = recorder.finish()?;
+        let read = read_run(buf.as_slice())?;
+
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[1], records[1]);
+        assert_eq!(read[0].timestamp, records[0].timestamp);
+        assert_eq!(read[0].landmarks, 150);
+        assert!((read[0].pose.rotation.angle() - 0.5).abs() < 1e-12);
+        assert_eq!(run_positions(&read)[0].1, na::Vector3::new(1.0, 2.0, 3.0));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{Duration, Result, Timestamp, Timestamped};
+
+/// Record paired with its time since the sequence start.
+#[derive(Debug, Clone)]
+pub struct Relative<T> {
+    pub time: Duration,
+    pub record: T,
+}
+
+/// Clock reporting time relative to a fixed epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelativeTime {
+    epoch: Timestamp,
+}
+
+impl RelativeTime {
+    pub const fn new(epoch: Timestamp) -> Self {
+        Self { epoch }
+    }
+
+    /// Return the absolute timestamp relative times are measured from.
+    pub const fn epoch(&self) -> Timestamp {
+        self.epoch
+    }
+
+    /// Return time elapsed since the epoch.
+    pub fn of(&self, timestamp: Timestamp) -> Duration {
+        timestamp - self.epoch
+    }
+
+    /// Return absolute timestamp of a relative time.
+    pub fn absolute(&self, time: Duration) -> Timestamp {
+        self.epoch + time
+    }
+
+    /// Wrap a record iterator so that every record reports its relative time.
+    pub fn records<I, T>(&self, records: I) -> RelativeIterator<I::IntoIter>
+    where
+        I: IntoIterator<Item = Result<T>>,
+        T: Timestamped,
+    {
+        RelativeIterator {
+            inner: records.into_iter(),
+            clock: *self,
+        }
+    }
+}
+
+pub struct RelativeIterator<I> {
+    inner: I,
+    clock: RelativeTime,
+}
+
+impl<I, T> Iterator for RelativeIterator<I>
+where
+    I: Iterator<Item = Result<T>>,
+    T: Timestamped,
+{
+    type Item = Result<Relative<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|record| {
+            let record = record?;
+            Ok(Relative {
+                time: self.clock.of(record.timestamp()),
+                record,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::Path, sync::Arc};
+
+    use super::*;
+    use crate::{EuRoC, EurocError, MemorySource};
+
+    #[test]
+    fn epoch() -> Result<()> {
+        let clock = EuRoC::new("test_data")?.relative_time()?;
+        assert_eq!(clock.epoch(), 1403636578922881280.into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn epoch_reports_broken_sensor() -> Result<()> {
+        // the IMU is complete, the Leica lacks its data.csv
+        let mut source = MemorySource::new();
+        for file in &["imu0/data.csv", "imu0/sensor.yaml", "leica0/sensor.yaml"] {
+            source.insert(file, std::fs::read(Path::new("test_data").join(file))?);
+        }
+        let data = EuRoC::from_source(Arc::new(source), "")?;
+        assert!(matches!(data.epoch(), Err(EurocError::MissingFile(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn records() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let clock = data.relative_time()?;
+        let first = clock.records(data.imu()?.records()?).next().unwrap()?;
+
+        assert_eq!(first.time, Duration::from_nsecs(835674112));
+        assert_eq!(clock.absolute(first.time), first.record.timestamp);
+
+        Ok(())
+    }
+}
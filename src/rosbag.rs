@@ -0,0 +1,1062 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use image::{
+    codecs::png::{CompressionType, FilterType, PngEncoder},
+    ColorType,
+};
+use nalgebra as na;
+
+use crate::{
+    source::FileTree, DataSource, EuRoC, EurocError, ReadSeek, RecordSink, Result, Sensor,
+    SensorEvent, Timestamp, Timestamped,
+};
+
+const MAGIC: &[u8] = b"#ROSBAG V2.0\n";
+const OP_MESSAGE_DATA: u8 = 0x02;
+const OP_BAG_HEADER: u8 = 0x03;
+const OP_INDEX_DATA: u8 = 0x04;
+const OP_CHUNK: u8 = 0x05;
+const OP_CHUNK_INFO: u8 = 0x06;
+const OP_CONNECTION: u8 = 0x07;
+/// size of the bag header record, padded so it can be rewritten in place
+const BAG_HEADER_LEN: usize = 4096;
+/// uncompressed size above which a chunk is closed, as `rosbag record` does
+const CHUNK_SIZE: usize = 768 * 1024;
+
+const HEADER_DEFINITION: &str = "uint32 seq\ntime stamp\nstring frame_id\n";
+const POINT_DEFINITION: &str = "float64 x\nfloat64 y\nfloat64 z\n";
+const QUATERNION_DEFINITION: &str = "float64 x\nfloat64 y\nfloat64 z\nfloat64 w\n";
+const VECTOR3_DEFINITION: &str = "float64 x\nfloat64 y\nfloat64 z\n";
+const SEPARATOR: &str =
+    "================================================================================\n";
+
+/// ROS message type of a topic.
+struct MessageType {
+    name: &'static str,
+    md5sum: &'static str,
+    definition: String,
+}
+
+/// Append the definitions of the types used by a message.
+fn definition(main: &str, dependencies: &[(&str, &str)]) -> String {
+    let mut text = main.to_owned();
+    for (name, def) in dependencies {
+        text.push_str(SEPARATOR);
+        text.push_str(&format!("MSG: {}\n", name));
+        text.push_str(def);
+    }
+    text
+}
+
+/// Return the topic and message type of `sensor`, following the official bags.
+fn topic(sensor: Sensor) -> (&'static str, MessageType) {
+    let header = ("std_msgs/Header", HEADER_DEFINITION);
+    let point = ("geometry_msgs/Point", POINT_DEFINITION);
+    let quaternion = ("geometry_msgs/Quaternion", QUATERNION_DEFINITION);
+    let vector3 = ("geometry_msgs/Vector3", VECTOR3_DEFINITION);
+    match sensor {
+        Sensor::Cam0 | Sensor::Cam1 => (
+            if sensor == Sensor::Cam0 {
+                "/cam0/image_raw"
+            } else {
+                "/cam1/image_raw"
+            },
+            MessageType {
+                name: "sensor_msgs/Image",
+                md5sum: "060021388200f6f0f447d0fcd9c64743",
+                definition: definition(
+                    "Header header\nuint32 height\nuint32 width\nstring encoding\n\
+                     uint8 is_bigendian\nuint32 step\nuint8[] data\n",
+                    &[header],
+                ),
+            },
+        ),
+        Sensor::Imu0 => (
+            "/imu0",
+            MessageType {
+                name: "sensor_msgs/Imu",
+                md5sum: "6a62c6daae103f4ff57a132d6f95cec2",
+                definition: definition(
+                    "Header header\ngeometry_msgs/Quaternion orientation\n\
+                     float64[9] orientation_covariance\ngeometry_msgs/Vector3 angular_velocity\n\
+                     float64[9] angular_velocity_covariance\n\
+                     geometry_msgs/Vector3 linear_acceleration\n\
+                     float64[9] linear_acceleration_covariance\n",
+                    &[header, quaternion, vector3],
+                ),
+            },
+        ),
+        Sensor::Leica0 => (
+            "/leica/position",
+            MessageType {
+                name: "geometry_msgs/PointStamped",
+                md5sum: "c63aecb41bfdfd6b7e1fac37c7cbe7bf",
+                definition: definition("Header header\nPoint point\n", &[header, point]),
+            },
+        ),
+        Sensor::Vicon0 => (
+            "/vicon/firefly_sbx/firefly_sbx",
+            MessageType {
+                name: "geometry_msgs/TransformStamped",
+                md5sum: "b5764a33bfeb3588febc2682852579b0",
+                definition: definition(
+                    "Header header\nstring child_frame_id\nTransform transform\n",
+                    &[
+                        header,
+                        (
+                            "geometry_msgs/Transform",
+                            "Vector3 translation\nQuaternion rotation\n",
+                        ),
+                        vector3,
+                        quaternion,
+                    ],
+                ),
+            },
+        ),
+        Sensor::GroundTruth => (
+            "/state_groundtruth_estimate0/pose",
+            MessageType {
+                name: "geometry_msgs/PoseStamped",
+                md5sum: "d3812c3cbc69362b77dc0b19b345f8f5",
+                definition: definition(
+                    "Header header\nPose pose\n",
+                    &[
+                        header,
+                        (
+                            "geometry_msgs/Pose",
+                            "Point position\nQuaternion orientation\n",
+                        ),
+                        point,
+                        quaternion,
+                    ],
+                ),
+            },
+        ),
+    }
+}
+
+/// ROS 1 serialization of a message.
+#[derive(Default)]
+struct Serializer(Vec<u8>);
+
+impl Serializer {
+    fn u8(&mut self, v: u8) -> &mut Self {
+        self.0.push(v);
+        self
+    }
+
+    fn u32(&mut self, v: u32) -> &mut Self {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    fn f64s(&mut self, values: &[f64]) -> &mut Self {
+        for v in values {
+            self.0.extend_from_slice(&v.to_le_bytes());
+        }
+        self
+    }
+
+    fn bytes(&mut self, data: &[u8]) -> &mut Self {
+        self.u32(data.len() as u32);
+        self.0.extend_from_slice(data);
+        self
+    }
+
+    fn header(&mut self, seq: u32, stamp: Timestamp, frame_id: &str) -> &mut Self {
+        self.u32(seq);
+        self.0.extend_from_slice(&time(stamp));
+        self.bytes(frame_id.as_bytes())
+    }
+
+    fn vector3(&mut self, v: &na::Vector3<f64>) -> &mut Self {
+        self.f64s(v.as_slice())
+    }
+
+    fn quaternion(&mut self, q: &na::Quaternion<f64>) -> &mut Self {
+        // x, y, z, w like nalgebra's storage
+        self.f64s(q.coords.as_slice())
+    }
+}
+
+/// Encode a ROS `time` as seconds and nanoseconds.
+fn time(timestamp: Timestamp) -> [u8; 8] {
+    let mut buf = [0; 8];
+    buf[..4].copy_from_slice(&((timestamp.nsecs() / 1_000_000_000) as u32).to_le_bytes());
+    buf[4..].copy_from_slice(&((timestamp.nsecs() % 1_000_000_000) as u32).to_le_bytes());
+    buf
+}
+
+/// Return the current position of `writer`.
+// `Seek::stream_position` needs Rust 1.55
+#[allow(clippy::seek_from_current)]
+fn position<S: Seek>(writer: &mut S) -> Result<u64> {
+    Ok(writer.seek(SeekFrom::Current(0))?)
+}
+
+/// Encode the header of a record, whose fields are `name=value`.
+fn record_header(fields: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut header = Vec::new();
+    for (name, value) in fields {
+        header.extend_from_slice(&((name.len() + 1 + value.len()) as u32).to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        header.push(b'=');
+        header.extend_from_slice(value);
+    }
+    header
+}
+
+fn write_record<W: Write>(writer: &mut W, fields: &[(&str, &[u8])], data: &[u8]) -> Result<()> {
+    let header = record_header(fields);
+    writer.write_all(&(header.len() as u32).to_le_bytes())?;
+    writer.write_all(&header)?;
+    writer.write_all(&(data.len() as u32).to_le_bytes())?;
+    writer.write_all(data)?;
+    Ok(())
+}
+
+struct ChunkInfo {
+    position: u64,
+    start: Timestamp,
+    end: Timestamp,
+    /// messages per connection
+    counts: BTreeMap<u32, u32>,
+}
+
+/// Writes messages into chunks and the indices needed by `rosbag play`.
+struct BagWriter<W: Write + Seek> {
+    writer: W,
+    /// connection records, each `(header fields, data)`
+    connections: Vec<(Vec<u8>, Vec<u8>)>,
+    chunk: Vec<u8>,
+    /// time and offset in `chunk` of the messages of each connection
+    index: BTreeMap<u32, Vec<(Timestamp, u32)>>,
+    chunks: Vec<ChunkInfo>,
+}
+
+impl<W: Write + Seek> BagWriter<W> {
+    fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(MAGIC)?;
+        let mut bag = Self {
+            writer,
+            connections: Vec::new(),
+            chunk: Vec::new(),
+            index: BTreeMap::new(),
+            chunks: Vec::new(),
+        };
+        bag.write_bag_header(0)?;
+        Ok(bag)
+    }
+
+    fn write_bag_header(&mut self, index_pos: u64) -> Result<()> {
+        let index_pos = index_pos.to_le_bytes();
+        let conn_count = (self.connections.len() as u32).to_le_bytes();
+        let chunk_count = (self.chunks.len() as u32).to_le_bytes();
+        let fields: [(&str, &[u8]); 4] = [
+            ("op", &[OP_BAG_HEADER]),
+            ("index_pos", &index_pos),
+            ("conn_count", &conn_count),
+            ("chunk_count", &chunk_count),
+        ];
+        let header_len = record_header(&fields).len();
+        let padding = vec![b' '; BAG_HEADER_LEN - 8 - header_len];
+        write_record(&mut self.writer, &fields, &padding)
+    }
+
+    fn add_connection(&mut self, topic: &str, message_type: &MessageType) -> u32 {
+        let id = self.connections.len() as u32;
+        let header = record_header(&[
+            ("op", &[OP_CONNECTION]),
+            ("conn", &id.to_le_bytes()),
+            ("topic", topic.as_bytes()),
+        ]);
+        let data = record_header(&[
+            ("topic", topic.as_bytes()),
+            ("type", message_type.name.as_bytes()),
+            ("md5sum", message_type.md5sum.as_bytes()),
+            ("message_definition", message_type.definition.as_bytes()),
+        ]);
+        self.connections.push((header, data));
+        id
+    }
+
+    fn write_connection<V: Write>(
+        writer: &mut V,
+        (header, data): &(Vec<u8>, Vec<u8>),
+    ) -> Result<()> {
+        writer.write_all(&(header.len() as u32).to_le_bytes())?;
+        writer.write_all(header)?;
+        writer.write_all(&(data.len() as u32).to_le_bytes())?;
+        writer.write_all(data)?;
+        Ok(())
+    }
+
+    fn write_message(&mut self, connection: u32, timestamp: Timestamp, data: &[u8]) -> Result<()> {
+        if self.chunk.is_empty() && self.chunks.is_empty() {
+            // connections precede their messages in the first chunk
+            for connection in &self.connections {
+                Self::write_connection(&mut self.chunk, connection)?;
+            }
+        }
+
+        self.index
+            .entry(connection)
+            .or_default()
+            .push((timestamp, self.chunk.len() as u32));
+        write_record(
+            &mut self.chunk,
+            &[
+                ("op", &[OP_MESSAGE_DATA]),
+                ("conn", &connection.to_le_bytes()),
+                ("time", &time(timestamp)),
+            ],
+            data,
+        )?;
+
+        if self.chunk.len() >= CHUNK_SIZE {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    fn flush_chunk(&mut self) -> Result<()> {
+        if self.index.is_empty() {
+            return Ok(());
+        }
+
+        let position = position(&mut self.writer)?;
+        write_record(
+            &mut self.writer,
+            &[
+                ("op", &[OP_CHUNK]),
+                ("compression", b"none"),
+                ("size", &(self.chunk.len() as u32).to_le_bytes()),
+            ],
+            &self.chunk,
+        )?;
+
+        let mut info = ChunkInfo {
+            position,
+            start: Timestamp::new(u64::MAX),
+            end: Timestamp::new(0),
+            counts: BTreeMap::new(),
+        };
+        for (&connection, entries) in &self.index {
+            let mut data = Vec::with_capacity(entries.len() * 12);
+            for &(timestamp, offset) in entries {
+                data.extend_from_slice(&time(timestamp));
+                data.extend_from_slice(&offset.to_le_bytes());
+                info.start = info.start.min(timestamp);
+                info.end = info.end.max(timestamp);
+            }
+            write_record(
+                &mut self.writer,
+                &[
+                    ("op", &[OP_INDEX_DATA]),
+                    ("ver", &1u32.to_le_bytes()),
+                    ("conn", &connection.to_le_bytes()),
+                    ("count", &(entries.len() as u32).to_le_bytes()),
+                ],
+                &data,
+            )?;
+            info.counts.insert(connection, entries.len() as u32);
+        }
+
+        self.chunks.push(info);
+        self.chunk.clear();
+        self.index.clear();
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.flush_chunk()?;
+
+        let index_pos = position(&mut self.writer)?;
+        for connection in &self.connections {
+            Self::write_connection(&mut self.writer, connection)?;
+        }
+        for info in &self.chunks {
+            let mut data = Vec::new();
+            for (connection, count) in &info.counts {
+                data.extend_from_slice(&connection.to_le_bytes());
+                data.extend_from_slice(&count.to_le_bytes());
+            }
+            write_record(
+                &mut self.writer,
+                &[
+                    ("op", &[OP_CHUNK_INFO]),
+                    ("ver", &1u32.to_le_bytes()),
+                    ("chunk_pos", &info.position.to_le_bytes()),
+                    ("start_time", &time(info.start)),
+                    ("end_time", &time(info.end)),
+                    ("count", &(info.counts.len() as u32).to_le_bytes()),
+                ],
+                &data,
+            )?;
+        }
+
+        self.writer.seek(SeekFrom::Start(MAGIC.len() as u64))?;
+        self.write_bag_header(index_pos)?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl EuRoC {
+    /// Open a ROS 1 bag, such as the official `MH_01_easy.bag`, through [`RosbagSource`].
+    pub fn from_rosbag<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_source(Arc::new(RosbagSource::open(path)?), "")
+    }
+
+    /// Write the records of `sensors` into a ROS 1 bag through a [`RosbagSink`].
+    ///
+    /// Return the number of messages written.
+    pub fn export_rosbag<P: AsRef<Path>>(&self, path: P, sensors: &[Sensor]) -> Result<usize> {
+        let mut sink = RosbagSink::new(BufWriter::new(File::create(path)?))?;
+        self.convert(sensors, &mut sink)
+    }
+}
+
+/// [`RecordSink`] writing a ROS 1 bag, like the bags distributed with the dataset.
+///
+/// Topics and message types follow the official bags: `/cam0/image_raw`
+/// and `/cam1/image_raw` as `mono8` `sensor_msgs/Image`, `/imu0` as
+/// `sensor_msgs/Imu`, `/leica/position` as `geometry_msgs/PointStamped`
+/// and `/vicon/firefly_sbx/firefly_sbx` as
+/// `geometry_msgs/TransformStamped`. The ground truth, which the official
+/// bags lack, is written to `/state_groundtruth_estimate0/pose` as
+/// `geometry_msgs/PoseStamped` in the `world` frame.
+///
+/// Messages are buffered by chunk, so the bag is written in constant memory.
+pub struct RosbagSink<W: Write + Seek> {
+    bag: BagWriter<W>,
+    /// sensor and sequence number of each connection, whose id is its index
+    connections: Vec<(Sensor, u32)>,
+}
+
+impl<W: Write + Seek> RosbagSink<W> {
+    /// Start the bag with its magic and a placeholder bag header.
+    pub fn new(writer: W) -> Result<Self> {
+        Ok(Self {
+            bag: BagWriter::new(writer)?,
+            connections: Vec::new(),
+        })
+    }
+
+    /// Return the writer the bag went to.
+    pub fn into_inner(self) -> W {
+        self.bag.writer
+    }
+}
+
+impl<W: Write + Seek> RecordSink for RosbagSink<W> {
+    fn begin(&mut self, sensors: &[Sensor]) -> Result<()> {
+        for &sensor in sensors {
+            let (topic, message_type) = topic(sensor);
+            let connection = self.bag.add_connection(topic, &message_type);
+            debug_assert_eq!(connection as usize, self.connections.len());
+            self.connections.push((sensor, 0));
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, event: &SensorEvent) -> Result<()> {
+        let connection = self
+            .connections
+            .iter()
+            .position(|&(sensor, _)| sensor == event.sensor())
+            .ok_or_else(|| {
+                EurocError::InvalidInput(format!("no bag connection for {:?}", event.sensor()))
+            })?;
+        let seq = &mut self.connections[connection].1;
+        let data = serialize(event, *seq);
+        *seq += 1;
+
+        self.bag
+            .write_message(connection as u32, event.timestamp(), &data)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.bag.finish()
+    }
+}
+
+/// Serialize `event` as the message of its topic.
+fn serialize(event: &SensorEvent, seq: u32) -> Vec<u8> {
+    let timestamp = event.timestamp();
+    let frame_id = event.sensor().dir_name();
+    let mut s = Serializer::default();
+    match event {
+        SensorEvent::Image(_, r) => {
+            let gray = r.image.to_luma8();
+            s.header(seq, timestamp, frame_id)
+                .u32(gray.height())
+                .u32(gray.width())
+                .bytes(b"mono8")
+                .u8(0)
+                .u32(gray.width())
+                .bytes(gray.as_raw());
+        }
+        SensorEvent::Imu(r) => {
+            let mut unknown = [0.0; 9];
+            unknown[0] = -1.0;
+            // no orientation estimate
+            s.header(seq, timestamp, frame_id)
+                .f64s(&[0.0, 0.0, 0.0, 1.0])
+                .f64s(&unknown)
+                .vector3(&r.gyro)
+                .f64s(&[0.0; 9])
+                .vector3(&r.accel)
+                .f64s(&[0.0; 9]);
+        }
+        SensorEvent::Position(r) => {
+            s.header(seq, timestamp, frame_id).vector3(&r.position);
+        }
+        SensorEvent::Vicon(r) => {
+            s.header(seq, timestamp, "world")
+                .bytes(frame_id.as_bytes())
+                .vector3(&r.position)
+                .quaternion(&r.quaternion);
+        }
+        SensorEvent::GroundTruth(r) => {
+            s.header(seq, timestamp, "world")
+                .vector3(&r.position)
+                .quaternion(&r.quaternion);
+        }
+    }
+    s.0
+}
+
+/// Location of an image message, encoded to PNG only when opened.
+#[derive(Debug, Clone, Copy)]
+struct ImageMessage {
+    offset: u64,
+    len: u32,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    File(Arc<[u8]>),
+    Image(ImageMessage),
+}
+
+/// [`DataSource`] presenting a ROS 1 bag, such as the official `.bag`
+/// downloads, as an extracted archive.
+///
+/// The messages of the topics written by [`EuRoC::export_rosbag`] are read
+/// into `data.csv` files when the bag is opened, and the `sensor.yaml` of each
+/// is the published EuRoC calibration, which the bags do not carry. Images are
+/// encoded as PNG when opened and must be `mono8`. The ground truth recorded
+/// as `geometry_msgs/PoseStamped` has no velocity or biases, which are NaN.
+///
+/// Only uncompressed bags are supported; run `rosbag decompress` first.
+#[derive(Debug)]
+pub struct RosbagSource {
+    path: PathBuf,
+    files: FileTree<Node>,
+}
+
+impl RosbagSource {
+    /// Read the messages of the bag at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut reader = BagReader::new(BufReader::new(File::open(path)?))?;
+
+        let mut connections = BTreeMap::new();
+        let mut rows = BTreeMap::<Sensor, Vec<(Timestamp, String)>>::new();
+        let mut images = Vec::new();
+        while let Some((header, len)) = reader.next_record()? {
+            let fields = record_fields(&header)?;
+            match field(&fields, "op")?.first() {
+                Some(&OP_CHUNK) => {
+                    let compression = field(&fields, "compression")?;
+                    if compression != b"none" {
+                        return Err(rosbag_error(format!(
+                            "{} compressed chunk, run `rosbag decompress` first",
+                            String::from_utf8_lossy(compression)
+                        )));
+                    }
+                    // the records of an uncompressed chunk follow in place
+                }
+                Some(&OP_CONNECTION) => {
+                    let id = u32_field(&fields, "conn")?;
+                    let data = reader.read(len)?;
+                    let data = record_fields(&data)?;
+                    let topic_name = field(&fields, "topic")?;
+                    let sensor = Sensor::ALL
+                        .iter()
+                        .copied()
+                        .find(|&s| topic(s).0.as_bytes() == topic_name);
+                    if let Some(sensor) = sensor {
+                        let expected = topic(sensor).1.name;
+                        let name = field(&data, "type")?;
+                        if name != expected.as_bytes() {
+                            return Err(rosbag_error(format!(
+                                "{} is {}, expected {}",
+                                topic(sensor).0,
+                                String::from_utf8_lossy(name),
+                                expected
+                            )));
+                        }
+                        connections.insert(id, sensor);
+                    }
+                }
+                Some(&OP_MESSAGE_DATA) => {
+                    let sensor = match connections.get(&u32_field(&fields, "conn")?) {
+                        Some(&sensor) => sensor,
+                        None => {
+                            reader.skip(len)?;
+                            continue;
+                        }
+                    };
+                    if matches!(sensor, Sensor::Cam0 | Sensor::Cam1) {
+                        let offset = reader.pos;
+                        let timestamp = Deserializer(&reader.read(len.min(12))?).stamp()?;
+                        reader.skip(len.saturating_sub(12))?;
+                        images.push((sensor, timestamp, ImageMessage { offset, len }));
+                        rows.entry(sensor)
+                            .or_default()
+                            .push((timestamp, format!("{}.png", timestamp.nsecs())));
+                    } else {
+                        let data = reader.read(len)?;
+                        let (timestamp, row) = csv_row(sensor, &mut Deserializer(&data))?;
+                        rows.entry(sensor).or_default().push((timestamp, row));
+                    }
+                }
+                _ => reader.skip(len)?,
+            }
+        }
+
+        let mut files = FileTree::default();
+        let mav = Path::new("mav0");
+        files.insert_file(
+            &mav.join("body.yaml"),
+            Node::File(BODY_YAML.as_bytes().into()),
+        );
+        for (sensor, mut rows) in rows {
+            rows.sort_by_key(|&(timestamp, _)| timestamp);
+            let mut csv = String::from(csv_header(sensor));
+            csv.push('\n');
+            for (timestamp, row) in rows {
+                csv.push_str(&format!("{},{}\n", timestamp.nsecs(), row));
+            }
+            let dir = mav.join(sensor.dir_name());
+            files.insert_file(&dir.join("data.csv"), Node::File(csv.into_bytes().into()));
+            files.insert_file(
+                &dir.join("sensor.yaml"),
+                Node::File(sensor_yaml(sensor).as_bytes().into()),
+            );
+        }
+        for (sensor, timestamp, image) in images {
+            let name = format!("{}/data/{}.png", sensor.dir_name(), timestamp.nsecs());
+            files.insert_file(&mav.join(name), Node::Image(image));
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            files,
+        })
+    }
+
+    /// Read the image message at `image` and encode it as PNG.
+    fn encode_image(&self, image: ImageMessage) -> Result<Vec<u8>> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(image.offset))?;
+        let mut data = vec![0; image.len as usize];
+        file.read_exact(&mut data)?;
+
+        let mut message = Deserializer(&data);
+        message.header()?;
+        let height = message.u32()?;
+        let width = message.u32()?;
+        let encoding = message.bytes()?;
+        if encoding != b"mono8" {
+            return Err(rosbag_error(format!(
+                "unsupported image encoding {}",
+                String::from_utf8_lossy(encoding)
+            )));
+        }
+        message.u8()?;
+        let step = message.u32()? as usize;
+        let pixels = message.bytes()?;
+        if step < width as usize || pixels.len() < step * height as usize {
+            return Err(rosbag_error("truncated image"));
+        }
+        let pixels = pixels
+            .chunks(step)
+            .take(height as usize)
+            .flat_map(|row| &row[..width as usize])
+            .copied()
+            .collect::<Vec<_>>();
+
+        let mut png = Vec::new();
+        PngEncoder::new_with_quality(&mut png, CompressionType::Fast, FilterType::Sub).encode(
+            &pixels,
+            width,
+            height,
+            ColorType::L8,
+        )?;
+        Ok(png)
+    }
+}
+
+impl DataSource for RosbagSource {
+    fn open(&self, path: &Path) -> Result<Box<dyn ReadSeek>> {
+        Ok(match self.files.get(path)? {
+            Node::File(data) => Box::new(Cursor::new(Arc::clone(data))),
+            Node::Image(image) => Box::new(Cursor::new(self.encode_image(*image)?)),
+        })
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.is_file(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.files.is_dir(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        self.files.read_dir(path)
+    }
+}
+
+const BODY_YAML: &str = include_str!("../test_data/body.yaml");
+
+/// Return the calibration of `sensor`, the same for every EuRoC sequence.
+const fn sensor_yaml(sensor: Sensor) -> &'static str {
+    match sensor {
+        Sensor::Cam0 => include_str!("../test_data/cam0/sensor.yaml"),
+        Sensor::Cam1 => include_str!("../test_data/cam1/sensor.yaml"),
+        Sensor::Imu0 => include_str!("../test_data/imu0/sensor.yaml"),
+        Sensor::Leica0 => include_str!("../test_data/leica0/sensor.yaml"),
+        Sensor::Vicon0 => include_str!("../test_data/vicon0/sensor.yaml"),
+        Sensor::GroundTruth => include_str!("../test_data/state_groundtruth_estimate0/sensor.yaml"),
+    }
+}
+
+/// Return the first line of the `data.csv` of `sensor`.
+const fn csv_header(sensor: Sensor) -> &'static str {
+    match sensor {
+        Sensor::Cam0 | Sensor::Cam1 => "#timestamp [ns],filename",
+        Sensor::Imu0 => {
+            "#timestamp [ns],w_RS_S_x [rad s^-1],w_RS_S_y [rad s^-1],w_RS_S_z [rad s^-1],\
+             a_RS_S_x [m s^-2],a_RS_S_y [m s^-2],a_RS_S_z [m s^-2]"
+        }
+        Sensor::Leica0 => "#timestamp [ns],p_RS_R_x [m],p_RS_R_y [m],p_RS_R_z [m]",
+        Sensor::Vicon0 => {
+            "#timestamp [ns],p_RS_R_x [m],p_RS_R_y [m],p_RS_R_z [m],\
+             q_RS_w [],q_RS_x [],q_RS_y [],q_RS_z []"
+        }
+        Sensor::GroundTruth => {
+            "#timestamp, p_RS_R_x [m], p_RS_R_y [m], p_RS_R_z [m], \
+             q_RS_w [], q_RS_x [], q_RS_y [], q_RS_z [], \
+             v_RS_R_x [m s^-1], v_RS_R_y [m s^-1], v_RS_R_z [m s^-1], \
+             b_w_RS_S_x [rad s^-1], b_w_RS_S_y [rad s^-1], b_w_RS_S_z [rad s^-1], \
+             b_a_RS_S_x [m s^-2], b_a_RS_S_y [m s^-2], b_a_RS_S_z [m s^-2]"
+        }
+    }
+}
+
+/// Convert a message of a non-camera `sensor` to its `data.csv` row, without the timestamp.
+fn csv_row(sensor: Sensor, message: &mut Deserializer<'_>) -> Result<(Timestamp, String)> {
+    let timestamp = message.header()?;
+    let join = |values: &[f64]| {
+        values
+            .iter()
+            .map(f64::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let row = match sensor {
+        Sensor::Imu0 => {
+            message.f64s(4 + 9)?;
+            let gyro = message.f64s(3)?;
+            message.f64s(9)?;
+            let accel = message.f64s(3)?;
+            format!("{},{}", join(&gyro), join(&accel))
+        }
+        Sensor::Leica0 => join(&message.f64s(3)?),
+        Sensor::Vicon0 | Sensor::GroundTruth => {
+            if sensor == Sensor::Vicon0 {
+                message.bytes()?; // child_frame_id
+            }
+            let position = message.f64s(3)?;
+            let q = message.f64s(4)?;
+            let row = format!("{},{},{}", join(&position), q[3], join(&q[..3]));
+            if sensor == Sensor::GroundTruth {
+                format!("{}{}", row, ",NaN".repeat(9))
+            } else {
+                row
+            }
+        }
+        Sensor::Cam0 | Sensor::Cam1 => unreachable!("images are read lazily"),
+    };
+    Ok((timestamp, row))
+}
+
+fn rosbag_error<M: ToString>(message: M) -> EurocError {
+    EurocError::RosbagParse(message.to_string())
+}
+
+/// Split the `name=value` fields of a record header.
+fn record_fields(header: &[u8]) -> Result<Vec<(&[u8], &[u8])>> {
+    let mut fields = Vec::new();
+    let mut header = Deserializer(header);
+    while !header.0.is_empty() {
+        let field = header.bytes()?;
+        let eq = field
+            .iter()
+            .position(|&b| b == b'=')
+            .ok_or_else(|| rosbag_error("record header field without `=`"))?;
+        fields.push((&field[..eq], &field[eq + 1..]));
+    }
+    Ok(fields)
+}
+
+fn field<'a>(fields: &[(&[u8], &'a [u8])], name: &str) -> Result<&'a [u8]> {
+    fields
+        .iter()
+        .find(|(n, _)| *n == name.as_bytes())
+        .map(|&(_, value)| value)
+        .ok_or_else(|| rosbag_error(format!("record without {}", name)))
+}
+
+fn u32_field(fields: &[(&[u8], &[u8])], name: &str) -> Result<u32> {
+    let value = field(fields, name)?;
+    Deserializer(value).u32()
+}
+
+/// Reads a ROS 1 serialization, advancing past what is read.
+struct Deserializer<'a>(&'a [u8]);
+
+impl<'a> Deserializer<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(rosbag_error("truncated message"));
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn f64s(&mut self, len: usize) -> Result<Vec<f64>> {
+        (0..len)
+            .map(|_| {
+                let mut buf = [0; 8];
+                buf.copy_from_slice(self.take(8)?);
+                Ok(f64::from_le_bytes(buf))
+            })
+            .collect()
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    /// Read the `seq` and `stamp` of a `std_msgs/Header`, returning the stamp.
+    fn stamp(&mut self) -> Result<Timestamp> {
+        self.u32()?;
+        let sec = u64::from(self.u32()?);
+        let nsec = u64::from(self.u32()?);
+        Ok(Timestamp::new(sec * 1_000_000_000 + nsec))
+    }
+
+    /// Read a `std_msgs/Header`, returning its stamp.
+    fn header(&mut self) -> Result<Timestamp> {
+        let stamp = self.stamp()?;
+        self.bytes()?; // frame_id
+        Ok(stamp)
+    }
+}
+
+/// Reads the records of a bag one after another, chunks included.
+struct BagReader<R: Read + Seek> {
+    reader: R,
+    pos: u64,
+    end: u64,
+}
+
+impl<R: Read + Seek> BagReader<R> {
+    fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0; 13];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(rosbag_error("not a ROS 1 bag (format 2.0)"));
+        }
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(MAGIC.len() as u64))?;
+
+        Ok(Self {
+            reader,
+            pos: MAGIC.len() as u64,
+            end,
+        })
+    }
+
+    /// Read the header of the next record, returning it and the length of its data.
+    fn next_record(&mut self) -> Result<Option<(Vec<u8>, u32)>> {
+        if self.pos >= self.end {
+            return Ok(None);
+        }
+        let len = Deserializer(&self.read(4)?).u32()?;
+        let header = self.read(len)?;
+        let data_len = Deserializer(&self.read(4)?).u32()?;
+        Ok(Some((header, data_len)))
+    }
+
+    fn read(&mut self, len: u32) -> Result<Vec<u8>> {
+        if self.pos + u64::from(len) > self.end {
+            return Err(rosbag_error("truncated record"));
+        }
+        let mut buf = vec![0; len as usize];
+        self.reader.read_exact(&mut buf)?;
+        self.pos += u64::from(len);
+        Ok(buf)
+    }
+
+    fn skip(&mut self, len: u32) -> Result<()> {
+        self.pos += u64::from(len);
+        if self.pos > self.end {
+            return Err(rosbag_error("truncated record"));
+        }
+        self.reader.seek(SeekFrom::Start(self.pos))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{convert::TryInto, fs};
+
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn u32_at(data: &[u8], offset: usize) -> usize {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize
+    }
+
+    /// Return the value of the header field `name` of the record at `offset`.
+    fn field<'a>(data: &'a [u8], offset: usize, name: &str) -> Option<&'a [u8]> {
+        let end = offset + 4 + u32_at(data, offset);
+        let mut i = offset + 4;
+        while i < end {
+            let len = u32_at(data, i);
+            let field = &data[i + 4..i + 4 + len];
+            let eq = field.iter().position(|&b| b == b'=')?;
+            if &field[..eq] == name.as_bytes() {
+                return Some(&field[eq + 1..]);
+            }
+            i += 4 + len;
+        }
+        None
+    }
+
+    #[test]
+    fn export_rosbag() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let tmp = TempDir::new("export_rosbag")?;
+        let path = tmp.join("export.bag");
+        assert_eq!(data.export_rosbag(&path, &Sensor::ALL)?, 30);
+
+        let bag = fs::read(&path)?;
+        assert!(bag.starts_with(MAGIC));
+        let header = MAGIC.len();
+        assert_eq!(field(&bag, header, "op"), Some(&[OP_BAG_HEADER][..]));
+        assert_eq!(
+            field(&bag, header, "conn_count"),
+            Some(&6u32.to_le_bytes()[..])
+        );
+        // a chunk is closed after every third image
+        assert_eq!(
+            field(&bag, header, "chunk_count"),
+            Some(&4u32.to_le_bytes()[..])
+        );
+
+        let chunk = header + BAG_HEADER_LEN;
+        assert_eq!(field(&bag, chunk, "op"), Some(&[OP_CHUNK][..]));
+        assert_eq!(field(&bag, chunk, "compression"), Some(&b"none"[..]));
+
+        let index_pos = u64::from_le_bytes(
+            field(&bag, header, "index_pos")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        ) as usize;
+        assert_eq!(field(&bag, index_pos, "op"), Some(&[OP_CONNECTION][..]));
+        assert_eq!(
+            field(&bag, index_pos, "topic"),
+            Some(&b"/cam0/image_raw"[..])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_rosbag() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let tmp = TempDir::new("from_rosbag")?;
+        let path = tmp.join("from_rosbag.bag");
+        data.export_rosbag(&path, &Sensor::ALL)?;
+
+        let bag = EuRoC::from_rosbag(&path)?;
+        assert_eq!(
+            bag.timestamps(Sensor::Cam1)?,
+            data.timestamps(Sensor::Cam1)?
+        );
+        assert_eq!(bag.left_camera()?.config(), data.left_camera()?.config());
+        let frame = bag.left_camera()?.records_gray()?.nth(2).unwrap()?;
+        let expected = data.left_camera()?.records_gray()?.nth(2).unwrap()?;
+        assert_eq!(frame.timestamp, expected.timestamp);
+        assert_eq!(frame.into_raw(), expected.into_raw());
+
+        let imu = bag.imu()?.records()?.collect::<Result<Vec<_>>>()?;
+        let expected = data.imu()?.records()?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(imu.len(), expected.len());
+        for (record, expected) in imu.iter().zip(&expected) {
+            assert_eq!(record.timestamp, expected.timestamp);
+            assert_eq!((record.gyro, record.accel), (expected.gyro, expected.accel));
+        }
+
+        let vicon = bag.vicon()?.records()?.last().unwrap()?;
+        let expected = data.vicon()?.records()?.last().unwrap()?;
+        assert_eq!(vicon.timestamp, expected.timestamp);
+        assert_eq!(
+            (vicon.position, vicon.quaternion),
+            (expected.position, expected.quaternion)
+        );
+
+        let truth = bag.ground_truth()?.records()?.next().unwrap()?;
+        let expected = data.ground_truth()?.records()?.next().unwrap()?;
+        assert_eq!(truth.timestamp, expected.timestamp);
+        assert_eq!(truth.position, expected.position);
+        assert_eq!(truth.quaternion, expected.quaternion);
+        assert!(truth.velocity.x.is_nan());
+        assert!(matches!(
+            RosbagSource::open("test_data/imu0/data.csv"),
+            Err(EurocError::RosbagParse(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use crate::{EuRoC, ZipSource};
+
+/// `sample_data/sample.zip`: the streams of `test_data` with three frames per
+/// camera, cropped to 376×240 around the image centre.
+const SAMPLE_ZIP: &[u8] = include_bytes!("../sample_data/sample.zip");
+
+impl EuRoC {
+    /// Open a tiny sequence embedded in the binary, for examples and tests without a download.
+    ///
+    /// It has every sensor folder of a real sequence, but only a few records
+    /// each and three cropped frames per camera; the camera intrinsics and
+    /// resolution are adjusted to the crop.
+    ///
+    /// ```
+    /// let data = euroc::EuRoC::sample();
+    /// assert_eq!(data.left_camera()?.len()?, 3);
+    /// # Ok::<(), euroc::EurocError>(())
+    /// ```
+    pub fn sample() -> Self {
+        let source = ZipSource::from_bytes(SAMPLE_ZIP).expect("embedded sample is a valid zip");
+        Self::from_source(Arc::new(source), "").expect("embedded sample has `mav0`")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::GenericImageView;
+
+    use super::*;
+    use crate::{Layout, Result, Sensor};
+
+    #[test]
+    fn sample() -> Result<()> {
+        let data = EuRoC::sample();
+        assert_eq!(data.layout(), Layout::Archive);
+        assert_eq!(data.sensors()?.len(), 7);
+
+        let camera = data.camera(Sensor::Cam1)?;
+        assert_eq!(camera.config().resolution, (376, 240));
+        let frame = camera.entries()?.next().unwrap()?.load()?;
+        assert_eq!(frame.image.dimensions(), (376, 240));
+
+        assert_eq!(data.imu()?.len()?, 5);
+        assert!(data.point_cloud()?.load().is_ok());
+        assert!(data.frame_graph().is_ok());
+
+        Ok(())
+    }
+}
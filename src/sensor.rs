@@ -0,0 +1,162 @@
+use crate::{yaml::SensorYaml, EuRoC, Result};
+
+/// Sensor folders of a EuRoC MAV dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sensor {
+    /// left camera
+    Cam0,
+    /// right camera
+    Cam1,
+    Imu0,
+    /// Leica position
+    Leica0,
+    /// Vicon pose
+    Vicon0,
+    /// visual-inertial ground truth
+    GroundTruth,
+}
+
+impl Sensor {
+    pub const ALL: [Self; 6] = [
+        Self::Cam0,
+        Self::Cam1,
+        Self::Imu0,
+        Self::Leica0,
+        Self::Vicon0,
+        Self::GroundTruth,
+    ];
+
+    pub const fn is_camera(self) -> bool {
+        matches!(self, Self::Cam0 | Self::Cam1)
+    }
+
+    /// Return folder name under `mav0`.
+    pub const fn dir_name(self) -> &'static str {
+        match self {
+            Self::Cam0 => "cam0",
+            Self::Cam1 => "cam1",
+            Self::Imu0 => "imu0",
+            Self::Leica0 => "leica0",
+            Self::Vicon0 => "vicon0",
+            Self::GroundTruth => "state_groundtruth_estimate0",
+        }
+    }
+
+    /// Return the kind of sensor stored in the folder.
+    pub const fn kind(self) -> SensorKind {
+        match self {
+            Self::Cam0 | Self::Cam1 => SensorKind::Camera,
+            Self::Imu0 => SensorKind::Imu,
+            Self::Leica0 => SensorKind::Position,
+            Self::Vicon0 => SensorKind::Pose,
+            Self::GroundTruth => SensorKind::VisualInertial,
+        }
+    }
+}
+
+/// `sensor_type` of a `sensor.yaml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SensorKind {
+    Camera,
+    Imu,
+    /// e.g. Leica
+    Position,
+    /// e.g. Vicon
+    Pose,
+    /// ground truth estimate
+    VisualInertial,
+    PointCloud,
+    /// any other `sensor_type`
+    Other(String),
+}
+
+impl SensorKind {
+    fn from_sensor_type(sensor_type: &str) -> Self {
+        match sensor_type {
+            "camera" => Self::Camera,
+            "imu" => Self::Imu,
+            "position" => Self::Position,
+            "pose" => Self::Pose,
+            "visual-inertial" => Self::VisualInertial,
+            "pointcloud" => Self::PointCloud,
+            other => Self::Other(other.to_owned()),
+        }
+    }
+}
+
+/// Sensor folder found by [`EuRoC::sensors`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SensorFolder {
+    /// folder name under `mav0`, e.g. `cam0`
+    pub name: String,
+    pub kind: SensorKind,
+}
+
+impl EuRoC {
+    /// List the sensor folders present, sorted by name, from their `sensor.yaml`.
+    ///
+    /// A folder without `sensor.yaml` is reported as a point cloud if it has
+    /// `data.ply`, and skipped otherwise.
+    pub fn sensors(&self) -> Result<Vec<SensorFolder>> {
+        let mut sensors = Vec::new();
+        for path in self.source.read_dir(&self.root)? {
+            if !self.source.is_dir(&path) {
+                continue;
+            }
+
+            let sensor_yaml = path.join("sensor.yaml");
+            let kind = if self.source.is_file(&sensor_yaml) {
+                let yaml = SensorYaml::load(&*self.source, sensor_yaml)?;
+                SensorKind::from_sensor_type(&yaml.str("sensor_type")?)
+            } else if self.source.is_file(&path.join("data.ply")) {
+                SensorKind::PointCloud
+            } else {
+                continue;
+            };
+
+            sensors.push(SensorFolder {
+                name: path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+                kind,
+            });
+        }
+
+        sensors.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(sensors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sensors() -> Result<()> {
+        let sensors = EuRoC::new("test_data")?.sensors()?;
+        let kinds = sensors
+            .iter()
+            .map(|s| (s.name.as_str(), s.kind.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ("cam0", SensorKind::Camera),
+                ("cam1", SensorKind::Camera),
+                ("imu0", SensorKind::Imu),
+                ("leica0", SensorKind::Position),
+                ("pointcloud0", SensorKind::PointCloud),
+                ("state_groundtruth_estimate0", SensorKind::VisualInertial),
+                ("vicon0", SensorKind::Pose),
+            ]
+        );
+
+        for sensor in Sensor::ALL.iter() {
+            assert!(kinds.contains(&(sensor.dir_name(), sensor.kind())));
+        }
+
+        Ok(())
+    }
+}
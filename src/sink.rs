@@ -0,0 +1,90 @@
+use crate::{EuRoC, Result, Sensor, SensorEvent};
+
+/// Output format fed one record at a time by [`EuRoC::convert`].
+///
+/// Records arrive merged in timestamp order, so a conversion holds only what
+/// the format itself buffers, and a new format needs only a sink.
+/// [`McapSink`](crate::McapSink) implements it, and so does `RosbagSink`
+/// with the `rosbag` feature.
+pub trait RecordSink {
+    /// Prepare for the records of `sensors`, e.g. write the file header.
+    fn begin(&mut self, sensors: &[Sensor]) -> Result<()>;
+
+    /// Write one record of a sensor passed to [`RecordSink::begin`].
+    fn write(&mut self, event: &SensorEvent) -> Result<()>;
+
+    /// Complete the output, e.g. write indices, and flush it.
+    fn finish(&mut self) -> Result<()>;
+}
+
+impl EuRoC {
+    /// Stream the records of `sensors` into `sink` in timestamp order.
+    ///
+    /// Stops at the first error. Return the number of records written.
+    pub fn convert<S: RecordSink + ?Sized>(
+        &self,
+        sensors: &[Sensor],
+        sink: &mut S,
+    ) -> Result<usize> {
+        sink.begin(sensors)?;
+
+        let mut count = 0;
+        for event in self.events(sensors)? {
+            sink.write(&event?)?;
+            count += 1;
+        }
+        sink.finish()?;
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Timestamped;
+
+    /// Sink keeping what it is given.
+    #[derive(Default)]
+    struct Collect {
+        sensors: Vec<Sensor>,
+        events: Vec<(Sensor, u64)>,
+        finished: bool,
+    }
+
+    impl RecordSink for Collect {
+        fn begin(&mut self, sensors: &[Sensor]) -> Result<()> {
+            self.sensors = sensors.to_vec();
+            Ok(())
+        }
+
+        fn write(&mut self, event: &SensorEvent) -> Result<()> {
+            self.events
+                .push((event.sensor(), event.timestamp().nsecs()));
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            self.finished = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn convert() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let mut sink = Collect::default();
+        let sensors = [Sensor::Imu0, Sensor::Cam0];
+        assert_eq!(data.convert(&sensors, &mut sink)?, 10);
+
+        assert_eq!(sink.sensors, sensors);
+        assert!(sink.finished);
+        assert!(sink.events.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert_eq!(
+            sink.events.iter().filter(|e| e.0 == Sensor::Cam0).count(),
+            5
+        );
+
+        Ok(())
+    }
+}
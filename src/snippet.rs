@@ -0,0 +1,246 @@
+use std::{
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::{EuRoC, EurocError, Result, Sensor, Timestamp};
+
+const DATA: &str = "data";
+const DATA_CSV: &str = "data.csv";
+const SENSOR_YAML: &str = "sensor.yaml";
+const BODY_YAML: &str = "body.yaml";
+const FULL_RANGE: Range<Timestamp> = Timestamp::new(0)..Timestamp::new(u64::MAX);
+
+/// Timestamps written by [`EuRoC::export`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportClock {
+    /// Keep the original Unix timestamps.
+    Original,
+    /// Subtract [`EuRoC::epoch`], preserving the offsets between streams.
+    ZeroBased,
+}
+
+impl EuRoC {
+    /// Write every time window as a separate EuRoC tree under `out_dir`.
+    ///
+    /// Snippet `i` is written to `out_dir/<i>` (zero-padded) and contains the rows of
+    /// each available sensor whose timestamp lies in `windows[i]`, together with
+    /// the sensor calibration and, for cameras, the referenced images.
+    /// Return the root of each snippet, which can be opened with [`EuRoC::new`].
+    pub fn export_snippets<P: AsRef<Path>>(
+        &self,
+        out_dir: P,
+        windows: &[Range<Timestamp>],
+    ) -> Result<Vec<PathBuf>> {
+        let width = windows.len().saturating_sub(1).to_string().len();
+
+        windows
+            .iter()
+            .enumerate()
+            .map(|(i, window)| {
+                let root = out_dir
+                    .as_ref()
+                    .join(format!("{:0width$}", i, width = width));
+                self.export_range(&root, window, None, None)?;
+                Ok(root)
+            })
+            .collect()
+    }
+
+    /// Write the whole dataset as a EuRoC tree under `out_dir` using `clock`.
+    pub fn export<P: AsRef<Path>>(&self, out_dir: P, clock: ExportClock) -> Result<()> {
+        let origin = match clock {
+            ExportClock::Original => None,
+            ExportClock::ZeroBased => Some(self.epoch()?),
+        };
+
+        self.export_range(out_dir.as_ref(), &FULL_RANGE, origin, None)
+    }
+
+    /// Write the whole dataset under `out_dir`, keeping only the camera frames in `keyframes`.
+    ///
+    /// `keyframes` must be sorted, e.g. from [`crate::KeyframeSelector::select`].
+    /// All other streams are written in full.
+    pub fn export_keyframes<P: AsRef<Path>>(
+        &self,
+        out_dir: P,
+        keyframes: &[Timestamp],
+    ) -> Result<()> {
+        self.export_range(out_dir.as_ref(), &FULL_RANGE, None, Some(keyframes))
+    }
+
+    /// Export the rows inside `window`, subtracting `origin` from every timestamp if given.
+    ///
+    /// Camera rows are further restricted to `frames` if given.
+    fn export_range(
+        &self,
+        root: &Path,
+        window: &Range<Timestamp>,
+        origin: Option<Timestamp>,
+        frames: Option<&[Timestamp]>,
+    ) -> Result<()> {
+        fs::create_dir_all(root)?;
+        if self.source.is_file(&self.root.join(BODY_YAML)) {
+            fs::write(
+                root.join(BODY_YAML),
+                self.source.read(&self.root.join(BODY_YAML))?,
+            )?;
+        }
+
+        for sensor in Sensor::ALL.iter() {
+            let src = self.root.join(sensor.dir_name());
+            if !self.source.is_file(&src.join(DATA_CSV)) {
+                continue;
+            }
+            let dst = root.join(sensor.dir_name());
+            fs::create_dir_all(&dst)?;
+
+            if self.source.is_file(&src.join(SENSOR_YAML)) {
+                fs::write(
+                    dst.join(SENSOR_YAML),
+                    self.source.read(&src.join(SENSOR_YAML))?,
+                )?;
+            }
+
+            let csv = self.source.read_to_string(&src.join(DATA_CSV))?;
+            let (header, rows) = filter_csv(&src.join(DATA_CSV), &csv, window)?;
+            if sensor.is_camera() {
+                fs::create_dir_all(dst.join(DATA))?;
+            }
+
+            let mut text = header;
+            text.push('\n');
+            for (timestamp, row) in rows {
+                if let (true, Some(frames)) = (sensor.is_camera(), frames) {
+                    if frames.binary_search(&timestamp).is_err() {
+                        continue;
+                    }
+                }
+
+                let mut fields: Vec<String> = row.split(',').map(ToOwned::to_owned).collect();
+                if let Some(origin) = origin {
+                    fields[0] = (timestamp - origin).nsecs().to_string();
+                }
+
+                if sensor.is_camera() && fields.len() > 1 {
+                    let file = fields[1].trim().to_owned();
+                    let renamed = match (origin, Path::new(&file).extension()) {
+                        (Some(_), Some(ext)) => format!("{}.{}", fields[0], ext.to_string_lossy()),
+                        (Some(_), None) => fields[0].clone(),
+                        (None, _) => file.clone(),
+                    };
+                    let image = self.source.read(&src.join(DATA).join(&file))?;
+                    fs::write(dst.join(DATA).join(&renamed), image)?;
+                    fields[1] = renamed;
+                }
+
+                text.push_str(&fields.join(","));
+                text.push('\n');
+            }
+            fs::write(dst.join(DATA_CSV), text)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Return the header of `text`, read from `path`, and the rows inside `window` with their timestamps.
+fn filter_csv(
+    path: &Path,
+    text: &str,
+    window: &Range<Timestamp>,
+) -> Result<(String, Vec<(Timestamp, String)>)> {
+    let mut lines = text.lines().enumerate();
+    let header = lines.next().map(|(_, l)| l.to_owned()).unwrap_or_default();
+    let mut rows = Vec::new();
+
+    for (i, line) in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let field = line.split(',').next().unwrap_or_default().trim();
+        let timestamp: Timestamp = field
+            .parse::<u64>()
+            .map_err(|e| EurocError::CsvParse {
+                path: path.to_owned(),
+                line: i as u64 + 1,
+                message: format!("column 0 `{}`: {}", field, e),
+            })?
+            .into();
+
+        if window.contains(&timestamp) {
+            rows.push((timestamp, line.to_owned()));
+        }
+    }
+
+    Ok((header, rows))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn export_snippets() -> Result<()> {
+        let out = TempDir::new("export_snippets")?;
+
+        let data = EuRoC::new("test_data")?;
+        let roots = data.export_snippets(
+            &out,
+            &[
+                1403636579763555584.into()..1403636579863555584.into(),
+                1403636579863555584.into()..1403636580000000000.into(),
+            ],
+        )?;
+        assert_eq!(roots, vec![out.join("0"), out.join("1")]);
+
+        let first = EuRoC::new(&roots[0])?;
+        let entries = first
+            .left_camera()?
+            .entries()?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(entries.len(), 2);
+        assert!(entries[1].load().is_ok());
+        assert_eq!(first.left_camera()?.config(), data.left_camera()?.config());
+
+        let second = EuRoC::new(&roots[1])?;
+        assert_eq!(second.right_camera()?.entries()?.count(), 3);
+        assert!(second.imu()?.records()?.count() <= data.imu()?.records()?.count());
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_zero_based() -> Result<()> {
+        let out = TempDir::new("export_zero_based")?;
+
+        let data = EuRoC::new("test_data")?;
+        data.export(&out, ExportClock::ZeroBased)?;
+
+        let exported = EuRoC::new(&out)?;
+        assert_eq!(exported.epoch()?, 0.into());
+
+        let offset = data.epoch()? - Timestamp::from(0);
+        let original = data.left_camera()?.entries()?.collect::<Result<Vec<_>>>()?;
+        let rebased = exported
+            .left_camera()?
+            .entries()?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(rebased.len(), original.len());
+        assert_eq!(rebased[2].timestamp, original[2].timestamp - offset);
+        assert_eq!(
+            rebased[2].path,
+            out.join(format!("cam0/data/{}.png", rebased[2].timestamp.nsecs()))
+        );
+        assert!(rebased[2].load().is_ok());
+
+        let imu = exported.imu()?.records()?.collect::<Result<Vec<_>>>()?;
+        let original_imu = data.imu()?.records()?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(imu[0].timestamp, original_imu[0].timestamp - offset);
+        assert_eq!(imu[0].gyro, original_imu[0].gyro);
+
+        Ok(())
+    }
+}
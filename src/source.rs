@@ -0,0 +1,271 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    fs::{self, File},
+    io::{Cursor, Read, Seek},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{EurocError, Result};
+
+/// Readable and seekable stream returned by [`DataSource::open`].
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Storage a dataset is read from, e.g. the filesystem or a zip archive.
+///
+/// Paths are as given to [`crate::EuRoC::from_source`], joined with the names
+/// of the dataset files.
+pub trait DataSource: fmt::Debug + Send + Sync {
+    fn open(&self, path: &Path) -> Result<Box<dyn ReadSeek>>;
+
+    fn is_file(&self, path: &Path) -> bool;
+
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Return the paths of the entries of the directory `path`.
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>>;
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.open(path)?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        let mut text = String::new();
+        self.open(path)?.read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    fn require_dir(&self, path: &Path) -> Result<()> {
+        if self.is_dir(path) {
+            Ok(())
+        } else {
+            Err(EurocError::MissingFile(path.to_owned()))
+        }
+    }
+
+    fn require_file(&self, path: &Path) -> Result<()> {
+        if self.is_file(path) {
+            Ok(())
+        } else {
+            Err(EurocError::MissingFile(path.to_owned()))
+        }
+    }
+}
+
+/// [`DataSource`] reading files from disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileSystem;
+
+impl FileSystem {
+    pub fn shared() -> Arc<dyn DataSource> {
+        Arc::new(Self)
+    }
+}
+
+impl DataSource for FileSystem {
+    fn open(&self, path: &Path) -> Result<Box<dyn ReadSeek>> {
+        Ok(Box::new(File::open(path)?))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        fs::read_dir(path)?.map(|entry| Ok(entry?.path())).collect()
+    }
+}
+
+/// [`DataSource`] holding its files in memory, e.g. for tests or generated data.
+///
+/// Directories exist implicitly as the ancestors of the inserted files.
+///
+/// ```
+/// use euroc::{EuRoC, MemorySource};
+///
+/// let mut source = MemorySource::new();
+/// source.insert("mav0/body.yaml", &b"comment: empty"[..]);
+/// let data = EuRoC::from_source(std::sync::Arc::new(source), "mav0")?;
+/// assert!(data.sensors()?.is_empty());
+/// # Ok::<(), euroc::EurocError>(())
+/// ```
+#[derive(Clone, Default)]
+pub struct MemorySource {
+    files: FileTree<Arc<[u8]>>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace the file at `path`.
+    pub fn insert<P: AsRef<Path>, B: Into<Arc<[u8]>>>(&mut self, path: P, data: B) {
+        self.files.insert_file(path.as_ref(), data.into());
+    }
+
+    /// Copy every file under `dir` on disk, keeping their paths relative to `dir`.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        fn copy(source: &mut MemorySource, dir: &Path, prefix: &Path) -> Result<()> {
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                let path = prefix.join(entry.file_name());
+                if entry.file_type()?.is_dir() {
+                    source.files.insert_dir(&path);
+                    copy(source, &entry.path(), &path)?;
+                } else {
+                    source.insert(path, fs::read(entry.path())?);
+                }
+            }
+            Ok(())
+        }
+
+        let mut source = Self::new();
+        copy(&mut source, dir.as_ref(), Path::new(""))?;
+        Ok(source)
+    }
+}
+
+impl fmt::Debug for MemorySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemorySource")
+            .field("files", &self.files.files.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl DataSource for MemorySource {
+    fn open(&self, path: &Path) -> Result<Box<dyn ReadSeek>> {
+        Ok(Box::new(Cursor::new(Arc::clone(self.files.get(path)?))))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.is_file(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.files.is_dir(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        self.files.read_dir(path)
+    }
+}
+
+/// Files and directories of a [`DataSource`] not backed by the filesystem,
+/// keyed by their relative paths.
+#[derive(Debug, Clone)]
+pub struct FileTree<T> {
+    files: BTreeMap<PathBuf, T>,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl<T> Default for FileTree<T> {
+    fn default() -> Self {
+        Self {
+            files: BTreeMap::new(),
+            dirs: std::iter::once(PathBuf::new()).collect(),
+        }
+    }
+}
+
+impl<T> FileTree<T> {
+    pub fn insert_file(&mut self, path: &Path, file: T) {
+        let path = normalize(path);
+        self.insert_ancestors(&path);
+        self.files.insert(path, file);
+    }
+
+    pub fn insert_dir(&mut self, path: &Path) {
+        let path = normalize(path);
+        self.insert_ancestors(&path);
+        self.dirs.insert(path);
+    }
+
+    fn insert_ancestors(&mut self, path: &Path) {
+        for dir in path.ancestors().skip(1) {
+            self.dirs.insert(dir.to_owned());
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Result<&T> {
+        self.files
+            .get(&normalize(path))
+            .ok_or_else(|| EurocError::MissingFile(path.to_owned()))
+    }
+
+    pub fn is_file(&self, path: &Path) -> bool {
+        self.files.contains_key(&normalize(path))
+    }
+
+    pub fn is_dir(&self, path: &Path) -> bool {
+        self.dirs.contains(&normalize(path))
+    }
+
+    /// Return the children of `path`, joined onto `path` as given.
+    pub fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let dir = normalize(path);
+        if !self.dirs.contains(&dir) {
+            return Err(EurocError::MissingFile(path.to_owned()));
+        }
+
+        let children = self
+            .files
+            .keys()
+            .chain(self.dirs.iter())
+            .filter(|child| child.parent() == Some(&dir))
+            .map(|child| path.join(child.file_name().unwrap_or_default()))
+            .collect::<BTreeSet<_>>();
+        Ok(children.into_iter().collect())
+    }
+}
+
+/// Drop `.` components, so that `./mav0/imu0` finds `mav0/imu0`.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EuRoC;
+
+    #[test]
+    fn memory() -> Result<()> {
+        let mut source = MemorySource::from_dir("test_data")?;
+        let csv = source.read_to_string(Path::new("imu0/data.csv"))?;
+        let mut lines = csv.lines();
+        let edited = format!("{}\n{}\n", lines.next().unwrap(), lines.nth(2).unwrap());
+        source.insert("imu0/data.csv", edited.as_bytes());
+
+        let data = EuRoC::from_source(Arc::new(source), ".")?;
+        let expected = EuRoC::new("test_data")?;
+        assert_eq!(data.sensors()?, expected.sensors()?);
+        assert_eq!(data.imu()?.len()?, 1);
+        assert_eq!(
+            data.imu()?.records()?.next().unwrap()?.gyro,
+            expected.imu()?.records()?.nth(2).unwrap()?.gyro
+        );
+        assert_eq!(
+            data.left_camera()?.config(),
+            expected.left_camera()?.config()
+        );
+        assert!(matches!(
+            data.source().open(Path::new("imu1/data.csv")),
+            Err(EurocError::MissingFile(_))
+        ));
+
+        Ok(())
+    }
+}
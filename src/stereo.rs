@@ -0,0 +1,256 @@
+use std::convert::TryInto;
+
+use image::{DynamicImage, GrayImage, Luma};
+use nalgebra as na;
+
+use crate::{CameraRecords, EuRoC, EurocError, PinholeRadTan, Result};
+
+const MAP_MAGIC: &[u8] = b"EUROCMAP";
+
+/// Pixel lookup table from a rectified image into the raw image.
+#[derive(Debug, Clone)]
+pub struct RectifyMap {
+    width: u32,
+    height: u32,
+    /// raw pixel coordinates, row-major, NaN where the ray misses the camera
+    map: Vec<(f32, f32)>,
+}
+
+impl RectifyMap {
+    /// Build a map from the raw pixel of every output pixel `(u, v)`, `None` if there is none.
+    pub fn from_fn<F>(width: u32, height: u32, f: F) -> Self
+    where
+        F: Fn(u32, u32) -> Option<na::Point2<f64>>,
+    {
+        let mut map = Vec::with_capacity((width * height) as usize);
+        for v in 0..height {
+            for u in 0..width {
+                map.push(f(u, v).map_or((f32::NAN, f32::NAN), |p| (p.x as f32, p.y as f32)));
+            }
+        }
+
+        Self { width, height, map }
+    }
+
+    /// Return the raw pixel sampled for rectified pixel `(u, v)`.
+    pub fn get(&self, u: u32, v: u32) -> (f32, f32) {
+        self.map[(v * self.width + u) as usize]
+    }
+
+    /// Serialize as `EUROCMAP`, width and height as `u32`, then the map as
+    /// pairs of `f32`, all little endian.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MAP_MAGIC.len() + 8 + self.map.len() * 8);
+        bytes.extend_from_slice(MAP_MAGIC);
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        for (x, y) in &self.map {
+            bytes.extend_from_slice(&x.to_le_bytes());
+            bytes.extend_from_slice(&y.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Parse the output of [`RectifyMap::to_bytes`].
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let error = || EurocError::InvalidInput("malformed rectification map".to_owned());
+        let word = |i: usize| -> Result<[u8; 4]> {
+            let i = MAP_MAGIC.len() + 4 * i;
+            bytes
+                .get(i..i + 4)
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(error)
+        };
+        if !bytes.starts_with(MAP_MAGIC) {
+            return Err(error());
+        }
+        let width = u32::from_le_bytes(word(0)?);
+        let height = u32::from_le_bytes(word(1)?);
+        let len = width as usize * height as usize;
+        if bytes.len() != MAP_MAGIC.len() + 8 + len * 8 {
+            return Err(error());
+        }
+        let map = (0..len)
+            .map(|i| {
+                Ok((
+                    f32::from_le_bytes(word(2 + 2 * i)?),
+                    f32::from_le_bytes(word(3 + 2 * i)?),
+                ))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { width, height, map })
+    }
+
+    /// Resample `raw` with bilinear interpolation; pixels mapping outside the image are black.
+    pub fn remap(&self, raw: &GrayImage) -> GrayImage {
+        GrayImage::from_fn(self.width, self.height, |u, v| {
+            let (x, y) = self.get(u, v);
+            Luma([bilinear(raw, x, y).unwrap_or(0)])
+        })
+    }
+}
+
+/// Sample `image` at `(x, y)` with bilinear interpolation, or `None` outside of it.
+pub fn bilinear(image: &GrayImage, x: f32, y: f32) -> Option<u8> {
+    let (width, height) = image.dimensions();
+    if !(x >= 0.0 && y >= 0.0 && x <= (width - 1) as f32 && y <= (height - 1) as f32) {
+        return None;
+    }
+
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (ax, ay) = (x - x0 as f32, y - y0 as f32);
+    let p = |x, y| f32::from(image.get_pixel(x, y)[0]);
+
+    let top = (p(x1, y0) - p(x0, y0)).mul_add(ax, p(x0, y0));
+    let bottom = (p(x1, y1) - p(x0, y1)).mul_add(ax, p(x0, y1));
+    Some((bottom - top).mul_add(ay, top).round() as u8)
+}
+
+/// Rectification of the EuRoC stereo pair.
+///
+/// Both rectified cameras share the intrinsics of `p_left` and are related by a
+/// pure translation of `baseline` along their x axis, so that corresponding
+/// points lie on the same image row.
+#[derive(Debug, Clone)]
+pub struct StereoRig {
+    /// rotation from the left camera frame to the rectified frame
+    pub r_left: na::Rotation3<f64>,
+    /// rotation from the right camera frame to the rectified frame
+    pub r_right: na::Rotation3<f64>,
+    /// projection matrix of the rectified left camera
+    pub p_left: na::Matrix3x4<f64>,
+    /// projection matrix of the rectified right camera
+    pub p_right: na::Matrix3x4<f64>,
+    /// distance between the camera centers (m)
+    pub baseline: f64,
+    left_map: RectifyMap,
+    right_map: RectifyMap,
+}
+
+impl StereoRig {
+    /// `left` and `right` must be radial-tangential pinhole cameras.
+    pub fn new(left: &CameraRecords, right: &CameraRecords) -> Result<Self> {
+        let t_lr = left.extrinsics_isometry()?.inverse() * right.extrinsics_isometry()?;
+        let center = t_lr.translation.vector;
+        let baseline = center.norm();
+
+        let z = na::Vector3::z();
+        let e1 = center / baseline;
+        let e2 = (z + t_lr.rotation * z).cross(&e1).normalize();
+        let e3 = e1.cross(&e2);
+        let r_left = na::Rotation3::from_matrix_unchecked(na::Matrix3::from_rows(&[
+            e1.transpose(),
+            e2.transpose(),
+            e3.transpose(),
+        ]));
+        let r_right = r_left * t_lr.rotation.to_rotation_matrix();
+
+        let (l, r) = (left.camera_model()?, right.camera_model()?);
+        let f = (l.intrinsics.0 + l.intrinsics.1 + r.intrinsics.0 + r.intrinsics.1) / 4.0;
+        let cu = (l.intrinsics.2 + r.intrinsics.2) / 2.0;
+        let cv = (l.intrinsics.3 + r.intrinsics.3) / 2.0;
+        let p_left = na::Matrix3x4::new(f, 0.0, cu, 0.0, 0.0, f, cv, 0.0, 0.0, 0.0, 1.0, 0.0);
+        let mut p_right = p_left;
+        p_right[(0, 3)] = -f * baseline;
+
+        Ok(Self {
+            r_left,
+            r_right,
+            p_left,
+            p_right,
+            baseline,
+            left_map: rectify_map(&l, &r_left, &p_left),
+            right_map: rectify_map(&r, &r_right, &p_left),
+        })
+    }
+
+    /// Return lookup tables of the (left, right) camera.
+    pub const fn maps(&self) -> (&RectifyMap, &RectifyMap) {
+        (&self.left_map, &self.right_map)
+    }
+
+    /// Undistort and rectify a (left, right) pair of images, converted to grayscale.
+    pub fn rectify(
+        &self,
+        left: &DynamicImage,
+        right: &DynamicImage,
+    ) -> (DynamicImage, DynamicImage) {
+        (
+            DynamicImage::ImageLuma8(self.left_map.remap(&left.to_luma8())),
+            DynamicImage::ImageLuma8(self.right_map.remap(&right.to_luma8())),
+        )
+    }
+}
+
+fn rectify_map(
+    model: &PinholeRadTan,
+    rotation: &na::Rotation3<f64>,
+    projection: &na::Matrix3x4<f64>,
+) -> RectifyMap {
+    let (width, height) = model.resolution;
+    let (f, cu, cv) = (projection[(0, 0)], projection[(0, 2)], projection[(1, 2)]);
+    let to_raw = rotation.inverse();
+
+    RectifyMap::from_fn(width, height, |u, v| {
+        let ray = na::Vector3::new((u as f64 - cu) / f, (v as f64 - cv) / f, 1.0);
+        model.project(&(to_raw * ray).into())
+    })
+}
+
+impl EuRoC {
+    /// Build the [`StereoRig`] of `cam0` and `cam1`.
+    pub fn stereo_rig(&self) -> Result<StereoRig> {
+        StereoRig::new(&self.left_camera()?, &self.right_camera()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::GenericImageView;
+
+    use super::*;
+
+    #[test]
+    fn rectified_rows_match() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let rig = data.stereo_rig()?;
+        assert!((rig.baseline - 0.11).abs() < 0.001);
+        assert!((rig.p_right[(0, 3)] / rig.p_left[(0, 0)] + rig.baseline).abs() < 1e-9);
+
+        // a point in front of the rig projects to the same row in both rectified images
+        let point = na::Point3::new(0.3, -0.2, 2.0);
+        let project = |p: &na::Matrix3x4<f64>| {
+            let x = p * point.to_homogeneous();
+            (x.x / x.z, x.y / x.z)
+        };
+        let (ul, vl) = project(&rig.p_left);
+        let (ur, vr) = project(&rig.p_right);
+        assert!((vl - vr).abs() < 1e-9);
+        assert!(ul > ur);
+
+        // and the left map samples the raw projection of the same point
+        let model = data.left_camera()?.camera_model()?;
+        let raw = model.project(&(rig.r_left.inverse() * point)).unwrap();
+        let (mx, my) = rig.maps().0.get(ul.round() as u32, vl.round() as u32);
+        assert!((f64::from(mx) - raw.x).abs() < 1.0);
+        assert!((f64::from(my) - raw.y).abs() < 1.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rectify_images() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let rig = data.stereo_rig()?;
+        let left = data.left_camera()?.records()?.next().unwrap()?.image;
+        let right = data.right_camera()?.records()?.next().unwrap()?.image;
+
+        let (left, right) = rig.rectify(&left, &right);
+        assert_eq!(left.dimensions(), (752, 480));
+        assert_eq!(right.dimensions(), (752, 480));
+
+        Ok(())
+    }
+}
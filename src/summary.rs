@@ -0,0 +1,115 @@
+use nalgebra as na;
+
+use crate::{
+    estimate_rate, Dropout, DropoutStats, Duration, EuRoC, RateEstimate, Result, Sensor, Timestamp,
+};
+
+/// Statistics of one sensor stream, see [`EuRoC::summary`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SensorSummary {
+    pub sensor: Sensor,
+    pub records: usize,
+    pub start: Option<Timestamp>,
+    pub end: Option<Timestamp>,
+    pub duration: Duration,
+    /// `None` with fewer than two records
+    pub rate: Option<RateEstimate>,
+    /// gaps longer than 1.5 nominal intervals, see [`DropoutStats`]
+    pub dropouts: Vec<Dropout>,
+}
+
+/// Statistics of a sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetSummary {
+    /// the sensors present, in [`Sensor::ALL`] order
+    pub sensors: Vec<SensorSummary>,
+    /// length of the ground truth trajectory (m), if available
+    pub trajectory_length: Option<f64>,
+}
+
+impl DatasetSummary {
+    pub fn sensor(&self, sensor: Sensor) -> Option<&SensorSummary> {
+        self.sensors.iter().find(|s| s.sensor == sensor)
+    }
+}
+
+impl EuRoC {
+    /// Summarize every sensor present, e.g. for sanity checks or paper tables.
+    ///
+    /// This reads every `data.csv`, but decodes no images.
+    pub fn summary(&self) -> Result<DatasetSummary> {
+        let mut sensors = Vec::new();
+        for &sensor in Sensor::ALL.iter() {
+            if !self.source.is_dir(&self.sensor_dir(sensor)) {
+                continue;
+            }
+
+            let timestamps = self.timestamps(sensor)?;
+            let (start, end) = (timestamps.first().copied(), timestamps.last().copied());
+            let dropouts = DropoutStats::new("", sensor, self.nominal_hz(sensor)?, &timestamps)
+                .map(|stats| stats.dropouts)
+                .unwrap_or_default();
+            sensors.push(SensorSummary {
+                sensor,
+                records: timestamps.len(),
+                start,
+                end,
+                duration: match (start, end) {
+                    (Some(start), Some(end)) => end - start,
+                    _ => Duration::ZERO,
+                },
+                rate: estimate_rate(timestamps),
+                dropouts,
+            });
+        }
+
+        let trajectory_length = if self.source.is_dir(&self.sensor_dir(Sensor::GroundTruth)) {
+            let mut length = 0.0;
+            let mut previous: Option<na::Vector3<f64>> = None;
+            for record in self.ground_truth()?.records()? {
+                let position = record?.position;
+                if let Some(previous) = previous {
+                    length += (position - previous).norm();
+                }
+                previous = Some(position);
+            }
+            Some(length)
+        } else {
+            None
+        };
+
+        Ok(DatasetSummary {
+            sensors,
+            trajectory_length,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn summary() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let summary = data.summary()?;
+        assert_eq!(summary.sensors.len(), Sensor::ALL.len());
+
+        let cam0 = summary.sensor(Sensor::Cam0).unwrap();
+        assert_eq!(cam0.records, 5);
+        assert_eq!(cam0.start, Some(1403636579763555584.into()));
+        assert_eq!(cam0.duration, Duration::from_nsecs(200_000_000));
+        assert!((cam0.rate.unwrap().median_hz() - 20.0).abs() < 0.01);
+        assert!(cam0.dropouts.is_empty());
+
+        let records = data
+            .ground_truth()?
+            .records()?
+            .collect::<Result<Vec<_>>>()?;
+        let expected = (records[4].position - records[0].position).norm();
+        let length = summary.trajectory_length.unwrap();
+        assert!(length >= expected - 1e-12 && length > 0.0);
+
+        Ok(())
+    }
+}
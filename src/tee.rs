@@ -0,0 +1,193 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use crate::{EurocError, Result};
+
+#[derive(Debug)]
+struct Shared<I, T> {
+    source: I,
+    /// records pulled from `source` but not yet yielded by each branch,
+    /// `None` once the branch is dropped
+    buffers: Vec<Option<VecDeque<Result<T>>>>,
+    capacity: usize,
+    done: bool,
+}
+
+/// One of the streams returned by [`tee`].
+#[derive(Debug)]
+pub struct TeeBranch<I, T> {
+    shared: Rc<RefCell<Shared<I, T>>>,
+    index: usize,
+}
+
+/// Duplicate a record stream into `branches` streams that can be consumed in any interleaving.
+///
+/// Each branch buffers the records the others have already pulled, up to
+/// `capacity`. A branch that would push another one past its capacity yields
+/// an error instead, and can continue once the other branch has caught up.
+/// An error of `records` is yielded by the branch that pulled it and reported
+/// to the others, after which every branch ends. Dropping a branch releases
+/// its buffer, so it no longer holds the others back.
+pub fn tee<I, T>(records: I, branches: usize, capacity: usize) -> Vec<TeeBranch<I::IntoIter, T>>
+where
+    I: IntoIterator<Item = Result<T>>,
+    T: Clone,
+{
+    let shared = Rc::new(RefCell::new(Shared {
+        source: records.into_iter(),
+        buffers: (0..branches).map(|_| Some(VecDeque::new())).collect(),
+        capacity,
+        done: false,
+    }));
+
+    (0..branches)
+        .map(|index| TeeBranch {
+            shared: Rc::clone(&shared),
+            index,
+        })
+        .collect()
+}
+
+impl<I, T> Iterator for TeeBranch<I, T>
+where
+    I: Iterator<Item = Result<T>>,
+    T: Clone,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut shared = self.shared.borrow_mut();
+        if let Some(record) = shared.buffers[self.index]
+            .as_mut()
+            .and_then(VecDeque::pop_front)
+        {
+            return Some(record);
+        }
+        if shared.done {
+            return None;
+        }
+
+        let capacity = shared.capacity;
+        if let Some(lagging) = shared
+            .buffers
+            .iter()
+            .position(|buffer| buffer.as_ref().map_or(false, |b| b.len() >= capacity))
+        {
+            return Some(Err(EurocError::InvalidInput(format!(
+                "tee branch {} is {} records behind",
+                lagging, capacity
+            ))));
+        }
+
+        let index = self.index;
+        match shared.source.next() {
+            Some(Ok(record)) => {
+                for (i, buffer) in shared.buffers.iter_mut().enumerate() {
+                    if let Some(buffer) = buffer.as_mut().filter(|_| i != index) {
+                        buffer.push_back(Ok(record.clone()));
+                    }
+                }
+                Some(Ok(record))
+            }
+            Some(Err(e)) => {
+                shared.done = true;
+                for (i, buffer) in shared.buffers.iter_mut().enumerate() {
+                    if let Some(buffer) = buffer.as_mut().filter(|_| i != index) {
+                        buffer.push_back(Err(EurocError::InvalidInput(format!(
+                            "tee source failed: {}",
+                            e
+                        ))));
+                    }
+                }
+                Some(Err(e))
+            }
+            None => {
+                shared.done = true;
+                None
+            }
+        }
+    }
+}
+
+impl<I, T> Drop for TeeBranch<I, T> {
+    fn drop(&mut self) {
+        // only fails while a branch is unwinding out of `next`
+        if let Ok(mut shared) = self.shared.try_borrow_mut() {
+            shared.buffers[self.index] = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EuRoC, Timestamped};
+
+    #[test]
+    fn interleaved() -> Result<()> {
+        let imu = EuRoC::new("test_data")?.imu()?;
+        let mut branches = tee(imu.records()?, 3, 8);
+        let (mut evaluation, mut logging, export) =
+            (branches.remove(0), branches.remove(0), branches.remove(0));
+
+        let first = evaluation.next().unwrap()?;
+        let second = evaluation.next().unwrap()?;
+        assert_eq!(logging.next().unwrap()?.timestamp, first.timestamp);
+
+        let exported = export.collect::<Result<Vec<_>>>()?;
+        assert_eq!(exported.len(), imu.records()?.count());
+        assert_eq!(exported[1].timestamp(), second.timestamp);
+
+        assert_eq!(evaluation.count() + 2, exported.len());
+        assert_eq!(logging.count() + 1, exported.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn bounded() {
+        let mut branches = tee((0..5).map(Ok), 2, 2);
+        let (mut fast, mut slow) = (branches.remove(0), branches.remove(0));
+
+        assert_eq!(fast.next().unwrap().unwrap(), 0);
+        assert_eq!(fast.next().unwrap().unwrap(), 1);
+        assert!(matches!(
+            fast.next(),
+            Some(Err(EurocError::InvalidInput(_)))
+        ));
+
+        assert_eq!(slow.next().unwrap().unwrap(), 0);
+        assert_eq!(fast.next().unwrap().unwrap(), 2);
+    }
+
+    #[test]
+    fn dropped_branch() {
+        let mut branches = tee((0..10).map(Ok), 3, 2);
+        let logging = branches.remove(2).take(1);
+        assert_eq!(logging.count(), 1);
+
+        // the dropped branch would have been past capacity after the third record
+        let (first, second) = (branches.remove(0), branches.remove(0));
+        let both = first.zip(second).collect::<Vec<_>>();
+        assert_eq!(both.len(), 10);
+        assert!(both.iter().all(|(a, b)| a.is_ok() && b.is_ok()));
+    }
+
+    #[test]
+    fn source_error() {
+        let records = vec![
+            Ok(0),
+            Err(EurocError::InvalidInput("bad".to_owned())),
+            Ok(2),
+        ];
+        let mut branches = tee(records, 2, 4);
+        let (mut first, mut second) = (branches.remove(0), branches.remove(0));
+
+        assert_eq!(first.next().unwrap().unwrap(), 0);
+        assert!(first.next().unwrap().is_err());
+        assert!(first.next().is_none());
+
+        assert_eq!(second.next().unwrap().unwrap(), 0);
+        assert!(second.next().unwrap().is_err());
+        assert!(second.next().is_none());
+    }
+}
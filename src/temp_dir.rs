@@ -0,0 +1,39 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Folder of a test, removed on drop also when the test fails.
+///
+/// Named after the test and the process, so that concurrent runs do not collide.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create an empty `euroc_<name>_<pid>` folder in the system temp dir.
+    pub fn new(name: &str) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("euroc_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
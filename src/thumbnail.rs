@@ -0,0 +1,72 @@
+use image::{imageops, DynamicImage, GrayImage};
+
+use crate::{CameraRecords, EurocError, Result};
+
+impl CameraRecords {
+    /// Return a horizontal strip of every `every`-th frame, downscaled by `scale`.
+    ///
+    /// e.g. `thumbnail_strip(10, 8)` lays out frames 0, 10, 20, ... at 1/8 resolution
+    /// side by side, which can be saved as a single preview image of the sequence.
+    pub fn thumbnail_strip(&self, every: usize, scale: u32) -> Result<DynamicImage> {
+        if every == 0 || scale == 0 {
+            return Err(EurocError::InvalidInput(
+                "every and scale must be positive".to_owned(),
+            ));
+        }
+
+        let (width, height) = self.image_size()?;
+        let (thumb_width, thumb_height) = ((width / scale).max(1), (height / scale).max(1));
+
+        // skip entries rather than records, so that only the kept frames are decoded
+        let thumbs = self
+            .entries()?
+            .step_by(every)
+            .map(|entry| {
+                let image = entry?.load()?.image;
+                Ok(image.thumbnail_exact(thumb_width, thumb_height).to_luma8())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut strip = GrayImage::new(thumb_width * thumbs.len() as u32, thumb_height);
+        for (i, thumb) in thumbs.iter().enumerate() {
+            imageops::replace(&mut strip, thumb, thumb_width * i as u32, 0);
+        }
+
+        Ok(DynamicImage::ImageLuma8(strip))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use image::GenericImageView;
+
+    use super::*;
+    use crate::{EuRoC, MemorySource, Sensor};
+
+    #[test]
+    fn thumbnail_strip() -> Result<()> {
+        let data = EuRoC::new("test_data")?.left_camera()?;
+        let strip = data.thumbnail_strip(2, 8)?;
+
+        assert_eq!(strip.dimensions(), (94 * 3, 60));
+
+        Ok(())
+    }
+
+    #[test]
+    fn skipped_frames_are_not_decoded() -> Result<()> {
+        let mut source = MemorySource::from_dir("test_data")?;
+        let frames = EuRoC::new("test_data")?.timestamps(Sensor::Cam0)?;
+        for ts in [frames[1], frames[3]].iter() {
+            source.insert(format!("cam0/data/{}.png", ts.nsecs()), &b"not a png"[..]);
+        }
+        let camera = EuRoC::from_source(Arc::new(source), "")?.left_camera()?;
+
+        assert_eq!(camera.thumbnail_strip(2, 8)?.dimensions(), (94 * 3, 60));
+        assert!(camera.thumbnail_strip(1, 8).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{Duration, EuRoC, EventIterator, Result, Sensor, SensorEvent, Timestamp, Timestamped};
+
+/// Event of one sequence of a [`Timeline`].
+#[derive(Debug, Clone)]
+pub struct TimelineEvent {
+    /// index into the sequences of the timeline
+    pub sequence: usize,
+    /// timestamp in the original sequence
+    pub original: Timestamp,
+    /// record carrying its timestamp on the shared timeline
+    pub event: SensorEvent,
+}
+
+impl Timestamped for TimelineEvent {
+    fn timestamp(&self) -> Timestamp {
+        self.event.timestamp()
+    }
+}
+
+/// Events of several sequences played back to back on one synthetic timeline.
+///
+/// The first sequence keeps its timestamps. Every further sequence starts
+/// `gap` after the last event of the previous one, keeping its own spacing.
+///
+/// Errors are yielded in place of the events they replace, and iteration
+/// goes on: past a bad row within its sequence, and past a sequence that
+/// cannot be opened to the next one.
+pub struct Timeline {
+    sequences: Vec<EuRoC>,
+    sensors: Vec<Sensor>,
+    gap: Duration,
+    current: Option<(usize, EventIterator)>,
+    /// index of the sequence opened after `current`
+    next: usize,
+    /// (original, shared) start of the current sequence
+    start: Option<(Timestamp, Timestamp)>,
+    last: Option<Timestamp>,
+}
+
+/// Concatenate the events of `sensors` over `sequences`, see [`Timeline`].
+pub fn timeline(sequences: &[EuRoC], sensors: &[Sensor], gap: Duration) -> Timeline {
+    Timeline {
+        sequences: sequences.to_vec(),
+        sensors: sensors.to_vec(),
+        gap,
+        current: None,
+        next: 0,
+        start: None,
+        last: None,
+    }
+}
+
+impl Timeline {
+    fn advance(&mut self) -> Result<Option<(usize, SensorEvent)>> {
+        loop {
+            if let Some((i, events)) = &mut self.current {
+                if let Some(event) = events.next().transpose()? {
+                    return Ok(Some((*i, event)));
+                }
+            }
+
+            self.current = None;
+            if self.next == self.sequences.len() {
+                return Ok(None);
+            }
+            let i = self.next;
+            self.next += 1;
+            self.start = None;
+            self.current = Some((i, self.sequences[i].events(&self.sensors)?));
+        }
+    }
+}
+
+impl Iterator for Timeline {
+    type Item = Result<TimelineEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (sequence, mut event) = match self.advance() {
+            Ok(next) => next?,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let original = event.timestamp();
+        let (gap, last) = (self.gap, self.last);
+        let (from, to) = *self
+            .start
+            .get_or_insert_with(|| (original, last.map_or(original, |last| last + gap)));
+        let shared = to + (original - from);
+        self.last = Some(shared);
+        event.set_timestamp(shared);
+
+        Some(Ok(TimelineEvent {
+            sequence,
+            original,
+            event,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn concatenated() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let sequences = vec![data.clone(), data];
+        let gap = Duration::from_millis(100);
+
+        let events =
+            timeline(&sequences, &[Sensor::Cam0, Sensor::Imu0], gap).collect::<Result<Vec<_>>>()?;
+        assert_eq!(events.len(), 20);
+        assert!(events
+            .windows(2)
+            .all(|w| w[0].timestamp() <= w[1].timestamp()));
+
+        let second = events.iter().position(|e| e.sequence == 1).unwrap();
+        assert_eq!(second, 10);
+        assert_eq!(events[0].timestamp(), events[0].original);
+        assert_eq!(events[second].original, events[0].original);
+        assert_eq!(
+            events[second].timestamp(),
+            events[second - 1].timestamp() + gap
+        );
+        // spacing within a sequence is kept
+        assert_eq!(
+            events[19].timestamp() - events[second].timestamp(),
+            events[9].original - events[0].original
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn errors_do_not_end_the_timeline() -> Result<()> {
+        use std::{path::Path, sync::Arc};
+
+        use crate::{DataSource, MemorySource};
+
+        let data = EuRoC::new("test_data")?;
+        let mut source = MemorySource::from_dir("test_data")?;
+        let csv = source.read_to_string(Path::new("imu0/data.csv"))?;
+        let mut lines: Vec<_> = csv.lines().collect();
+        lines[2] = "not,a,row";
+        source.insert(
+            "imu0/data.csv",
+            format!("{}\n", lines.join("\n")).as_bytes(),
+        );
+        let bad_row = EuRoC::from_source(Arc::new(source), "")?;
+        let mut source = MemorySource::new();
+        source.insert("body.yaml", &b"comment: empty"[..]);
+        let no_imu = EuRoC::from_source(Arc::new(source), "")?;
+
+        let sequences = vec![bad_row, no_imu, data];
+        let results: Vec<_> =
+            timeline(&sequences, &[Sensor::Imu0], Duration::from_millis(100)).collect();
+        assert_eq!(results.len(), 4 + 1 + 1 + 5);
+        assert!(results[1].is_err());
+        assert!(results[5].is_err());
+        let events = results
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+        assert_eq!(events.len(), 4 + 5);
+        assert_eq!(events[4].sequence, 2);
+        assert!(events
+            .windows(2)
+            .all(|w| w[0].timestamp() < w[1].timestamp()));
+
+        Ok(())
+    }
+}
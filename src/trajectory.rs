@@ -0,0 +1,401 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use nalgebra as na;
+
+use crate::{EurocError, Result, Timestamp};
+
+/// File format of an estimated trajectory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrajectoryFormat {
+    /// `timestamp tx ty tz qx qy qz qw`, as written by [`crate::write_tum`]
+    Tum,
+    /// `timestamp,p_x,p_y,p_z,q_w,q_x,q_y,q_z[,...]`, like the ground-truth `data.csv`
+    EurocCsv,
+    /// 12 values per line, the row-major 3x4 matrix `[R | t]`, without timestamps
+    Kitti,
+}
+
+impl TrajectoryFormat {
+    /// Detect the format from the first line of `text` that is neither empty nor a `#` comment.
+    ///
+    /// Comma-separated lines of at least 8 columns are EuRoC CSV; otherwise
+    /// 8 columns are TUM and 12 are KITTI.
+    pub fn detect(text: &str) -> Option<Self> {
+        let line = data_lines(text).next()?.1;
+        if line.contains(',') {
+            return if line.split(',').count() >= 8 {
+                Some(Self::EurocCsv)
+            } else {
+                None
+            };
+        }
+        match line.split_whitespace().count() {
+            8 => Some(Self::Tum),
+            12 => Some(Self::Kitti),
+            _ => None,
+        }
+    }
+}
+
+/// Unit of the timestamps of an estimated trajectory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    /// e.g. `1403636579.763555584`
+    Seconds,
+    /// e.g. `1403636579763555584`, like the EuRoC `data.csv` files
+    Nanoseconds,
+}
+
+impl TimeUnit {
+    /// Detect the unit of a timestamp since the Unix epoch from its magnitude.
+    ///
+    /// Anything below 10^12 is taken as seconds, which covers both integer
+    /// and fractional seconds of any recording made before the year 33000.
+    pub fn detect(timestamp: &str) -> Option<Self> {
+        let value: f64 = timestamp.parse().ok()?;
+        if !value.is_finite() || value < 0.0 {
+            None
+        } else if value < 1e12 {
+            Some(Self::Seconds)
+        } else {
+            Some(Self::Nanoseconds)
+        }
+    }
+
+    fn parse(self, timestamp: &str) -> Option<Timestamp> {
+        match self {
+            Self::Seconds => parse_seconds(timestamp),
+            Self::Nanoseconds => timestamp
+                .parse::<u64>()
+                .map(Timestamp::new)
+                .ok()
+                .or_else(|| {
+                    let nsecs: f64 = timestamp.parse().ok()?;
+                    if nsecs.is_finite() && nsecs >= 0.0 {
+                        Some(Timestamp::new(nsecs.round() as u64))
+                    } else {
+                        None
+                    }
+                }),
+        }
+    }
+}
+
+/// Options of [`read_trajectory`].
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// format of the file, detected with [`TrajectoryFormat::detect`] if `None`
+    pub format: Option<TrajectoryFormat>,
+    /// unit of the timestamps, detected from the first one with
+    /// [`TimeUnit::detect`] if `None`; either way the whole file uses one unit
+    pub time_unit: Option<TimeUnit>,
+    /// timestamps of the poses of a KITTI file, which stores none; usually the
+    /// camera frames of the sequence, e.g. `data.timestamps(Sensor::Cam0)?`
+    pub frame_timestamps: Vec<Timestamp>,
+}
+
+/// Read an estimated trajectory, e.g. the output of a VIO system, for
+/// [`crate::evaluate_trajectory`].
+pub fn read_trajectory<P: AsRef<Path>>(
+    path: P,
+    options: &ImportOptions,
+) -> Result<Vec<(Timestamp, na::Isometry3<f64>)>> {
+    let path = path.as_ref();
+    parse_trajectory(&fs::read_to_string(path)?, path, options)
+}
+
+fn parse_trajectory(
+    text: &str,
+    path: &Path,
+    options: &ImportOptions,
+) -> Result<Vec<(Timestamp, na::Isometry3<f64>)>> {
+    let format = match options.format {
+        Some(format) => format,
+        None => TrajectoryFormat::detect(text).ok_or_else(|| {
+            EurocError::InvalidInput(format!(
+                "{}: cannot detect the trajectory format",
+                path.display()
+            ))
+        })?,
+    };
+
+    let mut time_unit = options.time_unit;
+    let mut poses = Vec::new();
+    for (i, (line_no, line)) in data_lines(text).enumerate() {
+        let error = |message: &str| EurocError::CsvParse {
+            path: PathBuf::from(path),
+            line: line_no,
+            message: message.to_owned(),
+        };
+        let fields: Vec<_> = match format {
+            TrajectoryFormat::EurocCsv => line.split(',').map(str::trim).collect(),
+            TrajectoryFormat::Tum | TrajectoryFormat::Kitti => line.split_whitespace().collect(),
+        };
+        let numbers = |range: std::ops::Range<usize>| -> Result<Vec<f64>> {
+            fields
+                .get(range)
+                .ok_or_else(|| error("too few columns"))?
+                .iter()
+                .map(|v| v.parse().map_err(|_| error("invalid number")))
+                .collect()
+        };
+
+        let mut timestamp = || {
+            let unit = match time_unit {
+                Some(unit) => unit,
+                None => *time_unit.get_or_insert(
+                    TimeUnit::detect(fields[0]).ok_or_else(|| error("invalid timestamp"))?,
+                ),
+            };
+            unit.parse(fields[0])
+                .ok_or_else(|| error("invalid timestamp"))
+        };
+
+        let pose = match format {
+            TrajectoryFormat::Tum => {
+                let v = numbers(1..8)?;
+                (
+                    timestamp()?,
+                    isometry(&v[..3], na::Quaternion::new(v[6], v[3], v[4], v[5])),
+                )
+            }
+            TrajectoryFormat::EurocCsv => {
+                let v = numbers(1..8)?;
+                (
+                    timestamp()?,
+                    isometry(&v[..3], na::Quaternion::new(v[3], v[4], v[5], v[6])),
+                )
+            }
+            TrajectoryFormat::Kitti => {
+                let v = numbers(0..12)?;
+                let ts = *options
+                    .frame_timestamps
+                    .get(i)
+                    .ok_or_else(|| error("no frame timestamp for this pose"))?;
+                let r = na::Matrix3::new(v[0], v[1], v[2], v[4], v[5], v[6], v[8], v[9], v[10]);
+                let rotation = na::UnitQuaternion::from_rotation_matrix(
+                    &na::Rotation3::from_matrix_eps(&r, 1e-12, 100, na::Rotation3::identity()),
+                );
+                (
+                    ts,
+                    na::Isometry3::from_parts(na::Translation3::new(v[3], v[7], v[11]), rotation),
+                )
+            }
+        };
+        poses.push(pose);
+    }
+
+    Ok(poses)
+}
+
+/// Return the 1-based line numbers and contents of the lines that are neither empty nor comments.
+fn data_lines(text: &str) -> impl Iterator<Item = (u64, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i as u64 + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+fn isometry(t: &[f64], q: na::Quaternion<f64>) -> na::Isometry3<f64> {
+    na::Isometry3::from_parts(
+        na::Translation3::new(t[0], t[1], t[2]),
+        na::UnitQuaternion::from_quaternion(q),
+    )
+}
+
+/// Parse seconds such as `1403636579.763555584` without losing nanoseconds to `f64`.
+fn parse_seconds(s: &str) -> Option<Timestamp> {
+    if s.contains(|c| c == 'e' || c == 'E') {
+        let secs: f64 = s.parse().ok()?;
+        return if secs.is_finite() && secs >= 0.0 {
+            Some(Timestamp::new((secs * 1e9).round() as u64))
+        } else {
+            None
+        };
+    }
+    let (int, frac) = s.find('.').map_or((s, ""), |i| (&s[..i], &s[i + 1..]));
+    if !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let int: u64 = if int.is_empty() { 0 } else { int.parse().ok()? };
+    let nanos = frac
+        .bytes()
+        .chain(std::iter::repeat(b'0'))
+        .take(9)
+        .fold(0, |acc, b| acc * 10 + u64::from(b - b'0'));
+
+    int.checked_mul(1_000_000_000)?
+        .checked_add(nanos)
+        .map(Timestamp::new)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{temp_dir::TempDir, EuRoC};
+
+    fn parse(text: &str, options: &ImportOptions) -> Result<Vec<(Timestamp, na::Isometry3<f64>)>> {
+        parse_trajectory(text, Path::new("estimate.txt"), options)
+    }
+
+    #[test]
+    fn detect() {
+        assert_eq!(
+            TrajectoryFormat::detect("# timestamp tx ty tz qx qy qz qw\n1.5 0 0 0 0 0 0 1\n"),
+            Some(TrajectoryFormat::Tum)
+        );
+        assert_eq!(
+            TrajectoryFormat::detect("#timestamp,p_x,p_y,p_z,q_w,q_x,q_y,q_z\n1,0,0,0,1,0,0,0\n"),
+            Some(TrajectoryFormat::EurocCsv)
+        );
+        assert_eq!(
+            TrajectoryFormat::detect("1 0 0 0 0 1 0 0 0 0 1 0\n"),
+            Some(TrajectoryFormat::Kitti)
+        );
+        assert_eq!(TrajectoryFormat::detect("1 2 3\n"), None);
+        assert_eq!(TrajectoryFormat::detect("# only a header\n"), None);
+    }
+
+    #[test]
+    fn formats() -> Result<()> {
+        let yaw = na::UnitQuaternion::from_euler_angles(0.0, 0.0, 0.5);
+        let expected = na::Isometry3::from_parts(na::Translation3::new(1.0, 2.0, 3.0), yaw);
+        let q = yaw.quaternion();
+        let r = yaw.to_rotation_matrix();
+
+        let tum = format!(
+            "1403636579.763555584 1 2 3 {} {} {} {}\n",
+            q.i, q.j, q.k, q.w
+        );
+        let csv = format!(
+            "1403636579763555584,1,2,3,{},{},{},{},0,0\n",
+            q.w, q.i, q.j, q.k
+        );
+        let kitti = format!(
+            "{} {} {} 1 {} {} {} 2 {} {} {} 3\n",
+            r[(0, 0)],
+            r[(0, 1)],
+            r[(0, 2)],
+            r[(1, 0)],
+            r[(1, 1)],
+            r[(1, 2)],
+            r[(2, 0)],
+            r[(2, 1)],
+            r[(2, 2)]
+        );
+        let options = ImportOptions {
+            frame_timestamps: vec![1403636579763555584.into()],
+            ..ImportOptions::default()
+        };
+
+        for text in [tum, csv, kitti].iter() {
+            let poses = parse(text, &options)?;
+            assert_eq!(poses.len(), 1);
+            assert_eq!(poses[0].0, 1403636579763555584.into());
+            assert!((poses[0].1.translation.vector - expected.translation.vector).norm() < 1e-12);
+            assert!(poses[0].1.rotation.angle_to(&expected.rotation) < 1e-12);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn explicit_format() {
+        let options = ImportOptions {
+            format: Some(TrajectoryFormat::EurocCsv),
+            ..ImportOptions::default()
+        };
+        assert!(matches!(
+            parse("1.5 0 0 0 0 0 0 1\n", &options),
+            Err(EurocError::CsvParse { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse("1 0 0 0 1 0 0 0 0 1 0 0\n", &ImportOptions::default()),
+            Err(EurocError::CsvParse { .. })
+        ));
+        assert!(matches!(
+            parse("1 2 3\n", &ImportOptions::default()),
+            Err(EurocError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn seconds() {
+        assert_eq!(parse_seconds("1.5"), Some(1_500_000_000.into()));
+        assert_eq!(parse_seconds("12"), Some(12_000_000_000.into()));
+        assert_eq!(parse_seconds("1.0000000019"), Some(1_000_000_001.into()));
+        assert_eq!(parse_seconds("1.5e3"), Some(1_500_000_000_000.into()));
+        assert_eq!(parse_seconds("-1.5"), None);
+        assert_eq!(parse_seconds("1.x"), None);
+    }
+
+    #[test]
+    fn time_units() -> Result<()> {
+        assert_eq!(
+            TimeUnit::detect("1403636579.763555584"),
+            Some(TimeUnit::Seconds)
+        );
+        assert_eq!(TimeUnit::detect("1403636579"), Some(TimeUnit::Seconds));
+        assert_eq!(
+            TimeUnit::detect("1403636579763555584"),
+            Some(TimeUnit::Nanoseconds)
+        );
+        assert_eq!(
+            TimeUnit::detect("1.403636579763e18"),
+            Some(TimeUnit::Nanoseconds)
+        );
+        assert_eq!(TimeUnit::detect("x"), None);
+
+        let expected: Vec<Timestamp> = vec![1403636579763555584.into(), 1403636579813555456.into()];
+        let texts = [
+            "1403636579.763555584 0 0 0 0 0 0 1\n1403636579.813555456 0 0 0 0 0 0 1\n",
+            "1403636579763555584 0 0 0 0 0 0 1\n1403636579813555456 0 0 0 0 0 0 1\n",
+            "1403636579.763555584,0,0,0,1,0,0,0\n1403636579.813555456,0,0,0,1,0,0,0\n",
+            "1403636579763555584,0,0,0,1,0,0,0\n1403636579813555456,0,0,0,1,0,0,0\n",
+        ];
+        for text in texts.iter() {
+            let poses = parse(text, &ImportOptions::default())?;
+            assert_eq!(poses.iter().map(|p| p.0).collect::<Vec<_>>(), expected);
+        }
+
+        // an explicit unit wins, and the first timestamp decides for the whole file
+        let options = ImportOptions {
+            time_unit: Some(TimeUnit::Nanoseconds),
+            ..ImportOptions::default()
+        };
+        assert_eq!(parse("1000 0 0 0 0 0 0 1\n", &options)?[0].0, 1000.into());
+        // as seconds, the nanoseconds of the second line overflow
+        assert!(matches!(
+            parse(
+                "1 0 0 0 0 0 0 1\n1403636579763555584 0 0 0 0 0 0 1\n",
+                &ImportOptions::default()
+            ),
+            Err(EurocError::CsvParse { line: 2, .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_ground_truth_tum() -> Result<()> {
+        let tmp = TempDir::new("read_trajectory")?;
+        let path = tmp.join("ground_truth.tum");
+        let gt = EuRoC::new("test_data")?.ground_truth()?;
+        gt.write_tum(&path)?;
+
+        let poses = read_trajectory(&path, &ImportOptions::default())?;
+        let evaluation = gt.evaluate_trajectory(
+            &poses,
+            crate::Duration::from_nsecs(0),
+            crate::Alignment::None,
+            &[],
+        )?;
+        assert_eq!(evaluation.ate.count, 5);
+        assert!(evaluation.ate.max < 1e-6);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+use std::{any::type_name, fmt, marker::PhantomData, ops::Mul};
+
+use nalgebra as na;
+
+use crate::{EuRoC, EurocError, GroundTruthRecord, Result, Sensor};
+
+/// Marker types naming the coordinate frames of a [`Transform`].
+pub mod frames {
+    use crate::Sensor;
+
+    /// Body frame that every `T_BS` refers to.
+    #[derive(Debug)]
+    pub enum Body {}
+
+    /// World frame of the ground truth.
+    #[derive(Debug)]
+    pub enum World {}
+
+    #[derive(Debug)]
+    pub enum Cam0 {}
+
+    #[derive(Debug)]
+    pub enum Cam1 {}
+
+    #[derive(Debug)]
+    pub enum Imu0 {}
+
+    #[derive(Debug)]
+    pub enum Leica0 {}
+
+    #[derive(Debug)]
+    pub enum Vicon0 {}
+
+    /// Frame of a sensor folder with a `T_BS`.
+    pub trait SensorFrame {
+        const SENSOR: Sensor;
+    }
+
+    impl SensorFrame for Cam0 {
+        const SENSOR: Sensor = Sensor::Cam0;
+    }
+
+    impl SensorFrame for Cam1 {
+        const SENSOR: Sensor = Sensor::Cam1;
+    }
+
+    impl SensorFrame for Imu0 {
+        const SENSOR: Sensor = Sensor::Imu0;
+    }
+
+    impl SensorFrame for Leica0 {
+        const SENSOR: Sensor = Sensor::Leica0;
+    }
+
+    impl SensorFrame for Vicon0 {
+        const SENSOR: Sensor = Sensor::Vicon0;
+    }
+}
+
+use self::frames::{Body, SensorFrame, World};
+
+/// Rigid body transform mapping points expressed in frame `From` into frame `To`.
+///
+/// In EuRoC notation `T_BS` is a `Transform<S, B>`. Composition only
+/// type-checks when the frames line up: `Transform<B, W> * Transform<S, B>`
+/// is a `Transform<S, W>`.
+pub struct Transform<From, To> {
+    isometry: na::Isometry3<f64>,
+    frames: PhantomData<fn(From) -> To>,
+}
+
+impl<From, To> Transform<From, To> {
+    /// Tag `isometry`, which must map points from `From` into `To`.
+    pub const fn new(isometry: na::Isometry3<f64>) -> Self {
+        Self {
+            isometry,
+            frames: PhantomData,
+        }
+    }
+
+    pub const fn isometry(&self) -> &na::Isometry3<f64> {
+        &self.isometry
+    }
+
+    pub fn to_homogeneous(&self) -> na::Matrix4<f64> {
+        self.isometry.to_homogeneous()
+    }
+
+    pub fn inverse(&self) -> Transform<To, From> {
+        Transform::new(self.isometry.inverse())
+    }
+
+    /// Map a point expressed in `From` into `To`.
+    pub fn transform_point(&self, point: &na::Point3<f64>) -> na::Point3<f64> {
+        self.isometry.transform_point(point)
+    }
+}
+
+impl<From, To> Clone for Transform<From, To> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<From, To> Copy for Transform<From, To> {}
+
+impl<From, To> PartialEq for Transform<From, To> {
+    fn eq(&self, other: &Self) -> bool {
+        self.isometry == other.isometry
+    }
+}
+
+impl<From, To> fmt::Debug for Transform<From, To> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transform")
+            .field("from", &type_name::<From>())
+            .field("to", &type_name::<To>())
+            .field("isometry", &self.isometry)
+            .finish()
+    }
+}
+
+impl<A, B, C> Mul<Transform<A, B>> for Transform<B, C> {
+    type Output = Transform<A, C>;
+
+    fn mul(self, rhs: Transform<A, B>) -> Transform<A, C> {
+        Transform::new(self.isometry * rhs.isometry)
+    }
+}
+
+impl EuRoC {
+    /// Return `T_BS` of the sensor `S`.
+    pub fn t_bs<S: SensorFrame>(&self) -> Result<Transform<S, Body>> {
+        self.frame_graph()?
+            .body_to(S::SENSOR)
+            .map(Transform::new)
+            .ok_or_else(|| missing_sensor(S::SENSOR))
+    }
+
+    /// Return the transform between two sensor frames, e.g.
+    /// `transform_between::<Cam1, Cam0>()` is the stereo extrinsic `T_C0C1`.
+    pub fn transform_between<From, To>(&self) -> Result<Transform<From, To>>
+    where
+        From: SensorFrame,
+        To: SensorFrame,
+    {
+        let graph = self.frame_graph()?;
+        match graph.transform(To::SENSOR, From::SENSOR) {
+            Some(t) => Ok(Transform::new(t)),
+            None if graph.body_to(From::SENSOR).is_none() => Err(missing_sensor(From::SENSOR)),
+            None => Err(missing_sensor(To::SENSOR)),
+        }
+    }
+}
+
+fn missing_sensor(sensor: Sensor) -> EurocError {
+    EurocError::InvalidInput(format!("no `{}` in dataset", sensor.dir_name()))
+}
+
+impl GroundTruthRecord {
+    /// Return body pose wrt. the world frame, see [`GroundTruthRecord::pose`].
+    pub fn t_wb(&self) -> Transform<Body, World> {
+        Transform::new(self.pose())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{frames::*, *};
+
+    #[test]
+    fn extrinsics() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+
+        let t_bc0 = data.t_bs::<Cam0>()?;
+        assert!((t_bc0.to_homogeneous() - data.left_camera()?.extrinsics()?).amax() < 1e-6);
+
+        let t_c0c1: Transform<Cam1, Cam0> = data.transform_between()?;
+        let t_bc1 = data.t_bs::<Cam1>()?;
+        assert!(((t_bc0 * t_c0c1).to_homogeneous() - t_bc1.to_homogeneous()).amax() < 1e-9);
+        assert!((t_c0c1.isometry().translation.vector.norm() - 0.11).abs() < 0.001);
+
+        let identity = t_c0c1 * t_c0c1.inverse();
+        assert!(identity.isometry().translation.vector.norm() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ground_truth_pose() -> Result<()> {
+        let data = EuRoC::new("test_data")?;
+        let record = data.ground_truth()?.records()?.next().unwrap()?;
+
+        let t_wb = record.t_wb();
+        let t_wc0 = t_wb * data.t_bs::<Cam0>()?;
+        let origin = t_wc0.transform_point(&na::Point3::origin());
+        let camera =
+            t_wb.transform_point(&data.t_bs::<Cam0>()?.isometry().translation.vector.into());
+        assert!((origin - camera).norm() < 1e-9);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use nalgebra as na;
+
+use crate::{GroundTruthData, Result, Timestamp};
+
+/// Write `poses` in the TUM trajectory format read by evo and the TUM RGB-D tools.
+///
+/// Each line is `timestamp tx ty tz qx qy qz qw`, with the timestamp in seconds.
+pub fn write_tum<W: Write>(mut writer: W, poses: &[(Timestamp, na::Isometry3<f64>)]) -> Result<()> {
+    writeln!(writer, "# timestamp tx ty tz qx qy qz qw")?;
+
+    for (ts, pose) in poses {
+        let t = &pose.translation.vector;
+        let q = &pose.rotation;
+        writeln!(
+            writer,
+            "{}.{:09} {} {} {} {} {} {} {}",
+            ts.nsecs() / 1_000_000_000,
+            ts.nsecs() % 1_000_000_000,
+            t.x,
+            t.y,
+            t.z,
+            q.i,
+            q.j,
+            q.k,
+            q.w
+        )?;
+    }
+
+    Ok(())
+}
+
+impl GroundTruthData {
+    /// Write the ground-truth trajectory in the TUM trajectory format.
+    pub fn write_tum<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let poses = self
+            .records()?
+            .map(|record| record.map(|r| (r.timestamp, r.pose())))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_tum(&mut writer, &poses)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use crate::EuRoC;
+
+    #[test]
+    fn write_tum() -> Result<()> {
+        let pose = na::Isometry3::from_parts(
+            na::Translation3::new(1.0, 2.0, 3.0),
+            na::UnitQuaternion::identity(),
+        );
+        let mut buf = Vec::new();
+        super::write_tum(&mut buf, &[(1403636579000000123.into(), pose)])?;
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "# timestamp tx ty tz qx qy qz qw\n1403636579.000000123 1 2 3 0 0 0 1\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn ground_truth_write_tum() -> Result<()> {
+        let tmp = TempDir::new("ground_truth")?;
+        let path = tmp.join("ground_truth.tum");
+        EuRoC::new("test_data")?.ground_truth()?.write_tum(&path)?;
+
+        let text = std::fs::read_to_string(&path)?;
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 1 + 5);
+        assert!(lines[1].starts_with("1403636580.838555648 4.688319 -1.786938 0.783338 "));
+        assert_eq!(lines[1].split(' ').count(), 8);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use std::{path::Path, str::FromStr};
+
+use crate::{EuRoC, EurocError, Result, Sensor};
+
+/// Number of images decoded per camera by [`Validation::Deep`].
+const SAMPLE_IMAGES: usize = 3;
+
+/// How thoroughly [`EuRoC::open`] checks the dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Validation {
+    /// Only check that the root is a directory, like [`EuRoC::new`].
+    None,
+    /// Check the files and calibration of every sensor folder present.
+    Shallow,
+    /// Additionally parse every `data.csv` and decode a sample of images.
+    Deep,
+}
+
+impl Default for Validation {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl Validation {
+    /// Return the name used in config files, e.g. `"shallow"`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Shallow => "shallow",
+            Self::Deep => "deep",
+        }
+    }
+}
+
+impl FromStr for Validation {
+    type Err = EurocError;
+
+    /// Parse a name returned by [`Validation::as_str`].
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "none" => Ok(Self::None),
+            "shallow" => Ok(Self::Shallow),
+            "deep" => Ok(Self::Deep),
+            _ => Err(EurocError::InvalidInput(format!(
+                "unknown validation `{}`, expected none, shallow or deep",
+                name
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OpenOptions {
+    pub validate: Validation,
+}
+
+impl EuRoC {
+    /// Open a dataset, validating it as requested by `options`.
+    pub fn open<P: AsRef<Path>>(root: P, options: &OpenOptions) -> Result<Self> {
+        let data = Self::new(root)?;
+        data.validate(options.validate)?;
+
+        Ok(data)
+    }
+
+    /// Check every sensor folder present as requested by `validation`.
+    pub(crate) fn validate(&self, validation: Validation) -> Result<()> {
+        if validation != Validation::None {
+            for &sensor in Sensor::ALL.iter() {
+                if self.source.is_dir(&self.sensor_dir(sensor)) {
+                    self.validate_sensor(sensor, validation == Validation::Deep)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_sensor(&self, sensor: Sensor, deep: bool) -> Result<()> {
+        fn parse_all<I, T>(mut records: I) -> Result<()>
+        where
+            I: Iterator<Item = Result<T>>,
+        {
+            records.try_for_each(|r| r.map(drop))
+        }
+
+        match sensor {
+            Sensor::Cam0 | Sensor::Cam1 => {
+                let camera = self.camera(sensor)?;
+                if deep {
+                    let entries = camera.entries()?.collect::<Result<Vec<_>>>()?;
+                    let step = (entries.len() / SAMPLE_IMAGES).max(1);
+                    for entry in entries.iter().step_by(step).take(SAMPLE_IMAGES) {
+                        entry.load()?;
+                    }
+                }
+            }
+            Sensor::Imu0 => {
+                let imu = self.imu()?;
+                if deep {
+                    parse_all(imu.records()?)?;
+                }
+            }
+            Sensor::Leica0 => {
+                let position = self.position()?;
+                if deep {
+                    parse_all(position.records()?)?;
+                }
+            }
+            Sensor::Vicon0 => {
+                let vicon = self.vicon()?;
+                if deep {
+                    parse_all(vicon.records()?)?;
+                }
+            }
+            Sensor::GroundTruth => {
+                let ground_truth = self.ground_truth()?;
+                if deep {
+                    parse_all(ground_truth.records()?)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn names() {
+        for &validate in [Validation::None, Validation::Shallow, Validation::Deep].iter() {
+            assert_eq!(validate.as_str().parse::<Validation>().ok(), Some(validate));
+        }
+        assert!("always".parse::<Validation>().is_err());
+    }
+
+    #[test]
+    fn open_deep() -> Result<()> {
+        let options = OpenOptions {
+            validate: Validation::Deep,
+        };
+        let _ = EuRoC::open("test_data", &options)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_detects_bad_rows() -> Result<()> {
+        let root = TempDir::new("open_detects_bad_rows")?;
+        fs::create_dir_all(root.join("leica0"))?;
+        fs::copy(
+            "test_data/leica0/sensor.yaml",
+            root.join("leica0/sensor.yaml"),
+        )?;
+        fs::write(
+            root.join("leica0/data.csv"),
+            "#timestamp [ns],p_RS_R_x [m],p_RS_R_y [m],p_RS_R_z [m]\n1,0.0,x,0.0\n",
+        )?;
+
+        let open = |validate| EuRoC::open(&root, &OpenOptions { validate });
+        assert!(open(Validation::Shallow).is_ok());
+        assert!(matches!(
+            open(Validation::Deep),
+            Err(EurocError::CsvParse { line: 2, .. })
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use std::{path::PathBuf, sync::Arc};
+
+use nalgebra as na;
+
+use crate::{
+    csv_row::{Checkpoint, IndexCache, IndexedRows, Row},
+    yaml::SensorYaml,
+    DataSource, ErrorPolicy, FileSystem, Result, Timestamp, Timestamped,
+};
+
+const DATA_CSV: &str = "data.csv";
+const SENSOR_YAML: &str = "sensor.yaml";
+
+#[derive(Debug, Clone)]
+pub struct ViconData {
+    source: Arc<dyn DataSource>,
+    path: PathBuf,
+    index: IndexCache,
+    on_error: ErrorPolicy,
+}
+
+impl ViconData {
+    pub fn new(path: PathBuf) -> Result<Self> {
+        Self::with_source(FileSystem::shared(), path)
+    }
+
+    /// Open the folder `path` of `source`.
+    pub fn with_source(source: Arc<dyn DataSource>, path: PathBuf) -> Result<Self> {
+        source.require_dir(&path)?;
+        source.require_file(&path.join(DATA_CSV))?;
+        source.require_file(&path.join(SENSOR_YAML))?;
+
+        Ok(Self {
+            on_error: ErrorPolicy::default(),
+            index: IndexCache::new(Arc::clone(&source)),
+            source,
+            path,
+        })
+    }
+
+    /// Handle bad records in `load_all` with `policy`.
+    ///
+    /// The accessors of [`EuRoC`](crate::EuRoC) start from
+    /// [`Config::on_error`](crate::Config::on_error), [`Self::new`] from
+    /// [`ErrorPolicy::Strict`].
+    pub const fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.on_error = policy;
+        self
+    }
+
+    /// Return the policy `load_all` handles bad records with.
+    pub const fn error_policy(&self) -> ErrorPolicy {
+        self.on_error
+    }
+
+    #[inline]
+    fn read_sensor_yaml(&self) -> Result<SensorYaml> {
+        SensorYaml::load(&*self.source, self.path.join(SENSOR_YAML))
+    }
+
+    /// Return extrinsics wrt. the body-frame.
+    pub fn extrinsics(&self) -> Result<na::Matrix4<f64>> {
+        self.read_sensor_yaml()?.matrix4("T_BS")
+    }
+
+    /// Return extrinsics wrt. the body-frame as a rigid body transform.
+    ///
+    /// Fails if `T_BS` is not orthonormal. Use [`na::Isometry3::inverse`] for `T_SB`.
+    pub fn extrinsics_isometry(&self) -> Result<na::Isometry3<f64>> {
+        self.read_sensor_yaml()?.isometry("T_BS")
+    }
+
+    /// Return the number of records, counting the rows of `data.csv` on the first call.
+    pub fn len(&self) -> Result<usize> {
+        self.index.row_count(&self.path.join(DATA_CSV))
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Iterate over records in `data.csv` order.
+    pub fn records(&self) -> Result<ViconIterator> {
+        Ok(ViconIterator {
+            rows: self.index.rows(self.path.join(DATA_CSV))?,
+        })
+    }
+
+    /// Iterate over records with `start <= timestamp < end`.
+    ///
+    /// The first call indexes `data.csv`; later calls seek directly to `start`.
+    pub fn records_between(&self, start: Timestamp, end: Timestamp) -> Result<ViconIterator> {
+        Ok(ViconIterator {
+            rows: self
+                .index
+                .rows_between(self.path.join(DATA_CSV), start, end)?,
+        })
+    }
+
+    /// Continue an iteration where `checkpoint` was taken, without indexing `data.csv`.
+    pub fn resume(&self, checkpoint: &Checkpoint) -> Result<ViconIterator> {
+        Ok(ViconIterator {
+            rows: self.index.resume(self.path.join(DATA_CSV), checkpoint)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ViconRecord {
+    pub timestamp: Timestamp,
+    /// position (m)
+    pub position: na::Vector3<f64>,
+    /// quaternion
+    pub quaternion: na::Quaternion<f64>,
+}
+
+impl ViconRecord {
+    /// Return pose wrt. the world frame.
+    pub fn pose(&self) -> na::Isometry3<f64> {
+        na::Isometry3::from_parts(
+            self.position.into(),
+            na::UnitQuaternion::from_quaternion(self.quaternion),
+        )
+    }
+}
+
+impl Timestamped for ViconRecord {
+    fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+pub struct ViconIterator {
+    rows: IndexedRows,
+}
+
+impl ViconIterator {
+    /// Return where the iteration stopped, for [`ViconData::resume`].
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.rows.checkpoint()
+    }
+
+    fn parse(row: Result<Row>) -> Result<ViconRecord> {
+        let row = row?;
+        Ok(ViconRecord {
+            timestamp: row.timestamp()?,
+            position: row.vector3(1)?,
+            quaternion: row.quaternion(4)?,
+        })
+    }
+}
+
+impl Iterator for ViconIterator {
+    type Item = Result<ViconRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rows.next().map(Self::parse)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for ViconIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.rows.next_back().map(Self::parse)
+    }
+}
+
+impl ExactSizeIterator for ViconIterator {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EuRoC;
+
+    #[test]
+    fn extrinsics() -> Result<()> {
+        let data = EuRoC::new("test_data")?.vicon()?;
+        assert_eq!(
+            data.extrinsics()?,
+            na::Matrix4::from_rows(&[
+                na::RowVector4::new(0.33638, -0.01749, 0.94156, 0.06901),
+                na::RowVector4::new(-0.02078, -0.99972, -0.01114, -0.02781),
+                na::RowVector4::new(0.94150, -0.01582, -0.33665, -0.12395),
+                na::RowVector4::new(0.0, 0.0, 0.0, 1.0)
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn records() -> Result<()> {
+        let data = EuRoC::new("test_data")?.vicon()?;
+        let record = data.records()?.nth(2).unwrap()?;
+
+        assert_eq!(record.timestamp, 1403715271282142976.into());
+        assert_eq!(
+            record.position,
+            na::Vector3::new(0.878591, 2.142485, 0.947226)
+        );
+        assert_eq!(
+            record.quaternion,
+            na::Quaternion::new(0.060455, -0.828463, -0.058980, -0.553637)
+        );
+        assert_eq!(data.records()?.count(), 5);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::{fmt, path::PathBuf};
+
+use nalgebra as na;
+use yaml_rust::{Yaml, YamlLoader};
+
+use crate::{DataSource, EurocError, Result};
+
+/// Keys of every `sensor.yaml`.
+const GENERAL_FIELDS: [&str; 3] = ["sensor_type", "comment", "T_BS"];
+
+/// Top-level field of a `sensor.yaml` that a typed config did not use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigWarning {
+    pub file: PathBuf,
+    pub key: String,
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: ignored unknown field `{}`",
+            self.file.display(),
+            self.key
+        )
+    }
+}
+
+/// First document of a `sensor.yaml` with typed field accessors.
+pub struct SensorYaml {
+    path: PathBuf,
+    doc: Yaml,
+}
+
+impl SensorYaml {
+    pub fn load(source: &dyn DataSource, path: PathBuf) -> Result<Self> {
+        let text = source.read_to_string(&path)?;
+        let doc = YamlLoader::load_from_str(&text)
+            .map_err(|e| EurocError::YamlParse {
+                file: path.clone(),
+                message: e.to_string(),
+            })?
+            .into_iter()
+            .next()
+            .ok_or_else(|| EurocError::YamlParse {
+                file: path.clone(),
+                message: "empty document".to_owned(),
+            })?;
+
+        Ok(Self { path, doc })
+    }
+
+    fn field_error(&self, key: &str) -> EurocError {
+        EurocError::YamlField {
+            file: self.path.clone(),
+            key: key.to_owned(),
+        }
+    }
+
+    pub fn f64(&self, key: &str) -> Result<f64> {
+        as_f64(&self.doc[key]).ok_or_else(|| self.field_error(key))
+    }
+
+    pub fn f64_vec(&self, key: &str) -> Result<Vec<f64>> {
+        self.doc[key]
+            .as_vec()
+            .and_then(|v| v.iter().map(as_f64).collect())
+            .ok_or_else(|| self.field_error(key))
+    }
+
+    /// Parse a list of exactly `len` numbers.
+    pub fn f64_array(&self, key: &str, len: usize) -> Result<Vec<f64>> {
+        let data = self.f64_vec(key)?;
+        if data.len() == len {
+            Ok(data)
+        } else {
+            Err(self.field_error(key))
+        }
+    }
+
+    /// Return the raw value of `key`, [`Yaml::BadValue`] if absent.
+    pub fn field(&self, key: &str) -> &Yaml {
+        &self.doc[key]
+    }
+
+    pub fn str(&self, key: &str) -> Result<String> {
+        self.doc[key]
+            .as_str()
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| self.field_error(key))
+    }
+
+    /// Return a warning for every top-level key other than `known` and the general fields.
+    pub fn unknown_fields(&self, known: &[&str]) -> Vec<ConfigWarning> {
+        let keys = self.doc.as_hash().into_iter().flat_map(|hash| hash.keys());
+        keys.map(|key| {
+            key.as_str()
+                .map_or_else(|| format!("{:?}", key), ToOwned::to_owned)
+        })
+        .filter(|key| !GENERAL_FIELDS.contains(&key.as_str()) && !known.contains(&key.as_str()))
+        .map(|key| ConfigWarning {
+            file: self.path.clone(),
+            key,
+        })
+        .collect()
+    }
+
+    /// Parse a row-major 4x4 matrix such as `T_BS`.
+    pub fn matrix4(&self, key: &str) -> Result<na::Matrix4<f64>> {
+        self.doc[key]["data"]
+            .as_vec()
+            .and_then(|v| v.iter().map(as_f64).collect::<Option<Vec<_>>>())
+            .filter(|data| data.len() == 16)
+            .map(|data| na::Matrix4::from_row_slice(&data))
+            .ok_or_else(|| self.field_error(key))
+    }
+
+    /// Parse a 4x4 matrix such as `T_BS` that must be a rigid body transform.
+    pub fn isometry(&self, key: &str) -> Result<na::Isometry3<f64>> {
+        rigid_transform(&self.matrix4(key)?).ok_or_else(|| self.field_error(key))
+    }
+}
+
+/// Return `m` as an isometry, or `None` if it is not a rigid body transform.
+///
+/// Calibrations are stored with few digits, so the rotation only has to be
+/// orthonormal up to rounding and is snapped to the nearest rotation.
+pub fn rigid_transform(m: &na::Matrix4<f64>) -> Option<na::Isometry3<f64>> {
+    const TOLERANCE: f64 = 1e-3;
+
+    let rotation: na::Matrix3<f64> = m.fixed_slice::<3, 3>(0, 0).into_owned();
+    let orthonormal =
+        (rotation.transpose() * rotation - na::Matrix3::identity()).amax() < TOLERANCE;
+    let bottom = (m.fixed_slice::<1, 4>(3, 0) - na::RowVector4::new(0.0, 0.0, 0.0, 1.0)).amax();
+    if !orthonormal || rotation.determinant() <= 0.0 || bottom >= TOLERANCE {
+        return None;
+    }
+
+    Some(na::Isometry3::from_parts(
+        na::Vector3::new(m[(0, 3)], m[(1, 3)], m[(2, 3)]).into(),
+        na::UnitQuaternion::from_matrix(&rotation),
+    ))
+}
+
+fn as_f64(yaml: &Yaml) -> Option<f64> {
+    yaml.as_f64().or_else(|| yaml.as_i64().map(|v| v as f64))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rigid_transform_rejects_scale() {
+        let mut m = na::Matrix4::identity();
+        m[(0, 3)] = 1.0;
+        assert!(rigid_transform(&m).is_some());
+
+        m[(1, 1)] = 2.0;
+        assert!(rigid_transform(&m).is_none());
+    }
+}
@@ -0,0 +1,456 @@
+use std::{
+    convert::TryInto,
+    fmt,
+    fs::File,
+    io::{Cursor, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use miniz_oxide::inflate::{
+    core::{decompress, inflate_flags, DecompressorOxide},
+    TINFLStatus,
+};
+
+use crate::{source::FileTree, DataSource, EurocError, ReadSeek, Result};
+
+const EOCD: u32 = 0x0605_4b50;
+const EOCD64: u32 = 0x0606_4b50;
+const EOCD64_LOCATOR: u32 = 0x0706_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const ZIP64_EXTRA: u16 = 0x0001;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+/// fixed part of the end of central directory record; the comment follows
+const EOCD_LEN: usize = 22;
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    method: u16,
+    compressed: u64,
+    size: u64,
+    /// offset of the local header
+    offset: u64,
+}
+
+/// Where the zip file itself is read from.
+enum Archive {
+    File(PathBuf),
+    Memory(Arc<[u8]>),
+}
+
+impl Archive {
+    fn reader(&self) -> Result<Box<dyn ReadSeek>> {
+        Ok(match self {
+            Self::File(path) => Box::new(File::open(path)?),
+            Self::Memory(data) => Box::new(Cursor::new(Arc::clone(data))),
+        })
+    }
+}
+
+impl fmt::Debug for Archive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::Memory(data) => write!(f, "Memory({} bytes)", data.len()),
+        }
+    }
+}
+
+/// [`DataSource`] reading the entries of a zip file, such as the official
+/// EuRoC downloads, without extracting it.
+///
+/// Each opened file is decompressed into memory. Stored and deflated entries
+/// are supported, including zip64 archives.
+#[derive(Debug)]
+pub struct ZipSource {
+    archive: Archive,
+    entries: FileTree<Entry>,
+}
+
+impl ZipSource {
+    /// Read the central directory of the zip file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(Archive::File(path.as_ref().to_owned()))
+    }
+
+    /// Read a zip file held in memory.
+    pub fn from_bytes<B: Into<Arc<[u8]>>>(data: B) -> Result<Self> {
+        Self::new(Archive::Memory(data.into()))
+    }
+
+    fn new(archive: Archive) -> Result<Self> {
+        let mut file = archive.reader()?;
+        let (cd_offset, cd_size) = find_central_directory(&mut file)?;
+
+        let mut cd = vec![0; cd_size as usize];
+        file.seek(SeekFrom::Start(cd_offset))?;
+        file.read_exact(&mut cd)?;
+
+        let mut source = Self {
+            archive,
+            entries: FileTree::default(),
+        };
+
+        let mut pos = 0;
+        while pos < cd.len() {
+            if pos + 46 > cd.len() || u32_at(&cd, pos)? != CENTRAL_HEADER {
+                return Err(zip_error("bad central directory entry"));
+            }
+            let name_len = usize::from(u16_at(&cd, pos + 28)?);
+            let extra_len = usize::from(u16_at(&cd, pos + 30)?);
+            let comment_len = usize::from(u16_at(&cd, pos + 32)?);
+            let end = pos + 46 + name_len + extra_len + comment_len;
+            if end > cd.len() {
+                return Err(zip_error("truncated central directory"));
+            }
+
+            let name = String::from_utf8_lossy(&cd[pos + 46..pos + 46 + name_len]).into_owned();
+            let mut entry = Entry {
+                method: u16_at(&cd, pos + 10)?,
+                compressed: u64::from(u32_at(&cd, pos + 20)?),
+                size: u64::from(u32_at(&cd, pos + 24)?),
+                offset: u64::from(u32_at(&cd, pos + 42)?),
+            };
+            read_zip64_extra(
+                &cd[pos + 46 + name_len..pos + 46 + name_len + extra_len],
+                &mut entry,
+            )?;
+            source.insert(&name, entry);
+
+            pos = end;
+        }
+
+        Ok(source)
+    }
+
+    fn insert(&mut self, name: &str, entry: Entry) {
+        if name.ends_with('/') {
+            self.entries.insert_dir(Path::new(name));
+        } else {
+            self.entries.insert_file(Path::new(name), entry);
+        }
+    }
+}
+
+impl DataSource for ZipSource {
+    fn open(&self, path: &Path) -> Result<Box<dyn ReadSeek>> {
+        let entry = *self.entries.get(path)?;
+
+        let mut file = self.archive.reader()?;
+        let mut header = [0; 30];
+        file.seek(SeekFrom::Start(entry.offset))?;
+        file.read_exact(&mut header)?;
+        if u32_at(&header, 0)? != LOCAL_HEADER {
+            return Err(zip_error("bad local header"));
+        }
+        let skip = i64::from(u16_at(&header, 26)?) + i64::from(u16_at(&header, 28)?);
+        let start = file.seek(SeekFrom::Current(skip))?;
+
+        // check the sizes from the headers before allocating for them
+        let len = file.seek(SeekFrom::End(0))?;
+        if start
+            .checked_add(entry.compressed)
+            .map_or(true, |end| end > len)
+        {
+            return Err(zip_error(format!(
+                "{}: entry beyond the archive",
+                path.display()
+            )));
+        }
+        file.seek(SeekFrom::Start(start))?;
+        let mut data = vec![0; entry.compressed as usize];
+        file.read_exact(&mut data)?;
+        let data = match entry.method {
+            STORED => data,
+            DEFLATED => inflate(&data, entry.size)
+                .map_err(|e| zip_error(format!("{}: {}", path.display(), e)))?,
+            method => {
+                return Err(zip_error(format!(
+                    "{}: unsupported compression method {}",
+                    path.display(),
+                    method
+                )))
+            }
+        };
+        if data.len() as u64 != entry.size {
+            return Err(zip_error(format!("{}: size mismatch", path.display())));
+        }
+
+        Ok(Box::new(Cursor::new(data)))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.entries.is_file(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.entries.is_dir(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        self.entries.read_dir(path)
+    }
+}
+
+/// Decompress a deflated entry of `size` bytes, failing if it inflates beyond.
+///
+/// `decompress_to_vec_with_limit` of miniz_oxide 0.4 fails whenever doubling its
+/// buffer overshoots the limit, so the output is allocated here: a stream
+/// expands at most 1032:1, so a larger `size` is rejected before allocating.
+fn inflate(data: &[u8], size: u64) -> std::result::Result<Vec<u8>, String> {
+    const MAX_RATIO: u64 = 1032;
+    if size > (data.len() as u64).saturating_mul(MAX_RATIO) {
+        return Err(format!("{} bytes cannot inflate to {}", data.len(), size));
+    }
+
+    // one byte of slack tells a stream that ends at `size` from a longer one
+    let mut out = vec![0; size as usize + 1];
+    let mut decompressor = Box::<DecompressorOxide>::default();
+    match decompress(
+        &mut decompressor,
+        data,
+        &mut out,
+        0,
+        inflate_flags::TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF,
+    ) {
+        (TINFLStatus::Done, _, written) => {
+            out.truncate(written);
+            Ok(out)
+        }
+        (status, _, _) => Err(format!("{:?}", status)),
+    }
+}
+
+/// Return the offset and size of the central directory.
+fn find_central_directory<R: Read + Seek>(file: &mut R) -> Result<(u64, u64)> {
+    // the record is followed by a comment of at most u16::MAX bytes
+    let len = file.seek(SeekFrom::End(0))?;
+    let tail_len = len.min((EOCD_LEN + usize::from(u16::MAX)) as u64);
+    let mut tail = vec![0; tail_len as usize];
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    file.read_exact(&mut tail)?;
+    if tail.len() < EOCD_LEN {
+        return Err(zip_error("too short for a zip"));
+    }
+
+    let eocd = (0..=tail.len().saturating_sub(EOCD_LEN))
+        .rev()
+        .find(|&i| matches!(u32_at(&tail, i), Ok(EOCD)))
+        .ok_or_else(|| zip_error("no end of central directory"))?;
+    let cd_size = u64::from(u32_at(&tail, eocd + 12)?);
+    let cd_offset = u64::from(u32_at(&tail, eocd + 16)?);
+    if cd_offset != u64::from(u32::MAX) && cd_size != u64::from(u32::MAX) {
+        return check_central_directory(cd_offset, cd_size, len);
+    }
+
+    // zip64: the locator precedes the end of central directory record
+    let locator = eocd
+        .checked_sub(20)
+        .filter(|&i| matches!(u32_at(&tail, i), Ok(EOCD64_LOCATOR)))
+        .ok_or_else(|| zip_error("no zip64 end of central directory locator"))?;
+    let mut record = [0; 56];
+    file.seek(SeekFrom::Start(u64_at(&tail, locator + 8)?))?;
+    file.read_exact(&mut record)?;
+    if u32_at(&record, 0)? != EOCD64 {
+        return Err(zip_error("bad zip64 end of central directory"));
+    }
+
+    check_central_directory(u64_at(&record, 48)?, u64_at(&record, 40)?, len)
+}
+
+/// Reject a central directory extending beyond the `len` bytes of the file.
+fn check_central_directory(offset: u64, size: u64, len: u64) -> Result<(u64, u64)> {
+    match offset.checked_add(size) {
+        Some(end) if end <= len => Ok((offset, size)),
+        _ => Err(zip_error("central directory beyond the archive")),
+    }
+}
+
+/// Replace the 32-bit fields of `entry` that overflowed with their zip64 values.
+fn read_zip64_extra(mut extra: &[u8], entry: &mut Entry) -> Result<()> {
+    while extra.len() >= 4 {
+        let id = u16_at(extra, 0)?;
+        let len = usize::from(u16_at(extra, 2)?).min(extra.len() - 4);
+        if id == ZIP64_EXTRA {
+            let mut values = extra[4..4 + len].chunks_exact(8).map(|v| u64_at(v, 0));
+            let overflowed = u64::from(u32::MAX);
+            for field in [&mut entry.size, &mut entry.compressed, &mut entry.offset].iter_mut() {
+                if **field == overflowed {
+                    if let Some(value) = values.next() {
+                        **field = value?;
+                    }
+                }
+            }
+        }
+        extra = &extra[4 + len..];
+    }
+    Ok(())
+}
+
+fn zip_error<M: ToString>(message: M) -> EurocError {
+    EurocError::ZipParse(message.to_string())
+}
+
+fn u16_at(data: &[u8], i: usize) -> Result<u16> {
+    Ok(u16::from_le_bytes(bytes_at(data, i)?))
+}
+
+fn u32_at(data: &[u8], i: usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(bytes_at(data, i)?))
+}
+
+fn u64_at(data: &[u8], i: usize) -> Result<u64> {
+    Ok(u64::from_le_bytes(bytes_at(data, i)?))
+}
+
+fn bytes_at<const N: usize>(data: &[u8], i: usize) -> Result<[u8; N]> {
+    i.checked_add(N)
+        .and_then(|end| data.get(i..end))
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| zip_error("truncated record"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use crate::{EuRoC, Layout, Timestamped};
+
+    /// Write `files` of `test_data` under `mav0/` into a zip, deflated, with a zero CRC.
+    fn write_zip(path: &Path, files: &[PathBuf]) -> Result<()> {
+        let (mut out, mut central) = (Vec::new(), Vec::new());
+        for file in files {
+            let name = format!("mav0/{}", file.display());
+            let data = fs::read(Path::new("test_data").join(file))?;
+            let compressed = miniz_oxide::deflate::compress_to_vec(&data, 6);
+
+            let mut fields = Vec::new();
+            fields.extend_from_slice(&DEFLATED.to_le_bytes());
+            fields.extend_from_slice(&[0; 8]); // time, date, crc
+            fields.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+            fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            fields.extend_from_slice(&0u16.to_le_bytes()); // extra
+
+            central.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+            central.extend_from_slice(&[20, 0, 20, 0, 0, 0]); // versions, flags
+            central.extend_from_slice(&fields);
+            central.extend_from_slice(&[0; 6]); // comment, disk, internal attributes
+            central.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+            central.extend_from_slice(&(out.len() as u32).to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+
+            out.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+            out.extend_from_slice(&[20, 0, 0, 0]); // version, flags
+            out.extend_from_slice(&fields);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&compressed);
+        }
+
+        let (offset, size) = (out.len() as u32, central.len() as u32);
+        out.extend_from_slice(&central);
+        out.extend_from_slice(&EOCD.to_le_bytes());
+        out.extend_from_slice(&[0; 4]); // disks
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // comment
+        fs::write(path, out)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_zip() {
+        let mut eocd = EOCD.to_le_bytes().to_vec();
+        eocd.extend_from_slice(&[0; 10]);
+        for data in [&[][..], &[0x50, 0x4b], &eocd].iter() {
+            assert!(matches!(
+                ZipSource::from_bytes(data.to_vec()),
+                Err(EurocError::ZipParse(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn lying_sizes() -> Result<()> {
+        let tmp = TempDir::new("lying_sizes")?;
+        let path = tmp.join("lying_sizes.zip");
+        write_zip(&path, &[PathBuf::from("imu0/data.csv")])?;
+        let zip = fs::read(path)?;
+        let eocd = zip.len() - EOCD_LEN;
+        let central = u32_at(&zip, eocd + 16)? as usize;
+        let patched = |at: usize, value: u32| {
+            let mut zip = zip.clone();
+            zip[at..at + 4].copy_from_slice(&value.to_le_bytes());
+            zip
+        };
+        let file = Path::new("mav0/imu0/data.csv");
+        assert!(ZipSource::from_bytes(zip.clone())?.open(file).is_ok());
+
+        // central directory size
+        assert!(matches!(
+            ZipSource::from_bytes(patched(eocd + 12, u32::MAX - 1)),
+            Err(EurocError::ZipParse(_))
+        ));
+        // compressed size
+        let source = ZipSource::from_bytes(patched(central + 20, u32::MAX - 1))?;
+        assert!(matches!(source.open(file), Err(EurocError::ZipParse(_))));
+        // uncompressed size
+        for &size in [10, u32::MAX - 1].iter() {
+            let source = ZipSource::from_bytes(patched(central + 24, size))?;
+            assert!(matches!(source.open(file), Err(EurocError::ZipParse(_))));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_zip() -> Result<()> {
+        let mut files = vec![
+            PathBuf::from("imu0/data.csv"),
+            PathBuf::from("imu0/sensor.yaml"),
+            PathBuf::from("cam0/data.csv"),
+            PathBuf::from("cam0/sensor.yaml"),
+        ];
+        for entry in fs::read_dir("test_data/cam0/data")? {
+            files.push(Path::new("cam0/data").join(entry?.file_name()));
+        }
+        let tmp = TempDir::new("from_zip")?;
+        let path = tmp.join("from_zip.zip");
+        write_zip(&path, &files)?;
+
+        let zipped = EuRoC::from_zip(&path)?;
+        let in_memory = EuRoC::from_zip_bytes(fs::read(&path)?)?;
+        assert_eq!(in_memory.layout(), Layout::Archive);
+        assert_eq!(in_memory.imu()?.len()?, 5);
+        let data = EuRoC::new("test_data")?;
+        assert_eq!(zipped.layout(), Layout::Archive);
+        assert!(zipped.vicon().is_err());
+
+        let imu = zipped.imu()?.records()?.collect::<Result<Vec<_>>>()?;
+        let expected = data.imu()?.records()?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(imu.len(), expected.len());
+        assert_eq!(imu[4].accel, expected[4].accel);
+        assert_eq!(zipped.imu()?.config()?, data.imu()?.config()?);
+
+        let frame = zipped.left_camera()?.entries()?.nth(1).unwrap()?;
+        let expected_frame = data.left_camera()?.entries()?.nth(1).unwrap()?;
+        assert_eq!(frame.timestamp(), expected_frame.timestamp());
+        assert_eq!(
+            frame.load()?.image.to_luma8(),
+            expected_frame.load()?.image.to_luma8()
+        );
+
+        let sensors = zipped.sensors()?;
+        assert_eq!(sensors.len(), 2);
+        assert_eq!(sensors[0].name, "cam0");
+
+        Ok(())
+    }
+}